@@ -1,10 +1,13 @@
 use crate::errors::CoordinatorError;
 use ledger::{
-  compute_aggregated_block_hash, compute_cut_diffs, compute_max_cut,
+  compute_aggregated_block_hash, compute_cut_diffs, compute_deleted_ledgers, compute_max_cut,
   errors::VerificationError,
+  is_tombstone,
+  quorum::{QuorumPolicy, ViewConfig},
+  retrieve_view_config,
   signature::{PublicKey, PublicKeyTrait},
-  Block, CustomSerde, EndorserHostnames, Handle, IdSig, MetaBlock, NimbleDigest, NimbleHashTrait,
-  Nonce, Nonces, Receipt, Receipts, VerifierState,
+  tombstone_block, Block, CustomSerde, CutDiff, EndorserHostnames, Handle, IdSig, MetaBlock,
+  NimbleDigest, NimbleHashTrait, Nonce, Nonces, Receipt, Receipts, VerifierState,
 };
 use log::error;
 use rand::{random, Rng};
@@ -16,15 +19,15 @@ use std::{
     atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering::SeqCst},
     Arc, RwLock,
   },
-  time::Duration,
+  time::{Duration, SystemTime, UNIX_EPOCH},
   u64::MAX,
 };
 use store::ledger::{
   azure_table::TableLedgerStore, filestore::FileStore, in_memory::InMemoryLedgerStore,
-  mongodb_cosmos::MongoCosmosLedgerStore, LedgerEntry, LedgerStore,
+  mongodb_cosmos::MongoCosmosLedgerStore, Checkpoint, LedgerEntry, LedgerStore,
 };
 use store::{errors::LedgerStoreError, errors::StorageError};
-use tokio::sync::mpsc;
+use tokio::sync::{broadcast, mpsc};
 use tonic::{
   transport::{Channel, Endpoint},
   Code, Status,
@@ -54,8 +57,127 @@ struct EndorserClients {
 
 type EndorserConnMap = HashMap<Vec<u8>, EndorserClients>;
 
+/// Progress report of a planned endorser rotation.
+#[derive(Clone, Debug, Default)]
+pub struct RotationReport {
+  /// the endorsers that joined the new view
+  pub added: EndorserHostnames,
+  /// the endorsers that were finalized and disconnected
+  pub retired: EndorserHostnames,
+  /// the steps completed so far, in order
+  pub steps: Vec<String>,
+}
+
+/// Outcome of a view change that has been computed but not applied.
+pub struct ViewChangePlan {
+  /// the active endorsers that would be finalized
+  pub to_finalize: EndorserHostnames,
+  /// the endorsers that would be initialized in the new view
+  pub to_initialize: EndorserHostnames,
+  /// the candidate endorsers that could not be reached or are not uninitialized
+  pub unreachable: Vec<String>,
+  /// the max cut that the new endorsers would be initialized with
+  pub max_cut: Vec<endorser_proto::LedgerTailMapEntry>,
+  /// the ranges of each ledger on which the finalized endorsers disagree
+  pub cut_diffs: Vec<CutDiff>,
+  /// the number of ledgers on which each finalized endorser is behind the max cut
+  pub lagging: Vec<(String, usize)>,
+  /// set if a quorum of the active endorsers responded
+  pub finalize_quorum: bool,
+  /// set if the candidates that can be initialized form a quorum of the candidates under the
+  /// current quorum policy
+  pub initialize_quorum: bool,
+}
+
+/// A view recorded in the view ledger, as shown to operators.
+pub struct ViewInfo {
+  /// the height of the view in the view ledger
+  pub height: usize,
+  /// the digest that identifies the view in receipts, if the entry has receipts
+  pub view: Option<NimbleDigest>,
+  /// the endorsers of the view
+  pub endorsers: EndorserHostnames,
+  /// the quorum policy of the view
+  pub policy: QuorumPolicy,
+  /// seconds since the Unix epoch at which the view was created, or 0 if not recorded; the time
+  /// is kept out of the view ledger block, so it is only known for the views that this
+  /// coordinator created since it started, or that an older version recorded in the block
+  pub created_at: u64,
+  /// the endorsers with a valid signature on the entry, from this view or the previous one
+  pub signers: Vec<Vec<u8>>,
+  /// set if the signers from this view satisfy its quorum policy
+  pub quorum: bool,
+}
+
+/// What the coordinator does when the ledger store is behind the endorsers at startup, as
+/// happens when the store is restored from a backup.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RollbackPolicy {
+  /// refuse to start
+  Refuse,
+  /// start, and quarantine the ledgers on which the store is behind the endorsers
+  Quarantine,
+  /// recover the tails that the endorsers still hold, and quarantine the other ledgers
+  Reconcile,
+}
+
+/// A ledger on which the ledger store disagrees with the endorsers, with the evidence for it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LedgerMismatch {
+  pub handle: Handle,
+  /// the height of the ledger in the store, or None if the store lacks the whole ledger
+  pub store_height: Option<usize>,
+  /// the height of the tail that the endorsers hold
+  pub endorser_height: usize,
+  /// the hash of the tail metablock that the endorsers hold
+  pub endorsed_metablock: NimbleDigest,
+  /// the endorsers that hold the tail
+  pub endorsers: Vec<String>,
+  /// set if the store holds a different block than the endorsers at their height
+  pub diverged: bool,
+  /// set if the tail was recovered from the endorsers; it has no receipts in the store
+  pub recovered: bool,
+  /// seconds since the Unix epoch at which the mismatch was found
+  pub detected_at: u64,
+}
+
+/// A difference between the ledger store and the state of the endorsers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RollbackFinding {
+  /// the endorsers are in a view that is past the tail of the view ledger in the store
+  MissingViews {
+    store_height: usize,
+    endorser_height: usize,
+  },
+  /// the store is behind the endorsers on a ledger, or holds a different block
+  Ledger(LedgerMismatch),
+}
+
+impl RotationReport {
+  fn step(&mut self, msg: String) {
+    eprintln!("rotation: {}", msg);
+    self.steps.push(msg);
+  }
+}
+
 type LedgerStoreRef = Arc<Box<dyn LedgerStore + Send + Sync>>;
 
+/// The view ledger receipt and the ledger tails that each endorser reported.
+type EndorserStates = Vec<(String, Receipt, Vec<endorser_proto::LedgerTailMapEntry>)>;
+
+/// Quorum policy to record in new views, with endorsers identified by their URIs.
+#[derive(Clone, Debug)]
+pub enum QuorumPolicyConfig {
+  /// a strict majority of the endorsers
+  Majority,
+  /// at least k of the endorsers
+  Threshold(usize),
+  /// per-endorser weights (default 1) and the weight a quorum must reach
+  Weighted(HashMap<String, u64>, u64),
+  /// failure domain tag of each endorser; a quorum needs one endorser from every domain
+  FailureDomains(HashMap<String, String>),
+}
+
 #[derive(Clone)]
 pub struct CoordinatorState {
   pub(crate) ledger_store: LedgerStoreRef,
@@ -63,10 +185,16 @@ pub struct CoordinatorState {
   verifier_state: Arc<RwLock<VerifierState>>,
   num_grpc_channels: usize,
   _used_nonces: Arc<RwLock<HashSet<Vec<u8>>>>,
+  quorum_policy: Arc<RwLock<QuorumPolicyConfig>>,
+  quarantined: Arc<RwLock<HashMap<Handle, LedgerMismatch>>>,
+  appends: broadcast::Sender<(Handle, usize)>,
+  view_times: Arc<RwLock<HashMap<usize, u64>>>,
 }
 
 const ENDORSER_MPSC_CHANNEL_BUFFER: usize = 8; // limited by the number of endorsers
+const APPENDS_CHANNEL_BUFFER: usize = 1024; // watchers that fall further behind re-read the store
 const ENDORSER_CONNECT_TIMEOUT: u64 = 10; // seconds: the connect timeout to endorsres
+const STORE_HEALTH_TIMEOUT: u64 = 5; // seconds: how long a health check waits for the store
 
 const ATTESTATION_STR: &str = "THIS IS A PLACE HOLDER FOR ATTESTATION";
 
@@ -77,6 +205,19 @@ static ENDORSER_REQUEST_TIMEOUT: AtomicU64 = AtomicU64::new(10);
 static ENDORSER_DEAD_ALLOWANCE: AtomicU64 = AtomicU64::new(66);
 static PING_INTERVAL: AtomicU32 = AtomicU32::new(10); // seconds
 static DEACTIVATE_AUTO_RECONFIG: AtomicBool = AtomicBool::new(false);
+const STORE_CHECK_INTERVAL: u32 = 60; // seconds: how often the store is compared with the endorsers
+
+/// Returns true if the endorsers form a quorum of the view described by the view ledger block,
+/// under the quorum policy of that view.
+fn is_view_quorum(view_ledger_block: &Block, endorsers: &HashSet<Vec<u8>>) -> bool {
+  match retrieve_view_config(&view_ledger_block.to_bytes()) {
+    Ok((pks, policy)) => policy.is_satisfied(&pks, endorsers),
+    Err(e) => {
+      eprintln!("Failed to parse the view ledger block ({:?})", e);
+      false
+    },
+  }
+}
 
 async fn get_public_key_with_retry(
   endorser_client: &mut endorser_proto::endorser_call_client::EndorserCallClient<Channel>,
@@ -487,6 +628,14 @@ fn process_error(
       eprintln!("the endorser is not initialized");
       CoordinatorAction::DoNothing
     },
+    Code::PermissionDenied => {
+      if let Some(h) = handle {
+        eprintln!("ledger {:?} is deleted in endorser {}", h, endorser);
+      } else {
+        eprintln!("a ledger is deleted in endorser {}", endorser);
+      }
+      CoordinatorAction::DoNothing
+    },
     Code::ResourceExhausted => CoordinatorAction::Retry,
     Code::Internal | Code::Unknown => CoordinatorAction::RemoveEndorser,
     _ => {
@@ -504,6 +653,7 @@ impl CoordinatorState {
   /// * `ledger_store_type` - The type of ledger store to use.
   /// * `args` - A map of arguments for the ledger store.
   /// * `num_grpc_channels_opt` - An optional number of gRPC channels.
+  /// * `rollback_policy` - What to do if the store is behind the endorsers.
   ///
   /// # Returns
   ///
@@ -512,6 +662,7 @@ impl CoordinatorState {
     ledger_store_type: &str,
     args: &HashMap<String, String>,
     num_grpc_channels_opt: Option<usize>,
+    rollback_policy: RollbackPolicy,
   ) -> Result<CoordinatorState, CoordinatorError> {
     let num_grpc_channels = match num_grpc_channels_opt {
       Some(n) => n,
@@ -524,6 +675,10 @@ impl CoordinatorState {
         verifier_state: Arc::new(RwLock::new(VerifierState::new())),
         num_grpc_channels,
         _used_nonces: Arc::new(RwLock::new(HashSet::new())),
+        quorum_policy: Arc::new(RwLock::new(QuorumPolicyConfig::Majority)),
+        quarantined: Arc::new(RwLock::new(HashMap::new())),
+        appends: broadcast::channel(APPENDS_CHANNEL_BUFFER).0,
+        view_times: Arc::new(RwLock::new(HashMap::new())),
       },
       "table" => CoordinatorState {
         ledger_store: Arc::new(Box::new(TableLedgerStore::new(args).await.unwrap())),
@@ -531,6 +686,10 @@ impl CoordinatorState {
         verifier_state: Arc::new(RwLock::new(VerifierState::new())),
         num_grpc_channels,
         _used_nonces: Arc::new(RwLock::new(HashSet::new())),
+        quorum_policy: Arc::new(RwLock::new(QuorumPolicyConfig::Majority)),
+        quarantined: Arc::new(RwLock::new(HashMap::new())),
+        appends: broadcast::channel(APPENDS_CHANNEL_BUFFER).0,
+        view_times: Arc::new(RwLock::new(HashMap::new())),
       },
       "filestore" => CoordinatorState {
         ledger_store: Arc::new(Box::new(FileStore::new(args).await.unwrap())),
@@ -538,6 +697,10 @@ impl CoordinatorState {
         verifier_state: Arc::new(RwLock::new(VerifierState::new())),
         num_grpc_channels,
         _used_nonces: Arc::new(RwLock::new(HashSet::new())),
+        quorum_policy: Arc::new(RwLock::new(QuorumPolicyConfig::Majority)),
+        quarantined: Arc::new(RwLock::new(HashMap::new())),
+        appends: broadcast::channel(APPENDS_CHANNEL_BUFFER).0,
+        view_times: Arc::new(RwLock::new(HashMap::new())),
       },
       _ => CoordinatorState {
         ledger_store: Arc::new(Box::new(InMemoryLedgerStore::new())),
@@ -545,6 +708,10 @@ impl CoordinatorState {
         verifier_state: Arc::new(RwLock::new(VerifierState::new())),
         num_grpc_channels,
         _used_nonces: Arc::new(RwLock::new(HashSet::new())),
+        quorum_policy: Arc::new(RwLock::new(QuorumPolicyConfig::Majority)),
+        quarantined: Arc::new(RwLock::new(HashMap::new())),
+        appends: broadcast::channel(APPENDS_CHANNEL_BUFFER).0,
+        view_times: Arc::new(RwLock::new(HashMap::new())),
       },
     };
 
@@ -619,6 +786,7 @@ impl CoordinatorState {
               &prev_view_ledger_entry,
               view_ledger_tail.get_block(),
               tail_height,
+              false,
             )
             .await;
           if let Err(error) = res {
@@ -634,6 +802,12 @@ impl CoordinatorState {
         }
       }
 
+      // A store restored from a backup may be missing entries that the endorsers endorsed
+      let states = coordinator.endorser_read_state(&curr_endorsers).await;
+      coordinator
+        .check_rollback(states, tail_height, rollback_policy)
+        .await?;
+
       // Remove endorsers that don't have the latest view
       let res = coordinator
         .filter_endorsers(&curr_endorsers, tail_height)
@@ -685,6 +859,13 @@ impl CoordinatorState {
   /// Starts the auto scheduler for pinging endorsers.
   pub async fn start_auto_scheduler(self: Arc<Self>) {
     let mut scheduler = clokwerk::AsyncScheduler::new();
+    let checker = self.clone();
+    scheduler
+      .every(STORE_CHECK_INTERVAL.seconds())
+      .run(move || {
+        let value = checker.clone();
+        async move { value.check_store_lag().await }
+      });
     scheduler
       .every(PING_INTERVAL.load(SeqCst).seconds())
       .run(move || {
@@ -714,7 +895,7 @@ impl CoordinatorState {
     &self,
     view_ledger_block: &[u8],
   ) -> Result<EndorserHostnames, CoordinatorError> {
-    let res = bincode::deserialize(view_ledger_block);
+    let res = ViewConfig::from_bytes(view_ledger_block);
     if res.is_err() {
       eprintln!(
         "Failed to deserialize the view ledger tail's genesis block {:?}",
@@ -722,7 +903,7 @@ impl CoordinatorState {
       );
       return Err(CoordinatorError::FailedToSerde);
     }
-    let endorser_hostnames: EndorserHostnames = res.unwrap();
+    let endorser_hostnames: EndorserHostnames = res.unwrap().endorsers;
 
     let mut endorsers = EndorserHostnames::new();
 
@@ -1028,6 +1209,386 @@ impl CoordinatorState {
     Ok(())
   }
 
+  /// Compares the ledger store with the tails that the endorsers in the latest view hold, so
+  /// that a store restored from an older backup is not silently served.
+  ///
+  /// # Arguments
+  ///
+  /// * `states` - The states of the endorsers of the latest view.
+  /// * `view_ledger_height` - The height of the view ledger in the store.
+  /// * `policy` - Whether to refuse to start, or which ledgers to quarantine.
+  ///
+  /// # Returns
+  ///
+  /// A result indicating success, `CoordinatorError::StoreRolledBack` if the store is behind the
+  /// endorsers and the policy or the findings do not allow starting, or
+  /// `CoordinatorError::FailedToReadLatestState` if the endorsers that responded are not a quorum
+  /// of the latest view, so an endorsed entry that the store lost could go unnoticed.
+  async fn check_rollback(
+    &self,
+    states: EndorserStates,
+    view_ledger_height: usize,
+    policy: RollbackPolicy,
+  ) -> Result<(), CoordinatorError> {
+    let res = self
+      .find_store_lag(
+        states,
+        view_ledger_height,
+        policy == RollbackPolicy::Reconcile,
+      )
+      .await;
+    let findings = match res {
+      Ok(findings) => findings,
+      Err(CoordinatorError::FailedToReadLatestState) => {
+        eprintln!("No quorum of the latest view responded, so the store cannot be compared");
+        return Err(CoordinatorError::FailedToReadLatestState);
+      },
+      Err(error) => return Err(error),
+    };
+    if findings.is_empty() {
+      return Ok(());
+    }
+
+    eprintln!("The ledger store is behind the endorsers:");
+    report_rollback_findings(&findings);
+
+    // the endorsers cannot vouch for views that the store lacks
+    let missing_views = findings
+      .iter()
+      .any(|f| matches!(f, RollbackFinding::MissingViews { .. }));
+    if policy == RollbackPolicy::Refuse || missing_views {
+      return Err(CoordinatorError::StoreRolledBack);
+    }
+    self.quarantine_ledgers(findings);
+    Ok(())
+  }
+
+  /// Compares the ledger store with the ledger tails that the endorsers hold in the view at
+  /// `view_ledger_height`. The store is updated before the endorsers, so it can only be behind
+  /// them if it lost entries.
+  ///
+  /// # Arguments
+  ///
+  /// * `states` - The states that the endorsers reported.
+  /// * `view_ledger_height` - The height of the view ledger in the store.
+  /// * `recover` - Whether to append a missing tail that the endorsers hold to the store.
+  ///
+  /// # Returns
+  ///
+  /// The findings, or `CoordinatorError::FailedToReadLatestState` if the endorsers in the view
+  /// that responded are not a quorum. Without a verified view, any one of them is enough.
+  async fn find_store_lag(
+    &self,
+    states: EndorserStates,
+    view_ledger_height: usize,
+    recover: bool,
+  ) -> Result<Vec<RollbackFinding>, CoordinatorError> {
+    let detected_at = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map(|d| d.as_secs())
+      .unwrap_or(0);
+
+    let mut findings = Vec::new();
+    let mut responded = HashSet::new();
+    // the highest tail of each ledger, and the endorsers that hold it
+    let mut tails: HashMap<Vec<u8>, (endorser_proto::LedgerTailMapEntry, Vec<String>)> =
+      HashMap::new();
+    for (endorser, receipt, ledger_tail_map) in states {
+      if receipt.get_height() > view_ledger_height {
+        eprintln!(
+          "endorser {} is at view ledger height {}, but the store is at {}",
+          endorser,
+          receipt.get_height(),
+          view_ledger_height
+        );
+        let finding = RollbackFinding::MissingViews {
+          store_height: view_ledger_height,
+          endorser_height: receipt.get_height(),
+        };
+        if !findings.contains(&finding) {
+          findings.push(finding);
+        }
+      } else if receipt.get_height() == view_ledger_height {
+        responded.insert(receipt.get_id_sig().get_id().clone());
+        for entry in ledger_tail_map {
+          let tail = tails
+            .entry(entry.handle.clone())
+            .or_insert_with(|| (entry.clone(), Vec::new()));
+          if entry.height > tail.0.height {
+            *tail = (entry, vec![endorser.clone()]);
+          } else if entry.height == tail.0.height {
+            tail.1.push(endorser.clone());
+          }
+        }
+      }
+    }
+    // every entry endorsed by a quorum is held by at least one endorser of any other quorum
+    let is_quorum = match self.verifier_state.read() {
+      Ok(vs) => match vs.get_latest_view() {
+        Some((pks, policy)) => policy.is_satisfied(pks, &responded),
+        None => !responded.is_empty(),
+      },
+      Err(_) => return Err(CoordinatorError::FailedToAcquireReadLock),
+    };
+    if !is_quorum {
+      return Err(CoordinatorError::FailedToReadLatestState);
+    }
+
+    let mut tails = tails.into_values().collect::<Vec<_>>();
+    tails.sort_by(|a, b| a.0.handle.cmp(&b.0.handle));
+    for (entry, holders) in tails {
+      let (handle, metablock) = match (
+        NimbleDigest::from_bytes(&entry.handle),
+        MetaBlock::from_bytes(&entry.metablock),
+      ) {
+        (Ok(handle), Ok(metablock)) => (handle, metablock),
+        _ => {
+          eprintln!("Failed to parse a ledger tail map entry of {:?}", holders);
+          continue;
+        },
+      };
+      let endorser_height = entry.height as usize;
+
+      let store_height = match self.ledger_store.read_ledger_tail(&handle).await {
+        Ok((_tail, height)) => Some(height),
+        // the file store reports a missing ledger as an invalid key
+        Err(LedgerStoreError::LedgerError(StorageError::KeyDoesNotExist))
+        | Err(LedgerStoreError::LedgerError(StorageError::InvalidKey)) => None,
+        Err(error) => {
+          eprintln!(
+            "Failed to read the tail of ledger {:?} ({:?})",
+            handle, error
+          );
+          return Err(CoordinatorError::FailedToReadLedger);
+        },
+      };
+
+      let diverged = match store_height {
+        Some(height) if height >= endorser_height => {
+          let res = self
+            .ledger_store
+            .read_ledger_by_index(&handle, endorser_height)
+            .await;
+          match res {
+            Ok(stored) if stored.get_block().to_bytes() == entry.block => continue,
+            // the store kept the entries from a checkpoint above the endorsers' tail, and the
+            // checkpoint's receipts show that a quorum endorsed the ledger up to it
+            Err(LedgerStoreError::Pruned(_checkpoint)) => continue,
+            _ => true,
+          }
+        },
+        _ => false,
+      };
+
+      let recovered = recover
+        && !diverged
+        && self
+          .recover_ledger_tail(&handle, store_height, &entry)
+          .await;
+      findings.push(RollbackFinding::Ledger(LedgerMismatch {
+        handle,
+        store_height,
+        endorser_height,
+        endorsed_metablock: metablock.hash(),
+        endorsers: holders,
+        diverged,
+        recovered,
+        detected_at,
+      }));
+    }
+
+    Ok(findings)
+  }
+
+  /// Quarantines the ledgers in `findings` that were not recovered, making them read-only.
+  fn quarantine_ledgers(&self, findings: Vec<RollbackFinding>) {
+    if let Ok(mut quarantined) = self.quarantined.write() {
+      for finding in findings {
+        if let RollbackFinding::Ledger(mismatch) = finding {
+          if !mismatch.recovered && !quarantined.contains_key(&mismatch.handle) {
+            eprintln!("Quarantined ledger {:?}", mismatch.handle);
+            quarantined.insert(mismatch.handle, mismatch);
+          }
+        }
+      }
+    } else {
+      eprintln!("Failed to acquire the write lock on the quarantined ledgers");
+    }
+  }
+
+  /// Compares the ledger store with the tails that the endorsers hold, quarantines the ledgers
+  /// on which the store is behind, and releases the quarantined ledgers that have caught up.
+  pub async fn check_store_lag(&self) {
+    let view_ledger_height = match self.ledger_store.read_view_ledger_tail().await {
+      Ok((_tail, height)) => height,
+      Err(error) => {
+        eprintln!("Failed to read the view ledger tail {:?}", error);
+        return;
+      },
+    };
+    let endorsers = self.get_endorser_hostnames();
+    let states = self.endorser_read_state(&endorsers).await;
+    let res = self.find_store_lag(states, view_ledger_height, false).await;
+    match res {
+      Ok(findings) => self.update_quarantine(findings),
+      Err(error) => eprintln!("Failed to compare the store with the endorsers {:?}", error),
+    }
+  }
+
+  /// Quarantines the ledgers in `findings` that are not quarantined yet, and releases the
+  /// quarantined ledgers that are not in `findings`.
+  fn update_quarantine(&self, findings: Vec<RollbackFinding>) {
+    let lagging = findings
+      .iter()
+      .filter_map(|f| match f {
+        RollbackFinding::Ledger(mismatch) => Some(mismatch.handle),
+        _ => None,
+      })
+      .collect::<HashSet<Handle>>();
+    let new_findings = if let Ok(mut quarantined) = self.quarantined.write() {
+      quarantined.retain(|handle, _| {
+        let keep = lagging.contains(handle);
+        if !keep {
+          eprintln!(
+            "Ledger {:?} caught up with the endorsers and is released",
+            handle
+          );
+        }
+        keep
+      });
+      findings
+        .into_iter()
+        .filter(|f| match f {
+          RollbackFinding::Ledger(mismatch) => !quarantined.contains_key(&mismatch.handle),
+          _ => true,
+        })
+        .collect::<Vec<_>>()
+    } else {
+      eprintln!("Failed to acquire the write lock on the quarantined ledgers");
+      return;
+    };
+
+    if !new_findings.is_empty() {
+      eprintln!("The ledger store is behind the endorsers:");
+      report_rollback_findings(&new_findings);
+      self.quarantine_ledgers(new_findings);
+    }
+  }
+
+  /// Gets the ledgers that are quarantined, with the evidence for each.
+  pub fn get_quarantined_ledgers(&self) -> Vec<LedgerMismatch> {
+    if let Ok(quarantined) = self.quarantined.read() {
+      let mut ledgers = quarantined.values().cloned().collect::<Vec<_>>();
+      ledgers.sort_by_key(|m| m.handle);
+      ledgers
+    } else {
+      eprintln!("Failed to acquire the read lock on the quarantined ledgers");
+      Vec::new()
+    }
+  }
+
+  /// Releases a quarantined ledger, e.g., after an operator repaired the store. The ledger is
+  /// quarantined again by the next check if the store is still behind.
+  ///
+  /// # Returns
+  ///
+  /// Whether the ledger was quarantined.
+  pub fn release_quarantined_ledger(&self, handle: &Handle) -> bool {
+    if let Ok(mut quarantined) = self.quarantined.write() {
+      quarantined.remove(handle).is_some()
+    } else {
+      eprintln!("Failed to acquire the write lock on the quarantined ledgers");
+      false
+    }
+  }
+
+  fn is_quarantined(&self, handle: &Handle) -> bool {
+    match self.quarantined.read() {
+      Ok(quarantined) => quarantined.contains_key(handle),
+      Err(_) => false,
+    }
+  }
+
+  /// Returns true if the receipts carry the signatures of a quorum of endorsers.
+  fn has_quorum(&self, receipts: &Receipts) -> bool {
+    match self.verifier_state.read() {
+      Ok(vs) => receipts.check_quorum(&vs).is_ok(),
+      Err(_) => false,
+    }
+  }
+
+  /// Appends the tail of a ledger that the endorsers hold to the store, if the store lacks only
+  /// that entry and it extends the entry before it.
+  ///
+  /// # Arguments
+  ///
+  /// * `handle` - The handle of the ledger.
+  /// * `store_height` - The height of the ledger in the store, if the store has the ledger.
+  /// * `entry` - The tail of the ledger at the endorsers.
+  ///
+  /// # Returns
+  ///
+  /// Whether the tail was appended.
+  async fn recover_ledger_tail(
+    &self,
+    handle: &Handle,
+    store_height: Option<usize>,
+    entry: &endorser_proto::LedgerTailMapEntry,
+  ) -> bool {
+    let height = entry.height as usize;
+    let next_height = store_height.map(|h| h + 1).unwrap_or(0);
+    if next_height != height {
+      return false;
+    }
+
+    let block = Block::new(&entry.block);
+    let (metablock, nonces) = match (
+      MetaBlock::from_bytes(&entry.metablock),
+      Nonces::from_bytes(&entry.nonces),
+    ) {
+      (Ok(metablock), Ok(nonces)) => (metablock, nonces),
+      _ => return false,
+    };
+    let block_hash =
+      compute_aggregated_block_hash(&block.hash().to_bytes(), &nonces.hash().to_bytes());
+    if *metablock.get_block_hash() != block_hash {
+      return false;
+    }
+
+    if height == 0 {
+      return self.ledger_store.create_ledger(handle, block).await.is_ok();
+    }
+
+    // the endorsed metablock must extend the tail that the store has
+    let prev = match self
+      .ledger_store
+      .read_ledger_by_index(handle, height - 1)
+      .await
+    {
+      Ok(prev) => prev,
+      Err(_) => return false,
+    };
+    match prev.get_receipts().get_metablock() {
+      Ok(prev_metablock) if *metablock.get_prev() == prev_metablock.hash() => {},
+      _ => return false,
+    }
+    for nonce in nonces.get() {
+      if self
+        .ledger_store
+        .attach_ledger_nonce(handle, nonce)
+        .await
+        .is_err()
+      {
+        return false;
+      }
+    }
+    self
+      .ledger_store
+      .append_ledger(handle, &block, height)
+      .await
+      .is_ok()
+  }
+
   /// Initializes the state of the endorsers.
   ///
   /// # Arguments
@@ -1329,13 +1890,12 @@ impl CoordinatorState {
                 break;
               },
               _ => {
-                let _ = tx
-                  .send((
-                    endorser,
-                    pk_bytes,
-                    Err(CoordinatorError::FailedToAppendLedger),
-                  ))
-                  .await;
+                let error = if status.code() == Code::PermissionDenied {
+                  CoordinatorError::LedgerDeleted
+                } else {
+                  CoordinatorError::FailedToAppendLedger
+                };
+                let _ = tx.send((endorser, pk_bytes, Err(error))).await;
                 break;
               },
             },
@@ -1347,6 +1907,7 @@ impl CoordinatorState {
     drop(mpsc_tx);
 
     let mut receipts = Receipts::new();
+    let mut deleted = HashSet::new();
     while let Some((endorser, pk_bytes, res)) = mpsc_rx.recv().await {
       match res {
         Ok(receipt) => match Receipt::from_bytes(&receipt) {
@@ -1369,6 +1930,21 @@ impl CoordinatorState {
               endorser, error
             );
             self.disconnect_endorsers(&vec![(pk_bytes, endorser)]).await;
+          } else if error == CoordinatorError::LedgerDeleted {
+            // an endorser that holds a tombstone fails like any other, and the ledger is only
+            // deleted once a quorum of endorsers says so
+            eprintln!(
+              "append_ledger from endorser {} found ledger {:?} deleted",
+              endorser, ledger_handle
+            );
+            deleted.insert(pk_bytes);
+            if let Ok(vs) = self.verifier_state.read() {
+              if let Some((pks, policy)) = vs.get_latest_view() {
+                if policy.is_satisfied(pks, &deleted) {
+                  return Err(error);
+                }
+              }
+            }
           }
         },
       }
@@ -1685,7 +2261,7 @@ impl CoordinatorState {
     ledger_tail_maps: Vec<endorser_proto::LedgerTailMap>,
     ledger_chunks: Vec<endorser_proto::LedgerChunkEntry>,
     receipts: &Receipts,
-  ) -> usize {
+  ) -> HashSet<Vec<u8>> {
     let (mpsc_tx, mut mpsc_rx) = mpsc::channel(ENDORSER_MPSC_CHANNEL_BUFFER);
     let ledger_tail_maps_arc = Arc::new(ledger_tail_maps);
 
@@ -1718,7 +2294,7 @@ impl CoordinatorState {
 
     drop(mpsc_tx);
 
-    let mut num_verified_endorers = 0;
+    let mut verified_endorsers = HashSet::new();
 
     // TODO: Better error handling here
     while let Some((endorser, pk_bytes, res)) = mpsc_rx.recv().await {
@@ -1737,7 +2313,7 @@ impl CoordinatorState {
           } else {
             eprintln!("Couldn't get write lock on conn_map");
           }
-          num_verified_endorers += 1;
+          verified_endorsers.insert(pk_bytes);
         },
         Err(status) => {
           eprintln!(
@@ -1750,7 +2326,135 @@ impl CoordinatorState {
         },
       }
     }
-    num_verified_endorers
+    verified_endorsers
+  }
+
+  /// Sets the quorum policy recorded in the views created from now on.
+  ///
+  /// # Arguments
+  ///
+  /// * `policy` - The quorum policy.
+  pub fn set_quorum_policy(&self, policy: QuorumPolicyConfig) -> Result<(), CoordinatorError> {
+    if let Ok(mut quorum_policy) = self.quorum_policy.write() {
+      *quorum_policy = policy;
+      Ok(())
+    } else {
+      Err(CoordinatorError::FailedToAcquireWriteLock)
+    }
+  }
+
+  /// Builds the current quorum policy for a view with the given endorsers.
+  ///
+  /// # Arguments
+  ///
+  /// * `endorsers` - The endorsers of the view.
+  ///
+  /// # Returns
+  ///
+  /// A result containing the `QuorumPolicy` or a `CoordinatorError`.
+  fn view_policy(&self, endorsers: &EndorserHostnames) -> Result<QuorumPolicy, CoordinatorError> {
+    let policy_config = if let Ok(quorum_policy) = self.quorum_policy.read() {
+      quorum_policy.clone()
+    } else {
+      return Err(CoordinatorError::FailedToAcquireReadLock);
+    };
+
+    let policy = match policy_config {
+      QuorumPolicyConfig::Majority => QuorumPolicy::Majority,
+      QuorumPolicyConfig::Threshold(k) => QuorumPolicy::Threshold(k),
+      QuorumPolicyConfig::Weighted(weights, threshold) => QuorumPolicy::Weighted {
+        weights: endorsers
+          .iter()
+          .filter_map(|(pk, uri)| weights.get(uri).map(|w| (pk.clone(), *w)))
+          .collect(),
+        threshold,
+      },
+      QuorumPolicyConfig::FailureDomains(tags) => QuorumPolicy::FailureDomains(
+        endorsers
+          .iter()
+          .filter_map(|(pk, uri)| tags.get(uri).map(|tag| (pk.clone(), tag.clone())))
+          .collect(),
+      ),
+    };
+
+    Ok(policy)
+  }
+
+  /// Checks whether the ready endorsers form a quorum of the candidates for a new view under the
+  /// current quorum policy. Candidates that could not be reached have no known public key and
+  /// are identified by their URIs.
+  ///
+  /// # Arguments
+  ///
+  /// * `candidates` - The URIs of all the candidates for the new view.
+  /// * `ready` - The candidates that can be initialized.
+  ///
+  /// # Returns
+  ///
+  /// A result containing whether the ready endorsers form a quorum or a `CoordinatorError`.
+  fn is_candidate_quorum(
+    &self,
+    candidates: &[String],
+    ready: &EndorserHostnames,
+  ) -> Result<bool, CoordinatorError> {
+    if ready.is_empty() {
+      return Ok(false);
+    }
+
+    let mut view = ready.clone();
+    for uri in candidates {
+      if !view.iter().any(|(_pk, u)| u == uri) {
+        view.push((uri.as_bytes().to_vec(), uri.clone()));
+      }
+    }
+    let policy = self.view_policy(&view)?;
+    let pks = view
+      .iter()
+      .map(|(pk, _uri)| pk.clone())
+      .collect::<HashSet<Vec<u8>>>();
+    if policy.validate(&pks).is_err() {
+      return Ok(false);
+    }
+    let signers = ready
+      .iter()
+      .map(|(pk, _uri)| pk.clone())
+      .collect::<HashSet<Vec<u8>>>();
+    Ok(policy.is_satisfied(&pks, &signers))
+  }
+
+  /// Creates the view ledger block listing the given endorsers and the current quorum policy.
+  ///
+  /// # Arguments
+  ///
+  /// * `endorsers` - The endorsers of the new view.
+  ///
+  /// # Returns
+  ///
+  /// A result containing the view ledger block or a `CoordinatorError`.
+  fn create_view_config_block(
+    &self,
+    endorsers: &EndorserHostnames,
+  ) -> Result<Block, CoordinatorError> {
+    let policy = self.view_policy(endorsers)?;
+
+    let view_config = ViewConfig::new(endorsers.clone(), policy);
+    if let Err(e) = view_config.get_pks_and_policy() {
+      eprintln!("The quorum policy does not fit the endorsers ({:?})", e);
+      return Err(CoordinatorError::InvalidQuorumPolicy);
+    }
+
+    Ok(Block::new(&view_config.to_bytes()))
+  }
+
+  /// Records the time at which the view at the given height of the view ledger was created.
+  fn record_view_time(&self, height: usize) {
+    let now = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map(|d| d.as_secs())
+      .unwrap_or(0);
+    if let Ok(mut view_times) = self.view_times.write() {
+      view_times.insert(height, now);
+    }
   }
 
   /// Replaces the endorsers with the given hostnames.
@@ -1829,15 +2533,7 @@ impl CoordinatorState {
     // and old_endorsers should contain the currently active quorum
 
     // Package the list of endorsers into a genesis block of the view ledger
-    let view_ledger_genesis_block = {
-      let res = bincode::serialize(&new_endorsers);
-      if res.is_err() {
-        eprintln!("Failed to serialize endorser hostnames {:?}", res);
-        return Err(CoordinatorError::FailedToSerde);
-      }
-      let block_vec = res.unwrap();
-      Block::new(&block_vec)
-    };
+    let view_ledger_genesis_block = self.create_view_config_block(&new_endorsers)?;
     println!("created view ledger genesis block");
     // Read the current ledger tail
     let res = self.ledger_store.read_view_ledger_tail().await;
@@ -1866,6 +2562,7 @@ impl CoordinatorState {
     }
     println!("appended view ledger genesis block");
     let view_ledger_height = res.unwrap();
+    self.record_view_time(view_ledger_height);
 
     self
       .apply_view_change(
@@ -1874,36 +2571,475 @@ impl CoordinatorState {
         &tail,
         &view_ledger_genesis_block,
         view_ledger_height,
+        false,
       )
       .await
   }
 
-  /// Applies the view change to the verifier state.
+  /// Returns the subset of the given endorsers that report the uninitialized mode.
   ///
   /// # Arguments
   ///
-  /// * `existing_endorsers` - The existing endorsers.
-  /// * `new_endorsers` - The new endorsers.
-  /// * `view_ledger_entry` - The view ledger entry.
-  /// * `view_ledger_genesis_block` - The genesis block of the view ledger.
-  /// * `view_ledger_height` - The height of the view ledger.
+  /// * `endorsers` - The endorsers to probe.
   ///
   /// # Returns
   ///
-  /// A result indicating success or a `CoordinatorError`.
-  async fn apply_view_change(
-    &self,
-    existing_endorsers: &EndorserHostnames,
-    new_endorsers: &EndorserHostnames,
-    view_ledger_entry: &LedgerEntry,
-    view_ledger_genesis_block: &Block,
-    view_ledger_height: usize,
-  ) -> Result<(), CoordinatorError> {
-    // Retrieve the view tail metablock
-    let view_tail_receipts = view_ledger_entry.get_receipts();
-    let view_tail_metablock = if view_tail_receipts.is_empty() {
-      if view_ledger_height != 1 {
-        eprintln!(
+  /// The endorsers that are ready to be initialized.
+  async fn endorser_probe_uninitialized(&self, endorsers: &EndorserHostnames) -> EndorserHostnames {
+    let (mpsc_tx, mut mpsc_rx) = mpsc::channel(ENDORSER_MPSC_CHANNEL_BUFFER);
+    for (pk, _uri) in endorsers {
+      let (mut endorser_client, endorser) = match self.get_endorser_client(pk) {
+        Some((client, endorser)) => (client, endorser),
+        None => continue,
+      };
+
+      let tx = mpsc_tx.clone();
+      let pk_bytes = pk.clone();
+      let _job = tokio::spawn(async move {
+        let res =
+          read_state_with_retry(&mut endorser_client, endorser_proto::ReadStateReq {}).await;
+        let _ = tx.send((endorser, pk_bytes, res)).await;
+      });
+    }
+
+    drop(mpsc_tx);
+
+    let mut ready = EndorserHostnames::new();
+    while let Some((endorser, pk_bytes, res)) = mpsc_rx.recv().await {
+      match res {
+        Ok(resp) => {
+          let endorser_proto::ReadStateResp { mode, .. } = resp.into_inner();
+          if mode == endorser_proto::EndorserMode::Uninitialized as i32 {
+            ready.push((pk_bytes, endorser));
+          } else {
+            eprintln!("endorser {} is not uninitialized (mode={})", endorser, mode);
+          }
+        },
+        Err(status) => {
+          eprintln!(
+            "Failed to read the state of endorser {} (status={:?})",
+            endorser, status
+          );
+        },
+      }
+    }
+
+    ready
+  }
+
+  /// Rotates the active endorsers to a new set of endorsers in a single view change.
+  ///
+  /// The new endorsers are connected and probed first; if fewer than a quorum of them can be
+  /// initialized the rotation is rolled back before anything is appended to the view ledger or
+  /// the old endorsers are finalized, and `RotationRolledBack` is returned. If `InitializeState` still fails to reach a quorum during
+  /// the view change, the old endorsers are already finalized, so the rotation cannot be rolled
+  /// back: the new endorsers are disconnected, `FailedToInitializeEndorser` is returned, and the
+  /// incomplete view ledger entry is left for the recovery path in `CoordinatorState::new`.
+  ///
+  /// # Arguments
+  ///
+  /// * `hostnames` - The hostnames of the new endorsers.
+  ///
+  /// # Returns
+  ///
+  /// A result containing the `RotationReport` or a `CoordinatorError`.
+  pub async fn rotate_endorsers(
+    &self,
+    hostnames: &[String],
+  ) -> Result<RotationReport, CoordinatorError> {
+    let mut report = RotationReport::default();
+
+    let existing_endorsers = self.get_endorser_uris();
+    let mut requested: Vec<String> = hostnames.to_vec();
+    requested.retain(|x| !existing_endorsers.contains(x));
+    if requested.is_empty() {
+      eprintln!("No new endorsers to rotate to");
+      return Err(CoordinatorError::NoNewEndorsers);
+    }
+
+    let added_endorsers = self.connect_endorsers(&requested).await;
+    report.step(format!(
+      "connected to {} of {} new endorsers",
+      added_endorsers.len(),
+      requested.len()
+    ));
+
+    let mut new_endorsers = self.endorser_probe_uninitialized(&added_endorsers).await;
+    report.step(format!(
+      "{} new endorsers are ready to be initialized",
+      new_endorsers.len()
+    ));
+
+    if !self.is_candidate_quorum(&requested, &new_endorsers)? {
+      eprintln!(
+        "insufficient new endorsers: {} of {} do not form a quorum",
+        new_endorsers.len(),
+        requested.len()
+      );
+      self.disconnect_endorsers(&added_endorsers).await;
+      report.step("rolled back: disconnected the new endorsers".to_string());
+      return Err(CoordinatorError::RotationRolledBack {
+        steps: report.steps,
+      });
+    }
+
+    let not_ready = added_endorsers
+      .iter()
+      .filter(|e| !new_endorsers.contains(e))
+      .cloned()
+      .collect::<EndorserHostnames>();
+    self.disconnect_endorsers(&not_ready).await;
+    new_endorsers.truncate(DESIRED_QUORUM_SIZE.load(SeqCst).try_into().unwrap());
+
+    let old_endorsers: EndorserHostnames = if let Ok(conn_map_rd) = self.conn_map.read() {
+      conn_map_rd
+        .iter()
+        .filter(|(_pk, endorser)| matches!(endorser.usage_state, EndorserUsageState::Active))
+        .map(|(pk, endorser)| (pk.clone(), endorser.uri.clone()))
+        .collect()
+    } else {
+      eprintln!("Couldn't get read lock on conn_map");
+      return Err(CoordinatorError::FailedToAcquireReadLock);
+    };
+
+    let view_ledger_block = self.create_view_config_block(&new_endorsers)?;
+
+    let res = self.ledger_store.read_view_ledger_tail().await;
+    if res.is_err() {
+      eprintln!(
+        "Failed to read from the view ledger in the ledger store ({:?})",
+        res.unwrap_err()
+      );
+      return Err(CoordinatorError::FailedToCallLedgerStore);
+    }
+    let (tail, height) = res.unwrap();
+
+    let res = self
+      .ledger_store
+      .append_view_ledger(&view_ledger_block, height + 1)
+      .await;
+    if let Err(e) = res {
+      eprintln!(
+        "Failed to append to the view ledger in the ledger store ({:?})",
+        e,
+      );
+      return Err(CoordinatorError::FailedToCallLedgerStore);
+    }
+    let view_ledger_height = res.unwrap();
+    self.record_view_time(view_ledger_height);
+    report.step(format!(
+      "appended the new view at view ledger height {}",
+      view_ledger_height
+    ));
+
+    DEAD_ENDORSERS.store(0, SeqCst);
+
+    let res = self
+      .apply_view_change(
+        &old_endorsers,
+        &new_endorsers,
+        &tail,
+        &view_ledger_block,
+        view_ledger_height,
+        true,
+      )
+      .await;
+    match res {
+      Ok(()) => {
+        report.step(format!(
+          "finalized {} old endorsers and activated {} new endorsers",
+          old_endorsers.len(),
+          new_endorsers.len()
+        ));
+        report.added = new_endorsers;
+        report.retired = old_endorsers;
+        Ok(report)
+      },
+      Err(CoordinatorError::FailedToInitializeEndorser) => {
+        self.disconnect_endorsers(&new_endorsers).await;
+        eprintln!(
+          "initialize_state did not reach a quorum after the old endorsers were finalized; \
+           the view ledger entry at height {} is left for recovery",
+          view_ledger_height
+        );
+        Err(CoordinatorError::FailedToInitializeEndorser)
+      },
+      Err(e) => Err(e),
+    }
+  }
+
+  /// Connects to an endorser without adding it to the connection map and reads its mode.
+  ///
+  /// # Arguments
+  ///
+  /// * `hostname` - The hostname of the endorser.
+  ///
+  /// # Returns
+  ///
+  /// A result containing the public key and mode of the endorser or a `CoordinatorError`.
+  async fn probe_endorser_hostname(
+    &self,
+    hostname: &str,
+  ) -> Result<(Vec<u8>, i32), CoordinatorError> {
+    let res = Endpoint::from_shared(hostname.to_string());
+    if res.is_err() {
+      eprintln!("Failed to resolve the endorser host name: {:?}", res);
+      return Err(CoordinatorError::CannotResolveHostName);
+    }
+    let endorser_endpoint = res
+      .unwrap()
+      .connect_timeout(std::time::Duration::from_secs(ENDORSER_CONNECT_TIMEOUT))
+      .timeout(std::time::Duration::from_secs(
+        ENDORSER_REQUEST_TIMEOUT.load(SeqCst),
+      ));
+    let res = endorser_endpoint.connect().await;
+    if res.is_err() {
+      eprintln!("Failed to connect to the endorser {}: {:?}", hostname, res);
+      return Err(CoordinatorError::FailedToConnectToEndorser);
+    }
+    let mut client = endorser_proto::endorser_call_client::EndorserCallClient::new(res.unwrap());
+
+    let res = get_public_key_with_retry(&mut client, endorser_proto::GetPublicKeyReq {}).await;
+    if res.is_err() {
+      eprintln!("Failed to retrieve the public key: {:?}", res);
+      return Err(CoordinatorError::UnableToRetrievePublicKey);
+    }
+    let endorser_proto::GetPublicKeyResp { pk } = res.unwrap().into_inner();
+    if PublicKey::from_bytes(&pk).is_err() {
+      eprintln!("Public key is invalid from endorser {:?}", hostname);
+      return Err(CoordinatorError::UnableToRetrievePublicKey);
+    }
+
+    let res = read_state_with_retry(&mut client, endorser_proto::ReadStateReq {}).await;
+    if res.is_err() {
+      eprintln!(
+        "Failed to read the state of endorser {}: {:?}",
+        hostname, res
+      );
+      return Err(CoordinatorError::FailedToReadLatestState);
+    }
+    let endorser_proto::ReadStateResp { mode, .. } = res.unwrap().into_inner();
+
+    Ok((pk, mode))
+  }
+
+  /// Reads the ledger tail maps of the given endorsers without finalizing them.
+  ///
+  /// # Arguments
+  ///
+  /// * `endorsers` - The endorsers to read.
+  ///
+  /// # Returns
+  ///
+  /// A vector of the endorsers that responded along with their view receipts and ledger tail maps.
+  async fn endorser_read_state(&self, endorsers: &EndorserHostnames) -> EndorserStates {
+    let (mpsc_tx, mut mpsc_rx) = mpsc::channel(ENDORSER_MPSC_CHANNEL_BUFFER);
+    for (pk, _uri) in endorsers {
+      let (mut endorser_client, endorser) = match self.get_endorser_client(pk) {
+        Some((client, endorser)) => (client, endorser),
+        None => continue,
+      };
+
+      let tx = mpsc_tx.clone();
+      let _job = tokio::spawn(async move {
+        let res =
+          read_state_with_retry(&mut endorser_client, endorser_proto::ReadStateReq {}).await;
+        let _ = tx.send((endorser, res)).await;
+      });
+    }
+
+    drop(mpsc_tx);
+
+    let mut states = Vec::new();
+    while let Some((endorser, res)) = mpsc_rx.recv().await {
+      match res {
+        Ok(resp) => {
+          let endorser_proto::ReadStateResp {
+            receipt,
+            ledger_tail_map,
+            ..
+          } = resp.into_inner();
+          match Receipt::from_bytes(&receipt) {
+            Ok(receipt_rs) => states.push((endorser, receipt_rs, ledger_tail_map)),
+            Err(error) => eprintln!("Failed to parse a receipt ({:?})", error),
+          }
+        },
+        Err(status) => {
+          eprintln!(
+            "Failed to read the state of endorser {} (status={:?})",
+            endorser, status
+          );
+        },
+      }
+    }
+
+    states
+  }
+
+  /// Computes what `replace_endorsers` would do with the given hostnames, without mutating
+  /// the coordinator, the endorsers, or the ledger store.
+  ///
+  /// # Arguments
+  ///
+  /// * `hostnames` - The hostnames of the new endorsers.
+  ///
+  /// # Returns
+  ///
+  /// A result containing the `ViewChangePlan` or a `CoordinatorError`.
+  pub async fn plan_view_change(
+    &self,
+    hostnames: &[String],
+  ) -> Result<ViewChangePlan, CoordinatorError> {
+    let existing_endorsers = self.get_endorser_uris();
+
+    let mut connected_candidates: EndorserHostnames;
+    let to_finalize: EndorserHostnames;
+    if let Ok(conn_map_rd) = self.conn_map.read() {
+      connected_candidates = conn_map_rd
+        .iter()
+        .filter(|(_pk, endorser)| {
+          matches!(endorser.usage_state, EndorserUsageState::Uninitialized)
+            && endorser.failures == 0
+        })
+        .map(|(pk, endorser)| (pk.clone(), endorser.uri.clone()))
+        .collect();
+
+      to_finalize = conn_map_rd
+        .iter()
+        .filter(|(_pk, endorser)| matches!(endorser.usage_state, EndorserUsageState::Active))
+        .map(|(pk, endorser)| (pk.clone(), endorser.uri.clone()))
+        .collect();
+    } else {
+      eprintln!("Couldn't get read lock on conn_map");
+      return Err(CoordinatorError::FailedToAcquireReadLock);
+    }
+
+    // Connected candidates must still be uninitialized; others are probed on the side
+    let mut candidates = connected_candidates
+      .iter()
+      .map(|(_pk, uri)| uri.clone())
+      .collect::<Vec<String>>();
+    let mut unreachable = Vec::new();
+    let ready = self
+      .endorser_probe_uninitialized(&connected_candidates)
+      .await;
+    connected_candidates.retain(|e| {
+      if ready.contains(e) {
+        true
+      } else {
+        unreachable.push(e.1.clone());
+        false
+      }
+    });
+    let mut to_initialize = connected_candidates;
+    for hostname in hostnames {
+      if existing_endorsers.contains(hostname) {
+        continue;
+      }
+      candidates.push(hostname.clone());
+      match self.probe_endorser_hostname(hostname).await {
+        Ok((pk, mode)) if mode == endorser_proto::EndorserMode::Uninitialized as i32 => {
+          if !to_initialize.iter().any(|(p, _uri)| *p == pk) {
+            to_initialize.push((pk, hostname.clone()));
+          }
+        },
+        _ => unreachable.push(hostname.clone()),
+      }
+    }
+    let initialize_quorum = self.is_candidate_quorum(&candidates, &to_initialize)?;
+    to_initialize.truncate(DESIRED_QUORUM_SIZE.load(SeqCst).try_into().unwrap());
+
+    // Read the state that finalization would return, deduplicated by view as in finalization
+    let states = self.endorser_read_state(&to_finalize).await;
+    let finalize_quorum = to_finalize.is_empty() || {
+      let responded = states
+        .iter()
+        .filter_map(|(endorser, _receipt, _ledger_tail_map)| {
+          to_finalize
+            .iter()
+            .find(|(_pk, uri)| uri == endorser)
+            .map(|(pk, _uri)| pk.clone())
+        })
+        .collect::<HashSet<Vec<u8>>>();
+      match self.verifier_state.read() {
+        Ok(vs) => match vs.get_latest_view() {
+          Some((pks, policy)) => policy.is_satisfied(pks, &responded),
+          None => false,
+        },
+        Err(_) => return Err(CoordinatorError::FailedToAcquireReadLock),
+      }
+    };
+    let mut ledger_tail_maps = Vec::new();
+    let mut state_hashes = HashSet::new();
+    for (_endorser, receipt, ledger_tail_map) in &states {
+      if !state_hashes.contains(receipt.get_view()) {
+        ledger_tail_maps.push(endorser_proto::LedgerTailMap {
+          entries: ledger_tail_map.clone(),
+        });
+        state_hashes.insert(*receipt.get_view());
+      }
+    }
+
+    let max_cut = compute_max_cut(&ledger_tail_maps);
+    let cut_diffs = compute_cut_diffs(&ledger_tail_maps);
+
+    // Finalized endorsers lag on every ledger where their tail is below the max cut
+    let mut lagging = Vec::new();
+    for (endorser, _receipt, ledger_tail_map) in &states {
+      let heights = ledger_tail_map
+        .iter()
+        .map(|entry| (entry.handle.clone(), entry.height))
+        .collect::<HashMap<Vec<u8>, u64>>();
+      let num_lagging = max_cut
+        .iter()
+        .filter(|entry| match heights.get(&entry.handle) {
+          Some(height) => *height < entry.height,
+          None => true,
+        })
+        .count();
+      lagging.push((endorser.clone(), num_lagging));
+    }
+
+    Ok(ViewChangePlan {
+      to_finalize,
+      to_initialize,
+      unreachable,
+      max_cut,
+      cut_diffs,
+      lagging,
+      finalize_quorum,
+      initialize_quorum,
+    })
+  }
+
+  /// Applies the view change to the verifier state.
+  ///
+  /// # Arguments
+  ///
+  /// * `existing_endorsers` - The existing endorsers.
+  /// * `new_endorsers` - The new endorsers.
+  /// * `view_ledger_entry` - The view ledger entry.
+  /// * `view_ledger_genesis_block` - The genesis block of the view ledger.
+  /// * `view_ledger_height` - The height of the view ledger.
+  /// * `require_initialized_quorum` - Whether to stop before activation without an initialized quorum.
+  ///
+  /// # Returns
+  ///
+  /// A result indicating success or a `CoordinatorError`.
+  async fn apply_view_change(
+    &self,
+    existing_endorsers: &EndorserHostnames,
+    new_endorsers: &EndorserHostnames,
+    view_ledger_entry: &LedgerEntry,
+    view_ledger_genesis_block: &Block,
+    view_ledger_height: usize,
+    require_initialized_quorum: bool,
+  ) -> Result<(), CoordinatorError> {
+    // Retrieve the view tail metablock
+    let view_tail_receipts = view_ledger_entry.get_receipts();
+    let view_tail_metablock = if view_tail_receipts.is_empty() {
+      if view_ledger_height != 1 {
+        eprintln!(
           "cannot get view tail metablock from empty receipts (height = {}",
           view_ledger_height
         );
@@ -1982,6 +3118,28 @@ impl CoordinatorState {
       return Err(CoordinatorError::FailedToCallLedgerStore);
     }
 
+    // Stop before activation if the new endorsers could not be initialized
+    if require_initialized_quorum {
+      let initialized_endorsers = initialize_receipts
+        .get()
+        .values()
+        .flatten()
+        .map(|id_sig| id_sig.get_id().clone())
+        .collect::<HashSet<Vec<u8>>>();
+      if !is_view_quorum(view_ledger_genesis_block, &initialized_endorsers) {
+        eprintln!(
+          "insufficient initialized endorsers: {} of {} do not form a quorum",
+          initialized_endorsers.len(),
+          new_endorsers.len()
+        );
+        return Err(CoordinatorError::FailedToInitializeEndorser);
+      }
+    }
+
+    // Ledgers whose tail is a tombstone are left out of the max cut, so they can be removed from
+    // the ledger store once the new endorsers are active
+    let deleted_ledgers = compute_deleted_ledgers(&ledger_tail_maps);
+
     // Retrieve blocks that need for verifying the view change
     let cut_diffs = compute_cut_diffs(&ledger_tail_maps);
     let mut ledger_chunks: Vec<endorser_proto::LedgerChunkEntry> = Vec::new();
@@ -2016,7 +3174,7 @@ impl CoordinatorState {
       });
     }
 
-    let num_verified_endorsers = self
+    let verified_endorsers = self
       .endorser_verify_view_change(
         new_endorsers,
         view_ledger_entry.get_block().clone(),
@@ -2028,10 +3186,10 @@ impl CoordinatorState {
       .await;
     // TODO: Change this line? Would allow to use a smaller quorum if not enough eligible endorsers
     // are available
-    if num_verified_endorsers * 2 <= new_endorsers.len() {
+    if !is_view_quorum(view_ledger_genesis_block, &verified_endorsers) {
       eprintln!(
-        "insufficient verified endorsers {} * 2 <= {}",
-        num_verified_endorsers,
+        "insufficient verified endorsers: {} of {} do not form a quorum",
+        verified_endorsers.len(),
         new_endorsers.len()
       );
     }
@@ -2052,6 +3210,20 @@ impl CoordinatorState {
     // Disconnect existing endorsers
     self.disconnect_endorsers(existing_endorsers).await;
 
+    // Garbage-collect the entries of the deleted ledgers
+    for handle in &deleted_ledgers {
+      if let Err(e) = self.ledger_store.delete_ledger(handle).await {
+        eprintln!(
+          "Failed to delete ledger {:?} from the ledger store ({:?})",
+          handle, e
+        );
+        continue;
+      }
+      if let Ok(mut quarantined) = self.quarantined.write() {
+        quarantined.remove(handle);
+      }
+    }
+
     Ok(())
   }
 
@@ -2081,6 +3253,10 @@ impl CoordinatorState {
     let handle = NimbleDigest::digest(handle_bytes);
     let genesis_block = Block::new(block_bytes);
 
+    if is_tombstone(&handle, &genesis_block) {
+      return Err(CoordinatorError::ReservedBlock);
+    }
+
     let hash_block = genesis_block.hash();
     let hash_nonces = Nonces::new().hash();
     let block_hash = compute_aggregated_block_hash(&hash_block.to_bytes(), &hash_nonces.to_bytes());
@@ -2089,6 +3265,9 @@ impl CoordinatorState {
       .ledger_store
       .create_ledger(&handle, genesis_block.clone())
       .await;
+    if let Err(LedgerStoreError::LedgerError(StorageError::DuplicateKey)) = res {
+      return Err(CoordinatorError::LedgerAlreadyExists);
+    }
     if res.is_err() {
       eprintln!(
         "Failed to create ledger in the ledger store ({:?})",
@@ -2098,6 +3277,7 @@ impl CoordinatorState {
     }
 
     // Make a request to the endorsers for NewLedger using the handle which returns a signature.
+    let needs_quorum = endorsers_opt.is_none();
     let receipts = {
       let endorsers = match endorsers_opt {
         Some(ref endorsers) => endorsers.clone(),
@@ -2126,6 +3306,15 @@ impl CoordinatorState {
       return Err(CoordinatorError::FailedToAttachReceipt);
     }
 
+    // the ledger is stored and the endorsers that signed keep it, but a client cannot verify it
+    // without a quorum, and creating it again fails because it exists
+    if needs_quorum && !self.has_quorum(&receipts) {
+      eprintln!("Failed to obtain a quorum to create ledger {:?}", handle);
+      return Err(CoordinatorError::StoredWithoutQuorum);
+    }
+
+    self.notify_append(handle, 0);
+
     Ok(receipts)
   }
 
@@ -2155,25 +3344,192 @@ impl CoordinatorState {
     let handle = NimbleDigest::digest(handle_bytes);
     let data_block = Block::new(block_bytes);
 
-    let res = self
+    if is_tombstone(&handle, &data_block) {
+      return Err(CoordinatorError::ReservedBlock);
+    }
+
+    self
+      .append_ledger_block(endorsers_opt, handle, data_block, expected_height)
+      .await
+  }
+
+  /// Deletes the ledger with the given handle by appending its tombstone, after which the
+  /// endorsers reject appends. The ledger is dropped from the endorsers at the next view change,
+  /// which also removes its entries from the ledger store.
+  ///
+  /// # Arguments
+  ///
+  /// * `endorsers_opt` - An optional vector of endorsers.
+  /// * `handle_bytes` - The handle of the ledger.
+  /// * `expected_height` - The height at which the tombstone is appended.
+  ///
+  /// # Returns
+  ///
+  /// A result containing the hash of the nonces and the receipts or a `CoordinatorError`.
+  pub async fn delete_ledger(
+    &self,
+    endorsers_opt: Option<Vec<Vec<u8>>>,
+    handle_bytes: &[u8],
+    expected_height: usize,
+  ) -> Result<(NimbleDigest, Receipts), CoordinatorError> {
+    if expected_height == 0 {
+      return Err(CoordinatorError::InvalidHeight);
+    }
+
+    let handle = NimbleDigest::digest(handle_bytes);
+    self
+      .append_ledger_block(
+        endorsers_opt,
+        handle,
+        tombstone_block(&handle),
+        expected_height,
+      )
+      .await
+  }
+
+  /// Prunes the entries of the ledger with the given handle below `height`. The entry at
+  /// `height` becomes the checkpoint: its receipts endorse the metablock from which a client
+  /// can verify the entries that remain, and reads of pruned entries return it instead.
+  ///
+  /// # Arguments
+  ///
+  /// * `handle_bytes` - The handle of the ledger.
+  /// * `height` - The height of the entry to keep as the checkpoint.
+  ///
+  /// # Returns
+  ///
+  /// A result containing the receipts of the checkpoint or a `CoordinatorError`.
+  pub async fn checkpoint_ledger(
+    &self,
+    handle_bytes: &[u8],
+    height: usize,
+  ) -> Result<Receipts, CoordinatorError> {
+    let handle = NimbleDigest::digest(handle_bytes);
+    if self.is_quarantined(&handle) {
+      eprintln!("Ledger {:?} is quarantined and read-only", handle);
+      return Err(CoordinatorError::LedgerQuarantined);
+    }
+
+    // 1. The checkpoint must carry the receipts of a quorum for the metablock at its height
+    let entry = match self
       .ledger_store
-      .append_ledger(&handle, &data_block, expected_height)
-      .await;
-    if res.is_err() {
+      .read_ledger_by_index(&handle, height)
+      .await
+    {
+      Ok(entry) => entry,
+      Err(LedgerStoreError::Pruned(checkpoint)) => {
+        eprintln!(
+          "Ledger {:?} is already pruned below {}",
+          handle,
+          checkpoint.get_height()
+        );
+        return Err(CoordinatorError::LedgerPruned);
+      },
+      Err(LedgerStoreError::LedgerError(StorageError::InvalidIndex)) => {
+        return Err(CoordinatorError::InvalidHeight);
+      },
+      Err(error) => {
+        eprintln!("Failed to read the entry to checkpoint at ({:?})", error);
+        return Err(CoordinatorError::FailedToReadLedger);
+      },
+    };
+    let block_hash = compute_aggregated_block_hash(
+      &entry.get_block().hash().to_bytes(),
+      &entry.get_nonces().hash().to_bytes(),
+    );
+    let endorsed = if let Ok(vs) = self.verifier_state.read() {
+      entry
+        .get_receipts()
+        .get_endorsed_metablocks(&vs, &handle)
+        .iter()
+        .any(|m| m.get_height() == height && *m.get_block_hash() == block_hash)
+    } else {
+      false
+    };
+    if !endorsed {
       eprintln!(
-        "Failed to append to the ledger in the ledger store {:?}",
-        res.unwrap_err()
+        "The entry at {} of ledger {:?} is not endorsed by a quorum",
+        height, handle
       );
-      return Err(CoordinatorError::FailedToAppendLedger);
+      return Err(CoordinatorError::InvalidCheckpoint);
+    }
+
+    // 2. Every endorser must be at or past the checkpoint, since lagging endorsers and view
+    // changes are brought up to date from the entries in the store
+    let endorsers = self.get_endorser_hostnames();
+    let states = self.endorser_read_state(&endorsers).await;
+    let caught_up = states
+      .iter()
+      .filter(|(_endorser, _receipt, ledger_tail_map)| {
+        ledger_tail_map
+          .iter()
+          .any(|e| e.handle == handle.to_bytes() && e.height as usize >= height)
+      })
+      .count();
+    if caught_up < endorsers.len() {
+      eprintln!(
+        "Only {} of {} endorsers are at height {} of ledger {:?}",
+        caught_up,
+        endorsers.len(),
+        height,
+        handle
+      );
+      return Err(CoordinatorError::EndorsersBehindCheckpoint);
+    }
+
+    // 3. Drop the entries below the checkpoint
+    match self.ledger_store.prune_ledger(&handle, height).await {
+      Ok(checkpoint) => Ok(checkpoint.get_receipts().clone()),
+      Err(error) => {
+        eprintln!(
+          "Failed to prune the ledger in the ledger store ({:?})",
+          error
+        );
+        Err(CoordinatorError::FailedToCallLedgerStore)
+      },
     }
+  }
 
-    let (actual_height, nonces) = res.unwrap();
+  async fn append_ledger_block(
+    &self,
+    endorsers_opt: Option<Vec<Vec<u8>>>,
+    handle: Handle,
+    data_block: Block,
+    expected_height: usize,
+  ) -> Result<(NimbleDigest, Receipts), CoordinatorError> {
+    if self.is_quarantined(&handle) {
+      eprintln!("Ledger {:?} is quarantined and read-only", handle);
+      return Err(CoordinatorError::LedgerQuarantined);
+    }
+
+    let res = self
+      .ledger_store
+      .append_ledger(&handle, &data_block, expected_height)
+      .await;
+    let (actual_height, nonces) = match res {
+      Ok(v) => v,
+      Err(LedgerStoreError::LedgerError(StorageError::IncorrectConditionalData)) => {
+        return Err(CoordinatorError::InvalidHeight);
+      },
+      Err(LedgerStoreError::LedgerError(StorageError::KeyDoesNotExist))
+      | Err(LedgerStoreError::LedgerError(StorageError::InvalidKey)) => {
+        return Err(CoordinatorError::LedgerNotFound);
+      },
+      Err(error) => {
+        eprintln!(
+          "Failed to append to the ledger in the ledger store {:?}",
+          error
+        );
+        return Err(CoordinatorError::FailedToAppendLedger);
+      },
+    };
     assert!(actual_height == expected_height);
 
     let hash_block = data_block.hash();
     let hash_nonces = nonces.hash();
     let block_hash = compute_aggregated_block_hash(&hash_block.to_bytes(), &hash_nonces.to_bytes());
 
+    let needs_quorum = endorsers_opt.is_none();
     let receipts = {
       let endorsers = match endorsers_opt {
         Some(endorsers) => endorsers,
@@ -2208,9 +3564,35 @@ impl CoordinatorState {
       return Err(CoordinatorError::FailedToAttachReceipt);
     }
 
+    // the entry is stored, so appending it again fails with a height mismatch even though a
+    // client cannot verify it without a quorum
+    if needs_quorum && !self.has_quorum(&receipts) {
+      eprintln!(
+        "Failed to obtain a quorum to append to ledger {:?} at {}",
+        handle, expected_height
+      );
+      return Err(CoordinatorError::StoredWithoutQuorum);
+    }
+
+    self.notify_append(handle, expected_height);
+
     Ok((hash_nonces, receipts))
   }
 
+  /// Tells the watchers of a ledger that the entry at `height` is endorsed by a quorum.
+  fn notify_append(&self, handle: Handle, height: usize) {
+    // sending only fails if nobody is watching
+    let _ = self.appends.send((handle, height));
+  }
+
+  /// Subscribes to the appends to every ledger. Each message is the handle of a ledger and the
+  /// height of an entry that was just endorsed by a quorum, including the genesis entry at 0.
+  /// A receiver that falls behind by more than `APPENDS_CHANNEL_BUFFER` messages is told how
+  /// many it missed, and should read the ledgers it watches from the store.
+  pub fn subscribe_appends(&self) -> broadcast::Receiver<(Handle, usize)> {
+    self.appends.subscribe()
+  }
+
   async fn read_ledger_tail_internal(
     &self,
     handle: &NimbleDigest,
@@ -2269,67 +3651,168 @@ impl CoordinatorState {
     let mut nonce_attached_height = 0;
 
     loop {
-      match self.read_ledger_tail_internal(&handle, &nonce).await {
-        Ok(ledger_entry) => return Ok(ledger_entry),
-        Err(error) => match error {
-          CoordinatorError::FailedToObtainQuorum => {
-            if !nonce_attached {
-              let res = self.ledger_store.attach_ledger_nonce(&handle, &nonce).await;
-              if res.is_err() {
-                eprintln!(
-                  "Failed to attach the nonce for reading ledger tail {:?}",
-                  res.unwrap_err()
-                );
-                return Err(CoordinatorError::FailedToAttachNonce);
-              }
-              nonce_attached = true;
-              nonce_attached_height = res.unwrap();
-            }
-            match self
-              .read_ledger_by_index_internal(&handle, nonce_attached_height)
-              .await
-            {
-              Ok(ledger_entry) => return Ok(ledger_entry),
-              Err(error) => match error {
-                CoordinatorError::FailedToObtainQuorum | CoordinatorError::InvalidHeight => {
-                  continue;
-                },
-                _ => {
-                  return Err(error);
-                },
-              },
-            }
-          },
-          _ => {
-            return Err(error);
-          },
+      match self.read_ledger_tail_internal(&handle, &nonce).await {
+        Ok(ledger_entry) => return Ok(ledger_entry),
+        Err(error) => match error {
+          CoordinatorError::FailedToObtainQuorum => {
+            if !nonce_attached {
+              let res = self.ledger_store.attach_ledger_nonce(&handle, &nonce).await;
+              if let Err(LedgerStoreError::LedgerError(StorageError::KeyDoesNotExist))
+              | Err(LedgerStoreError::LedgerError(StorageError::InvalidKey)) = res
+              {
+                return Err(CoordinatorError::LedgerNotFound);
+              }
+              if res.is_err() {
+                eprintln!(
+                  "Failed to attach the nonce for reading ledger tail {:?}",
+                  res.unwrap_err()
+                );
+                return Err(CoordinatorError::FailedToAttachNonce);
+              }
+              nonce_attached = true;
+              nonce_attached_height = res.unwrap();
+            }
+            match self
+              .read_ledger_by_index_internal(&handle, nonce_attached_height)
+              .await
+            {
+              Ok(ledger_entry) => return Ok(ledger_entry),
+              Err(error) => match error {
+                CoordinatorError::FailedToObtainQuorum | CoordinatorError::InvalidHeight => {
+                  continue;
+                },
+                _ => {
+                  return Err(error);
+                },
+              },
+            }
+          },
+          _ => {
+            return Err(error);
+          },
+        },
+      }
+    }
+  }
+
+  /// Reads a block from the ledger by index.
+  ///
+  /// # Arguments
+  ///
+  /// * `handle_bytes` - The handle of the ledger.
+  /// * `index` - The index of the block to read.
+  ///
+  /// # Returns
+  ///
+  /// A result containing the ledger entry or a `CoordinatorError`.
+  pub async fn read_ledger_by_index(
+    &self,
+    handle_bytes: &[u8],
+    index: usize,
+  ) -> Result<LedgerEntry, CoordinatorError> {
+    let handle = NimbleDigest::digest(handle_bytes);
+
+    match self.ledger_store.read_ledger_by_index(&handle, index).await {
+      Ok(ledger_entry) => Ok(ledger_entry),
+      Err(LedgerStoreError::Pruned(_checkpoint)) => Err(CoordinatorError::LedgerPruned),
+      Err(LedgerStoreError::LedgerError(StorageError::InvalidIndex)) => {
+        Err(CoordinatorError::InvalidHeight)
+      },
+      Err(LedgerStoreError::LedgerError(StorageError::KeyDoesNotExist))
+      | Err(LedgerStoreError::LedgerError(StorageError::InvalidKey)) => {
+        Err(CoordinatorError::LedgerNotFound)
+      },
+      Err(error) => {
+        eprintln!(
+          "Failed to read ledger by index from the ledger store {:?}",
+          error,
+        );
+        Err(CoordinatorError::FailedToReadLedger)
+      },
+    }
+  }
+
+  /// Reads the height of the tail of the ledger with the given handle from the ledger store.
+  ///
+  /// # Arguments
+  ///
+  /// * `handle_bytes` - The handle of the ledger.
+  ///
+  /// # Returns
+  ///
+  /// A result containing the height or a `CoordinatorError`.
+  pub async fn read_ledger_height(&self, handle_bytes: &[u8]) -> Result<usize, CoordinatorError> {
+    let handle = NimbleDigest::digest(handle_bytes);
+
+    match self.ledger_store.read_ledger_tail(&handle).await {
+      Ok((_entry, height)) => Ok(height),
+      Err(LedgerStoreError::LedgerError(StorageError::KeyDoesNotExist))
+      | Err(LedgerStoreError::LedgerError(StorageError::InvalidKey)) => {
+        Err(CoordinatorError::LedgerNotFound)
+      },
+      Err(error) => {
+        eprintln!(
+          "Failed to read the ledger tail from the ledger store {:?}",
+          error,
+        );
+        Err(CoordinatorError::FailedToReadLedger)
+      },
+    }
+  }
+
+  /// Reads the first entry at or after `index` of the ledger with the given handle that is
+  /// endorsed by a quorum. An append that is still in flight is stored before the endorsers sign
+  /// it, so watchers use this to stop at the last entry a client can verify. An entry that was
+  /// stored without a quorum is never endorsed, and is skipped once a later entry is.
+  ///
+  /// # Arguments
+  ///
+  /// * `handle_bytes` - The handle of the ledger.
+  /// * `index` - The index of the first block to read.
+  ///
+  /// # Returns
+  ///
+  /// A result containing the index and the ledger entry, or `None` if there is no endorsed
+  /// entry at or after the index yet, or a `CoordinatorError`.
+  pub async fn read_endorsed_ledger_entry(
+    &self,
+    handle_bytes: &[u8],
+    index: usize,
+  ) -> Result<Option<(usize, LedgerEntry)>, CoordinatorError> {
+    let mut index = index;
+    loop {
+      match self.read_ledger_by_index(handle_bytes, index).await {
+        Ok(ledger_entry) if self.has_quorum(ledger_entry.get_receipts()) => {
+          return Ok(Some((index, ledger_entry)))
         },
+        Ok(_) => index += 1,
+        Err(CoordinatorError::InvalidHeight) => return Ok(None),
+        Err(error) => return Err(error),
       }
     }
   }
 
-  /// Reads a block from the ledger by index.
+  /// Reads the checkpoint of the ledger with the given handle.
   ///
   /// # Arguments
   ///
   /// * `handle_bytes` - The handle of the ledger.
-  /// * `index` - The index of the block to read.
   ///
   /// # Returns
   ///
-  /// A result containing the ledger entry or a `CoordinatorError`.
-  pub async fn read_ledger_by_index(
+  /// A result containing the checkpoint, or `None` if the ledger was never pruned, or a
+  /// `CoordinatorError`.
+  pub async fn read_ledger_checkpoint(
     &self,
     handle_bytes: &[u8],
-    index: usize,
-  ) -> Result<LedgerEntry, CoordinatorError> {
+  ) -> Result<Option<Checkpoint>, CoordinatorError> {
     let handle = NimbleDigest::digest(handle_bytes);
 
-    match self.ledger_store.read_ledger_by_index(&handle, index).await {
-      Ok(ledger_entry) => Ok(ledger_entry),
+    match self.ledger_store.read_ledger_checkpoint(&handle).await {
+      Ok(checkpoint) => Ok(checkpoint),
       Err(error) => {
         eprintln!(
-          "Failed to read ledger by index from the ledger store {:?}",
+          "Failed to read the checkpoint from the ledger store {:?}",
           error,
         );
         Err(CoordinatorError::FailedToReadLedger)
@@ -2377,6 +3860,79 @@ impl CoordinatorState {
     Ok((ledger_entry, height, ATTESTATION_STR.as_bytes().to_vec()))
   }
 
+  /// Walks the view ledger and describes each view.
+  ///
+  /// # Returns
+  ///
+  /// A result containing the views in the order of the view ledger or a `CoordinatorError`.
+  pub async fn inspect_view_ledger(&self) -> Result<Vec<ViewInfo>, CoordinatorError> {
+    let (_tail, tail_height, _attestations) = self.read_view_tail().await?;
+
+    let mut group_identity = NimbleDigest::default();
+    let mut views = Vec::new();
+    // the entry at index 0 is a placeholder; the views start at index 1
+    for height in 1..=tail_height {
+      let ledger_entry = self.read_view_by_index(height).await?;
+      if height == 1 {
+        group_identity = ledger_entry.get_block().hash();
+      }
+
+      let res = ViewConfig::from_bytes(&ledger_entry.get_block().to_bytes());
+      if res.is_err() {
+        eprintln!(
+          "Failed to parse the view ledger entry at index {} ({:?})",
+          height, res
+        );
+        return Err(CoordinatorError::FailedToReadViewLedger);
+      }
+      let view_config = res.unwrap();
+
+      let receipts = ledger_entry.get_receipts();
+      let view = if receipts.is_empty() {
+        None
+      } else {
+        receipts
+          .get_metablock()
+          .ok()
+          .map(|metablock| metablock.hash())
+      };
+      let signers = receipts.get_view_change_signers(&group_identity);
+      let quorum = match view_config.get_pks_and_policy() {
+        Ok((pks, policy)) => {
+          let own_signers = signers
+            .intersection(&pks)
+            .cloned()
+            .collect::<HashSet<Vec<u8>>>();
+          policy.is_satisfied(&pks, &own_signers)
+        },
+        Err(_) => false,
+      };
+
+      let mut signers = signers.into_iter().collect::<Vec<Vec<u8>>>();
+      signers.sort();
+      views.push(ViewInfo {
+        height,
+        view,
+        endorsers: view_config.endorsers,
+        policy: view_config.policy,
+        created_at: if view_config.created_at != 0 {
+          view_config.created_at
+        } else {
+          self
+            .view_times
+            .read()
+            .ok()
+            .and_then(|view_times| view_times.get(&height).cloned())
+            .unwrap_or(0)
+        },
+        signers,
+        quorum,
+      });
+    }
+
+    Ok(views)
+  }
+
   /// Pings all endorsers.
   pub async fn ping_all_endorsers(self: Arc<Self>) {
     println!("Pinging all endorsers from coordinator_state");
@@ -2628,6 +4184,51 @@ impl CoordinatorState {
     }
   }
 
+  /// Returns true if the active endorsers that have not failed too many pings in a row form a
+  /// quorum of the latest view.
+  pub fn has_quorum_available(&self) -> bool {
+    let alive = if let Ok(conn_map_rd) = self.conn_map.read() {
+      conn_map_rd
+        .iter()
+        .filter(|(_pk, endorser)| {
+          matches!(endorser.usage_state, EndorserUsageState::Active)
+            && endorser.failures <= MAX_FAILURES.load(SeqCst)
+        })
+        .map(|(pk, _endorser)| pk.clone())
+        .collect::<HashSet<Vec<u8>>>()
+    } else {
+      eprintln!("Failed to acquire read lock on conn_map");
+      return false;
+    };
+
+    match self.verifier_state.read() {
+      Ok(vs) => match vs.get_latest_view() {
+        Some((pks, policy)) => policy.is_satisfied(pks, &alive),
+        None => false,
+      },
+      Err(_) => false,
+    }
+  }
+
+  /// Returns true if the ledger store answers a read of the view ledger in time.
+  pub async fn is_store_connected(&self) -> bool {
+    let read = self.ledger_store.read_view_ledger_tail();
+    match tokio::time::timeout(Duration::from_secs(STORE_HEALTH_TIMEOUT), read).await {
+      Ok(Ok(_)) => true,
+      Ok(Err(e)) => {
+        eprintln!(
+          "Failed to read the view ledger tail for a health check {:?}",
+          e
+        );
+        false
+      },
+      Err(_) => {
+        eprintln!("The ledger store did not answer a health check in time");
+        false
+      },
+    }
+  }
+
   /// Gets the timeout map for the endorsers.
   ///
   /// # Returns
@@ -2675,8 +4276,668 @@ impl CoordinatorState {
   }
 }
 
+fn report_rollback_findings(findings: &[RollbackFinding]) {
+  for finding in findings {
+    match finding {
+      RollbackFinding::MissingViews {
+        store_height,
+        endorser_height,
+      } => eprintln!(
+        "  the view ledger lacks the views at heights {}..={}",
+        store_height + 1,
+        endorser_height
+      ),
+      RollbackFinding::Ledger(mismatch) => {
+        let first = mismatch.store_height.map(|h| h + 1).unwrap_or(0);
+        let lost = if mismatch.recovered {
+          mismatch.endorser_height.saturating_sub(1)
+        } else {
+          mismatch.endorser_height
+        };
+        if mismatch.diverged {
+          eprintln!(
+            "  ledger {:?} holds a different block than the endorsers at height {}",
+            mismatch.handle, mismatch.endorser_height
+          );
+        } else if first <= lost {
+          eprintln!(
+            "  ledger {:?} lost the entries at heights {}..={}",
+            mismatch.handle, first, lost
+          );
+        }
+        if mismatch.recovered {
+          eprintln!(
+            "  ledger {:?} recovered the entry at height {} without receipts",
+            mismatch.handle, mismatch.endorser_height
+          );
+        }
+        eprintln!(
+          "    endorsers {:?} hold metablock {:?} at height {}",
+          mismatch.endorsers, mismatch.endorsed_metablock, mismatch.endorser_height
+        );
+      },
+    }
+  }
+}
+
 fn generate_secure_nonce_bytes(size: usize) -> Vec<u8> {
   let mut rng = rand::thread_rng();
   let nonce: Vec<u8> = (0..size).map(|_| rng.gen()).collect();
   nonce
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use ledger::signature::{PrivateKey, PrivateKeyTrait};
+
+  const VIEW_LEDGER_HEIGHT: usize = 3;
+
+  async fn in_memory_coordinator() -> CoordinatorState {
+    CoordinatorState::new("memory", &HashMap::new(), None, RollbackPolicy::Refuse)
+      .await
+      .unwrap()
+  }
+
+  fn id_sig() -> IdSig {
+    let sk = PrivateKey::new();
+    IdSig::new(sk.get_public_key().unwrap(), sk.sign(b"message").unwrap())
+  }
+
+  fn metablock_of(prev: &NimbleDigest, block: &[u8], height: usize) -> MetaBlock {
+    let block_hash = compute_aggregated_block_hash(
+      &Block::new(block).hash().to_bytes(),
+      &Nonces::new().hash().to_bytes(),
+    );
+    MetaBlock::new(prev, &block_hash, height)
+  }
+
+  // creates a ledger with the given blocks and attaches a receipt to each entry, returning the
+  // metablock of the tail
+  async fn create_ledger(
+    coordinator: &CoordinatorState,
+    handle: &Handle,
+    blocks: &[&[u8]],
+  ) -> MetaBlock {
+    let mut prev = NimbleDigest::default();
+    let mut metablock = MetaBlock::default();
+    for (idx, block) in blocks.iter().enumerate() {
+      if idx == 0 {
+        coordinator
+          .ledger_store
+          .create_ledger(handle, Block::new(block))
+          .await
+          .unwrap();
+      } else {
+        coordinator
+          .ledger_store
+          .append_ledger(handle, &Block::new(block), idx)
+          .await
+          .unwrap();
+      }
+      metablock = metablock_of(&prev, block, idx);
+      let mut receipts = Receipts::new();
+      receipts.add(&Receipt::new(
+        NimbleDigest::default(),
+        metablock.clone(),
+        id_sig(),
+      ));
+      coordinator
+        .ledger_store
+        .attach_ledger_receipts(handle, idx, &receipts)
+        .await
+        .unwrap();
+      prev = metablock.hash();
+    }
+    metablock
+  }
+
+  fn tail_entry(
+    handle: &Handle,
+    metablock: &MetaBlock,
+    block: &[u8],
+  ) -> endorser_proto::LedgerTailMapEntry {
+    endorser_proto::LedgerTailMapEntry {
+      handle: handle.to_bytes(),
+      height: metablock.get_height() as u64,
+      metablock: metablock.to_bytes(),
+      block: block.to_vec(),
+      nonces: Nonces::new().to_bytes(),
+    }
+  }
+
+  // the state of an endorser at the given view ledger height that holds the given tails
+  fn endorser_state(
+    view_ledger_height: usize,
+    tails: Vec<endorser_proto::LedgerTailMapEntry>,
+  ) -> EndorserStates {
+    let view_metablock = MetaBlock::new(
+      &NimbleDigest::default(),
+      &NimbleDigest::default(),
+      view_ledger_height,
+    );
+    vec![(
+      "http://endorser:9090".to_string(),
+      Receipt::new(NimbleDigest::default(), view_metablock, id_sig()),
+      tails,
+    )]
+  }
+
+  async fn store_height(coordinator: &CoordinatorState, handle: &Handle) -> usize {
+    coordinator
+      .ledger_store
+      .read_ledger_tail(handle)
+      .await
+      .unwrap()
+      .1
+  }
+
+  #[tokio::test]
+  async fn test_check_rollback() {
+    let coordinator = in_memory_coordinator().await;
+    let handle = NimbleDigest::digest(b"ledger");
+    let genesis = create_ledger(&coordinator, &handle, &[b"block0"]).await;
+    // the endorsers hold an entry that the store lost
+    let lost = metablock_of(&genesis.hash(), b"block1", 1);
+    let states = || {
+      endorser_state(
+        VIEW_LEDGER_HEIGHT,
+        vec![tail_entry(&handle, &lost, b"block1")],
+      )
+    };
+
+    // the findings name the ledger, the heights, and the endorsed metablock
+    let findings = coordinator
+      .find_store_lag(states(), VIEW_LEDGER_HEIGHT, false)
+      .await
+      .unwrap();
+    assert_eq!(findings.len(), 1);
+    match &findings[0] {
+      RollbackFinding::Ledger(mismatch) => {
+        assert_eq!(mismatch.handle, handle);
+        assert_eq!(mismatch.store_height, Some(0));
+        assert_eq!(mismatch.endorser_height, 1);
+        assert_eq!(mismatch.endorsed_metablock, lost.hash());
+        assert!(!mismatch.diverged);
+        assert!(!mismatch.recovered);
+      },
+      finding => panic!("unexpected finding {:?}", finding),
+    }
+
+    // refusing leaves the store as it is
+    let res = coordinator
+      .check_rollback(states(), VIEW_LEDGER_HEIGHT, RollbackPolicy::Refuse)
+      .await;
+    assert_eq!(res, Err(CoordinatorError::StoreRolledBack));
+    assert_eq!(store_height(&coordinator, &handle).await, 0);
+    assert!(coordinator.get_quarantined_ledgers().is_empty());
+
+    // quarantining starts, but leaves the ledger read-only
+    let res = coordinator
+      .check_rollback(states(), VIEW_LEDGER_HEIGHT, RollbackPolicy::Quarantine)
+      .await;
+    assert_eq!(res, Ok(()));
+    assert_eq!(store_height(&coordinator, &handle).await, 0);
+    assert!(coordinator.is_quarantined(&handle));
+
+    // reconciling recovers the tail, so the ledger is not quarantined
+    assert!(coordinator.release_quarantined_ledger(&handle));
+    let res = coordinator
+      .check_rollback(states(), VIEW_LEDGER_HEIGHT, RollbackPolicy::Reconcile)
+      .await;
+    assert_eq!(res, Ok(()));
+    assert_eq!(store_height(&coordinator, &handle).await, 1);
+    assert!(!coordinator.is_quarantined(&handle));
+    let recovered = coordinator
+      .ledger_store
+      .read_ledger_by_index(&handle, 1)
+      .await
+      .unwrap();
+    assert_eq!(recovered.get_block().to_bytes(), b"block1".to_vec());
+    assert!(recovered.get_receipts().is_empty());
+
+    // the store is no longer behind
+    let findings = coordinator
+      .find_store_lag(states(), VIEW_LEDGER_HEIGHT, false)
+      .await
+      .unwrap();
+    assert!(findings.is_empty());
+
+    // endorsers in an older view cannot vouch for anything, so the store cannot be compared
+    let res = coordinator
+      .check_rollback(
+        endorser_state(VIEW_LEDGER_HEIGHT - 1, Vec::new()),
+        VIEW_LEDGER_HEIGHT,
+        RollbackPolicy::Refuse,
+      )
+      .await;
+    assert_eq!(res, Err(CoordinatorError::FailedToReadLatestState));
+  }
+
+  #[tokio::test]
+  async fn test_find_store_lag_pruned_ledger() {
+    let coordinator = in_memory_coordinator().await;
+    let handle = NimbleDigest::digest(b"ledger");
+    let genesis = create_ledger(&coordinator, &handle, &[b"block0", b"block1", b"block2"]).await;
+    coordinator
+      .ledger_store
+      .prune_ledger(&handle, 2)
+      .await
+      .unwrap();
+
+    // an endorser whose tail is below the checkpoint agrees with the store
+    let below = metablock_of(&NimbleDigest::default(), b"block1", 1);
+    let findings = coordinator
+      .find_store_lag(
+        endorser_state(
+          VIEW_LEDGER_HEIGHT,
+          vec![tail_entry(&handle, &below, b"block1")],
+        ),
+        VIEW_LEDGER_HEIGHT,
+        false,
+      )
+      .await
+      .unwrap();
+    assert!(findings.is_empty());
+
+    // above the checkpoint the store is compared as before
+    let lost = metablock_of(&genesis.hash(), b"block3", 3);
+    let findings = coordinator
+      .find_store_lag(
+        endorser_state(
+          VIEW_LEDGER_HEIGHT,
+          vec![tail_entry(&handle, &lost, b"block3")],
+        ),
+        VIEW_LEDGER_HEIGHT,
+        false,
+      )
+      .await
+      .unwrap();
+    assert_eq!(findings.len(), 1);
+    match &findings[0] {
+      RollbackFinding::Ledger(mismatch) => {
+        assert_eq!(mismatch.store_height, Some(2));
+        assert!(!mismatch.diverged);
+      },
+      finding => panic!("unexpected finding {:?}", finding),
+    }
+  }
+
+  #[tokio::test]
+  async fn test_check_rollback_unreachable_endorsers() {
+    let coordinator = in_memory_coordinator().await;
+    let handle = NimbleDigest::digest(b"ledger");
+    create_ledger(&coordinator, &handle, &[b"block0"]).await;
+
+    // no endorser answered, so a lost entry would go unnoticed and no policy allows starting
+    for policy in [
+      RollbackPolicy::Refuse,
+      RollbackPolicy::Quarantine,
+      RollbackPolicy::Reconcile,
+    ] {
+      let res = coordinator
+        .check_rollback(Vec::new(), VIEW_LEDGER_HEIGHT, policy)
+        .await;
+      assert_eq!(res, Err(CoordinatorError::FailedToReadLatestState));
+    }
+    assert!(coordinator.get_quarantined_ledgers().is_empty());
+  }
+
+  #[tokio::test]
+  async fn test_check_rollback_diverged_and_missing_views() {
+    let coordinator = in_memory_coordinator().await;
+    let handle = NimbleDigest::digest(b"ledger");
+    create_ledger(&coordinator, &handle, &[b"block0", b"block1"]).await;
+
+    // the endorsers hold a different block at a height that the store has
+    let other = metablock_of(&NimbleDigest::default(), b"other", 0);
+    let states = || {
+      endorser_state(
+        VIEW_LEDGER_HEIGHT,
+        vec![tail_entry(&handle, &other, b"other")],
+      )
+    };
+    let findings = coordinator
+      .find_store_lag(states(), VIEW_LEDGER_HEIGHT, true)
+      .await
+      .unwrap();
+    assert_eq!(findings.len(), 1);
+    match &findings[0] {
+      RollbackFinding::Ledger(mismatch) => {
+        assert_eq!(mismatch.store_height, Some(1));
+        assert_eq!(mismatch.endorser_height, 0);
+        assert!(mismatch.diverged);
+        assert!(!mismatch.recovered);
+      },
+      finding => panic!("unexpected finding {:?}", finding),
+    }
+
+    // a diverged ledger is never recovered, so reconciling quarantines it
+    let res = coordinator
+      .check_rollback(states(), VIEW_LEDGER_HEIGHT, RollbackPolicy::Reconcile)
+      .await;
+    assert_eq!(res, Ok(()));
+    assert_eq!(store_height(&coordinator, &handle).await, 1);
+    assert!(coordinator.is_quarantined(&handle));
+
+    // the endorsers are in a view that the store lacks, so no policy allows starting
+    let states = || endorser_state(VIEW_LEDGER_HEIGHT + 1, Vec::new());
+    let findings = coordinator
+      .find_store_lag(states(), VIEW_LEDGER_HEIGHT, false)
+      .await;
+    assert_eq!(findings, Err(CoordinatorError::FailedToReadLatestState));
+    let mut both = states();
+    both.extend(endorser_state(VIEW_LEDGER_HEIGHT, Vec::new()));
+    let findings = coordinator
+      .find_store_lag(both.clone(), VIEW_LEDGER_HEIGHT, false)
+      .await
+      .unwrap();
+    assert_eq!(
+      findings,
+      vec![RollbackFinding::MissingViews {
+        store_height: VIEW_LEDGER_HEIGHT,
+        endorser_height: VIEW_LEDGER_HEIGHT + 1,
+      }]
+    );
+    for policy in [
+      RollbackPolicy::Refuse,
+      RollbackPolicy::Quarantine,
+      RollbackPolicy::Reconcile,
+    ] {
+      let res = coordinator
+        .check_rollback(both.clone(), VIEW_LEDGER_HEIGHT, policy)
+        .await;
+      assert_eq!(res, Err(CoordinatorError::StoreRolledBack));
+    }
+  }
+
+  #[tokio::test]
+  async fn test_recover_ledger_tail() {
+    let coordinator = in_memory_coordinator().await;
+    let handle = NimbleDigest::digest(b"ledger");
+    let genesis = create_ledger(&coordinator, &handle, &[b"block0"]).await;
+
+    // only the entry right after the tail of the store is recovered
+    let gap = metablock_of(&NimbleDigest::default(), b"block2", 2);
+    let entry = tail_entry(&handle, &gap, b"block2");
+    assert!(
+      !coordinator
+        .recover_ledger_tail(&handle, Some(0), &entry)
+        .await
+    );
+
+    // the block and nonces must match the endorsed metablock
+    let next = metablock_of(&genesis.hash(), b"block1", 1);
+    let entry = tail_entry(&handle, &next, b"forged");
+    assert!(
+      !coordinator
+        .recover_ledger_tail(&handle, Some(0), &entry)
+        .await
+    );
+
+    // the endorsed metablock must extend the tail of the store
+    let unrelated = metablock_of(&NimbleDigest::digest(b"unrelated"), b"block1", 1);
+    let entry = tail_entry(&handle, &unrelated, b"block1");
+    assert!(
+      !coordinator
+        .recover_ledger_tail(&handle, Some(0), &entry)
+        .await
+    );
+    assert_eq!(store_height(&coordinator, &handle).await, 0);
+
+    let entry = tail_entry(&handle, &next, b"block1");
+    assert!(
+      coordinator
+        .recover_ledger_tail(&handle, Some(0), &entry)
+        .await
+    );
+    assert_eq!(store_height(&coordinator, &handle).await, 1);
+
+    // a ledger that the store lacks is recovered from its genesis entry
+    let missing = NimbleDigest::digest(b"missing");
+    let genesis = metablock_of(&NimbleDigest::default(), b"genesis", 0);
+    let entry = tail_entry(&missing, &genesis, b"genesis");
+    assert!(
+      coordinator
+        .recover_ledger_tail(&missing, None, &entry)
+        .await
+    );
+    assert_eq!(store_height(&coordinator, &missing).await, 0);
+  }
+
+  #[tokio::test]
+  async fn test_quarantine() {
+    let coordinator = in_memory_coordinator().await;
+    let handle = NimbleDigest::digest(b"ledger");
+    let genesis = create_ledger(&coordinator, &handle, &[b"block0"]).await;
+    let lost = metablock_of(&genesis.hash(), b"block1", 1);
+    let states = || {
+      endorser_state(
+        VIEW_LEDGER_HEIGHT,
+        vec![tail_entry(&handle, &lost, b"block1")],
+      )
+    };
+
+    // a lagging ledger is quarantined with the evidence, and a recovered one is not
+    let findings = coordinator
+      .find_store_lag(states(), VIEW_LEDGER_HEIGHT, false)
+      .await
+      .unwrap();
+    let mut recovered = findings.clone();
+    if let RollbackFinding::Ledger(mismatch) = &mut recovered[0] {
+      mismatch.recovered = true;
+    }
+    coordinator.quarantine_ledgers(recovered);
+    assert!(coordinator.get_quarantined_ledgers().is_empty());
+    coordinator.quarantine_ledgers(findings.clone());
+    let quarantined = coordinator.get_quarantined_ledgers();
+    assert_eq!(quarantined.len(), 1);
+    assert_eq!(quarantined[0].handle, handle);
+    assert_eq!(quarantined[0].endorsed_metablock, lost.hash());
+
+    // a ledger that is found again keeps the evidence from when it was first quarantined
+    let mut later = findings.clone();
+    if let RollbackFinding::Ledger(mismatch) = &mut later[0] {
+      mismatch.detected_at += 60;
+    }
+    coordinator.update_quarantine(later);
+    assert_eq!(coordinator.get_quarantined_ledgers(), quarantined);
+
+    // a quarantined ledger is read-only
+    let res = coordinator
+      .append_ledger(None, b"ledger", b"block1", 1)
+      .await;
+    assert_eq!(res.unwrap_err(), CoordinatorError::LedgerQuarantined);
+    let res = coordinator.checkpoint_ledger(b"ledger", 0).await;
+    assert_eq!(res.unwrap_err(), CoordinatorError::LedgerQuarantined);
+
+    // a ledger that caught up with the endorsers is released
+    coordinator
+      .ledger_store
+      .append_ledger(&handle, &Block::new(b"block1"), 1)
+      .await
+      .unwrap();
+    let findings = coordinator
+      .find_store_lag(states(), VIEW_LEDGER_HEIGHT, false)
+      .await
+      .unwrap();
+    assert!(findings.is_empty());
+    coordinator.update_quarantine(findings);
+    assert!(coordinator.get_quarantined_ledgers().is_empty());
+    let res = coordinator
+      .append_ledger(None, b"ledger", b"block2", 2)
+      .await;
+    assert_ne!(res.unwrap_err(), CoordinatorError::LedgerQuarantined);
+
+    // an operator may release a ledger, which is quarantined again if it is still behind
+    let far = MetaBlock::new(&NimbleDigest::default(), &NimbleDigest::default(), 5);
+    let behind = coordinator
+      .find_store_lag(
+        endorser_state(
+          VIEW_LEDGER_HEIGHT,
+          vec![tail_entry(&handle, &far, b"block5")],
+        ),
+        VIEW_LEDGER_HEIGHT,
+        false,
+      )
+      .await
+      .unwrap();
+    coordinator.update_quarantine(behind.clone());
+    assert!(coordinator.is_quarantined(&handle));
+    assert!(coordinator.release_quarantined_ledger(&handle));
+    assert!(!coordinator.release_quarantined_ledger(&handle));
+    assert!(!coordinator.is_quarantined(&handle));
+    coordinator.update_quarantine(behind);
+    assert!(coordinator.is_quarantined(&handle));
+
+    // without an endorser in the latest view, the check leaves the quarantine as it is
+    coordinator.check_store_lag().await;
+    assert!(coordinator.is_quarantined(&handle));
+  }
+
+  #[tokio::test]
+  async fn test_rotate_endorsers_rolled_back() {
+    let coordinator = in_memory_coordinator().await;
+    // no endorser listens on the port, so none of the new endorsers can be initialized
+    let res = coordinator
+      .rotate_endorsers(&["http://127.0.0.1:1".to_string()])
+      .await;
+    match res {
+      Err(CoordinatorError::RotationRolledBack { steps }) => {
+        assert_eq!(
+          steps.last().unwrap(),
+          "rolled back: disconnected the new endorsers"
+        );
+      },
+      res => panic!("unexpected result of the rotation {:?}", res),
+    }
+    assert!(coordinator.get_endorser_uris().is_empty());
+  }
+
+  #[tokio::test]
+  async fn test_stored_without_quorum() {
+    let coordinator = in_memory_coordinator().await;
+    let handle = NimbleDigest::digest(b"ledger");
+
+    // without endorsers no write reaches a quorum, but the store keeps it
+    let res = coordinator.create_ledger(None, b"ledger", b"genesis").await;
+    assert_eq!(res.unwrap_err(), CoordinatorError::StoredWithoutQuorum);
+    assert_eq!(store_height(&coordinator, &handle).await, 0);
+    let res = coordinator.create_ledger(None, b"ledger", b"genesis").await;
+    assert_eq!(res.unwrap_err(), CoordinatorError::LedgerAlreadyExists);
+
+    let res = coordinator
+      .append_ledger(None, b"ledger", b"block1", 1)
+      .await;
+    assert_eq!(res.unwrap_err(), CoordinatorError::StoredWithoutQuorum);
+    assert_eq!(store_height(&coordinator, &handle).await, 1);
+    let res = coordinator
+      .append_ledger(None, b"ledger", b"block1", 1)
+      .await;
+    assert_eq!(res.unwrap_err(), CoordinatorError::InvalidHeight);
+  }
+
+  #[tokio::test]
+  async fn test_read_endorsed_ledger_entry() {
+    let coordinator = in_memory_coordinator().await;
+    let handle = NimbleDigest::digest(b"ledger");
+
+    // a view with a single endorser, whose receipt alone is a quorum
+    let sk = PrivateKey::new();
+    let pk = sk.get_public_key().unwrap();
+    let config = ViewConfig::new(
+      vec![(pk.to_bytes(), "http://endorser:9090".to_string())],
+      QuorumPolicy::Majority,
+    );
+    let genesis = Block::new(&config.to_bytes());
+    let group_identity = genesis.hash();
+    let view_metablock = MetaBlock::new(&NimbleDigest::default(), &genesis.hash(), 1);
+    let message =
+      group_identity.digest_with(&NimbleDigest::default().digest_with(&view_metablock.hash()));
+    let mut view_receipts = Receipts::new();
+    view_receipts.add(&Receipt::new(
+      NimbleDigest::default(),
+      view_metablock.clone(),
+      IdSig::new(pk.clone(), sk.sign(&message.to_bytes()).unwrap()),
+    ));
+    {
+      let mut vs = coordinator.verifier_state.write().unwrap();
+      vs.set_group_identity(group_identity);
+      vs.apply_view_change(
+        &genesis.to_bytes(),
+        &view_receipts.to_bytes(),
+        Some(b"THIS IS A PLACE HOLDER FOR ATTESTATION"),
+      )
+      .unwrap();
+    }
+
+    // the endorser signs the genesis entry and the entry at height 2, but the append at height 1
+    // was stored without a quorum
+    let blocks: [&[u8]; 3] = [b"block0", b"block1", b"block2"];
+    let mut prev = NimbleDigest::default();
+    for (idx, block) in blocks.iter().enumerate() {
+      if idx == 0 {
+        coordinator
+          .ledger_store
+          .create_ledger(&handle, Block::new(block))
+          .await
+          .unwrap();
+      } else {
+        coordinator
+          .ledger_store
+          .append_ledger(&handle, &Block::new(block), idx)
+          .await
+          .unwrap();
+      }
+      let metablock = metablock_of(&prev, block, idx);
+      prev = metablock.hash();
+      if idx == 1 {
+        continue;
+      }
+      let mut receipts = Receipts::new();
+      receipts.add(&Receipt::new(
+        view_metablock.hash(),
+        metablock,
+        IdSig::new(pk.clone(), sk.sign(b"message").unwrap()),
+      ));
+      coordinator
+        .ledger_store
+        .attach_ledger_receipts(&handle, idx, &receipts)
+        .await
+        .unwrap();
+    }
+
+    let read = |index| coordinator.read_endorsed_ledger_entry(b"ledger", index);
+    let (index, entry) = read(0).await.unwrap().unwrap();
+    assert_eq!(
+      (index, entry.get_block().to_bytes()),
+      (0, b"block0".to_vec())
+    );
+    // the entry without a quorum is skipped, since a later entry is endorsed
+    let (index, entry) = read(1).await.unwrap().unwrap();
+    assert_eq!(
+      (index, entry.get_block().to_bytes()),
+      (2, b"block2".to_vec())
+    );
+    assert!(read(3).await.unwrap().is_none());
+
+    // an entry without a quorum at the tail may still be endorsed, so it is waited for
+    coordinator
+      .ledger_store
+      .append_ledger(&handle, &Block::new(b"block3"), 3)
+      .await
+      .unwrap();
+    assert!(read(3).await.unwrap().is_none());
+
+    // a watcher from height 1 gets the endorsed entry after the one without a quorum
+    let service = crate::CoordinatorServiceState::new(Arc::new(coordinator));
+    let (tx, mut rx) = mpsc::channel(1);
+    tokio::spawn(async move {
+      service
+        .watch_ledger_entries(b"ledger".to_vec(), 1, tx)
+        .await;
+    });
+    let reply = rx.recv().await.unwrap().unwrap();
+    assert_eq!((reply.index, reply.block), (2, b"block2".to_vec()));
+  }
+}
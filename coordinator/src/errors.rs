@@ -56,14 +56,37 @@ pub enum CoordinatorError {
   NoNewEndorsers,
   /// returned if a ledger or an entry already exists
   LedgerAlreadyExists,
+  /// returned if the ledger does not exist
+  LedgerNotFound,
   /// returned if hit unexpected error
   UnexpectedError,
   /// returned if failed to attach nonce into the ledger store
   FailedToAttachNonce,
   /// returned if failed to obtain a quorum
   FailedToObtainQuorum,
+  /// returned if an entry is stored but not endorsed by a quorum, so the write cannot be repeated
+  StoredWithoutQuorum,
   /// returned if failed to verify view change
   FailedToActivate,
   /// returned if get timeout map fails
   FailedToGetTimeoutMap,
+  /// returned if the quorum policy cannot be applied to the endorsers of a view
+  InvalidQuorumPolicy,
+  /// returned if the ledger store is behind the endorsers and the coordinator refuses to start
+  StoreRolledBack,
+  /// returned if the ledger is quarantined because the store is behind the endorsers
+  LedgerQuarantined,
+  /// returned if the ledger was deleted and accepts no more appends
+  LedgerDeleted,
+  /// returned if a client supplies the block that is reserved for tombstones
+  ReservedBlock,
+  /// returned if the requested entry was pruned from the ledger store
+  LedgerPruned,
+  /// returned if the entry to checkpoint at is not endorsed by a quorum
+  InvalidCheckpoint,
+  /// returned if an endorser has not reached the height at which a ledger is checkpointed
+  EndorsersBehindCheckpoint,
+  /// returned if a rotation of the endorsers was abandoned before it took effect, with the steps
+  /// completed until then
+  RotationRolledBack { steps: Vec<String> },
 }
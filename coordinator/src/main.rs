@@ -1,13 +1,24 @@
 mod coordinator_state;
 mod errors;
 
-use crate::coordinator_state::CoordinatorState;
-use ledger::CustomSerde;
+use crate::{
+  coordinator_state::{CoordinatorState, QuorumPolicyConfig, RollbackPolicy},
+  errors::CoordinatorError,
+};
+use ledger::{
+  health::{HealthService, Readiness},
+  is_tombstone,
+  quorum::QuorumPolicy,
+  CustomSerde, EndorserHostnames, NimbleDigest,
+};
 use std::{
   collections::HashMap, 
   sync::{atomic::{AtomicBool, Ordering::SeqCst}, Arc},
 };
-use tonic::{transport::Server, Request, Response, Status};
+use prost::Message;
+use tokio::sync::{broadcast::error::RecvError, mpsc};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{codegen::Bytes, transport::Server, Code, Request, Response, Status};
 #[allow(clippy::derive_partial_eq_without_eq)]
 pub mod coordinator_proto {
   tonic::include_proto!("coordinator_proto");
@@ -16,16 +27,18 @@ pub mod coordinator_proto {
 use clap::{App, Arg};
 use coordinator_proto::{
   call_server::{Call, CallServer},
-  AppendReq, AppendResp, NewLedgerReq, NewLedgerResp, ReadByIndexReq, ReadByIndexResp,
+  AppendReq, AppendResp, CheckpointLedgerReq, CheckpointLedgerResp, DeleteLedgerReq,
+  DeleteLedgerResp, LedgerCheckpoint, NewLedgerReq, NewLedgerResp, ReadByIndexReq, ReadByIndexResp,
   ReadLatestReq, ReadLatestResp, ReadViewByIndexReq, ReadViewByIndexResp, ReadViewTailReq,
   ReadViewTailResp, PingAllReq, PingAllResp, GetTimeoutMapReq, GetTimeoutMapResp, AddEndorsersReq, AddEndorsersResp,
+  WatchLedgerReq, WatchLedgerResp,
 };
 
 use axum::{
   extract::{Extension, Path},
   http::StatusCode,
   response::IntoResponse,
-  routing::get,
+  routing::{delete, get, put},
   Json, Router,
 };
 use serde::{Deserialize, Serialize};
@@ -37,6 +50,9 @@ use tower::ServiceBuilder;
 
 static DEACTIVATE_AUTO_RECONFIG: AtomicBool = AtomicBool::new(false);
 
+/// The number of entries buffered for a watcher of a ledger before the coordinator waits for it.
+const WATCH_CHANNEL_BUFFER: usize = 16;
+
 pub struct CoordinatorServiceState {
   state: Arc<CoordinatorState>,
 }
@@ -47,10 +63,105 @@ impl CoordinatorServiceState {
     CoordinatorServiceState { state: coordinator }
   }
 
+  /// Streams the entries of the ledger with the given handle from `next` on, as each is endorsed
+  /// by a quorum, until the watcher goes away or the ledger is deleted. Entries that were stored
+  /// without a quorum are left out.
+  async fn watch_ledger_entries(
+    &self,
+    handle_bytes: Vec<u8>,
+    mut next: usize,
+    tx: mpsc::Sender<Result<WatchLedgerResp, Status>>,
+  ) {
+    let handle = NimbleDigest::digest(&handle_bytes);
+    // subscribe before reading, so that an append between the two is not missed
+    let mut appends = self.state.subscribe_appends();
+
+    loop {
+      // stream the endorsed entries the watcher has not seen yet
+      loop {
+        let ledger_entry = match self
+          .state
+          .read_endorsed_ledger_entry(&handle_bytes, next)
+          .await
+        {
+          Ok(Some((index, ledger_entry))) => {
+            next = index;
+            ledger_entry
+          },
+          Ok(None) => break,
+          Err(error) => {
+            let _ = tx
+              .send(Err(self.process_error(error, "Failed to watch a ledger")))
+              .await;
+            return;
+          },
+        };
+        let deleted = is_tombstone(&handle, ledger_entry.get_block());
+        let reply = WatchLedgerResp {
+          index: next as u64,
+          block: ledger_entry.get_block().to_bytes(),
+          nonces: ledger_entry.get_nonces().to_bytes(),
+          receipts: ledger_entry.get_receipts().to_bytes(),
+        };
+        if tx.send(Ok(reply)).await.is_err() || deleted {
+          return;
+        }
+        next += 1;
+      }
+
+      // wait for the next entry to be endorsed
+      loop {
+        tokio::select! {
+          _ = tx.closed() => return,
+          res = appends.recv() => match res {
+            Ok((h, height)) if h == handle && height >= next => break,
+            Ok(_) => continue,
+            // the notification may be among the missed ones, so read the store again
+            Err(RecvError::Lagged(_)) => break,
+            Err(RecvError::Closed) => return,
+          },
+        }
+      }
+    }
+  }
+
   #[cfg(test)]
   pub fn get_state(&self) -> &CoordinatorState {
     &self.state
   }
+
+  /// Processes an error of a ledger operation and returns the corresponding status, whose code
+  /// lets the endpoint tell the cases apart.
+  ///
+  /// # Arguments
+  ///
+  /// * `error` - The error to process.
+  /// * `default_msg` - A default message to use if the error does not match any known cases.
+  fn process_error(&self, error: CoordinatorError, default_msg: impl Into<String>) -> Status {
+    match error {
+      CoordinatorError::LedgerAlreadyExists => Status::already_exists("The ledger already exists"),
+      CoordinatorError::LedgerNotFound => Status::not_found("The ledger does not exist"),
+      CoordinatorError::InvalidHeight => {
+        Status::aborted("The expected height does not match the height of the ledger")
+      },
+      CoordinatorError::ReservedBlock => {
+        Status::invalid_argument("The block is reserved for tombstones")
+      },
+      CoordinatorError::InvalidNonce => Status::invalid_argument("The nonce is invalid"),
+      CoordinatorError::LedgerDeleted => Status::permission_denied("The ledger is deleted"),
+      CoordinatorError::LedgerQuarantined => Status::failed_precondition(
+        "The ledger is quarantined because the store is behind the endorsers",
+      ),
+      CoordinatorError::LedgerPruned => Status::out_of_range("The entry is pruned"),
+      CoordinatorError::FailedToObtainQuorum => {
+        Status::unavailable("Failed to obtain a quorum of endorsers")
+      },
+      CoordinatorError::StoredWithoutQuorum => {
+        Status::deadline_exceeded("The entry is stored but not endorsed by a quorum of endorsers")
+      },
+      _ => Status::internal(default_msg),
+    }
+  }
 }
 
 #[tonic::async_trait]
@@ -69,11 +180,10 @@ impl Call for CoordinatorServiceState {
       .state
       .create_ledger(None, &handle_bytes, &block_bytes)
       .await;
-    if res.is_err() {
-      return Err(Status::aborted("Failed to create a new ledger"));
-    }
-
-    let receipts = res.unwrap();
+    let receipts = match res {
+      Ok(receipts) => receipts,
+      Err(error) => return Err(self.process_error(error, "Failed to create a new ledger")),
+    };
     let reply = NewLedgerResp {
       receipts: receipts.to_bytes(),
     };
@@ -92,11 +202,10 @@ impl Call for CoordinatorServiceState {
       .state
       .append_ledger(None, &handle_bytes, &block_bytes, expected_height as usize)
       .await;
-    if res.is_err() {
-      return Err(Status::aborted("Failed to append to a ledger"));
-    }
-
-    let (hash_nonces, receipts) = res.unwrap();
+    let (hash_nonces, receipts) = match res {
+      Ok(v) => v,
+      Err(error) => return Err(self.process_error(error, "Failed to append to a ledger")),
+    };
     let reply = AppendResp {
       hash_nonces: hash_nonces.to_bytes(),
       receipts: receipts.to_bytes(),
@@ -105,6 +214,87 @@ impl Call for CoordinatorServiceState {
     Ok(Response::new(reply))
   }
 
+  /// Deletes the ledger with the given handle by appending a tombstone at the expected height.
+  async fn delete_ledger(
+    &self,
+    request: Request<DeleteLedgerReq>,
+  ) -> Result<Response<DeleteLedgerResp>, Status> {
+    let DeleteLedgerReq {
+      handle: handle_bytes,
+      expected_height,
+    } = request.into_inner();
+
+    let res = self
+      .state
+      .delete_ledger(None, &handle_bytes, expected_height as usize)
+      .await;
+    let (hash_nonces, receipts) = match res {
+      Ok(v) => v,
+      Err(CoordinatorError::LedgerDeleted) => {
+        return Err(Status::permission_denied("The ledger is already deleted"));
+      },
+      Err(error) => return Err(self.process_error(error, "Failed to delete a ledger")),
+    };
+    let reply = DeleteLedgerResp {
+      hash_nonces: hash_nonces.to_bytes(),
+      receipts: receipts.to_bytes(),
+    };
+
+    Ok(Response::new(reply))
+  }
+
+  /// Prunes the entries of the ledger with the given handle below the given height.
+  async fn checkpoint_ledger(
+    &self,
+    request: Request<CheckpointLedgerReq>,
+  ) -> Result<Response<CheckpointLedgerResp>, Status> {
+    let CheckpointLedgerReq {
+      handle: handle_bytes,
+      height,
+    } = request.into_inner();
+
+    let res = self
+      .state
+      .checkpoint_ledger(&handle_bytes, height as usize)
+      .await;
+    let receipts = match res {
+      Ok(v) => v,
+      Err(CoordinatorError::LedgerQuarantined) => {
+        return Err(Status::failed_precondition(
+          "The ledger is quarantined because the store is behind the endorsers",
+        ));
+      },
+      Err(CoordinatorError::LedgerPruned) => {
+        return Err(Status::out_of_range(
+          "The entry at the height is already pruned",
+        ));
+      },
+      Err(CoordinatorError::InvalidHeight) => {
+        return Err(Status::out_of_range(
+          "The ledger has no entry at the height",
+        ));
+      },
+      Err(CoordinatorError::InvalidCheckpoint) => {
+        return Err(Status::failed_precondition(
+          "The entry at the height is not endorsed by a quorum",
+        ));
+      },
+      Err(CoordinatorError::EndorsersBehindCheckpoint) => {
+        return Err(Status::unavailable(
+          "Not every endorser has reached the height",
+        ));
+      },
+      Err(_) => {
+        return Err(Status::aborted("Failed to checkpoint a ledger"));
+      },
+    };
+    let reply = CheckpointLedgerResp {
+      receipts: receipts.to_bytes(),
+    };
+
+    Ok(Response::new(reply))
+  }
+
   /// Reads the latest block from the ledger with the given handle and nonce.
   async fn read_latest(
     &self,
@@ -119,11 +309,10 @@ impl Call for CoordinatorServiceState {
       .state
       .read_ledger_tail(&handle_bytes, &nonce_bytes)
       .await;
-    if res.is_err() {
-      return Err(Status::aborted("Failed to read a ledger tail"));
-    }
-
-    let ledger_entry = res.unwrap();
+    let ledger_entry = match res {
+      Ok(ledger_entry) => ledger_entry,
+      Err(error) => return Err(self.process_error(error, "Failed to read a ledger tail")),
+    };
     let reply = ReadLatestResp {
       block: ledger_entry.get_block().to_bytes(),
       nonces: ledger_entry.get_nonces().to_bytes(),
@@ -156,10 +345,62 @@ impl Call for CoordinatorServiceState {
         };
         Ok(Response::new(reply))
       },
-      Err(_) => return Err(Status::aborted("Failed to read a ledger")),
+      Err(CoordinatorError::LedgerPruned) => {
+        // the checkpoint lets the client verify where the ledger now starts
+        let checkpoint = match self.state.read_ledger_checkpoint(&handle_bytes).await {
+          Ok(Some(checkpoint)) => checkpoint,
+          _ => return Err(Status::internal("Failed to read a ledger")),
+        };
+        let details = LedgerCheckpoint {
+          height: checkpoint.get_height() as u64,
+          receipts: checkpoint.get_receipts().to_bytes(),
+        };
+        Err(Status::with_details(
+          Code::OutOfRange,
+          "The entry is pruned",
+          Bytes::from(details.encode_to_vec()),
+        ))
+      },
+      Err(CoordinatorError::InvalidHeight) => {
+        // unlike a pruned entry, an entry past the tail has no checkpoint in the details
+        Err(Status::out_of_range("The ledger has no entry at the index"))
+      },
+      Err(error) => Err(self.process_error(error, "Failed to read a ledger")),
     }
   }
 
+  type WatchLedgerStream = ReceiverStream<Result<WatchLedgerResp, Status>>;
+
+  /// Streams the entries of a ledger, starting at the given index or at its tail, as they are
+  /// endorsed by a quorum.
+  async fn watch_ledger(
+    &self,
+    request: Request<WatchLedgerReq>,
+  ) -> Result<Response<Self::WatchLedgerStream>, Status> {
+    let WatchLedgerReq {
+      handle: handle_bytes,
+      index,
+      from_tail,
+    } = request.into_inner();
+
+    let next = if from_tail {
+      match self.state.read_ledger_height(&handle_bytes).await {
+        Ok(height) => height,
+        Err(error) => return Err(self.process_error(error, "Failed to watch a ledger")),
+      }
+    } else {
+      index as usize
+    };
+
+    let (tx, rx) = mpsc::channel(WATCH_CHANNEL_BUFFER);
+    let service = CoordinatorServiceState::new(self.state.clone());
+    tokio::spawn(async move {
+      service.watch_ledger_entries(handle_bytes, next, tx).await;
+    });
+
+    Ok(Response::new(ReceiverStream::new(rx)))
+  }
+
   /// Reads a block from the view ledger by index.
   async fn read_view_by_index(
     &self,
@@ -405,6 +646,342 @@ async fn delete_endorser(
   (StatusCode::OK, Json(json!(resp)))
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+struct RotateEndorsersResponse {
+  #[serde(rename = "Added")]
+  pub added: Vec<String>,
+  #[serde(rename = "Retired")]
+  pub retired: Vec<String>,
+  #[serde(rename = "Steps")]
+  pub steps: Vec<String>,
+  #[serde(rename = "RolledBack")]
+  pub rolled_back: bool,
+}
+
+/// Rotates the active endorsers to a new set of endorsers.
+async fn rotate_endorsers(
+  Path(uris): Path<String>,
+  Extension(state): Extension<Arc<CoordinatorState>>,
+) -> impl IntoResponse {
+  let res = base64_url::decode(&uris);
+  if res.is_err() {
+    eprintln!("received bad endorser uris {:?}", res);
+    return (StatusCode::BAD_REQUEST, Json(json!({})));
+  }
+  let endorser_uris = res.unwrap();
+
+  let res = String::from_utf8(endorser_uris.clone());
+  if res.is_err() {
+    eprintln!(
+      "cannot convert the endorser uris {:?} to string {:?}",
+      endorser_uris, res
+    );
+    return (StatusCode::BAD_REQUEST, Json(json!({})));
+  }
+  let endorser_uris_string = res.unwrap();
+
+  let endorsers = endorser_uris_string
+    .split(';')
+    .filter(|e| !e.is_empty())
+    .map(|e| e.to_string())
+    .collect::<Vec<String>>();
+
+  let res = state.rotate_endorsers(&endorsers).await;
+  if let Err(CoordinatorError::RotationRolledBack { steps }) = res {
+    eprintln!("rolled back the rotation of the endorsers");
+    let resp = RotateEndorsersResponse {
+      added: Vec::new(),
+      retired: Vec::new(),
+      steps,
+      rolled_back: true,
+    };
+    return (StatusCode::CONFLICT, Json(json!(resp)));
+  }
+  if res.is_err() {
+    eprintln!("failed to rotate the endorsers ({:?})", res);
+    return (StatusCode::BAD_REQUEST, Json(json!({})));
+  }
+  let report = res.unwrap();
+
+  let resp = RotateEndorsersResponse {
+    added: report.added.iter().map(|(_pk, uri)| uri.clone()).collect(),
+    retired: report
+      .retired
+      .iter()
+      .map(|(_pk, uri)| uri.clone())
+      .collect(),
+    steps: report.steps,
+    rolled_back: false,
+  };
+  (StatusCode::OK, Json(json!(resp)))
+}
+
+/// The height of a ledger in the max cut that a view change plan would initialize endorsers with.
+#[derive(Debug, Serialize, Deserialize)]
+struct MaxCutEntry {
+  #[serde(rename = "Handle")]
+  pub handle: String,
+  #[serde(rename = "Height")]
+  pub height: u64,
+}
+
+/// The range of heights of a ledger on which the finalized endorsers of a view change plan
+/// disagree.
+#[derive(Debug, Serialize, Deserialize)]
+struct CutEntry {
+  #[serde(rename = "Handle")]
+  pub handle: String,
+  #[serde(rename = "Low")]
+  pub low: usize,
+  #[serde(rename = "High")]
+  pub high: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PlanViewChangeResponse {
+  #[serde(rename = "Finalize")]
+  pub finalize: Vec<String>,
+  #[serde(rename = "Initialize")]
+  pub initialize: Vec<String>,
+  #[serde(rename = "Unreachable")]
+  pub unreachable: Vec<String>,
+  #[serde(rename = "MaxCut")]
+  pub max_cut: Vec<MaxCutEntry>,
+  #[serde(rename = "CutDiffs")]
+  pub cut_diffs: Vec<CutEntry>,
+  #[serde(rename = "Lagging")]
+  pub lagging: HashMap<String, usize>,
+  #[serde(rename = "FinalizeQuorum")]
+  pub finalize_quorum: bool,
+  #[serde(rename = "InitializeQuorum")]
+  pub initialize_quorum: bool,
+}
+
+/// Computes a view change plan for the currently connected endorsers.
+async fn plan_view_change(Extension(state): Extension<Arc<CoordinatorState>>) -> impl IntoResponse {
+  plan_view_change_with_endorsers(Path(String::new()), Extension(state)).await
+}
+
+/// Computes a view change plan for the given new endorsers.
+async fn plan_view_change_with_endorsers(
+  Path(uris): Path<String>,
+  Extension(state): Extension<Arc<CoordinatorState>>,
+) -> impl IntoResponse {
+  let res = base64_url::decode(&uris);
+  if res.is_err() {
+    eprintln!("received bad endorser uris {:?}", res);
+    return (StatusCode::BAD_REQUEST, Json(json!({})));
+  }
+  let endorser_uris = res.unwrap();
+
+  let res = String::from_utf8(endorser_uris.clone());
+  if res.is_err() {
+    eprintln!(
+      "cannot convert the endorser uris {:?} to string {:?}",
+      endorser_uris, res
+    );
+    return (StatusCode::BAD_REQUEST, Json(json!({})));
+  }
+  let endorser_uris_string = res.unwrap();
+
+  let endorsers = endorser_uris_string
+    .split(';')
+    .filter(|e| !e.is_empty())
+    .map(|e| e.to_string())
+    .collect::<Vec<String>>();
+
+  let res = state.plan_view_change(&endorsers).await;
+  if res.is_err() {
+    eprintln!("failed to plan the view change ({:?})", res.err());
+    return (StatusCode::BAD_REQUEST, Json(json!({})));
+  }
+  let plan = res.unwrap();
+
+  let resp = PlanViewChangeResponse {
+    finalize: plan.to_finalize.into_iter().map(|(_pk, uri)| uri).collect(),
+    initialize: plan
+      .to_initialize
+      .into_iter()
+      .map(|(_pk, uri)| uri)
+      .collect(),
+    unreachable: plan.unreachable,
+    max_cut: plan
+      .max_cut
+      .iter()
+      .map(|entry| MaxCutEntry {
+        handle: base64_url::encode(&entry.handle),
+        height: entry.height,
+      })
+      .collect(),
+    cut_diffs: plan
+      .cut_diffs
+      .iter()
+      .map(|diff| CutEntry {
+        handle: base64_url::encode(&diff.handle),
+        low: diff.low,
+        high: diff.high,
+      })
+      .collect(),
+    lagging: plan.lagging.into_iter().collect(),
+    finalize_quorum: plan.finalize_quorum,
+    initialize_quorum: plan.initialize_quorum,
+  };
+  (StatusCode::OK, Json(json!(resp)))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ViewEndorser {
+  #[serde(rename = "Uri")]
+  pub uri: String,
+  #[serde(rename = "PublicKey")]
+  pub pk: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ViewResponse {
+  #[serde(rename = "Height")]
+  pub height: usize,
+  #[serde(rename = "View")]
+  pub view: String,
+  #[serde(rename = "Endorsers")]
+  pub endorsers: Vec<ViewEndorser>,
+  #[serde(rename = "Policy")]
+  pub policy: String,
+  #[serde(rename = "CreatedAt")]
+  pub created_at: u64,
+  #[serde(rename = "Signers")]
+  pub signers: Vec<String>,
+  #[serde(rename = "Quorum")]
+  pub quorum: bool,
+}
+
+/// Describes a quorum policy with endorsers identified by their URIs.
+fn describe_policy(policy: &QuorumPolicy, endorsers: &EndorserHostnames) -> String {
+  let uri_of = |pk: &Vec<u8>| {
+    endorsers
+      .iter()
+      .find(|(p, _uri)| p == pk)
+      .map(|(_pk, uri)| uri.clone())
+      .unwrap_or_else(|| base64_url::encode(pk))
+  };
+  match policy {
+    QuorumPolicy::Majority => "Majority".to_string(),
+    QuorumPolicy::Threshold(k) => format!("Threshold({})", k),
+    QuorumPolicy::Weighted { weights, threshold } => format!(
+      "Weighted({}; threshold {})",
+      weights
+        .iter()
+        .map(|(pk, w)| format!("{}={}", uri_of(pk), w))
+        .collect::<Vec<String>>()
+        .join(";"),
+      threshold
+    ),
+    QuorumPolicy::FailureDomains(tags) => format!(
+      "FailureDomains({})",
+      tags
+        .iter()
+        .map(|(pk, tag)| format!("{}={}", uri_of(pk), tag))
+        .collect::<Vec<String>>()
+        .join(";")
+    ),
+  }
+}
+
+/// Retrieves every view in the view ledger.
+async fn get_view_ledger(Extension(state): Extension<Arc<CoordinatorState>>) -> impl IntoResponse {
+  let res = state.inspect_view_ledger().await;
+  if res.is_err() {
+    eprintln!("failed to inspect the view ledger ({:?})", res.err());
+    return (StatusCode::BAD_REQUEST, Json(json!([])));
+  }
+
+  let resp = res
+    .unwrap()
+    .into_iter()
+    .map(|view| ViewResponse {
+      height: view.height,
+      view: view
+        .view
+        .map(|v| base64_url::encode(&v.to_bytes()))
+        .unwrap_or_default(),
+      policy: describe_policy(&view.policy, &view.endorsers),
+      endorsers: view
+        .endorsers
+        .iter()
+        .map(|(pk, uri)| ViewEndorser {
+          uri: uri.clone(),
+          pk: base64_url::encode(pk),
+        })
+        .collect(),
+      created_at: view.created_at,
+      signers: view.signers.iter().map(base64_url::encode).collect(),
+      quorum: view.quorum,
+    })
+    .collect::<Vec<ViewResponse>>();
+  (StatusCode::OK, Json(json!(resp)))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct QuarantinedLedgerResponse {
+  #[serde(rename = "Handle")]
+  pub handle: String,
+  #[serde(rename = "StoreHeight")]
+  pub store_height: Option<usize>,
+  #[serde(rename = "EndorserHeight")]
+  pub endorser_height: usize,
+  #[serde(rename = "EndorsedMetablock")]
+  pub endorsed_metablock: String,
+  #[serde(rename = "Endorsers")]
+  pub endorsers: Vec<String>,
+  #[serde(rename = "Diverged")]
+  pub diverged: bool,
+  #[serde(rename = "DetectedAt")]
+  pub detected_at: u64,
+}
+
+/// Lists the quarantined ledgers with the evidence that the store is behind the endorsers.
+async fn get_quarantined_ledgers(
+  Extension(state): Extension<Arc<CoordinatorState>>,
+) -> impl IntoResponse {
+  let resp = state
+    .get_quarantined_ledgers()
+    .into_iter()
+    .map(|m| QuarantinedLedgerResponse {
+      handle: base64_url::encode(&m.handle.to_bytes()),
+      store_height: m.store_height,
+      endorser_height: m.endorser_height,
+      endorsed_metablock: base64_url::encode(&m.endorsed_metablock.to_bytes()),
+      endorsers: m.endorsers,
+      diverged: m.diverged,
+      detected_at: m.detected_at,
+    })
+    .collect::<Vec<QuarantinedLedgerResponse>>();
+  (StatusCode::OK, Json(json!(resp)))
+}
+
+/// Releases a quarantined ledger, identified as in the list of quarantined ledgers.
+async fn release_quarantined_ledger(
+  Path(handle): Path<String>,
+  Extension(state): Extension<Arc<CoordinatorState>>,
+) -> impl IntoResponse {
+  let res = base64_url::decode(&handle);
+  if res.is_err() {
+    eprintln!("received a bad ledger handle {:?}", res);
+    return (StatusCode::BAD_REQUEST, Json(json!({})));
+  }
+  let res = NimbleDigest::from_bytes(&res.unwrap());
+  if res.is_err() {
+    eprintln!("received a bad ledger handle {:?}", res);
+    return (StatusCode::BAD_REQUEST, Json(json!({})));
+  }
+
+  if state.release_quarantined_ledger(&res.unwrap()) {
+    (StatusCode::OK, Json(json!({})))
+  } else {
+    (StatusCode::NOT_FOUND, Json(json!({})))
+  }
+}
+
 /// Retrieves the timeout map of endorsers.
 async fn get_timeout_map(
   Extension(state): Extension<Arc<CoordinatorState>>,
@@ -418,6 +995,40 @@ async fn get_timeout_map(
   return (StatusCode::OK, Json(json!(res.unwrap())));
 }
 
+/// The coordinator is ready if the endorsers that are alive form a quorum and the store answers.
+#[tonic::async_trait]
+impl Readiness for CoordinatorState {
+  async fn is_ready(&self) -> bool {
+    self.has_quorum_available() && self.is_store_connected().await
+  }
+}
+
+/// Reports that the coordinator is running.
+async fn get_health() -> impl IntoResponse {
+  (StatusCode::OK, Json(json!({ "Status": "ok" })))
+}
+
+/// Reports whether a quorum of endorsers is available and the store answers, and so whether the
+/// coordinator is ready.
+async fn get_readiness(Extension(state): Extension<Arc<CoordinatorState>>) -> impl IntoResponse {
+  let quorum_available = state.has_quorum_available();
+  let store_connected = state.is_store_connected().await;
+  let ready = quorum_available && store_connected;
+  let status = if ready {
+    StatusCode::OK
+  } else {
+    StatusCode::SERVICE_UNAVAILABLE
+  };
+  (
+    status,
+    Json(json!({
+      "Ready": ready,
+      "QuorumAvailable": quorum_available,
+      "StoreConnected": store_connected,
+    })),
+  )
+}
+
 /// Pings all endorsers.
 async fn ping_all_endorsers(
   Extension(state): Extension<Arc<CoordinatorState>>,
@@ -426,6 +1037,20 @@ async fn ping_all_endorsers(
   return (StatusCode::OK, Json(json!({})));
 }
 
+/// Parses "uri=value;uri=value" into a map from endorser URI to value.
+fn parse_uri_map(arg: &str) -> Result<HashMap<String, String>, String> {
+  let mut map = HashMap::new();
+  for e in arg.split(';').filter(|e| !e.is_empty()) {
+    let (uri, value) = e
+      .rsplit_once('=')
+      .ok_or_else(|| format!("Expected URI=VALUE, got {}", e))?;
+    if map.insert(uri.to_string(), value.to_string()).is_some() {
+      return Err(format!("Endorser {} is listed more than once", uri));
+    }
+  }
+  Ok(map)
+}
+
 /// Main function to start the coordinator service.
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -550,6 +1175,45 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
       .long("deactivate_auto_reconfig")
       .help("Deactivate automatic reconfiguration of endorsers")
       .takes_value(false),
+    )
+    .arg(
+      Arg::with_name("quorum_threshold")
+        .long("quorum-threshold")
+        .value_name("COUNT")
+        .help("Number of endorsers that form a quorum (default: a strict majority)")
+        .takes_value(true)
+        .conflicts_with_all(&["endorser_weights", "failure_domains"]),
+    )
+    .arg(
+      Arg::with_name("endorser_weights")
+        .long("endorser-weights")
+        .value_name("URI=WEIGHT;...")
+        .help("Weights of endorsers in a weighted quorum; unlisted endorsers weigh 1")
+        .takes_value(true)
+        .requires("weight_threshold")
+        .conflicts_with("failure_domains"),
+    )
+    .arg(
+      Arg::with_name("weight_threshold")
+        .long("weight-threshold")
+        .value_name("WEIGHT")
+        .help("Combined weight that forms a quorum")
+        .takes_value(true)
+        .requires("endorser_weights"),
+    )
+    .arg(
+      Arg::with_name("failure_domains")
+        .long("failure-domains")
+        .value_name("URI=DOMAIN;...")
+        .help("Failure domain of each endorser; a quorum needs a majority covering every domain")
+        .takes_value(true),
+    )
+    .arg(
+      Arg::with_name("on_rollback")
+        .long("on-rollback")
+        .possible_values(&["refuse", "quarantine", "reconcile"])
+        .help("What to do if the store is behind the endorsers, e.g., after a restore")
+        .default_value("refuse"),
     );
 
   let cli_matches = config.get_matches();
@@ -611,9 +1275,49 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
   } else {
     None
   };
-  let res = CoordinatorState::new(store, &ledger_store_args, num_grpc_channels).await;
+  let quorum_policy = if let Some(x) = cli_matches.value_of("quorum_threshold") {
+    let k = x
+      .parse::<usize>()
+      .map_err(|_e| format!("Failed to parse the quorum threshold {}", x))?;
+    QuorumPolicyConfig::Threshold(k)
+  } else if let Some(x) = cli_matches.value_of("endorser_weights") {
+    let weights = parse_uri_map(x)?
+      .into_iter()
+      .map(|(uri, w)| match w.parse::<u64>() {
+        Ok(w) if w > 0 => Ok((uri, w)),
+        Ok(_) => Err(format!("The weight of endorser {} must be positive", uri)),
+        Err(_) => Err(format!("Failed to parse the weight of endorser {}", uri)),
+      })
+      .collect::<Result<HashMap<String, u64>, String>>()?;
+    let threshold_str = cli_matches.value_of("weight_threshold").unwrap();
+    let threshold = threshold_str
+      .parse::<u64>()
+      .map_err(|_e| format!("Failed to parse the weight threshold {}", threshold_str))?;
+    QuorumPolicyConfig::Weighted(weights, threshold)
+  } else if let Some(x) = cli_matches.value_of("failure_domains") {
+    QuorumPolicyConfig::FailureDomains(parse_uri_map(x)?)
+  } else {
+    QuorumPolicyConfig::Majority
+  };
+
+  let rollback_policy = match cli_matches.value_of("on_rollback") {
+    Some("quarantine") => RollbackPolicy::Quarantine,
+    Some("reconcile") => RollbackPolicy::Reconcile,
+    _ => RollbackPolicy::Refuse,
+  };
+
+  let res = CoordinatorState::new(
+    store,
+    &ledger_store_args,
+    num_grpc_channels,
+    rollback_policy,
+  )
+  .await;
   assert!(res.is_ok());
   let coordinator = res.unwrap();
+  if coordinator.set_quorum_policy(quorum_policy).is_err() {
+    panic!("Failed to set the quorum policy");
+  }
   let mut mutcoordinator = coordinator.clone();
 
   mutcoordinator.overwrite_variables(
@@ -644,9 +1348,17 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
   coordinator_ref.clone().start_auto_scheduler().await;
   // Start the REST server for management
   let control_server = Router::new()
+      .route("/healthz", get(get_health))
+      .route("/readyz", get(get_readiness))
       .route("/endorsers/:uri", get(get_endorser).put(new_endorser).delete(delete_endorser))
+      .route("/rotate/:uris", put(rotate_endorsers))
+      .route("/plan", get(plan_view_change))
+      .route("/plan/:uris", get(plan_view_change_with_endorsers))
       .route("/pingallendorsers", get(ping_all_endorsers))
       .route("/timeoutmap", get(get_timeout_map))
+      .route("/viewledger", get(get_view_ledger))
+      .route("/quarantine", get(get_quarantined_ledgers))
+      .route("/quarantine/:handle", delete(release_quarantined_ledger))
       // Add middleware to all routes
       .layer(
           ServiceBuilder::new()
@@ -663,10 +1375,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
       .await;
   });
 
+  let health_service =
+    HealthService::new_server(coordinator_ref.clone(), &["coordinator_proto.Call"]);
   let job2 = tokio::spawn(async move {
     println!("Running gRPC Coordinator Service at {:?}", addr);
     let _ = Server::builder()
       .add_service(CallServer::new(server))
+      .add_service(health_service)
       .serve(addr)
       .await;
   });
@@ -680,10 +1395,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 mod tests {
   use crate::{
     coordinator_proto::{
-      call_server::Call, AppendReq, AppendResp, NewLedgerReq, NewLedgerResp, ReadByIndexReq,
-      ReadByIndexResp, ReadLatestReq, ReadLatestResp, ReadViewTailReq, ReadViewTailResp, PingAllReq
+      call_server::Call, AppendReq, AppendResp, CheckpointLedgerReq, CheckpointLedgerResp,
+      DeleteLedgerReq, DeleteLedgerResp, LedgerCheckpoint, NewLedgerReq, NewLedgerResp, PingAllReq,
+      ReadByIndexReq, ReadByIndexResp, ReadLatestReq, ReadLatestResp, ReadViewTailReq,
+      ReadViewTailResp, WatchLedgerReq, WatchLedgerResp,
     },
-    CoordinatorServiceState, CoordinatorState,
+    CoordinatorServiceState, CoordinatorState, RollbackPolicy,
   };
   use ledger::{Block, CustomSerde, NimbleDigest, VerifierState};
   use rand::Rng;
@@ -694,6 +1411,7 @@ mod tests {
     process::{Child, Command, Stdio},
     sync::Arc,
   };
+  use tokio_stream::StreamExt;
 
   struct BoxChild {
     pub child: Child,
@@ -811,7 +1529,7 @@ mod tests {
     println!("Endorser started");
     // Create the coordinator
     let coordinator = Arc::new(
-      CoordinatorState::new(&store, &ledger_store_args, None)
+      CoordinatorState::new(&store, &ledger_store_args, None, RollbackPolicy::Refuse)
         .await
         .unwrap(),
     );
@@ -922,6 +1640,30 @@ mod tests {
       assert!(res.is_ok());
     }
 
+    // Step 4a: the status tells a duplicate ledger apart from a stale expected height
+    let req = tonic::Request::new(NewLedgerReq {
+      handle: handle.clone(),
+      block: block_bytes.to_vec(),
+    });
+    let status = server.new_ledger(req).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::AlreadyExists);
+
+    let req = tonic::Request::new(AppendReq {
+      handle: handle.clone(),
+      block: b1.clone(),
+      expected_height: 1,
+    });
+    let status = server.append(req).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::Aborted);
+
+    let req = tonic::Request::new(ReadByIndexReq {
+      handle: handle.clone(),
+      index: 10,
+    });
+    let status = server.read_by_index(req).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::OutOfRange);
+    assert!(status.details().is_empty());
+
     // Step 4: Read Latest with the Nonce generated and check for new data
     let nonce = rand::thread_rng().gen::<[u8; 16]>();
     let latest_state_query = tonic::Request::new(ReadLatestReq {
@@ -997,6 +1739,17 @@ mod tests {
     println!("Applying ReadViewByIndexResp Response: {:?}", res);
     assert!(res.is_ok());
 
+    // Step 6a: watch the ledger from its tail
+    let req = tonic::Request::new(WatchLedgerReq {
+      handle: handle.clone(),
+      index: 0,
+      from_tail: true,
+    });
+    let mut updates = server.watch_ledger(req).await.unwrap().into_inner();
+    let WatchLedgerResp { index, block, .. } = updates.next().await.unwrap().unwrap();
+    assert_eq!(index, expected_height as u64);
+    assert_eq!(block, b3.clone());
+
     // Step 7: Append after view change
     expected_height += 1;
 
@@ -1016,6 +1769,19 @@ mod tests {
     println!("Append verification: {:?}", res.is_ok());
     assert!(res.is_ok());
 
+    // Step 7a: the watcher receives the appended entry once it is endorsed
+    let WatchLedgerResp {
+      index,
+      block,
+      nonces,
+      receipts,
+    } = updates.next().await.unwrap().unwrap();
+    assert_eq!(index, expected_height as u64);
+    assert_eq!(block, message);
+    let res = vs.verify_read_by_index(&handle, &block, &nonces, expected_height, &receipts);
+    println!("Watched append verification: {:?}", res.is_ok());
+    assert!(res.is_ok());
+
     // Step 8: Read Latest with the Nonce generated and check for new data appended without condition
     let nonce = rand::thread_rng().gen::<[u8; 16]>();
     let latest_state_query = tonic::Request::new(ReadLatestReq {
@@ -1042,6 +1808,33 @@ mod tests {
     );
     assert!(is_latest_valid.is_ok());
 
+    // Step 8a: Checkpoint the ledger at height 1, after which the genesis entry is pruned
+    let req = tonic::Request::new(CheckpointLedgerReq {
+      handle: handle.clone(),
+      height: 1,
+    });
+    let CheckpointLedgerResp { receipts } =
+      server.checkpoint_ledger(req).await.unwrap().into_inner();
+    let res = vs.verify_checkpoint(&handle, 1, &receipts);
+    println!("Checkpoint verification: {:?}", res.is_ok());
+    assert!(res.is_ok());
+
+    let req = tonic::Request::new(ReadByIndexReq {
+      handle: handle.clone(),
+      index: 0,
+    });
+    let status = server.read_by_index(req).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::OutOfRange);
+    let LedgerCheckpoint { height, receipts } = prost::Message::decode(status.details()).unwrap();
+    assert_eq!(height, 1);
+    assert!(vs.verify_checkpoint(&handle, 1, &receipts).is_ok());
+
+    let req = tonic::Request::new(ReadByIndexReq {
+      handle: handle.clone(),
+      index: 1,
+    });
+    assert!(server.read_by_index(req).await.is_ok());
+
     // Step 9: create a ledger and append to it only on the first endorser
     let mut endorsers = server.get_state().get_endorser_pks();
     endorsers.remove(1);
@@ -1230,6 +2023,29 @@ mod tests {
     println!("Append verification: {:?}", res.is_ok());
     assert!(res.is_ok());
 
+    // Step 12a: Delete the ledger, after which appends are rejected
+    let req = tonic::Request::new(DeleteLedgerReq {
+      handle: new_handle.clone(),
+      expected_height: 3_u64,
+    });
+
+    let DeleteLedgerResp {
+      hash_nonces,
+      receipts,
+    } = server.delete_ledger(req).await.unwrap().into_inner();
+
+    let res = vs.verify_delete(&new_handle, &hash_nonces, 3, &receipts);
+    println!("Delete verification: {:?}", res.is_ok());
+    assert!(res.is_ok());
+
+    let req = tonic::Request::new(AppendReq {
+      handle: new_handle.clone(),
+      block: message.to_vec(),
+      expected_height: 4_u64,
+    });
+    let res = server.append(req).await;
+    assert!(res.is_err());
+
     if store != "memory" {
       // set up the endorsers to be at different heights
       let mut endorsers = server.get_state().get_endorser_pks();
@@ -1321,7 +2137,7 @@ mod tests {
       drop(server);
 
       let coordinator2 = Arc::new(
-        CoordinatorState::new(&store, &ledger_store_args, None)
+        CoordinatorState::new(&store, &ledger_store_args, None, RollbackPolicy::Refuse)
           .await
           .unwrap(),
       );
@@ -1475,7 +2291,7 @@ mod tests {
     println!("Endorser started");
     // Create the coordinator
     let coordinator = Arc::new(
-      CoordinatorState::new(&store, &ledger_store_args, None)
+      CoordinatorState::new(&store, &ledger_store_args, None, RollbackPolicy::Refuse)
         .await
         .unwrap(),
     );
@@ -1513,4 +2329,13 @@ mod tests {
       timeout_map
     );
   }
+
+  #[test]
+  fn test_parse_uri_map() {
+    let map = crate::parse_uri_map("http://[::1]:9090=2;http://[::1]:9091=eu;").unwrap();
+    assert_eq!(map.len(), 2);
+    assert_eq!(map["http://[::1]:9090"], "2");
+    assert_eq!(map["http://[::1]:9091"], "eu");
+    assert!(crate::parse_uri_map("http://[::1]:9090").is_err());
+  }
 }
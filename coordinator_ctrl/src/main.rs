@@ -9,6 +9,18 @@ struct EndorserOpResponse {
   pub pk: String,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+struct RotateEndorsersResponse {
+  #[serde(rename = "Added")]
+  pub added: Vec<String>,
+  #[serde(rename = "Retired")]
+  pub retired: Vec<String>,
+  #[serde(rename = "Steps")]
+  pub steps: Vec<String>,
+  #[serde(rename = "RolledBack")]
+  pub rolled_back: bool,
+}
+
 /// Main function to start the coordinator control client.
 #[tokio::main]
 async fn main() {
@@ -41,6 +53,27 @@ async fn main() {
         .takes_value(true)
         .help("Endorser to read"),
     )
+    .arg(
+      Arg::with_name("rotate")
+        .short("r")
+        .long("rotate")
+        .takes_value(true)
+        .help("Endorsers to rotate to, separated by ';'"),
+    )
+    .arg(
+      Arg::with_name("plan")
+        .long("plan")
+        .takes_value(true)
+        .min_values(0)
+        .help("Show the view change plan, optionally with endorsers to add, separated by ';'"),
+    )
+    .arg(
+      Arg::with_name("views")
+        .long("views")
+        .takes_value(true)
+        .min_values(0)
+        .help("Show every view in the view ledger, optionally exporting them as JSON to a file"),
+    )
     .arg(
       Arg::with_name("gettimeoutmap")
       .long("gettimeoutmap")
@@ -119,6 +152,126 @@ async fn main() {
     }
   }
 
+  // Rotates the active endorsers to a new set of endorsers.
+  if let Some(x) = cli_matches.value_of("rotate") {
+    let uris = base64_url::encode(&x);
+    let rotate_url = reqwest::Url::parse(&format!("{}/rotate/{}", coordinator_addr, uris)).unwrap();
+
+    let now = Instant::now();
+    let res = client.put(rotate_url).send().await;
+    println!("Rotation time: {} ms", now.elapsed().as_millis());
+
+    match res {
+      Ok(resp) => {
+        assert!(
+          resp.status() == reqwest::StatusCode::OK
+            || resp.status() == reqwest::StatusCode::CONFLICT
+        );
+        let rotate_resp: RotateEndorsersResponse = resp.json().await.unwrap();
+        for step in &rotate_resp.steps {
+          println!("rotate_endorsers: {}", step);
+        }
+        if rotate_resp.rolled_back {
+          // the rotation did not take effect, which scripts must not mistake for success
+          eprintln!("rotate_endorsers: rolled back");
+          std::process::exit(1);
+        } else {
+          println!(
+            "rotate_endorsers: added {:?} retired {:?}",
+            rotate_resp.added, rotate_resp.retired
+          );
+        }
+      },
+      Err(error) => {
+        eprintln!("rotate_endorsers failed: {:?}", error);
+        std::process::exit(1);
+      },
+    }
+  }
+
+  // Shows what a view change would do without applying it.
+  if cli_matches.is_present("plan") {
+    let plan_url = match cli_matches.value_of("plan") {
+      Some(x) => reqwest::Url::parse(&format!(
+        "{}/plan/{}",
+        coordinator_addr,
+        base64_url::encode(&x)
+      ))
+      .unwrap(),
+      None => reqwest::Url::parse(&format!("{}/plan", coordinator_addr)).unwrap(),
+    };
+    let res = client.get(plan_url).send().await;
+    match res {
+      Ok(resp) => {
+        assert!(resp.status() == reqwest::StatusCode::OK);
+        let plan: serde_json::Value = resp.json().await.unwrap();
+        println!("{}", serde_json::to_string_pretty(&plan).unwrap());
+      },
+      Err(error) => {
+        eprintln!("plan_view_change failed: {:?}", error);
+      },
+    }
+  }
+
+  // Walks the view ledger.
+  if cli_matches.is_present("views") {
+    let views_url = reqwest::Url::parse(&format!("{}/viewledger", coordinator_addr)).unwrap();
+    let res = client.get(views_url).send().await;
+    match res {
+      Ok(resp) => {
+        assert!(resp.status() == reqwest::StatusCode::OK);
+        // the views are described by the coordinator's ViewResponse
+        let views: serde_json::Value = resp.json().await.unwrap();
+        let list =
+          |value: &serde_json::Value, key: &str| value[key].as_array().cloned().unwrap_or_default();
+        let mut prev_endorsers = Vec::new();
+        for view in views.as_array().unwrap_or(&Vec::new()) {
+          let digest = match view["View"].as_str().unwrap_or_default() {
+            "" => "-",
+            v => v,
+          };
+          println!(
+            "view {}: {} created_at {} policy {} quorum {}",
+            view["Height"],
+            digest,
+            view["CreatedAt"],
+            view["Policy"].as_str().unwrap_or_default(),
+            view["Quorum"]
+          );
+          let endorsers = list(view, "Endorsers");
+          for endorser in &endorsers {
+            println!(
+              "  endorser {} {}",
+              endorser["Uri"].as_str().unwrap_or_default(),
+              endorser["PublicKey"].as_str().unwrap_or_default()
+            );
+          }
+          // signers come from this view or, when it replaced another view, from that one
+          for signer in list(view, "Signers") {
+            let uri = endorsers
+              .iter()
+              .chain(prev_endorsers.iter())
+              .find(|e| e["PublicKey"] == signer)
+              .and_then(|e| e["Uri"].as_str())
+              .unwrap_or("unknown");
+            println!("  signer {} {}", uri, signer.as_str().unwrap_or_default());
+          }
+          prev_endorsers = endorsers;
+        }
+
+        if let Some(path) = cli_matches.value_of("views") {
+          let json = serde_json::to_string_pretty(&views).unwrap();
+          if let Err(error) = std::fs::write(path, json) {
+            eprintln!("failed to export the views to {}: {:?}", path, error);
+          }
+        }
+      },
+      Err(error) => {
+        eprintln!("get_view_ledger failed: {:?}", error);
+      },
+    }
+  }
+
   // Retrieves the timeout map of endorsers.
   if cli_matches.is_present("gettimeoutmap") {
     let endorser_url = reqwest::Url::parse(&format!("{}/timeoutmap", coordinator_addr)).unwrap();
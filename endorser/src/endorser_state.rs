@@ -5,8 +5,8 @@ use itertools::Itertools;
 use ledger::endorser_proto::{EndorserMode, LedgerChunkEntry, LedgerTailMap, LedgerTailMapEntry};
 
 use ledger::{
-  produce_hash_of_state,
-  signature::{PrivateKey, PrivateKeyTrait, PublicKey},
+  is_tombstone, produce_hash_of_state,
+  signature::{PrivateKey, PrivateKeyTrait, PublicKey, SignatureScheme},
   Block, CustomSerde, Handle, IdSig, MetaBlock, NimbleDigest, NimbleHashTrait, Nonces, Receipt,
   Receipts,
 };
@@ -47,7 +47,12 @@ pub struct EndorserState {
 impl EndorserState {
   /// Creates a new instance of `EndorserState`.
   pub fn new() -> Self {
-    let private_key = PrivateKey::new();
+    EndorserState::new_with_scheme(SignatureScheme::default())
+  }
+
+  /// Creates a new instance of `EndorserState` that signs with the given scheme.
+  pub fn new_with_scheme(scheme: SignatureScheme) -> Self {
+    let private_key = PrivateKey::new_with_scheme(scheme);
     let public_key = private_key.get_public_key().unwrap();
     EndorserState {
       private_key,
@@ -318,6 +323,11 @@ impl EndorserState {
           None => Err(EndorserError::InvalidLedgerName),
           Some(protected_metablock) => {
             if let Ok(mut e) = protected_metablock.write() {
+              // a deleted ledger stays read-only until the next view change drops it
+              if is_tombstone(handle, &e.1) {
+                return Err(EndorserError::LedgerDeleted);
+              }
+
               let metablock = &e.0;
               // increment height and returning an error in case of overflow
               let height_plus_one = {
@@ -546,6 +556,15 @@ impl EndorserState {
     }
   }
 
+  /// Returns the mode of the endorser.
+  pub fn get_mode(&self) -> Result<EndorserMode, EndorserError> {
+    if let Ok(view_ledger_state) = self.view_ledger_state.read() {
+      Ok(view_ledger_state.endorser_mode)
+    } else {
+      Err(EndorserError::FailedToAcquireViewLedgerReadLock)
+    }
+  }
+
   /// Activates the endorser with the given parameters.
   ///
   /// # Arguments
@@ -872,6 +891,25 @@ mod tests {
     } else {
       panic!("Signature verification failed when it should not have failed");
     }
+
+    // Deleting the ledger appends its tombstone, after which appends are rejected
+    let tombstone = ledger::tombstone_block(&handle);
+    let res = endorser_state.append(
+      &handle,
+      &tombstone.hash(),
+      height_plus_one + 1,
+      &tombstone,
+      &Nonces::new(),
+    );
+    assert!(res.is_ok());
+    let res = endorser_state.append(
+      &handle,
+      &block_hash_to_append,
+      height_plus_one + 2,
+      &block_hash_to_append_data,
+      &Nonces::new(),
+    );
+    assert_eq!(res.unwrap_err(), EndorserError::LedgerDeleted);
   }
 
   #[test]
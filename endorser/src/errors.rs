@@ -34,4 +34,6 @@ pub enum EndorserError {
   NotActive,
   /// returned if the endorser is already activated
   AlreadyActivated,
+  /// returned if one attempts to append to a ledger whose tail is a tombstone
+  LedgerDeleted,
 }
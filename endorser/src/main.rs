@@ -1,10 +1,21 @@
 use crate::{endorser_state::EndorserState, errors::EndorserError};
 use clap::{App, Arg};
 use ledger::{
-  signature::PublicKeyTrait, Block, CustomSerde, MetaBlock, NimbleDigest, Nonces, Receipts,
+  signature::{PublicKeyTrait, SignatureScheme},
+  Block, CustomSerde, MetaBlock, NimbleDigest, Nonces, Receipts,
 };
+use std::sync::Arc;
 use tonic::{transport::Server, Code, Request, Response, Status};
 
+use axum::{
+  extract::Extension, http::StatusCode, response::IntoResponse, routing::get, Json, Router,
+};
+use ledger::{
+  endorser_proto::EndorserMode,
+  health::{HealthService, Readiness},
+};
+use serde_json::json;
+
 mod endorser_state;
 mod errors;
 
@@ -27,6 +38,13 @@ impl EndorserServiceState {
     }
   }
 
+  /// Creates a new instance of `EndorserServiceState` that signs with the given scheme.
+  pub fn new_with_scheme(scheme: SignatureScheme) -> Self {
+    EndorserServiceState {
+      state: EndorserState::new_with_scheme(scheme),
+    }
+  }
+
   /// Processes an error and returns a corresponding gRPC `Status`.
   ///
   /// # Arguments
@@ -62,6 +80,7 @@ impl EndorserServiceState {
       },
       EndorserError::NotInitialized => Status::unimplemented("Endorser is not initialized"),
       EndorserError::AlreadyFinalized => Status::unavailable("Endorser is already finalized"),
+      EndorserError::LedgerDeleted => Status::permission_denied("Ledger is deleted"),
       _ => Status::internal(default_msg),
     }
   }
@@ -403,6 +422,40 @@ impl EndorserCall for EndorserServiceState {
   }
 }
 
+/// An endorser is ready unless it is finalized, after which it no longer endorses anything. An
+/// uninitialized endorser is ready, since the coordinator must reach it to initialize it.
+#[tonic::async_trait]
+impl Readiness for EndorserServiceState {
+  async fn is_ready(&self) -> bool {
+    matches!(
+      self.state.get_mode(),
+      Ok(EndorserMode::Uninitialized) | Ok(EndorserMode::Initialized) | Ok(EndorserMode::Active)
+    )
+  }
+}
+
+/// Reports that the endorser is running.
+async fn get_health() -> impl IntoResponse {
+  (StatusCode::OK, Json(json!({ "Status": "ok" })))
+}
+
+/// Reports the mode of the endorser and whether it is ready.
+async fn get_readiness(
+  Extension(server): Extension<Arc<EndorserServiceState>>,
+) -> impl IntoResponse {
+  let ready = server.is_ready().await;
+  let mode = match server.state.get_mode() {
+    Ok(mode) => format!("{:?}", mode),
+    Err(_) => "Unknown".to_string(),
+  };
+  let status = if ready {
+    StatusCode::OK
+  } else {
+    StatusCode::SERVICE_UNAVAILABLE
+  };
+  (status, Json(json!({ "Ready": ready, "Mode": mode })))
+}
+
 /// Main function to start the endorser service.
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -420,18 +473,52 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .long("port")
         .help("The port number to run the Service On. Default: 9096")
         .default_value("9090"),
+    )
+    .arg(
+      Arg::with_name("scheme")
+        .short("s")
+        .long("scheme")
+        .help("The signature scheme of the endorser's key: p256 or ed25519")
+        .default_value("p256"),
+    )
+    .arg(
+      Arg::with_name("health_port")
+        .long("health-port")
+        .takes_value(true)
+        .help("The port number to serve /healthz and /readyz on"),
     );
   let cli_matches = config.get_matches();
   let hostname = cli_matches.value_of("host").unwrap();
   let port_number = cli_matches.value_of("port").unwrap();
   let addr = format!("{}:{}", hostname, port_number).parse()?;
-  let server = EndorserServiceState::new();
+  let scheme_str = cli_matches.value_of("scheme").unwrap();
+  let scheme = match scheme_str.parse::<SignatureScheme>() {
+    Ok(scheme) => scheme,
+    Err(_) => panic!("Unknown signature scheme {}", scheme_str),
+  };
+  let server = Arc::new(EndorserServiceState::new_with_scheme(scheme));
+
+  if let Some(health_port) = cli_matches.value_of("health_port") {
+    let health_addr = format!("{}:{}", hostname, health_port).parse()?;
+    let health_server = Router::new()
+      .route("/healthz", get(get_health))
+      .route("/readyz", get(get_readiness))
+      .layer(Extension(server.clone()));
+    tokio::spawn(async move {
+      println!("Endorser health checks at {:?}", health_addr);
+      let _ = axum::Server::bind(&health_addr)
+        .serve(health_server.into_make_service())
+        .await;
+    });
+  }
 
+  let health_service = HealthService::new_server(server.clone(), &["endorser_proto.EndorserCall"]);
   let job = tokio::spawn(async move {
     println!("Endorser host listening on {:?}", addr);
 
     let _ = Server::builder()
-      .add_service(EndorserCallServer::new(server))
+      .add_service(EndorserCallServer::from_arc(server))
+      .add_service(health_service)
       .serve(addr)
       .await;
   });
@@ -32,4 +32,57 @@ pub enum EndpointError {
   FailedToPingAllEndorsers,
   /// returned if failed to add endorsers
   FailedToAddEndorsers,
+  /// returned if the endpoint fails to assemble a verifiable freshness proof
+  FailedToCreateFreshnessProof,
+  /// returned if the counter was deleted
+  CounterDeleted,
+  /// returned if the endpoint fails to create a new log
+  FailedToCreateNewLog,
+  /// returned if the endpoint fails to verify a new log
+  FailedToVerifyNewLog,
+  /// returned if the endpoint fails to append to the log
+  FailedToAppendLog,
+  /// returned if the endpoint fails to verify the appended entry of the log
+  FailedToVerifyAppendedLog,
+  /// returned if the endpoint fails to read the log
+  FailedToReadLog,
+  /// returned if the endpoint fails to verify the read entry of the log
+  FailedToVerifyReadLog,
+  /// returned if the requested entry of the log was pruned, with the height and receipts of the
+  /// checkpoint at which the log now starts
+  LogEntryPruned { height: u64, receipts: Vec<u8> },
+  /// returned if the counter or log was deleted
+  LedgerDeleted,
+  /// returned if the coordinator rejects the arguments of a request
+  InvalidRequest,
+  /// returned if a client writes to a handle that the endpoint reserves for itself
+  ReservedHandle,
+  /// returned if a counter or log with the handle already exists
+  LedgerAlreadyExists,
+  /// returned if the counter or log does not exist
+  LedgerNotFound,
+  /// returned if the counter or log has no entry at the requested index
+  EntryNotFound,
+  /// returned if the expected counter or height does not match the counter or log
+  UnexpectedHeight,
+  /// returned if the coordinator quarantined the counter or log because its store is behind
+  LedgerQuarantined,
+  /// returned if the coordinator or a quorum of endorsers cannot be reached
+  Unavailable,
+  /// returned if the coordinator stored the write but a quorum of endorsers did not endorse it
+  NotEndorsed,
+  /// returned if the endpoint fails to watch the counter
+  FailedToWatchCounter,
+  /// returned if the endpoint fails to verify a change of a watched counter
+  FailedToVerifyWatchedCounter,
+  /// returned if the endpoint fails to verify the history of its keys in the key ledger
+  FailedToVerifyKeyHistory,
+  /// returned if the endpoint's key is not the latest one in the key ledger
+  KeyNotCurrent,
+  /// returned if the endpoint fails to rotate its key
+  FailedToRotateKey,
+  /// returned if the endpoint fails to follow a rotation of its key by another replica
+  FailedToRefreshKey,
+  /// returned if signatures of the endpoint's key cannot be encoded in the requested format
+  UnsupportedSignatureFormat,
 }
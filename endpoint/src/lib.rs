@@ -2,7 +2,7 @@ mod errors;
 
 use tonic::{
   transport::{Channel, Endpoint},
-  Request,
+  Code, Request, Status, Streaming,
 };
 
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -10,20 +10,35 @@ pub mod coordinator_proto {
   tonic::include_proto!("coordinator_proto");
 }
 
-use crate::errors::EndpointError;
+pub use crate::errors::EndpointError;
 use coordinator_proto::{
-  call_client::CallClient, AppendReq, AppendResp, NewLedgerReq, NewLedgerResp, ReadLatestReq,
-  ReadLatestResp, ReadViewByIndexReq, ReadViewByIndexResp, ReadViewTailReq, ReadViewTailResp, GetTimeoutMapReq, GetTimeoutMapResp, PingAllReq, PingAllResp, AddEndorsersReq, AddEndorsersResp
+  call_client::CallClient, AppendReq, AppendResp, NewLedgerReq, NewLedgerResp, ReadByIndexReq,
+  ReadByIndexResp, ReadLatestReq, ReadLatestResp, ReadViewByIndexReq, ReadViewByIndexResp, ReadViewTailReq, ReadViewTailResp, GetTimeoutMapReq, GetTimeoutMapResp, PingAllReq, PingAllResp, AddEndorsersReq, AddEndorsersResp,
+  LedgerCheckpoint, WatchLedgerReq, WatchLedgerResp,
 };
 use ledger::{
   errors::VerificationError,
-  signature::{PrivateKey, PrivateKeyTrait, PublicKey, PublicKeyTrait, Signature, SignatureTrait},
-  Block, CustomSerde, NimbleDigest, NimbleHashTrait, VerifierState,
+  freshness::FreshnessProof,
+  health::Readiness,
+  is_tombstone,
+  signature::{
+    PrivateKey, PrivateKeyTrait, PublicKey, PublicKeyTrait, Signature, SignatureScheme,
+    SignatureTrait,
+  },
+  Block, CustomSerde, NimbleDigest, NimbleHashTrait, Receipts, VerifierState,
 };
+
+use fs2::FileExt;
+use futures::stream::{self, Stream, StreamExt};
+pub use ledger::{health::HealthService, ViewRetention};
+use prost::Message;
 use rand::random;
 use std::{
-  collections::HashMap, convert::TryFrom, sync::{Arc, RwLock}
+  collections::HashMap, convert::{TryFrom, TryInto},
+  sync::{atomic::{AtomicBool, Ordering::SeqCst}, Arc, RwLock},
+  time::Duration,
 };
+use tokio::sync::Mutex;
 
 #[allow(dead_code)]
 enum MessageType {
@@ -33,10 +48,70 @@ enum MessageType {
   IncrementCounterResp,
   ReadCounterReq,
   ReadCounterResp,
+  NewLogReq,
+  NewLogResp,
+  AppendLogReq,
+  AppendLogResp,
+  ReadLogByIndexResp,
+  ReadLogLatestResp,
+  WatchCounterResp,
+  EndpointKey,
 }
 
 const DEFAULT_NUM_GRPC_CHANNELS: usize = 1;
 
+/// The prefix of the handles of the ledgers in which endpoints record the history of their
+/// public keys. Only endpoints write to handles with this prefix.
+const KEY_LEDGER_PREFIX: &[u8] = b"nimble.endpoint.keys.";
+
+/// The maximum number of operations of a batch that are in flight to the coordinator at a time.
+pub const MAX_BATCH_CONCURRENCY: usize = 64;
+
+/// How long a health check waits for the coordinator, in seconds.
+const COORDINATOR_HEALTH_TIMEOUT: u64 = 5;
+
+/// Maps the status of a failed call to the coordinator to the `EndpointError` that tells the
+/// client why, or to `default` if the client cannot act on the failure.
+fn from_status(status: &Status, default: EndpointError) -> EndpointError {
+  match status.code() {
+    Code::InvalidArgument => EndpointError::InvalidRequest,
+    Code::AlreadyExists => EndpointError::LedgerAlreadyExists,
+    Code::NotFound => EndpointError::LedgerNotFound,
+    Code::Aborted => EndpointError::UnexpectedHeight,
+    Code::PermissionDenied => EndpointError::LedgerDeleted,
+    Code::FailedPrecondition => EndpointError::LedgerQuarantined,
+    // a pruned entry comes with the checkpoint, while an entry past the tail comes with nothing
+    Code::OutOfRange => match LedgerCheckpoint::decode(status.details()) {
+      Ok(LedgerCheckpoint { height, receipts }) if height > 0 => {
+        EndpointError::LogEntryPruned { height, receipts }
+      },
+      _ => EndpointError::EntryNotFound,
+    },
+    Code::Unavailable => EndpointError::Unavailable,
+    // the coordinator stored the write, so repeating it fails
+    Code::DeadlineExceeded => EndpointError::NotEndorsed,
+    _ => default,
+  }
+}
+
+/// Keeps an error that tells the client why the coordinator refused a request, and otherwise
+/// replaces it with `default`.
+fn refine(error: EndpointError, default: EndpointError) -> EndpointError {
+  match error {
+    EndpointError::InvalidRequest
+    | EndpointError::LedgerAlreadyExists
+    | EndpointError::LedgerNotFound
+    | EndpointError::EntryNotFound
+    | EndpointError::UnexpectedHeight
+    | EndpointError::LedgerDeleted
+    | EndpointError::LedgerQuarantined
+    | EndpointError::LogEntryPruned { .. }
+    | EndpointError::Unavailable
+    | EndpointError::NotEndorsed => error,
+    _ => default,
+  }
+}
+
 #[derive(Debug, Clone)]
 pub struct Connection {
   clients: Vec<CallClient<Channel>>,
@@ -82,7 +157,7 @@ impl Connection {
       .await
       .map_err(|e| {
         eprintln!("Failed to create a new ledger {:?}", e);
-        EndpointError::FailedToCreateNewCounter
+        from_status(&e, EndpointError::FailedToCreateNewCounter)
       })?
       .into_inner();
     Ok(receipts)
@@ -109,7 +184,7 @@ impl Connection {
       .await
       .map_err(|e| {
         eprintln!("Failed to append to a ledger {:?}", e);
-        EndpointError::FailedToIncrementCounter
+        from_status(&e, EndpointError::FailedToIncrementCounter)
       })?
       .into_inner();
     Ok((hash_nonces, receipts))
@@ -134,12 +209,61 @@ impl Connection {
       .await
       .map_err(|e| {
         eprintln!("Failed to read a ledger {:?}", e);
-        EndpointError::FailedToReadCounter
+        from_status(&e, EndpointError::FailedToReadCounter)
+      })?
+      .into_inner();
+    Ok((block, nonces, receipts))
+  }
+
+  /// Reads a block from the ledger with the given handle by index.
+  pub async fn read_by_index(
+    &self,
+    handle: &[u8],
+    index: u64,
+  ) -> Result<(Vec<u8>, Vec<u8>, Vec<u8>), EndpointError> {
+    let ReadByIndexResp {
+      block,
+      nonces,
+      receipts,
+    } = self.clients[random::<usize>() % self.num_grpc_channels]
+      .clone()
+      .read_by_index(ReadByIndexReq {
+        handle: handle.to_vec(),
+        index,
+      })
+      .await
+      .map_err(|e| {
+        eprintln!("Failed to read a ledger by index {:?}", e);
+        from_status(&e, EndpointError::FailedToReadLog)
       })?
       .into_inner();
     Ok((block, nonces, receipts))
   }
 
+  /// Watches the ledger with the given handle, starting at the given index or at its tail.
+  /// The coordinator streams each entry once it is endorsed by a quorum.
+  pub async fn watch_ledger(
+    &self,
+    handle: &[u8],
+    index: u64,
+    from_tail: bool,
+  ) -> Result<Streaming<WatchLedgerResp>, EndpointError> {
+    let updates = self.clients[random::<usize>() % self.num_grpc_channels]
+      .clone()
+      .watch_ledger(WatchLedgerReq {
+        handle: handle.to_vec(),
+        index,
+        from_tail,
+      })
+      .await
+      .map_err(|e| {
+        eprintln!("Failed to watch a ledger {:?}", e);
+        from_status(&e, EndpointError::FailedToWatchCounter)
+      })?
+      .into_inner();
+    Ok(updates)
+  }
+
   /// Reads a block from the view ledger by index.
   pub async fn read_view_by_index(
     &self,
@@ -221,30 +345,204 @@ impl Connection {
 pub struct EndpointState {
   conn: Connection,
   id: NimbleDigest,
+  keys: RwLock<EndpointKeys>,
+  key_current: AtomicBool,
+  vs: Arc<RwLock<VerifierState>>,
+  view_update: Mutex<()>,
+  snapshot_path: Option<String>,
+}
+
+/// The endpoint's signing key and the history of its keys recorded in the key ledger. The
+/// history is empty if the endpoint generated its key, since that key does not outlive it.
+struct EndpointKeys {
   sk: PrivateKey,
   pk: PublicKey,
-  vs: Arc<RwLock<VerifierState>>,
+  ledger: Vec<u8>,
+  history: Vec<KeyRecord>,
+}
+
+/// An entry of the key ledger. The block is a public key followed by a signature over it by
+/// the previous key, or by the key itself for the first entry.
+#[derive(Clone, Debug)]
+struct KeyRecord {
+  pk: PublicKey,
+  block: Vec<u8>,
+  nonces: Vec<u8>,
+  receipts: Vec<u8>,
+}
+
+/// A public key of the endpoint, with the entry of the key ledger that records it.
+#[derive(Clone, Debug)]
+pub struct EndpointKey {
+  pub key_id: Vec<u8>,
+  pub public_key: Vec<u8>,
+  pub block: Vec<u8>,
+  pub nonces: Vec<u8>,
+  pub receipts: Vec<u8>,
+}
+
+/// Treats an empty request id as the absence of one, since proto3 cannot tell the two apart
+/// and every front end must record the same block for the same request.
+fn non_empty(request_id: Option<&[u8]>) -> Option<&[u8]> {
+  request_id.filter(|request_id| !request_id.is_empty())
+}
+
+/// Rejects a write by a client to a key ledger, which only endpoints may write to.
+fn check_handle(handle: &[u8]) -> Result<(), EndpointError> {
+  if handle.starts_with(KEY_LEDGER_PREFIX) {
+    return Err(EndpointError::ReservedHandle);
+  }
+  Ok(())
+}
+
+/// Returns the handle that belongs to a key of an endpoint: that of the key ledger if the key is
+/// the first one in it, or that of a ledger which links to the key ledger if the endpoint
+/// rotated to the key, so that an endpoint finds its key ledger from any key in the history.
+fn key_ledger_handle(pk: &PublicKey) -> Vec<u8> {
+  [KEY_LEDGER_PREFIX, &key_id(pk)].concat()
+}
+
+/// Identifies a public key of the endpoint by the hash of its serialization.
+fn key_id(pk: &PublicKey) -> Vec<u8> {
+  NimbleDigest::digest(&pk.to_bytes()).to_bytes()
+}
+
+/// Encodes a public key in the requested format.
+fn encode_public_key(pk: &PublicKey, pkformat: PublicKeyFormat) -> Vec<u8> {
+  match pkformat {
+    PublicKeyFormat::COMPRESSED => pk.to_bytes(),
+    PublicKeyFormat::DER => pk.to_der(),
+    _ => pk.to_uncompressed(),
+  }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug)]
 pub enum PublicKeyFormat {
   UNCOMPRESSED = 0,
   COMPRESSED = 1,
   DER = 2,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug)]
 pub enum SignatureFormat {
   RAW = 0,
   DER = 1,
 }
 
+/// An operation on a counter in a batch.
+#[derive(Clone, Debug)]
+pub enum CounterOp {
+  /// Creates a counter, as `EndpointState::new_counter` does.
+  New {
+    handle: Vec<u8>,
+    tag: Vec<u8>,
+    request_id: Option<Vec<u8>>,
+  },
+  /// Increments a counter, as `EndpointState::increment_counter` does.
+  Increment {
+    handle: Vec<u8>,
+    tag: Vec<u8>,
+    expected_counter: u64,
+    request_id: Option<Vec<u8>>,
+  },
+  /// Reads a counter, as `EndpointState::read_counter` does.
+  Read { handle: Vec<u8>, nonce: Vec<u8> },
+}
+
+/// The endpoint's signature over a response, with the id of the key that made it. The signed
+/// message is the digest of the response's message followed by the key id, so the key id cannot
+/// be swapped for that of another key in the history.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResponseSignature {
+  pub signature: Vec<u8>,
+  pub key_id: Vec<u8>,
+}
+
+/// The signed result of a successful operation on a counter in a batch.
+#[derive(Clone, Debug)]
+pub enum CounterOpResult {
+  /// `replayed` is true if the operation retried a creation that already landed.
+  New {
+    signature: ResponseSignature,
+    replayed: bool,
+  },
+  Increment {
+    signature: ResponseSignature,
+  },
+  Read {
+    tag: Vec<u8>,
+    counter: u64,
+    signature: ResponseSignature,
+  },
+}
+
+/// A verified change of a watched counter. The block, nonces, and receipts are those the
+/// coordinator returned, so that the watcher can verify the change against the view ledger.
+#[derive(Clone, Debug)]
+pub struct CounterUpdate {
+  pub counter: u64,
+  pub tag: Vec<u8>,
+  pub block: Vec<u8>,
+  pub nonces: Vec<u8>,
+  pub receipts: Vec<u8>,
+  pub signature: ResponseSignature,
+}
+
+/// The state of the endpoint that its readiness depends on.
+#[derive(Clone, Debug)]
+pub struct EndpointHealth {
+  /// the coordinator answered a read of the view ledger in time
+  pub coordinator_reachable: bool,
+  /// the verifier state holds every view in the view ledger
+  pub view_current: bool,
+  /// the endpoint is catching up with the view ledger
+  pub updating_view: bool,
+  /// the endpoint signs with the latest key in the key ledger
+  pub key_current: bool,
+}
+
+impl EndpointHealth {
+  /// Returns true if the endpoint can serve requests without delay.
+  pub fn is_ready(&self) -> bool {
+    self.coordinator_reachable && self.view_current && !self.updating_view && self.key_current
+  }
+}
+
+/// Reads a snapshot of the verifier state persisted by `EndpointState::persist_snapshot`.
+/// The snapshot is only used if it was signed with the endpoint's current key.
+fn load_snapshot(path: &str, pk: &PublicKey) -> Option<VerifierState> {
+  let bytes = std::fs::read(path).ok()?;
+  if bytes.len() < Signature::num_bytes() {
+    eprintln!("Ignoring a truncated snapshot at {}", path);
+    return None;
+  }
+  let (sig, snapshot) = bytes.split_at(Signature::num_bytes());
+  let res = Signature::from_bytes(sig).map(|sig| sig.verify(pk, snapshot));
+  if !matches!(res, Ok(Ok(()))) {
+    eprintln!(
+      "Ignoring a snapshot at {} not signed by this endpoint",
+      path
+    );
+    return None;
+  }
+  let res = VerifierState::from_snapshot(snapshot);
+  if let Err(e) = &res {
+    eprintln!("Ignoring an invalid snapshot at {}: {:?}", path, e);
+  }
+  res.ok()
+}
+
 impl EndpointState {
   /// Creates a new endpoint state.
+  ///
+  /// If `snapshot_path` holds a snapshot of the verifier state signed with the endpoint's key,
+  /// the endpoint resumes from it and only reads the view ledger entries appended since.
+  /// The snapshot is kept up to date as the endpoint learns about new views.
   pub async fn new(
     hostname: String,
     pem_opt: Option<String>,
     num_grpc_channels_opt: Option<usize>,
+    snapshot_path: Option<String>,
   ) -> Result<Self, EndpointError> {
     // make a connection to the coordinator
     let conn = {
@@ -258,8 +556,21 @@ impl EndpointState {
       }
     };
 
+    // produce a private key pair to sign responses
+    let sk = if let Some(pem) = &pem_opt {
+      let res = PrivateKey::from_pem(pem.as_bytes());
+      if let Err(error) = res {
+        panic!("Endpoint Error: {:?}", error);
+      }
+      res.unwrap()
+    } else {
+      PrivateKey::new()
+    };
+
+    let pk = sk.get_public_key().unwrap();
+
     // initialize id and vs
-    let (id, vs) = {
+    let (id, vs, resumed) = {
       let mut vs = VerifierState::default();
 
       let (block, _r) = conn.read_view_by_index(1usize).await.unwrap();
@@ -268,39 +579,122 @@ impl EndpointState {
       let id = Block::from_bytes(&block).unwrap().hash();
       vs.set_group_identity(id);
 
-      let (block, receipts, height, attestations) = conn.read_view_tail().await.unwrap();
-      let res = vs.apply_view_change(&block, &receipts, Some(&attestations));
-      assert!(res.is_ok());
-
-      for index in (1..height).rev() {
-        let (block, receipts) = conn.read_view_by_index(index).await.unwrap();
-        let res = vs.apply_view_change(&block, &receipts, None);
+      let snapshot = snapshot_path
+        .as_ref()
+        .and_then(|path| load_snapshot(path, &pk))
+        .filter(|snapshot| *snapshot.get_group_identity() == id);
+      if let Some(snapshot) = snapshot {
+        (id, snapshot, true)
+      } else {
+        let (block, receipts, height, attestations) = conn.read_view_tail().await.unwrap();
+        let res = vs.apply_view_change(&block, &receipts, Some(&attestations));
         assert!(res.is_ok());
-      }
 
-      (id, vs)
-    };
+        for index in (1..height).rev() {
+          let (block, receipts) = conn.read_view_by_index(index).await.unwrap();
+          let res = vs.apply_view_change(&block, &receipts, None);
+          assert!(res.is_ok());
+        }
 
-    // produce a private key pair to sign responses
-    let sk = if let Some(pem) = pem_opt {
-      let res = PrivateKey::from_pem(pem.as_bytes());
-      if let Err(error) = res {
-        panic!("Endpoint Error: {:?}", error);
+        (id, vs, false)
       }
-      res.unwrap()
-    } else {
-      PrivateKey::new()
     };
 
-    let pk = sk.get_public_key().unwrap();
-
-    Ok(EndpointState {
+    let durable_key = pem_opt.is_some();
+    let endpoint_state = EndpointState {
       conn,
       id,
-      sk,
-      pk,
+      keys: RwLock::new(EndpointKeys {
+        sk,
+        pk,
+        ledger: Vec::new(),
+        history: Vec::new(),
+      }),
+      key_current: AtomicBool::new(true),
       vs: Arc::new(RwLock::new(vs)),
-    })
+      view_update: Mutex::new(()),
+      snapshot_path,
+    };
+
+    if resumed {
+      // catch up with the views created since the snapshot was taken
+      endpoint_state.update_view().await?;
+    } else {
+      endpoint_state.persist_snapshot();
+    }
+
+    if durable_key {
+      endpoint_state.load_key_history().await?;
+    } else {
+      eprintln!("The endpoint generated its key, so it does not record the key in the key ledger");
+    }
+
+    Ok(endpoint_state)
+  }
+
+  /// Sets which views the endpoint accepts receipts from; receipts from older views are rejected.
+  pub fn set_view_retention(&self, retention: ViewRetention) -> Result<(), EndpointError> {
+    if let Ok(mut vs_wr) = self.vs.write() {
+      vs_wr.set_view_retention(retention);
+    } else {
+      return Err(EndpointError::FailedToAcquireWriteLock);
+    }
+    self.persist_snapshot();
+    Ok(())
+  }
+
+  /// Persists a signed snapshot of the verifier state, if the endpoint was given a snapshot path.
+  fn persist_snapshot(&self) {
+    let path = match &self.snapshot_path {
+      Some(path) => path,
+      None => return,
+    };
+
+    let (snapshot, height) = if let Ok(vs_rd) = self.vs.read() {
+      (vs_rd.to_snapshot(), vs_rd.get_view_ledger_height())
+    } else {
+      eprintln!("Failed to acquire the read lock to snapshot the verifier state");
+      return;
+    };
+
+    // replicas that share the path must not replace a snapshot with more views, so the snapshot
+    // is only replaced under a lock, held until the lock file is closed, while its height is no
+    // greater than ours
+    let lock_path = format!("{}.lock", path);
+    let lock = std::fs::OpenOptions::new()
+      .write(true)
+      .create(true)
+      .truncate(false)
+      .open(&lock_path)
+      .and_then(|lock| lock.lock_exclusive().map(|_| lock));
+    let _lock = match lock {
+      Ok(lock) => lock,
+      Err(e) => {
+        eprintln!("Failed to lock the snapshot at {}: {:?}", lock_path, e);
+        return;
+      },
+    };
+    let pk = self.keys.read().unwrap().pk.clone();
+    if let Some(existing) = load_snapshot(path, &pk) {
+      if existing.get_view_ledger_height() > height {
+        return;
+      }
+    }
+
+    let sig = self.sign(&snapshot);
+
+    let mut bytes = sig.to_bytes();
+    bytes.extend(snapshot);
+    // write to a temporary file first so that a crash never leaves a partial snapshot behind,
+    // with a name of its own so that replicas do not write to the same one
+    let tmp_path = format!("{}.{}.tmp", path, random::<u64>());
+    let res = std::fs::write(&tmp_path, &bytes).and_then(|_| std::fs::rename(&tmp_path, path));
+    if let Err(e) = res {
+      eprintln!(
+        "Failed to persist the verifier state snapshot to {}: {:?}",
+        path, e
+      );
+    }
   }
 
   /// Gets the identity of the endpoint.
@@ -308,19 +702,418 @@ impl EndpointState {
     &self,
     pkformat: PublicKeyFormat,
   ) -> Result<(Vec<u8>, Vec<u8>), EndpointError> {
-    let public_key = self.sk.get_public_key().unwrap();
-    Ok((
-      self.id.to_bytes(),
-      match pkformat {
-        PublicKeyFormat::COMPRESSED => public_key.to_bytes(),
-        PublicKeyFormat::DER => public_key.to_der(),
-        _ => public_key.to_uncompressed(),
+    let keys = self
+      .keys
+      .read()
+      .map_err(|_e| EndpointError::FailedToAcquireReadLock)?;
+    Ok((self.id.to_bytes(), encode_public_key(&keys.pk, pkformat)))
+  }
+
+  /// Gets the id of the endpoint's current key.
+  pub fn get_key_id(&self) -> Result<Vec<u8>, EndpointError> {
+    let keys = self
+      .keys
+      .read()
+      .map_err(|_e| EndpointError::FailedToAcquireReadLock)?;
+    Ok(key_id(&keys.pk))
+  }
+
+  /// Gets the history of the endpoint's keys from the first to the current one, each with the
+  /// entry of the key ledger that records it, so that clients can verify signatures made with
+  /// an older key.
+  pub fn get_key_history(
+    &self,
+    pkformat: PublicKeyFormat,
+  ) -> Result<Vec<EndpointKey>, EndpointError> {
+    let keys = self
+      .keys
+      .read()
+      .map_err(|_e| EndpointError::FailedToAcquireReadLock)?;
+    Ok(
+      keys
+        .history
+        .iter()
+        .map(|record| EndpointKey {
+          key_id: key_id(&record.pk),
+          public_key: encode_public_key(&record.pk, pkformat),
+          block: record.block.clone(),
+          nonces: record.nonces.clone(),
+          receipts: record.receipts.clone(),
+        })
+        .collect(),
+    )
+  }
+
+  /// Constructs an entry at `height` of the key ledger with the given handle that records `pk`,
+  /// signed by `signer`.
+  fn key_block(&self, ledger: &[u8], signer: &PrivateKey, pk: &PublicKey, height: u64) -> Vec<u8> {
+    let pk_bytes = pk.to_bytes();
+    let msg = self.log_message(MessageType::EndpointKey, ledger, height, &pk_bytes, None);
+    let sig = signer.sign(&msg.to_bytes()).unwrap();
+    [pk_bytes, sig.to_bytes()].concat()
+  }
+
+  /// Returns the public key recorded at `height` of the key ledger with the given handle after
+  /// checking that it is signed by the previous key, or by itself if it is the first, in which
+  /// case the handle must be its own.
+  fn open_key_block(
+    &self,
+    ledger: &[u8],
+    block: &[u8],
+    height: u64,
+    prev: Option<&PublicKey>,
+  ) -> Result<PublicKey, EndpointError> {
+    if block.len() != PublicKey::num_bytes() + Signature::num_bytes() {
+      return Err(EndpointError::FailedToVerifyKeyHistory);
+    }
+    let (pk_bytes, sig) = block.split_at(PublicKey::num_bytes());
+    let pk =
+      PublicKey::from_bytes(pk_bytes).map_err(|_e| EndpointError::FailedToVerifyKeyHistory)?;
+    let sig = Signature::from_bytes(sig).map_err(|_e| EndpointError::FailedToVerifyKeyHistory)?;
+
+    let msg = self.log_message(MessageType::EndpointKey, ledger, height, pk_bytes, None);
+    if sig.verify(prev.unwrap_or(&pk), &msg.to_bytes()).is_err() {
+      return Err(EndpointError::FailedToVerifyKeyHistory);
+    }
+    if prev.is_none() && key_ledger_handle(&pk) != ledger {
+      return Err(EndpointError::FailedToVerifyKeyHistory);
+    }
+    Ok(pk)
+  }
+
+  /// Returns the handle of the key ledger that records `pk`, following the link from the handle
+  /// of `pk` if the endpoint rotated to it, or `None` if the handle of `pk` is not in use.
+  async fn find_key_ledger(&self, pk: &PublicKey) -> Result<Option<Vec<u8>>, EndpointError> {
+    let handle = key_ledger_handle(pk);
+    let (block, nonces, receipts) = match self.conn.read_by_index(&handle, 0).await {
+      Ok(res) => res,
+      Err(EndpointError::LedgerNotFound) | Err(EndpointError::EntryNotFound) => return Ok(None),
+      Err(e) => return Err(e),
+    };
+    self
+      .verify_with_view_update(
+        |vs| vs.verify_read_by_index(&handle, &block, &nonces, 0, &receipts),
+        EndpointError::FailedToVerifyKeyHistory,
+      )
+      .await?;
+
+    // the first entry of a key ledger is a key, and that of a link is the handle it links to
+    if block.len() == PublicKey::num_bytes() + Signature::num_bytes() {
+      Ok(Some(handle))
+    } else if block.starts_with(KEY_LEDGER_PREFIX) {
+      Ok(Some(block))
+    } else {
+      Err(EndpointError::FailedToVerifyKeyHistory)
+    }
+  }
+
+  /// Reads and verifies every entry of the key ledger with the given handle. The history is
+  /// empty if the ledger does not exist yet.
+  async fn read_key_history(&self, ledger: &[u8]) -> Result<Vec<KeyRecord>, EndpointError> {
+    let mut history: Vec<KeyRecord> = Vec::new();
+    loop {
+      let index = history.len();
+      let (block, nonces, receipts) = match self.conn.read_by_index(ledger, index as u64).await {
+        Ok(res) => res,
+        Err(EndpointError::LedgerNotFound) | Err(EndpointError::EntryNotFound) => {
+          return Ok(history)
+        },
+        Err(e) => return Err(e),
+      };
+      self
+        .verify_with_view_update(
+          |vs| vs.verify_read_by_index(ledger, &block, &nonces, index, &receipts),
+          EndpointError::FailedToVerifyKeyHistory,
+        )
+        .await?;
+      let prev = history.last().map(|r| &r.pk);
+      let pk = self.open_key_block(ledger, &block, index as u64, prev)?;
+      history.push(KeyRecord {
+        pk,
+        block,
+        nonces,
+        receipts,
+      });
+    }
+  }
+
+  /// Loads the history of the endpoint's keys from the key ledger that records its key, and
+  /// creates a key ledger with the endpoint's key as the first one if there is none. An endpoint
+  /// started with an older key of its history only verifies, and signs nothing until it is
+  /// given the latest key. It refuses to start with a key that is not in the history at all.
+  async fn load_key_history(&self) -> Result<(), EndpointError> {
+    let (pk, handle, genesis) = {
+      let keys = self
+        .keys
+        .read()
+        .map_err(|_e| EndpointError::FailedToAcquireReadLock)?;
+      let handle = key_ledger_handle(&keys.pk);
+      let genesis = self.key_block(&handle, &keys.sk, &keys.pk, 0);
+      (keys.pk.clone(), handle, genesis)
+    };
+
+    let ledger = loop {
+      if let Some(ledger) = self.find_key_ledger(&pk).await? {
+        break ledger;
+      }
+      // another replica with the same key may create the ledger first, and then it is found
+      // on the next try
+      match self.conn.new_ledger(&handle, &genesis).await {
+        Ok(receipts) => {
+          self
+            .verify_with_view_update(
+              |vs| vs.verify_new_ledger(&handle, &genesis, &receipts),
+              EndpointError::FailedToVerifyKeyHistory,
+            )
+            .await?
+        },
+        Err(EndpointError::LedgerAlreadyExists) => {},
+        Err(e) => return Err(e),
+      }
+    };
+
+    let history = self.read_key_history(&ledger).await?;
+    let pk_bytes = pk.to_bytes();
+    if !history
+      .iter()
+      .any(|record| record.pk.to_bytes() == pk_bytes)
+    {
+      eprintln!("The key of the endpoint is not in its key ledger");
+      return Err(EndpointError::KeyNotCurrent);
+    }
+    let current = history.last().map(|record| record.pk.to_bytes()) == Some(pk_bytes);
+    if !current {
+      eprintln!("The key of the endpoint is an older one in its key ledger, so it only verifies");
+    }
+
+    let mut keys = self
+      .keys
+      .write()
+      .map_err(|_e| EndpointError::FailedToAcquireWriteLock)?;
+    keys.ledger = ledger;
+    keys.history = history;
+    drop(keys);
+    self.key_current.store(current, SeqCst);
+    Ok(())
+  }
+
+  /// Follows a rotation of the key by another replica: switches to the key in the given PEM if
+  /// it is the latest one in the key ledger, and refreshes the history. Replicas that share a
+  /// key file call it periodically with the contents of the file.
+  ///
+  /// # Returns
+  ///
+  /// `KeyNotCurrent` if neither the endpoint's key nor the given one is the latest key, in which
+  /// case `is_key_current` is false, and the endpoint signs nothing, until the key file is
+  /// updated.
+  pub async fn refresh_key(&self, pem: &str) -> Result<(), EndpointError> {
+    let sk = PrivateKey::from_pem(pem.as_bytes()).map_err(|e| {
+      eprintln!("Failed to parse the key {:?}", e);
+      EndpointError::FailedToRefreshKey
+    })?;
+    let pk = sk.get_public_key().unwrap();
+
+    let ledger = {
+      let keys = self
+        .keys
+        .read()
+        .map_err(|_e| EndpointError::FailedToAcquireReadLock)?;
+      keys.ledger.clone()
+    };
+    if ledger.is_empty() {
+      // a generated key has no history to follow
+      return Ok(());
+    }
+
+    let history = self.read_key_history(&ledger).await?;
+    let latest = history.last().map(|record| record.pk.to_bytes());
+
+    let mut keys = self
+      .keys
+      .write()
+      .map_err(|_e| EndpointError::FailedToAcquireWriteLock)?;
+    let res = if latest == Some(keys.pk.to_bytes()) {
+      keys.history = history;
+      Ok(())
+    } else if latest == Some(pk.to_bytes()) {
+      *keys = EndpointKeys {
+        sk,
+        pk,
+        ledger,
+        history,
+      };
+      Ok(())
+    } else {
+      keys.history = history;
+      Err(EndpointError::KeyNotCurrent)
+    };
+    drop(keys);
+
+    self.key_current.store(res.is_ok(), SeqCst);
+    res
+  }
+
+  /// Rotates the endpoint's signing key to the key in the given PEM. The new public key is
+  /// appended to the key ledger with a signature by the current key, and the endpoint signs
+  /// with the new key from then on. Blocks signed with an older key in the history remain
+  /// readable. Rotating to the key that is already the latest one is a no-op.
+  pub async fn rotate_key(&self, pem: &str) -> Result<(), EndpointError> {
+    let new_sk = PrivateKey::from_pem(pem.as_bytes()).map_err(|e| {
+      eprintln!("Failed to parse the new key {:?}", e);
+      EndpointError::FailedToRotateKey
+    })?;
+    let new_pk = new_sk.get_public_key().unwrap();
+
+    let (ledger, height, block) = {
+      let keys = self
+        .keys
+        .read()
+        .map_err(|_e| EndpointError::FailedToAcquireReadLock)?;
+      if keys.history.is_empty() {
+        eprintln!("The endpoint cannot rotate a key that it generated");
+        return Err(EndpointError::FailedToRotateKey);
+      }
+      if keys.pk.to_bytes() == new_pk.to_bytes() {
+        return Ok(());
+      }
+      let height = keys.history.len() as u64;
+      let block = self.key_block(&keys.ledger, &keys.sk, &new_pk, height);
+      (keys.ledger.clone(), height, block)
+    };
+
+    // the handle of the new key links to the key ledger before the key is appended, so that the
+    // endpoint finds the ledger when it restarts with the new key
+    let link = key_ledger_handle(&new_pk);
+    match self.conn.new_ledger(&link, &ledger).await {
+      Ok(receipts) => {
+        self
+          .verify_with_view_update(
+            |vs| vs.verify_new_ledger(&link, &ledger, &receipts),
+            EndpointError::FailedToRotateKey,
+          )
+          .await?
       },
-    ))
+      // another replica may have linked the new key already
+      Err(EndpointError::LedgerAlreadyExists) => {
+        if self.find_key_ledger(&new_pk).await? != Some(ledger.clone()) {
+          eprintln!("The new key belongs to another key ledger");
+          return Err(EndpointError::FailedToRotateKey);
+        }
+      },
+      Err(e) => return Err(refine(e, EndpointError::FailedToRotateKey)),
+    }
+
+    match self.conn.append(&ledger, &block, height).await {
+      Ok(_) => {},
+      // another replica may have rotated to the same key already
+      Err(EndpointError::UnexpectedHeight) => {},
+      Err(e) => return Err(refine(e, EndpointError::FailedToRotateKey)),
+    }
+
+    let history = self.read_key_history(&ledger).await?;
+    match history.last() {
+      Some(latest) if latest.pk.to_bytes() == new_pk.to_bytes() => {},
+      _ => {
+        eprintln!("The key ledger does not end with the new key");
+        return Err(EndpointError::FailedToRotateKey);
+      },
+    }
+
+    let mut keys = self
+      .keys
+      .write()
+      .map_err(|_e| EndpointError::FailedToAcquireWriteLock)?;
+    *keys = EndpointKeys {
+      sk: new_sk,
+      pk: new_pk,
+      ledger,
+      history,
+    };
+    drop(keys);
+    self.key_current.store(true, SeqCst);
+
+    // a snapshot signed with the old key would be ignored on the next start
+    self.persist_snapshot();
+    Ok(())
+  }
+
+  /// Replaces the verifier state with the snapshot persisted by another replica that shares the
+  /// snapshot path, if the snapshot is signed with the endpoint's key and has more views. The
+  /// endpoint keeps its own view retention.
+  fn adopt_shared_snapshot(&self) {
+    let path = match &self.snapshot_path {
+      Some(path) => path,
+      None => return,
+    };
+    let pk = self.keys.read().unwrap().pk.clone();
+    let snapshot = match load_snapshot(path, &pk) {
+      Some(snapshot) => snapshot,
+      None => return,
+    };
+
+    if let Ok(mut vs_wr) = self.vs.write() {
+      if snapshot.get_group_identity() == vs_wr.get_group_identity()
+        && snapshot.get_view_ledger_height() > vs_wr.get_view_ledger_height()
+      {
+        let retention = vs_wr.get_view_retention();
+        *vs_wr = snapshot;
+        vs_wr.set_view_retention(retention);
+      }
+    } else {
+      eprintln!("Failed to acquire the write lock to adopt the shared snapshot");
+    }
+  }
+
+  /// Returns true while the endpoint is catching up with the view ledger, during which requests
+  /// that need a new view wait for it.
+  pub fn is_updating_view(&self) -> bool {
+    self.view_update.try_lock().is_err()
+  }
+
+  /// Returns false if the endpoint holds a key that was rotated away from, e.g., by another
+  /// replica, because the key file it was given does not hold the new key yet. The endpoint
+  /// signs nothing until then.
+  pub fn is_key_current(&self) -> bool {
+    self.key_current.load(SeqCst)
+  }
+
+  /// Checks whether the coordinator answers and the verifier state has every view of the view
+  /// ledger, along with `is_updating_view` and `is_key_current`.
+  pub async fn check_health(&self) -> EndpointHealth {
+    let updating_view = self.is_updating_view();
+    let key_current = self.is_key_current();
+    let read = self.conn.read_view_tail();
+    let tail_height =
+      match tokio::time::timeout(Duration::from_secs(COORDINATOR_HEALTH_TIMEOUT), read).await {
+        Ok(Ok((_block, _receipts, height, _attestations))) => Some(height),
+        _ => None,
+      };
+    let view_current = match (tail_height, self.vs.read()) {
+      (Some(height), Ok(vs_rd)) => height <= vs_rd.get_view_ledger_height(),
+      _ => false,
+    };
+
+    EndpointHealth {
+      coordinator_reachable: tail_height.is_some(),
+      view_current,
+      updating_view,
+      key_current,
+    }
+  }
+
+  /// Catches up with the view ledger, so that the endpoint rarely has to do it while serving a
+  /// request. Replicas call it periodically.
+  pub async fn refresh_view(&self) -> Result<(), EndpointError> {
+    self.update_view().await
   }
 
-  /// Updates the view of the endpoint.
+  /// Updates the view of the endpoint. Concurrent updates are serialized, and each first adopts
+  /// the shared snapshot in case another replica has verified the new views already.
   async fn update_view(&self) -> Result<(), EndpointError> {
+    let _update = self.view_update.lock().await;
+
+    self.adopt_shared_snapshot();
+
     let start_height = {
       if let Ok(vs_rd) = self.vs.read() {
         vs_rd.get_view_ledger_height() + 1
@@ -329,7 +1122,11 @@ impl EndpointState {
       }
     };
 
-    let (block, receipts, height, attestations) = self.conn.read_view_tail().await.unwrap();
+    let (block, receipts, height, attestations) = self.conn.read_view_tail().await?;
+    if height < start_height {
+      // an update that ran while this one waited already caught up
+      return Ok(());
+    }
     if let Ok(mut vs_wr) = self.vs.write() {
       let res = vs_wr.apply_view_change(&block, &receipts, Some(&attestations));
       if res.is_err() {
@@ -340,7 +1137,7 @@ impl EndpointState {
     }
 
     for index in (start_height..height).rev() {
-      let (block, receipts) = self.conn.read_view_by_index(index).await.unwrap();
+      let (block, receipts) = self.conn.read_view_by_index(index).await?;
       if let Ok(mut vs_wr) = self.vs.write() {
         let res = vs_wr.apply_view_change(&block, &receipts, None);
         if res.is_err() {
@@ -351,43 +1148,60 @@ impl EndpointState {
       }
     }
 
+    self.persist_snapshot();
+
     Ok(())
   }
 
   /// Creates a new counter with the given handle, tag, and signature format.
+  ///
+  /// If the client supplies a request id, a retry of a creation that already landed with the
+  /// same tag and request id returns the original signed response instead of an error. An empty
+  /// request id is the same as none.
   pub async fn new_counter(
     &self,
     handle: &[u8],
     tag: &[u8],
+    request_id: Option<&[u8]>,
     sigformat: SignatureFormat,
-  ) -> Result<Vec<u8>, EndpointError> {
-    // construct a block that unequivocally identifies the client's intent to create a new counter
-    let block = {
-      let msg = {
-        let s = format!(
-          "{}.{}.{}.{}.{}",
-          base64_url::encode(&(MessageType::NewCounterReq as u64).to_le_bytes()),
-          base64_url::encode(&self.id.to_bytes()),
-          base64_url::encode(handle),
-          base64_url::encode(&0_u64.to_le_bytes()),
-          base64_url::encode(tag),
-        );
-        NimbleDigest::digest(s.as_bytes())
-      };
+  ) -> Result<ResponseSignature, EndpointError> {
+    let (signature, _replayed) = self
+      .create_counter(handle, tag, request_id, sigformat)
+      .await?;
+    Ok(signature)
+  }
 
-      let sig = self.sk.sign(&msg.to_bytes()).unwrap();
+  /// Like `new_counter`, but also returns whether the response replays an earlier creation
+  /// rather than creating the counter.
+  pub async fn create_counter(
+    &self,
+    handle: &[u8],
+    tag: &[u8],
+    request_id: Option<&[u8]>,
+    sigformat: SignatureFormat,
+  ) -> Result<(ResponseSignature, bool), EndpointError> {
+    check_handle(handle)?;
+    self.check_can_sign(sigformat)?;
+    let request_id = non_empty(request_id);
 
-      // concatenate tag and signature
-      [tag.to_vec(), sig.to_bytes()].concat()
-    };
+    // construct a block that unequivocally identifies the client's intent to create a new counter
+    let block = self.counter_block(MessageType::NewCounterReq, handle, 0, tag, request_id);
 
     // issue a request to the coordinator and receive a response
-    let receipts = {
-      let res = self.conn.new_ledger(handle, &block).await;
-      if res.is_err() {
-        return Err(EndpointError::FailedToCreateNewCounter);
-      }
-      res.unwrap()
+    let receipts = match self.conn.new_ledger(handle, &block).await {
+      Ok(receipts) => receipts,
+      Err(e) => {
+        let e = refine(e, EndpointError::FailedToCreateNewCounter);
+        if let (EndpointError::LedgerAlreadyExists, Some(request_id)) = (&e, request_id) {
+          let res = self
+            .replay_counter_write(handle, tag, 0, request_id, sigformat)
+            .await;
+          if let Some(signature) = res {
+            return Ok((signature, true));
+          }
+        }
+        return Err(e);
+      },
     };
 
     // verify the response received from the coordinator;
@@ -433,23 +1247,45 @@ impl EndpointState {
       );
       NimbleDigest::digest(s.as_bytes())
     };
-    let sig = self.sk.sign(&msg.to_bytes()).unwrap();
-    let signature = match sigformat {
-      SignatureFormat::DER => sig.to_der(),
-      _ => sig.to_bytes(),
-    };
+    let signature = self.sign_response(&msg, sigformat)?;
+    Ok((signature, false))
+  }
 
-    Ok(signature)
+  /// Returns the original signed response to the creation of a counter if the client is
+  /// retrying it with the same tag and request id, and the counter was created. A caller that
+  /// charges clients for the counters they create checks this first, so a retry is not charged.
+  pub async fn replay_new_counter(
+    &self,
+    handle: &[u8],
+    tag: &[u8],
+    request_id: &[u8],
+    sigformat: SignatureFormat,
+  ) -> Option<ResponseSignature> {
+    check_handle(handle).ok()?;
+    self.check_can_sign(sigformat).ok()?;
+    let request_id = non_empty(Some(request_id))?;
+    self
+      .replay_counter_write(handle, tag, 0, request_id, sigformat)
+      .await
   }
 
   /// Increments the counter with the given handle, tag, expected counter, and signature format.
+  ///
+  /// If the client supplies a request id, a retry of an increment that already landed at the
+  /// expected counter with the same tag and request id returns the original signed response
+  /// instead of an error. An empty request id is the same as none.
   pub async fn increment_counter(
     &self,
     handle: &[u8],
     tag: &[u8],
     expected_counter: u64,
+    request_id: Option<&[u8]>,
     sigformat: SignatureFormat,
-  ) -> Result<Vec<u8>, EndpointError> {
+  ) -> Result<ResponseSignature, EndpointError> {
+    check_handle(handle)?;
+    self.check_can_sign(sigformat)?;
+    let request_id = non_empty(request_id);
+
     // convert u64 to usize, returning error
     let expected_height = {
       let res = usize::try_from(expected_counter);
@@ -460,32 +1296,29 @@ impl EndpointState {
     };
 
     // construct a block that unequivocally identifies the client's intent to update the counter and tag
-    let block = {
-      let msg = {
-        let s = format!(
-          "{}.{}.{}.{}.{}",
-          base64_url::encode(&(MessageType::IncrementCounterReq as u64).to_le_bytes()),
-          base64_url::encode(&self.id.to_bytes()),
-          base64_url::encode(handle),
-          base64_url::encode(&expected_counter.to_le_bytes()),
-          base64_url::encode(tag),
-        );
-        NimbleDigest::digest(s.as_bytes())
-      };
-
-      let sig = self.sk.sign(&msg.to_bytes()).unwrap();
-
-      [tag.to_vec(), sig.to_bytes()].concat()
-    };
+    let block = self.counter_block(
+      MessageType::IncrementCounterReq,
+      handle,
+      expected_counter,
+      tag,
+      request_id,
+    );
 
     // issue a request to the coordinator and receive a response
-    let (hash_nonces, receipts) = {
-      let res = self.conn.append(handle, &block, expected_counter).await;
-
-      if res.is_err() {
-        return Err(EndpointError::FailedToIncrementCounter);
-      }
-      res.unwrap()
+    let (hash_nonces, receipts) = match self.conn.append(handle, &block, expected_counter).await {
+      Ok(res) => res,
+      Err(e) => {
+        let e = refine(e, EndpointError::FailedToIncrementCounter);
+        if let (EndpointError::UnexpectedHeight, Some(request_id)) = (&e, request_id) {
+          let res = self
+            .replay_counter_write(handle, tag, expected_counter, request_id, sigformat)
+            .await;
+          if let Some(signature) = res {
+            return Ok(signature);
+          }
+        }
+        return Err(e);
+      },
     };
 
     // verify the response received from the coordinator; TODO: handle the case where vs does not have the returned view hash
@@ -530,13 +1363,7 @@ impl EndpointState {
       );
       NimbleDigest::digest(s.as_bytes())
     };
-    let sig = self.sk.sign(&msg.to_bytes()).unwrap();
-    let signature = match sigformat {
-      SignatureFormat::DER => sig.to_der(),
-      _ => sig.to_bytes(),
-    };
-
-    Ok(signature)
+    self.sign_response(&msg, sigformat)
   }
 
   /// Reads the counter with the given handle, nonce, and signature format.
@@ -545,16 +1372,13 @@ impl EndpointState {
     handle: &[u8],
     nonce: &[u8],
     sigformat: SignatureFormat,
-  ) -> Result<(Vec<u8>, u64, Vec<u8>), EndpointError> {
+  ) -> Result<(Vec<u8>, u64, ResponseSignature), EndpointError> {
     // issue a request to the coordinator and receive a response
-    let (block, nonces, receipts) = {
-      let res = self.conn.read_latest(handle, nonce).await;
-
-      if res.is_err() {
-        return Err(EndpointError::FailedToReadCounter);
-      }
-      res.unwrap()
-    };
+    let (block, nonces, receipts) = self
+      .conn
+      .read_latest(handle, nonce)
+      .await
+      .map_err(|e| refine(e, EndpointError::FailedToReadCounter))?;
 
     // verify the response received from the coordinator
     let res = {
@@ -591,36 +1415,13 @@ impl EndpointState {
       }
     };
 
-    // verify the integrity of the coordinator's response by checking the signature
-    if block.len() < Signature::num_bytes() {
-      return Err(EndpointError::FaieldToVerifyReadCounter);
+    // a tombstone endorsed by a quorum shows that the counter was deleted, not rolled back
+    if is_tombstone(&NimbleDigest::digest(handle), &Block::new(&block)) {
+      return Err(EndpointError::CounterDeleted);
     }
-    let (tag, sig) = {
-      let (t, s) = block.split_at(block.len() - Signature::num_bytes());
-      assert_eq!(t.len(), block.len() - Signature::num_bytes());
-      assert_eq!(s.len(), Signature::num_bytes());
-      (t, Signature::from_bytes(s).unwrap())
-    };
-
-    let msg = {
-      let s = format!(
-        "{}.{}.{}.{}.{}",
-        base64_url::encode(&if counter == 0 {
-          (MessageType::NewCounterReq as u64).to_le_bytes()
-        } else {
-          (MessageType::IncrementCounterReq as u64).to_le_bytes()
-        }),
-        base64_url::encode(&self.id.to_bytes()),
-        base64_url::encode(handle),
-        base64_url::encode(&(counter as u64).to_le_bytes()),
-        base64_url::encode(&tag),
-      );
-      NimbleDigest::digest(s.as_bytes())
-    };
 
-    if sig.verify(&self.pk, &msg.to_bytes()).is_err() {
-      return Err(EndpointError::FaieldToVerifyReadCounter);
-    }
+    // verify the integrity of the coordinator's response by checking the signature
+    let (tag, _request_id) = self.open_counter_block(handle, &block, counter as u64)?;
 
     // sign a message to the client that unequivocally identifies the counter and tag
     let msg = {
@@ -635,14 +1436,647 @@ impl EndpointState {
       );
       NimbleDigest::digest(s.as_bytes())
     };
-    let sig = self.sk.sign(&msg.to_bytes()).unwrap();
+    let signature = self.sign_response(&msg, sigformat)?;
+
+    // respond to the light client
+    Ok((tag.to_vec(), counter as u64, signature))
+  }
+
+  /// Runs a batch of operations on counters, pipelining up to `MAX_BATCH_CONCURRENCY` of them to
+  /// the coordinator at a time. Each operation succeeds or fails on its own, and the results are
+  /// in the order of the operations. Operations on the same counter are not ordered with respect
+  /// to each other, so a batch should touch a counter at most once.
+  pub async fn batch_counters(
+    &self,
+    ops: Vec<CounterOp>,
+    sigformat: SignatureFormat,
+  ) -> Vec<Result<CounterOpResult, EndpointError>> {
+    stream::iter(ops)
+      .map(|op| async move { self.run_counter_op(&op, sigformat).await })
+      .buffered(MAX_BATCH_CONCURRENCY)
+      .collect()
+      .await
+  }
+
+  /// Runs a single operation of a batch.
+  async fn run_counter_op(
+    &self,
+    op: &CounterOp,
+    sigformat: SignatureFormat,
+  ) -> Result<CounterOpResult, EndpointError> {
+    match op {
+      CounterOp::New {
+        handle,
+        tag,
+        request_id,
+      } => {
+        let (signature, replayed) = self
+          .create_counter(handle, tag, request_id.as_deref(), sigformat)
+          .await?;
+        Ok(CounterOpResult::New {
+          signature,
+          replayed,
+        })
+      },
+      CounterOp::Increment {
+        handle,
+        tag,
+        expected_counter,
+        request_id,
+      } => {
+        let signature = self
+          .increment_counter(
+            handle,
+            tag,
+            *expected_counter,
+            request_id.as_deref(),
+            sigformat,
+          )
+          .await?;
+        Ok(CounterOpResult::Increment { signature })
+      },
+      CounterOp::Read { handle, nonce } => {
+        let (tag, counter, signature) = self.read_counter(handle, nonce, sigformat).await?;
+        Ok(CounterOpResult::Read {
+          tag,
+          counter,
+          signature,
+        })
+      },
+    }
+  }
+
+  /// Watches the counter with the given handle, starting at `from_counter`, or at its current
+  /// value if it is `None`. Each change is verified like a read before it is signed and handed
+  /// out. The stream ends after the first error, e.g., with `CounterDeleted` once the counter
+  /// is deleted.
+  pub async fn watch_counter(
+    self: Arc<Self>,
+    handle: &[u8],
+    from_counter: Option<u64>,
+    sigformat: SignatureFormat,
+  ) -> Result<impl Stream<Item = Result<CounterUpdate, EndpointError>>, EndpointError> {
+    let updates = self
+      .conn
+      .watch_ledger(handle, from_counter.unwrap_or(0), from_counter.is_none())
+      .await
+      .map_err(|e| refine(e, EndpointError::FailedToWatchCounter))?;
+
+    let handle = handle.to_vec();
+    Ok(stream::unfold(Some(updates), move |updates| {
+      let state = self.clone();
+      let handle = handle.clone();
+      async move {
+        let mut updates = updates?;
+        let res = match updates.message().await {
+          Ok(Some(update)) => state.open_counter_update(&handle, update, sigformat).await,
+          Ok(None) => return None,
+          Err(status) => Err(from_status(&status, EndpointError::FailedToWatchCounter)),
+        };
+        let updates = if res.is_ok() { Some(updates) } else { None };
+        Some((res, updates))
+      }
+    }))
+  }
+
+  /// Verifies an entry of a watched counter streamed by the coordinator, and signs a message
+  /// that unequivocally identifies the counter and tag.
+  async fn open_counter_update(
+    &self,
+    handle: &[u8],
+    update: WatchLedgerResp,
+    sigformat: SignatureFormat,
+  ) -> Result<CounterUpdate, EndpointError> {
+    let WatchLedgerResp {
+      index,
+      block,
+      nonces,
+      receipts,
+    } = update;
+    let idx = usize::try_from(index).map_err(|_e| EndpointError::FailedToConvertCounter)?;
+
+    self
+      .verify_with_view_update(
+        |vs| vs.verify_read_by_index(handle, &block, &nonces, idx, &receipts),
+        EndpointError::FailedToVerifyWatchedCounter,
+      )
+      .await?;
+
+    if is_tombstone(&NimbleDigest::digest(handle), &Block::new(&block)) {
+      return Err(EndpointError::CounterDeleted);
+    }
+    let (tag, _request_id) = self.open_counter_block(handle, &block, index)?;
+    let tag = tag.to_vec();
+
+    let msg = self.log_message(MessageType::WatchCounterResp, handle, index, &tag, None);
+    Ok(CounterUpdate {
+      counter: index,
+      tag,
+      block,
+      nonces,
+      receipts,
+      signature: self.sign_response(&msg, sigformat)?,
+    })
+  }
+
+  /// Produces a freshness proof for the latest state of a counter that can be verified offline.
+  ///
+  /// # Arguments
+  ///
+  /// * `handle` - The handle of the counter.
+  /// * `nonce` - The nonce the proof is fresh with respect to.
+  ///
+  /// # Returns
+  ///
+  /// The serialized `FreshnessProof` or an `EndpointError`.
+  pub async fn get_freshness_proof(
+    &self,
+    handle: &[u8],
+    nonce: &[u8],
+  ) -> Result<Vec<u8>, EndpointError> {
+    let (block, nonces, receipts) = self
+      .conn
+      .read_latest(handle, nonce)
+      .await
+      .map_err(|e| refine(e, EndpointError::FailedToReadCounter))?;
+
+    // the views in which the receipts were issued
+    let views = {
+      let res = Receipts::from_bytes(&receipts);
+      if res.is_err() {
+        return Err(EndpointError::FailedToCreateFreshnessProof);
+      }
+      res
+        .unwrap()
+        .get()
+        .keys()
+        .map(|ex_meta_block| *ex_meta_block.get_view())
+        .collect::<Vec<NimbleDigest>>()
+    };
+
+    let (_block, _receipts, height, attestations) = self.conn.read_view_tail().await?;
+
+    // collect the view ledger from the genesis view up to the view of the receipts
+    let mut view_entries = Vec::new();
+    for index in 1..=height {
+      let (view_block, view_receipts) = self.conn.read_view_by_index(index).await?;
+      let view = {
+        let res = Receipts::from_bytes(&view_receipts).map(|r| r.get_metablock());
+        match res {
+          Ok(Ok(metablock)) => metablock.hash(),
+          _ => return Err(EndpointError::FailedToCreateFreshnessProof),
+        }
+      };
+      view_entries.push((view_block, view_receipts));
+      if views.contains(&view) {
+        break;
+      }
+    }
+
+    let proof = FreshnessProof::new(
+      handle,
+      &block,
+      &nonces,
+      &receipts,
+      view_entries,
+      &attestations,
+    );
+
+    // make sure that the proof is self-contained before handing it out
+    let res = proof.verify(&self.id, nonce);
+    if res.is_err() {
+      eprintln!("failed to verify the freshness proof {:?}", res);
+      return Err(EndpointError::FailedToCreateFreshnessProof);
+    }
+
+    Ok(proof.to_bytes())
+  }
+
+  /// Constructs a block that unequivocally identifies the client's intent to write the tag at
+  /// `counter`. Without a request id, the block is the tag followed by the endpoint's signature;
+  /// with one, the request id and its length (as a little-endian u64) sit between the two, and
+  /// the signature covers the request id as well.
+  fn counter_block(
+    &self,
+    msg_type: MessageType,
+    handle: &[u8],
+    counter: u64,
+    tag: &[u8],
+    request_id: Option<&[u8]>,
+  ) -> Vec<u8> {
+    let msg = self.log_message(msg_type, handle, counter, tag, request_id);
+    let sig = self.sign(&msg.to_bytes());
+    match request_id {
+      Some(request_id) => [
+        tag,
+        request_id,
+        &(request_id.len() as u64).to_le_bytes(),
+        &sig.to_bytes(),
+      ]
+      .concat(),
+      None => [tag.to_vec(), sig.to_bytes()].concat(),
+    }
+  }
+
+  /// Returns the tag and the request id (if any) of a block at `counter` after checking the
+  /// endpoint's signature. The signature tells the two layouts of `counter_block` apart.
+  fn open_counter_block<'a>(
+    &self,
+    handle: &[u8],
+    block: &'a [u8],
+    counter: u64,
+  ) -> Result<(&'a [u8], Option<&'a [u8]>), EndpointError> {
+    if block.len() < Signature::num_bytes() {
+      return Err(EndpointError::FaieldToVerifyReadCounter);
+    }
+    let (body, sig) = block.split_at(block.len() - Signature::num_bytes());
+    let sig = Signature::from_bytes(sig).map_err(|_e| EndpointError::FaieldToVerifyReadCounter)?;
+
+    let msg_type = || {
+      if counter == 0 {
+        MessageType::NewCounterReq
+      } else {
+        MessageType::IncrementCounterReq
+      }
+    };
+
+    if body.len() >= 8 {
+      let (rest, len) = body.split_at(body.len() - 8);
+      let len = u64::from_le_bytes(len.try_into().unwrap());
+      if len <= rest.len() as u64 {
+        let (tag, request_id) = rest.split_at(rest.len() - len as usize);
+        let msg = self.log_message(msg_type(), handle, counter, tag, Some(request_id));
+        if self.verify_own(&sig, &msg) {
+          return Ok((tag, Some(request_id)));
+        }
+      }
+    }
+
+    let msg = self.log_message(msg_type(), handle, counter, body, None);
+    if !self.verify_own(&sig, &msg) {
+      return Err(EndpointError::FaieldToVerifyReadCounter);
+    }
+    Ok((body, None))
+  }
+
+  /// Returns the original signed response to a write of the tag at `counter` if the client is
+  /// retrying it with the same request id, and the write already landed.
+  async fn replay_counter_write(
+    &self,
+    handle: &[u8],
+    tag: &[u8],
+    counter: u64,
+    request_id: &[u8],
+    sigformat: SignatureFormat,
+  ) -> Option<ResponseSignature> {
+    let index = usize::try_from(counter).ok()?;
+    let (block, nonces, receipts) = self.conn.read_by_index(handle, counter).await.ok()?;
+    self
+      .verify_with_view_update(
+        |vs| vs.verify_read_by_index(handle, &block, &nonces, index, &receipts),
+        EndpointError::FaieldToVerifyReadCounter,
+      )
+      .await
+      .ok()?;
+    self.replay_response(handle, &block, tag, counter, request_id, sigformat)
+  }
+
+  /// Returns the signed response to a write of the tag at `counter` if `block` records that
+  /// write with the same request id.
+  fn replay_response(
+    &self,
+    handle: &[u8],
+    block: &[u8],
+    tag: &[u8],
+    counter: u64,
+    request_id: &[u8],
+    sigformat: SignatureFormat,
+  ) -> Option<ResponseSignature> {
+    let (t, id) = self.open_counter_block(handle, block, counter).ok()?;
+    if t != tag || id != Some(request_id) {
+      return None;
+    }
+
+    let msg_type = if counter == 0 {
+      MessageType::NewCounterResp
+    } else {
+      MessageType::IncrementCounterResp
+    };
+    let msg = self.log_message(msg_type, handle, counter, tag, None);
+    self.sign_response(&msg, sigformat).ok()
+  }
+
+  /// Digests a message that unequivocally identifies the payload at `height` of a log, and the
+  /// nonce of a read if there is one. Counters reuse it with the tag as the payload, and the
+  /// client's request id in place of the nonce when writing.
+  fn log_message(
+    &self,
+    msg_type: MessageType,
+    handle: &[u8],
+    height: u64,
+    payload: &[u8],
+    nonce: Option<&[u8]>,
+  ) -> NimbleDigest {
+    let mut s = format!(
+      "{}.{}.{}.{}.{}",
+      base64_url::encode(&(msg_type as u64).to_le_bytes()),
+      base64_url::encode(&self.id.to_bytes()),
+      base64_url::encode(handle),
+      base64_url::encode(&height.to_le_bytes()),
+      base64_url::encode(payload),
+    );
+    if let Some(nonce) = nonce {
+      s.push('.');
+      s.push_str(&base64_url::encode(nonce));
+    }
+    NimbleDigest::digest(s.as_bytes())
+  }
+
+  /// Constructs a block that unequivocally identifies the client's intent to write the payload
+  /// at `height` of a log: the payload followed by the endpoint's signature.
+  fn log_block(
+    &self,
+    msg_type: MessageType,
+    handle: &[u8],
+    height: u64,
+    payload: &[u8],
+  ) -> Vec<u8> {
+    let msg = self.log_message(msg_type, handle, height, payload, None);
+    let sig = self.sign(&msg.to_bytes());
+    [payload.to_vec(), sig.to_bytes()].concat()
+  }
+
+  /// Returns the payload of a block at `height` of a log after checking the endpoint's signature.
+  fn open_log_block<'a>(
+    &self,
+    handle: &[u8],
+    block: &'a [u8],
+    height: u64,
+  ) -> Result<&'a [u8], EndpointError> {
+    if block.len() < Signature::num_bytes() {
+      return Err(EndpointError::FailedToVerifyReadLog);
+    }
+    let (payload, sig) = block.split_at(block.len() - Signature::num_bytes());
+    let sig = Signature::from_bytes(sig).map_err(|_e| EndpointError::FailedToVerifyReadLog)?;
+
+    let msg_type = if height == 0 {
+      MessageType::NewLogReq
+    } else {
+      MessageType::AppendLogReq
+    };
+    let msg = self.log_message(msg_type, handle, height, payload, None);
+    if !self.verify_own(&sig, &msg) {
+      return Err(EndpointError::FailedToVerifyReadLog);
+    }
+    Ok(payload)
+  }
+
+  /// Checks that the endpoint may sign with its key, which it may not while the key is not
+  /// current, and that signatures of the key can be encoded in the requested format, so that a
+  /// write is not issued only to fail to sign the response.
+  fn check_can_sign(&self, sigformat: SignatureFormat) -> Result<(), EndpointError> {
+    if !self.is_key_current() {
+      return Err(EndpointError::KeyNotCurrent);
+    }
+    let scheme = self.keys.read().unwrap().sk.get_scheme();
+    match (sigformat, scheme) {
+      (SignatureFormat::DER, SignatureScheme::Ed25519) => {
+        Err(EndpointError::UnsupportedSignatureFormat)
+      },
+      _ => Ok(()),
+    }
+  }
+
+  /// Signs a message to the client in the requested signature format, together with the id of
+  /// the key that signs it.
+  fn sign_response(
+    &self,
+    msg: &NimbleDigest,
+    sigformat: SignatureFormat,
+  ) -> Result<ResponseSignature, EndpointError> {
+    if !self.is_key_current() {
+      return Err(EndpointError::KeyNotCurrent);
+    }
+    let keys = self.keys.read().unwrap();
+    let key_id = key_id(&keys.pk);
+    let sig = keys
+      .sk
+      .sign(&msg.digest_with_bytes(&key_id).to_bytes())
+      .unwrap();
     let signature = match sigformat {
-      SignatureFormat::DER => sig.to_der(),
+      SignatureFormat::DER => sig
+        .to_der(keys.sk.get_scheme())
+        .map_err(|_e| EndpointError::UnsupportedSignatureFormat)?,
       _ => sig.to_bytes(),
     };
+    Ok(ResponseSignature { signature, key_id })
+  }
 
-    // respond to the light client
-    Ok((tag.to_vec(), counter as u64, signature))
+  /// Signs bytes with the endpoint's current key.
+  fn sign(&self, bytes: &[u8]) -> Signature {
+    self.keys.read().unwrap().sk.sign(bytes).unwrap()
+  }
+
+  /// Returns true if the signature over the message was made with the endpoint's current key or
+  /// with an older key in its history.
+  fn verify_own(&self, sig: &Signature, msg: &NimbleDigest) -> bool {
+    let keys = self.keys.read().unwrap();
+    let msg = msg.to_bytes();
+    sig.verify(&keys.pk, &msg).is_ok()
+      || keys
+        .history
+        .iter()
+        .rev()
+        .any(|record| sig.verify(&record.pk, &msg).is_ok())
+  }
+
+  /// Verifies a response from the coordinator, catching up with the view ledger and retrying
+  /// once if the receipts were issued in a view the endpoint has not seen yet.
+  async fn verify_with_view_update<T>(
+    &self,
+    verify: impl Fn(&VerifierState) -> Result<T, VerificationError>,
+    err: EndpointError,
+  ) -> Result<T, EndpointError> {
+    let res = if let Ok(vs_rd) = self.vs.read() {
+      verify(&vs_rd)
+    } else {
+      return Err(EndpointError::FailedToAcquireReadLock);
+    };
+    let res = match res {
+      // once views were pruned, a view created since the last update is reported as pruned
+      Err(VerificationError::ViewNotFound) | Err(VerificationError::PrunedView) => {
+        if self.update_view().await.is_err() {
+          return Err(err);
+        }
+        if let Ok(vs_rd) = self.vs.read() {
+          verify(&vs_rd)
+        } else {
+          return Err(EndpointError::FailedToAcquireReadLock);
+        }
+      },
+      res => res,
+    };
+    res.map_err(|e| {
+      eprintln!("failed to verify a response from the coordinator {:?}", e);
+      err
+    })
+  }
+
+  /// Creates a new log with the given handle, whose first entry (at height 0) is the payload.
+  pub async fn new_log(
+    &self,
+    handle: &[u8],
+    payload: &[u8],
+    sigformat: SignatureFormat,
+  ) -> Result<ResponseSignature, EndpointError> {
+    check_handle(handle)?;
+    self.check_can_sign(sigformat)?;
+
+    let block = self.log_block(MessageType::NewLogReq, handle, 0, payload);
+
+    // issue a request to the coordinator and receive a response
+    let receipts = self
+      .conn
+      .new_ledger(handle, &block)
+      .await
+      .map_err(|e| refine(e, EndpointError::FailedToCreateNewLog))?;
+
+    // verify the response received from the coordinator
+    self
+      .verify_with_view_update(
+        |vs| vs.verify_new_ledger(handle, &block, &receipts),
+        EndpointError::FailedToVerifyNewLog,
+      )
+      .await?;
+
+    let msg = self.log_message(MessageType::NewLogResp, handle, 0, payload, None);
+    self.sign_response(&msg, sigformat)
+  }
+
+  /// Appends the payload to the log with the given handle at the expected height.
+  pub async fn append_log(
+    &self,
+    handle: &[u8],
+    payload: &[u8],
+    expected_height: u64,
+    sigformat: SignatureFormat,
+  ) -> Result<ResponseSignature, EndpointError> {
+    check_handle(handle)?;
+    self.check_can_sign(sigformat)?;
+
+    let height = usize::try_from(expected_height).map_err(|_e| EndpointError::FailedToAppendLog)?;
+
+    let block = self.log_block(MessageType::AppendLogReq, handle, expected_height, payload);
+
+    // issue a request to the coordinator and receive a response
+    let (hash_nonces, receipts) = self
+      .conn
+      .append(handle, &block, expected_height)
+      .await
+      .map_err(|e| refine(e, EndpointError::FailedToAppendLog))?;
+
+    // verify the response received from the coordinator
+    self
+      .verify_with_view_update(
+        |vs| vs.verify_append(handle, &block, &hash_nonces, height, &receipts),
+        EndpointError::FailedToVerifyAppendedLog,
+      )
+      .await?;
+
+    let msg = self.log_message(
+      MessageType::AppendLogResp,
+      handle,
+      expected_height,
+      payload,
+      None,
+    );
+    self.sign_response(&msg, sigformat)
+  }
+
+  /// Reads the payload at the given index of the log with the given handle.
+  ///
+  /// # Returns
+  ///
+  /// The payload and the endpoint's signature over it, or an `EndpointError`.
+  pub async fn read_log_by_index(
+    &self,
+    handle: &[u8],
+    index: u64,
+    sigformat: SignatureFormat,
+  ) -> Result<(Vec<u8>, ResponseSignature), EndpointError> {
+    let idx = usize::try_from(index).map_err(|_e| EndpointError::FailedToReadLog)?;
+
+    // issue a request to the coordinator and receive a response
+    let (block, nonces, receipts) = self.conn.read_by_index(handle, index).await?;
+
+    // verify the response received from the coordinator
+    self
+      .verify_with_view_update(
+        |vs| vs.verify_read_by_index(handle, &block, &nonces, idx, &receipts),
+        EndpointError::FailedToVerifyReadLog,
+      )
+      .await?;
+
+    if is_tombstone(&NimbleDigest::digest(handle), &Block::new(&block)) {
+      return Err(EndpointError::LedgerDeleted);
+    }
+    let payload = self.open_log_block(handle, &block, index)?;
+
+    let msg = self.log_message(
+      MessageType::ReadLogByIndexResp,
+      handle,
+      index,
+      payload,
+      None,
+    );
+    Ok((payload.to_vec(), self.sign_response(&msg, sigformat)?))
+  }
+
+  /// Reads the latest payload of the log with the given handle, fresh with respect to the nonce.
+  ///
+  /// # Returns
+  ///
+  /// The payload, its height, and the endpoint's signature over them and the nonce, or an
+  /// `EndpointError`.
+  pub async fn read_log_latest(
+    &self,
+    handle: &[u8],
+    nonce: &[u8],
+    sigformat: SignatureFormat,
+  ) -> Result<(Vec<u8>, u64, ResponseSignature), EndpointError> {
+    // issue a request to the coordinator and receive a response
+    let (block, nonces, receipts) = self
+      .conn
+      .read_latest(handle, nonce)
+      .await
+      .map_err(|e| refine(e, EndpointError::FailedToReadLog))?;
+
+    // verify the response received from the coordinator
+    let height = self
+      .verify_with_view_update(
+        |vs| vs.verify_read_latest(handle, &block, &nonces, nonce, &receipts),
+        EndpointError::FailedToVerifyReadLog,
+      )
+      .await? as u64;
+
+    // a tombstone endorsed by a quorum shows that the log was deleted, not rolled back
+    if is_tombstone(&NimbleDigest::digest(handle), &Block::new(&block)) {
+      return Err(EndpointError::LedgerDeleted);
+    }
+    let payload = self.open_log_block(handle, &block, height)?;
+
+    let msg = self.log_message(
+      MessageType::ReadLogLatestResp,
+      handle,
+      height,
+      payload,
+      Some(nonce),
+    );
+    Ok((
+      payload.to_vec(),
+      height,
+      self.sign_response(&msg, sigformat)?,
+    ))
   }
 
   /// Gets the timeout map from the coordinator.
@@ -703,3 +2137,495 @@ impl EndpointState {
     Ok(())
   }
 }
+
+#[tonic::async_trait]
+impl Readiness for EndpointState {
+  async fn is_ready(&self) -> bool {
+    self.check_health().await.is_ready()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use coordinator_proto::{
+    call_server::{Call, CallServer},
+    AddEndorsersReq, CheckpointLedgerReq, CheckpointLedgerResp, DeleteLedgerReq, DeleteLedgerResp,
+  };
+  use ledger::{
+    compute_aggregated_block_hash,
+    quorum::{QuorumPolicy, ViewConfig},
+    tombstone_block, IdSig, MetaBlock, Nonces, Receipt,
+  };
+  use tokio::net::TcpListener;
+  use tokio_stream::wrappers::TcpListenerStream;
+  use tonic::{codegen::Bytes, transport::Server, Response};
+
+  const ATTESTATION: &[u8] = b"THIS IS A PLACE HOLDER FOR ATTESTATION";
+
+  async fn endpoint_state(coordinator: &str, vs: VerifierState) -> EndpointState {
+    let sk = PrivateKey::new();
+    let pk = sk.get_public_key().unwrap();
+    EndpointState {
+      conn: Connection::new(coordinator.to_string(), None)
+        .await
+        .unwrap(),
+      id: NimbleDigest::digest(b"nimble"),
+      keys: RwLock::new(EndpointKeys {
+        sk,
+        pk,
+        ledger: Vec::new(),
+        history: Vec::new(),
+      }),
+      key_current: AtomicBool::new(true),
+      vs: Arc::new(RwLock::new(vs)),
+      view_update: Mutex::new(()),
+      snapshot_path: None,
+    }
+  }
+
+  // an endpoint whose connection to the coordinator is never used
+  async fn offline_state() -> EndpointState {
+    endpoint_state("http://127.0.0.1:1", VerifierState::default()).await
+  }
+
+  const DELETED_LOG: &[u8] = b"deleted log";
+  const CHECKPOINT_HEIGHT: u64 = 2;
+
+  // A coordinator with a single endorser that holds one log, which was pruned below
+  // `CHECKPOINT_HEIGHT` and then deleted, so its tombstone is the entry right after the
+  // checkpoint. It answers the calls that the log API makes the way the coordinator does.
+  struct FakeCoordinator {
+    sk: PrivateKey,
+    group_identity: NimbleDigest,
+    view: NimbleDigest,
+  }
+
+  impl FakeCoordinator {
+    // the tombstone of the log, with a receipt that binds it to the nonce of a read if any
+    fn tombstone(&self, nonce: Option<&[u8]>) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
+      let handle = NimbleDigest::digest(DELETED_LOG);
+      let block = tombstone_block(&handle);
+      let nonces = Nonces::new();
+      let block_hash =
+        compute_aggregated_block_hash(&block.hash().to_bytes(), &nonces.hash().to_bytes());
+      let metablock = MetaBlock::new(
+        &NimbleDigest::digest(b"checkpoint"),
+        &block_hash,
+        CHECKPOINT_HEIGHT as usize + 1,
+      );
+      let tail_hash = match nonce {
+        Some(nonce) => metablock.hash().digest_with_bytes(nonce),
+        None => metablock.hash(),
+      };
+      // the message of a receipt, as `compute_receipt_message` builds it
+      let message = self
+        .group_identity
+        .digest_with(&self.view.digest_with(&handle.digest_with(&tail_hash)));
+      let mut receipts = Receipts::new();
+      receipts.add(&Receipt::new(
+        self.view,
+        metablock,
+        IdSig::new(
+          self.sk.get_public_key().unwrap(),
+          self.sk.sign(&message.to_bytes()).unwrap(),
+        ),
+      ));
+      (block.to_bytes(), nonces.to_bytes(), receipts.to_bytes())
+    }
+  }
+
+  #[tonic::async_trait]
+  impl Call for FakeCoordinator {
+    async fn new_ledger(
+      &self,
+      _req: Request<NewLedgerReq>,
+    ) -> Result<Response<NewLedgerResp>, Status> {
+      Err(Status::unimplemented("new_ledger"))
+    }
+
+    async fn append(&self, req: Request<AppendReq>) -> Result<Response<AppendResp>, Status> {
+      if req.into_inner().handle != DELETED_LOG {
+        return Err(Status::not_found("The ledger does not exist"));
+      }
+      Err(Status::permission_denied("The ledger is deleted"))
+    }
+
+    async fn delete_ledger(
+      &self,
+      _req: Request<DeleteLedgerReq>,
+    ) -> Result<Response<DeleteLedgerResp>, Status> {
+      Err(Status::unimplemented("delete_ledger"))
+    }
+
+    async fn checkpoint_ledger(
+      &self,
+      _req: Request<CheckpointLedgerReq>,
+    ) -> Result<Response<CheckpointLedgerResp>, Status> {
+      Err(Status::unimplemented("checkpoint_ledger"))
+    }
+
+    async fn read_latest(
+      &self,
+      req: Request<ReadLatestReq>,
+    ) -> Result<Response<ReadLatestResp>, Status> {
+      let ReadLatestReq { handle, nonce } = req.into_inner();
+      if handle != DELETED_LOG {
+        return Err(Status::not_found("The ledger does not exist"));
+      }
+      let (block, nonces, receipts) = self.tombstone(Some(&nonce));
+      Ok(Response::new(ReadLatestResp {
+        block,
+        nonces,
+        receipts,
+      }))
+    }
+
+    async fn read_by_index(
+      &self,
+      req: Request<ReadByIndexReq>,
+    ) -> Result<Response<ReadByIndexResp>, Status> {
+      let ReadByIndexReq { handle, index } = req.into_inner();
+      if handle != DELETED_LOG {
+        return Err(Status::not_found("The ledger does not exist"));
+      }
+      if index < CHECKPOINT_HEIGHT {
+        let checkpoint = LedgerCheckpoint {
+          height: CHECKPOINT_HEIGHT,
+          receipts: vec![1, 2, 3],
+        };
+        return Err(Status::with_details(
+          Code::OutOfRange,
+          "The entry is pruned",
+          Bytes::from(checkpoint.encode_to_vec()),
+        ));
+      }
+      if index > CHECKPOINT_HEIGHT + 1 {
+        return Err(Status::out_of_range("The entry does not exist"));
+      }
+      if index == CHECKPOINT_HEIGHT {
+        return Err(Status::unimplemented("read_by_index of the checkpoint"));
+      }
+      let (block, nonces, receipts) = self.tombstone(None);
+      Ok(Response::new(ReadByIndexResp {
+        block,
+        nonces,
+        receipts,
+      }))
+    }
+
+    type WatchLedgerStream = stream::Empty<Result<WatchLedgerResp, Status>>;
+
+    async fn watch_ledger(
+      &self,
+      _req: Request<WatchLedgerReq>,
+    ) -> Result<Response<Self::WatchLedgerStream>, Status> {
+      Err(Status::unimplemented("watch_ledger"))
+    }
+
+    async fn read_view_by_index(
+      &self,
+      _req: Request<ReadViewByIndexReq>,
+    ) -> Result<Response<ReadViewByIndexResp>, Status> {
+      Err(Status::unimplemented("read_view_by_index"))
+    }
+
+    async fn read_view_tail(
+      &self,
+      _req: Request<ReadViewTailReq>,
+    ) -> Result<Response<ReadViewTailResp>, Status> {
+      Err(Status::unimplemented("read_view_tail"))
+    }
+
+    async fn ping_all_endorsers(
+      &self,
+      _req: Request<PingAllReq>,
+    ) -> Result<Response<PingAllResp>, Status> {
+      Err(Status::unimplemented("ping_all_endorsers"))
+    }
+
+    async fn get_timeout_map(
+      &self,
+      _req: Request<GetTimeoutMapReq>,
+    ) -> Result<Response<GetTimeoutMapResp>, Status> {
+      Err(Status::unimplemented("get_timeout_map"))
+    }
+
+    async fn add_endorsers(
+      &self,
+      _req: Request<AddEndorsersReq>,
+    ) -> Result<Response<AddEndorsersResp>, Status> {
+      Err(Status::unimplemented("add_endorsers"))
+    }
+  }
+
+  // starts a `FakeCoordinator` and returns an endpoint that verifies its view
+  async fn fake_coordinator_state() -> EndpointState {
+    let sk = PrivateKey::new();
+    let pk = sk.get_public_key().unwrap();
+
+    // the genesis entry of the view ledger and the endorser's receipt on it
+    let config = ViewConfig::new(
+      vec![(pk.to_bytes(), "http://[::1]:9090".to_string())],
+      QuorumPolicy::Majority,
+    );
+    let genesis = Block::new(&config.to_bytes());
+    let group_identity = genesis.hash();
+    let view_metablock = MetaBlock::new(&NimbleDigest::default(), &genesis.hash(), 1);
+    let message =
+      group_identity.digest_with(&NimbleDigest::default().digest_with(&view_metablock.hash()));
+    let mut view_receipts = Receipts::new();
+    view_receipts.add(&Receipt::new(
+      NimbleDigest::default(),
+      view_metablock.clone(),
+      IdSig::new(pk, sk.sign(&message.to_bytes()).unwrap()),
+    ));
+    let mut vs = VerifierState::new();
+    vs.set_group_identity(group_identity);
+    vs.apply_view_change(
+      &genesis.to_bytes(),
+      &view_receipts.to_bytes(),
+      Some(ATTESTATION),
+    )
+    .unwrap();
+
+    let coordinator = FakeCoordinator {
+      sk,
+      group_identity,
+      view: view_metablock.hash(),
+    };
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(
+      Server::builder()
+        .add_service(CallServer::new(coordinator))
+        .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+    endpoint_state(&format!("http://{}", addr), vs).await
+  }
+
+  #[tokio::test]
+  pub async fn test_counter_block() {
+    let state = offline_state().await;
+    let handle = b"counter";
+    // a tag whose last 8 bytes read as the length of a request id that it contains
+    let length_like = [b"abc".as_slice(), &3u64.to_le_bytes()].concat();
+
+    for counter in [0, 7] {
+      let msg_type = || {
+        if counter == 0 {
+          MessageType::NewCounterReq
+        } else {
+          MessageType::IncrementCounterReq
+        }
+      };
+      for tag in [b"tag".as_slice(), b"", &length_like] {
+        for request_id in [None, Some(b"id".as_slice()), Some(b"abc".as_slice())] {
+          let block = state.counter_block(msg_type(), handle, counter, tag, request_id);
+          let opened = state.open_counter_block(handle, &block, counter).unwrap();
+          assert_eq!(opened, (tag, request_id));
+
+          // the signature binds the block to the handle and the counter
+          assert!(state.open_counter_block(b"other", &block, counter).is_err());
+          assert!(state
+            .open_counter_block(handle, &block, counter + 1)
+            .is_err());
+        }
+      }
+    }
+
+    // a block that the endpoint did not sign is rejected
+    let mut block = state.counter_block(MessageType::NewCounterReq, handle, 0, b"tag", None);
+    block[0] ^= 1;
+    assert!(state.open_counter_block(handle, &block, 0).is_err());
+    assert!(state.open_counter_block(handle, b"short", 0).is_err());
+  }
+
+  #[tokio::test]
+  pub async fn test_key_block() {
+    let state = offline_state().await;
+    let sk = PrivateKey::new();
+    let pk = sk.get_public_key().unwrap();
+    let next_sk = PrivateKey::new();
+    let next_pk = next_sk.get_public_key().unwrap();
+    let ledger = key_ledger_handle(&pk);
+
+    let genesis = state.key_block(&ledger, &sk, &pk, 0);
+    let opened = state.open_key_block(&ledger, &genesis, 0, None).unwrap();
+    assert_eq!(opened.to_bytes(), pk.to_bytes());
+    let rotated = state.key_block(&ledger, &sk, &next_pk, 1);
+    let opened = state
+      .open_key_block(&ledger, &rotated, 1, Some(&pk))
+      .unwrap();
+    assert_eq!(opened.to_bytes(), next_pk.to_bytes());
+
+    // the first key must be that of the handle, so one endpoint cannot start another's ledger
+    let other = key_ledger_handle(&next_pk);
+    let foreign = state.key_block(&other, &sk, &pk, 0);
+    assert!(state.open_key_block(&other, &foreign, 0, None).is_err());
+    // a later key must be signed by the key before it
+    let forged = state.key_block(&ledger, &next_sk, &next_pk, 1);
+    assert!(state
+      .open_key_block(&ledger, &forged, 1, Some(&pk))
+      .is_err());
+    assert!(state
+      .open_key_block(&ledger, &rotated, 2, Some(&pk))
+      .is_err());
+
+    // an endpoint with a key that was rotated away from signs nothing
+    state.key_current.store(false, SeqCst);
+    let msg = NimbleDigest::digest(b"message");
+    let res = state.sign_response(&msg, SignatureFormat::RAW);
+    assert_eq!(res.unwrap_err(), EndpointError::KeyNotCurrent);
+    let res = state
+      .new_counter(b"counter", b"tag", None, SignatureFormat::RAW)
+      .await;
+    assert_eq!(res.unwrap_err(), EndpointError::KeyNotCurrent);
+  }
+
+  #[tokio::test]
+  pub async fn test_log_errors() {
+    let state = fake_coordinator_state().await;
+    let raw = SignatureFormat::RAW;
+
+    // an endorsed tombstone shows that the log was deleted, and appends are refused
+    let res = state
+      .read_log_by_index(DELETED_LOG, CHECKPOINT_HEIGHT + 1, raw)
+      .await;
+    assert_eq!(res.unwrap_err(), EndpointError::LedgerDeleted);
+    let res = state.read_log_latest(DELETED_LOG, b"nonce", raw).await;
+    assert_eq!(res.unwrap_err(), EndpointError::LedgerDeleted);
+    let res = state
+      .append_log(DELETED_LOG, b"payload", CHECKPOINT_HEIGHT + 2, raw)
+      .await;
+    assert_eq!(res.unwrap_err(), EndpointError::LedgerDeleted);
+
+    // a pruned entry comes with the checkpoint at which the log now starts
+    let res = state.read_log_by_index(DELETED_LOG, 0, raw).await;
+    assert_eq!(
+      res.unwrap_err(),
+      EndpointError::LogEntryPruned {
+        height: CHECKPOINT_HEIGHT,
+        receipts: vec![1, 2, 3],
+      }
+    );
+
+    // an entry past the tail is missing, while another handle has no log at all
+    let res = state
+      .read_log_by_index(DELETED_LOG, CHECKPOINT_HEIGHT + 2, raw)
+      .await;
+    assert_eq!(res.unwrap_err(), EndpointError::EntryNotFound);
+    let res = state.read_log_by_index(b"missing log", 0, raw).await;
+    assert_eq!(res.unwrap_err(), EndpointError::LedgerNotFound);
+    let res = state.read_log_latest(b"missing log", b"nonce", raw).await;
+    assert_eq!(res.unwrap_err(), EndpointError::LedgerNotFound);
+    let res = state.append_log(b"missing log", b"payload", 1, raw).await;
+    assert_eq!(res.unwrap_err(), EndpointError::LedgerNotFound);
+  }
+
+  #[tokio::test]
+  pub async fn test_replay_response() {
+    let state = offline_state().await;
+    let handle = b"counter";
+    let block = state.counter_block(
+      MessageType::IncrementCounterReq,
+      handle,
+      3,
+      b"tag",
+      Some(b"id"),
+    );
+
+    // a retry of the same write gets the response that the original write got
+    let replayed = state
+      .replay_response(handle, &block, b"tag", 3, b"id", SignatureFormat::RAW)
+      .unwrap();
+    let msg = state.log_message(MessageType::IncrementCounterResp, handle, 3, b"tag", None);
+    let sig = Signature::from_bytes(&replayed.signature).unwrap();
+    assert_eq!(replayed.key_id, state.get_key_id().unwrap());
+    assert!(state.verify_own(&sig, &msg.digest_with_bytes(&replayed.key_id)));
+
+    // a write with another tag or request id, or none, is not the one that landed
+    let replay = |tag: &[u8], request_id: &[u8]| {
+      state.replay_response(handle, &block, tag, 3, request_id, SignatureFormat::RAW)
+    };
+    assert!(replay(b"other", b"id").is_none());
+    assert!(replay(b"tag", b"other").is_none());
+    assert!(replay(b"tag", b"").is_none());
+    let without_id = state.counter_block(MessageType::IncrementCounterReq, handle, 3, b"tag", None);
+    assert!(state
+      .replay_response(handle, &without_id, b"tag", 3, b"id", SignatureFormat::RAW)
+      .is_none());
+
+    // an empty request id is the same as none
+    assert_eq!(non_empty(Some(b"")), None);
+    assert_eq!(non_empty(Some(b"id")), Some(b"id".as_slice()));
+  }
+
+  #[tokio::test]
+  pub async fn test_reserved_handle() {
+    let state = offline_state().await;
+    let raw = SignatureFormat::RAW;
+    let handle = [KEY_LEDGER_PREFIX, b"x"].concat();
+    // the writes are rejected before they reach the coordinator, which is not running
+    let res = state.new_counter(&handle, b"tag", None, raw).await;
+    assert_eq!(res.unwrap_err(), EndpointError::ReservedHandle);
+    let res = state.increment_counter(&handle, b"tag", 1, None, raw).await;
+    assert_eq!(res.unwrap_err(), EndpointError::ReservedHandle);
+    let res = state.new_log(&handle, b"payload", raw).await;
+    assert_eq!(res.unwrap_err(), EndpointError::ReservedHandle);
+    let res = state.append_log(&handle, b"payload", 1, raw).await;
+    assert_eq!(res.unwrap_err(), EndpointError::ReservedHandle);
+
+    let ops = vec![
+      CounterOp::New {
+        handle: handle.clone(),
+        tag: b"tag".to_vec(),
+        request_id: None,
+      },
+      CounterOp::Increment {
+        handle,
+        tag: b"tag".to_vec(),
+        expected_counter: 1,
+        request_id: None,
+      },
+    ];
+    for res in state.batch_counters(ops, raw).await {
+      assert_eq!(res.unwrap_err(), EndpointError::ReservedHandle);
+    }
+  }
+
+  #[test]
+  pub fn test_from_status() {
+    let default = EndpointError::FailedToReadLog;
+    assert_eq!(
+      from_status(&Status::not_found("no ledger"), default.clone()),
+      EndpointError::LedgerNotFound
+    );
+    assert_eq!(
+      from_status(&Status::internal("failed"), default.clone()),
+      default
+    );
+
+    // an index past the tail and a pruned index are told apart by the checkpoint in the details
+    assert_eq!(
+      from_status(&Status::out_of_range("no entry"), default.clone()),
+      EndpointError::EntryNotFound
+    );
+    let checkpoint = LedgerCheckpoint {
+      height: 5,
+      receipts: vec![1, 2, 3],
+    };
+    let status = Status::with_details(
+      Code::OutOfRange,
+      "pruned",
+      Bytes::from(checkpoint.encode_to_vec()),
+    );
+    assert_eq!(
+      from_status(&status, default),
+      EndpointError::LogEntryPruned {
+        height: 5,
+        receipts: vec![1, 2, 3],
+      }
+    );
+  }
+}
@@ -0,0 +1,526 @@
+use endpoint::{
+  EndpointError, EndpointState, HealthService, PublicKeyFormat, ResponseSignature, SignatureFormat,
+  ViewRetention,
+};
+
+use std::sync::Arc;
+use tonic::{
+  transport::{Identity, Server, ServerTlsConfig},
+  Request, Response, Status,
+};
+#[allow(clippy::derive_partial_eq_without_eq)]
+pub mod endpoint_proto {
+  tonic::include_proto!("endpoint_proto");
+}
+
+use clap::{App, Arg};
+use endpoint_proto::{
+  call_server::{Call, CallServer},
+  AddEndorsersReq, AddEndorsersResp, GetFreshnessProofReq, GetFreshnessProofResp, GetIdentityReq,
+  GetIdentityResp, GetTimeoutMapReq, GetTimeoutMapResp, IncrementCounterReq, IncrementCounterResp,
+  NewCounterReq, NewCounterResp, PingAllReq, PingAllResp, ReadCounterReq, ReadCounterResp,
+};
+
+/// The `Call` service on top of the endpoint state. Unlike `endpoint_rest` with `--clients`, it
+/// neither authenticates its callers nor enforces namespaces and quotas, so it must only be
+/// reachable by trusted clients.
+pub struct EndpointServiceState {
+  state: Arc<EndpointState>,
+}
+
+impl EndpointServiceState {
+  /// Creates a new instance of `EndpointServiceState`.
+  pub fn new(endpoint: Arc<EndpointState>) -> Self {
+    EndpointServiceState { state: endpoint }
+  }
+}
+
+/// Converts the wire encoding of a public key format into the one used by `EndpointState`.
+fn to_pkformat(pkformat: i32) -> Option<PublicKeyFormat> {
+  match endpoint_proto::PublicKeyFormat::from_i32(pkformat)? {
+    endpoint_proto::PublicKeyFormat::Uncompressed => Some(PublicKeyFormat::UNCOMPRESSED),
+    endpoint_proto::PublicKeyFormat::Compressed => Some(PublicKeyFormat::COMPRESSED),
+    endpoint_proto::PublicKeyFormat::Der => Some(PublicKeyFormat::DER),
+  }
+}
+
+/// Converts the wire encoding of a signature format into the one used by `EndpointState`.
+fn to_sigformat(sigformat: i32) -> Option<SignatureFormat> {
+  match endpoint_proto::SignatureFormat::from_i32(sigformat)? {
+    endpoint_proto::SignatureFormat::Raw => Some(SignatureFormat::RAW),
+    endpoint_proto::SignatureFormat::Der => Some(SignatureFormat::DER),
+  }
+}
+
+/// Maps an error of the endpoint to the status returned to the client, using the same codes as
+/// the coordinator for the reasons it gives for refusing a request.
+fn process_error(error: EndpointError, default_msg: impl Into<String>) -> Status {
+  match error {
+    EndpointError::InvalidRequest => Status::invalid_argument("The request is invalid"),
+    EndpointError::ReservedHandle => {
+      Status::permission_denied("The handle is reserved for the endpoint's key ledger")
+    },
+    EndpointError::UnsupportedSignatureFormat => Status::invalid_argument(
+      "Signatures of the endpoint's key have no encoding in the requested format",
+    ),
+    EndpointError::LedgerAlreadyExists => Status::already_exists("The counter already exists"),
+    EndpointError::LedgerNotFound => Status::not_found("The counter does not exist"),
+    EndpointError::UnexpectedHeight => {
+      Status::aborted("The expected counter does not match the counter")
+    },
+    EndpointError::CounterDeleted | EndpointError::LedgerDeleted => {
+      Status::permission_denied("The counter is deleted")
+    },
+    EndpointError::LedgerQuarantined => Status::failed_precondition("The counter is quarantined"),
+    EndpointError::Unavailable | EndpointError::UnableToConnectToCoordinator => {
+      Status::unavailable("The coordinator or a quorum of endorsers cannot be reached")
+    },
+    EndpointError::NotEndorsed => {
+      Status::deadline_exceeded("The write is stored but not endorsed by a quorum of endorsers")
+    },
+    EndpointError::KeyNotCurrent => {
+      Status::unavailable("The endpoint's key was rotated away from, so it signs nothing")
+    },
+    _ => Status::internal(default_msg),
+  }
+}
+
+#[tonic::async_trait]
+impl Call for EndpointServiceState {
+  /// Returns the identity and public key of the endpoint.
+  async fn get_identity(
+    &self,
+    req: Request<GetIdentityReq>,
+  ) -> Result<Response<GetIdentityResp>, Status> {
+    let GetIdentityReq { pkformat } = req.into_inner();
+    let pkformat = to_pkformat(pkformat)
+      .ok_or_else(|| Status::invalid_argument("Unsupported public key format"))?;
+
+    let res = self.state.get_identity(pkformat);
+    if res.is_err() {
+      eprintln!("failed to get the identity {:?}", res);
+      return Err(Status::internal("Failed to get the identity"));
+    }
+
+    let (id, pk) = res.unwrap();
+
+    let res = self
+      .state
+      .get_key_id()
+      .and_then(|key_id| Ok((key_id, self.state.get_key_history(pkformat)?)));
+    if res.is_err() {
+      eprintln!("failed to get the key history {:?}", res);
+      return Err(Status::internal("Failed to get the identity"));
+    }
+
+    let (key_id, key_history) = res.unwrap();
+    let reply = GetIdentityResp {
+      id,
+      pk,
+      key_id,
+      key_history: key_history
+        .into_iter()
+        .map(|key| endpoint_proto::EndpointKey {
+          key_id: key.key_id,
+          pk: key.public_key,
+          block: key.block,
+          nonces: key.nonces,
+          receipts: key.receipts,
+        })
+        .collect(),
+    };
+    Ok(Response::new(reply))
+  }
+
+  /// Creates a new counter with the given handle and tag.
+  async fn new_counter(
+    &self,
+    req: Request<NewCounterReq>,
+  ) -> Result<Response<NewCounterResp>, Status> {
+    let NewCounterReq {
+      handle,
+      tag,
+      sigformat,
+      request_id,
+    } = req.into_inner();
+    let sigformat = to_sigformat(sigformat)
+      .ok_or_else(|| Status::invalid_argument("Unsupported signature format"))?;
+
+    // the endpoint treats an empty request id, which proto3 sends for none, as none
+    let res = self
+      .state
+      .new_counter(&handle, &tag, Some(&request_id), sigformat)
+      .await;
+    if let Err(e) = res {
+      eprintln!("failed to create a new counter {:?}", e);
+      return Err(process_error(e, "Failed to create a new counter"));
+    }
+
+    let ResponseSignature { signature, key_id } = res.unwrap();
+    let reply = NewCounterResp { signature, key_id };
+    Ok(Response::new(reply))
+  }
+
+  /// Increments the counter with the given handle to the expected value.
+  async fn increment_counter(
+    &self,
+    req: Request<IncrementCounterReq>,
+  ) -> Result<Response<IncrementCounterResp>, Status> {
+    let IncrementCounterReq {
+      handle,
+      tag,
+      expected_counter,
+      sigformat,
+      request_id,
+    } = req.into_inner();
+    let sigformat = to_sigformat(sigformat)
+      .ok_or_else(|| Status::invalid_argument("Unsupported signature format"))?;
+
+    let res = self
+      .state
+      .increment_counter(
+        &handle,
+        &tag,
+        expected_counter,
+        Some(&request_id),
+        sigformat,
+      )
+      .await;
+    if let Err(e) = res {
+      eprintln!("failed to increment a counter {:?}", e);
+      return Err(process_error(e, "Failed to increment a counter"));
+    }
+
+    let ResponseSignature { signature, key_id } = res.unwrap();
+    let reply = IncrementCounterResp { signature, key_id };
+    Ok(Response::new(reply))
+  }
+
+  /// Reads the latest value of the counter with the given handle.
+  async fn read_counter(
+    &self,
+    req: Request<ReadCounterReq>,
+  ) -> Result<Response<ReadCounterResp>, Status> {
+    let ReadCounterReq {
+      handle,
+      nonce,
+      sigformat,
+    } = req.into_inner();
+    let sigformat = to_sigformat(sigformat)
+      .ok_or_else(|| Status::invalid_argument("Unsupported signature format"))?;
+
+    let res = self.state.read_counter(&handle, &nonce, sigformat).await;
+    if let Err(e) = res {
+      eprintln!("failed to read a counter {:?}", e);
+      return Err(process_error(e, "Failed to read a counter"));
+    }
+
+    let (tag, counter, ResponseSignature { signature, key_id }) = res.unwrap();
+    let reply = ReadCounterResp {
+      tag,
+      counter,
+      signature,
+      key_id,
+    };
+    Ok(Response::new(reply))
+  }
+
+  /// Produces a proof that the counter with the given handle is fresh with respect to the nonce.
+  async fn get_freshness_proof(
+    &self,
+    req: Request<GetFreshnessProofReq>,
+  ) -> Result<Response<GetFreshnessProofResp>, Status> {
+    let GetFreshnessProofReq { handle, nonce } = req.into_inner();
+
+    let res = self.state.get_freshness_proof(&handle, &nonce).await;
+    if let Err(e) = res {
+      eprintln!("failed to produce a freshness proof {:?}", e);
+      return Err(process_error(e, "Failed to produce a freshness proof"));
+    }
+
+    let reply = GetFreshnessProofResp {
+      proof: res.unwrap(),
+    };
+    Ok(Response::new(reply))
+  }
+
+  /// Pings all endorsers through the coordinator.
+  async fn ping_all_endorsers(
+    &self,
+    _req: Request<PingAllReq>,
+  ) -> Result<Response<PingAllResp>, Status> {
+    let res = self.state.ping_all_endorsers().await;
+    if let Err(e) = res {
+      eprintln!("failed to ping all endorsers {:?}", e);
+      return Err(process_error(e, "Failed to ping all endorsers"));
+    }
+
+    Ok(Response::new(PingAllResp {}))
+  }
+
+  /// Returns the timeout map of the endorsers.
+  async fn get_timeout_map(
+    &self,
+    _req: Request<GetTimeoutMapReq>,
+  ) -> Result<Response<GetTimeoutMapResp>, Status> {
+    let res = self.state.get_timeout_map().await;
+    if let Err(e) = res {
+      eprintln!("failed to get the timeout map {:?}", e);
+      return Err(process_error(e, "Failed to get the timeout map"));
+    }
+
+    let reply = GetTimeoutMapResp {
+      timeout_map: res.unwrap(),
+    };
+    Ok(Response::new(reply))
+  }
+
+  /// Adds the endorsers with the given URIs.
+  async fn add_endorsers(
+    &self,
+    req: Request<AddEndorsersReq>,
+  ) -> Result<Response<AddEndorsersResp>, Status> {
+    let AddEndorsersReq { endorsers } = req.into_inner();
+
+    let res = self.state.add_endorsers(endorsers).await;
+    if let Err(e) = res {
+      eprintln!("failed to add endorsers {:?}", e);
+      return Err(process_error(e, "Failed to add endorsers"));
+    }
+
+    Ok(Response::new(AddEndorsersResp {}))
+  }
+}
+
+/// Main function to start the endpoint gRPC service.
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+  let config = App::new("endpoint_grpc")
+    .arg(
+      Arg::with_name("coordinator")
+        .short("c")
+        .long("coordinator")
+        .help("The hostname of the coordinator")
+        .default_value("http://[::1]:8080"),
+    )
+    .arg(
+      Arg::with_name("host")
+        .short("t")
+        .long("host")
+        .help("The hostname to run the service on.")
+        .default_value("[::1]"),
+    )
+    .arg(
+      Arg::with_name("port")
+        .short("p")
+        .long("port")
+        .help("The port number to run the endpoint service on.")
+        .default_value("8083"),
+    )
+    .arg(
+      Arg::with_name("cert")
+        .short("e")
+        .long("cert")
+        .takes_value(true)
+        .help("The certificate to run tls"),
+    )
+    .arg(
+      Arg::with_name("key")
+        .short("k")
+        .long("key")
+        .takes_value(true)
+        .help("The key to run tls"),
+    )
+    .arg(
+      Arg::with_name("pem")
+        .short("m")
+        .long("pem")
+        .takes_value(true)
+        .help("The ECDSA prime256v1 or Ed25519 private key pem file"),
+    )
+    .arg(
+      Arg::with_name("rotate_key")
+        .long("rotate-key")
+        .takes_value(true)
+        .requires("pem")
+        .help("The pem file of a new private key to rotate to at startup"),
+    )
+    .arg(
+      Arg::with_name("channels")
+        .short("l")
+        .long("channels")
+        .takes_value(true)
+        .help("The number of grpc channels"),
+    )
+    .arg(
+      Arg::with_name("snapshot")
+        .short("s")
+        .long("snapshot")
+        .takes_value(true)
+        .help("The file in which to persist the verifier state to speed up restarts"),
+    )
+    .arg(
+      Arg::with_name("retain_views")
+        .long("retain-views")
+        .takes_value(true)
+        .help("Only accept receipts from the latest N views of the view ledger")
+        .conflicts_with("min_view_height"),
+    )
+    .arg(
+      Arg::with_name("min_view_height")
+        .long("min-view-height")
+        .takes_value(true)
+        .help("Only accept receipts from views at or above this height of the view ledger"),
+    );
+  let cli_matches = config.get_matches();
+  let hostname = cli_matches.value_of("host").unwrap();
+  let port_num = cli_matches.value_of("port").unwrap();
+  let addr = format!("{}:{}", hostname, port_num).parse()?;
+  let coordinator_hostname = cli_matches.value_of("coordinator").unwrap().to_string();
+  let cert = cli_matches.value_of("cert");
+  let key = cli_matches.value_of("key");
+  let pem = cli_matches
+    .value_of("pem")
+    .map(|p| std::fs::read_to_string(p).expect("Failed to read the private key pem file"));
+
+  let snapshot = cli_matches.value_of("snapshot").map(|p| p.to_string());
+
+  let num_grpc_channels: Option<usize> = if let Some(x) = cli_matches.value_of("channels") {
+    match x.to_string().parse() {
+      Ok(v) => Some(v),
+      Err(_) => panic!("Failed to parse the number of grpc channels"),
+    }
+  } else {
+    None
+  };
+
+  let endpoint_state = Arc::new(
+    EndpointState::new(coordinator_hostname, pem, num_grpc_channels, snapshot)
+      .await
+      .unwrap(),
+  );
+
+  let retention = if let Some(x) = cli_matches.value_of("retain_views") {
+    match x.parse() {
+      Ok(n) => ViewRetention::LatestViews(n),
+      Err(_) => panic!("Failed to parse the number of views to retain"),
+    }
+  } else if let Some(x) = cli_matches.value_of("min_view_height") {
+    match x.parse() {
+      Ok(h) => ViewRetention::MinHeight(h),
+      Err(_) => panic!("Failed to parse the minimum view height"),
+    }
+  } else {
+    ViewRetention::All
+  };
+  endpoint_state.set_view_retention(retention).unwrap();
+
+  if let Some(p) = cli_matches.value_of("rotate_key") {
+    let pem = std::fs::read_to_string(p).expect("Failed to read the new private key pem file");
+    endpoint_state.rotate_key(&pem).await.unwrap();
+    println!("Rotated the endpoint key to {}", p);
+  }
+
+  let mut server = Server::builder();
+  if let Some(c) = cert {
+    if let Some(k) = key {
+      let cert = std::fs::read_to_string(c).expect("Failed to read the certificate");
+      let key = std::fs::read_to_string(k).expect("Failed to read the key");
+      server = server.tls_config(ServerTlsConfig::new().identity(Identity::from_pem(cert, key)))?;
+    } else {
+      panic!("cert and key must be provided together!");
+    }
+  } else if key.is_some() {
+    panic!("cert and key must be provided together!");
+  }
+
+  let health_service = HealthService::new_server(endpoint_state.clone(), &["endpoint_proto.Call"]);
+  let service = EndpointServiceState::new(endpoint_state);
+  let job = tokio::spawn(async move {
+    println!("Running gRPC Endpoint Service at {:?}", addr);
+    let _ = server
+      .add_service(CallServer::new(service))
+      .add_service(health_service)
+      .serve(addr)
+      .await;
+  });
+
+  job.await?;
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use tonic::Code;
+
+  #[test]
+  fn test_to_pkformat() {
+    assert!(matches!(
+      to_pkformat(endpoint_proto::PublicKeyFormat::Uncompressed as i32),
+      Some(PublicKeyFormat::UNCOMPRESSED)
+    ));
+    assert!(matches!(
+      to_pkformat(endpoint_proto::PublicKeyFormat::Compressed as i32),
+      Some(PublicKeyFormat::COMPRESSED)
+    ));
+    assert!(matches!(
+      to_pkformat(endpoint_proto::PublicKeyFormat::Der as i32),
+      Some(PublicKeyFormat::DER)
+    ));
+    assert!(to_pkformat(3).is_none());
+    assert!(to_pkformat(-1).is_none());
+  }
+
+  #[test]
+  fn test_to_sigformat() {
+    assert!(matches!(
+      to_sigformat(endpoint_proto::SignatureFormat::Raw as i32),
+      Some(SignatureFormat::RAW)
+    ));
+    assert!(matches!(
+      to_sigformat(endpoint_proto::SignatureFormat::Der as i32),
+      Some(SignatureFormat::DER)
+    ));
+    assert!(to_sigformat(2).is_none());
+  }
+
+  #[test]
+  fn test_process_error() {
+    let cases = [
+      (EndpointError::InvalidRequest, Code::InvalidArgument),
+      (EndpointError::ReservedHandle, Code::PermissionDenied),
+      (
+        EndpointError::UnsupportedSignatureFormat,
+        Code::InvalidArgument,
+      ),
+      (EndpointError::LedgerAlreadyExists, Code::AlreadyExists),
+      (EndpointError::LedgerNotFound, Code::NotFound),
+      (EndpointError::UnexpectedHeight, Code::Aborted),
+      (EndpointError::CounterDeleted, Code::PermissionDenied),
+      (EndpointError::LedgerDeleted, Code::PermissionDenied),
+      (EndpointError::LedgerQuarantined, Code::FailedPrecondition),
+      (EndpointError::Unavailable, Code::Unavailable),
+      (
+        EndpointError::UnableToConnectToCoordinator,
+        Code::Unavailable,
+      ),
+      (EndpointError::NotEndorsed, Code::DeadlineExceeded),
+      (EndpointError::KeyNotCurrent, Code::Unavailable),
+    ];
+    for (error, code) in cases {
+      assert_eq!(
+        process_error(error.clone(), "default").code(),
+        code,
+        "{:?}",
+        error
+      );
+    }
+
+    // the client cannot act on any other failure
+    let status = process_error(EndpointError::FailedToVerifyNewCounter, "Failed to verify");
+    assert_eq!(status.code(), Code::Internal);
+    assert_eq!(status.message(), "Failed to verify");
+  }
+}
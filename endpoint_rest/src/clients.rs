@@ -0,0 +1,563 @@
+//! The clients of the endpoint: how they authenticate, the namespace of handles each may use,
+//! and their quotas.
+
+use axum::{middleware::AddExtension, Extension};
+use axum_server::{
+  accept::Accept,
+  tls_rustls::{RustlsAcceptor, RustlsConfig},
+};
+use futures::future::BoxFuture;
+use rustls::{
+  server::AllowAnyAnonymousOrAuthenticatedClient, Certificate, PrivateKey, RootCertStore,
+  ServerConfig,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+  collections::HashMap,
+  io,
+  sync::{Arc, Mutex},
+  time::{Duration, Instant},
+};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_rustls::server::TlsStream;
+use tower::Layer;
+
+/// The file given with `--clients`.
+#[derive(Debug, Serialize, Deserialize)]
+struct ClientsConfig {
+  #[serde(rename = "Clients")]
+  pub clients: Vec<ClientConfig>,
+}
+
+/// A client in the file given with `--clients`. The client authenticates with `ApiKey` in the
+/// `nimble-api-key` header, or with a TLS client certificate whose SHA-256 fingerprint, in hex,
+/// is `CertFingerprint`. Every handle it uses must start with the bytes of `Namespace`, which
+/// ends with `NAMESPACE_SEPARATOR`. Only an `Admin` client may leave it out to use any handle.
+#[derive(Debug, Serialize, Deserialize)]
+struct ClientConfig {
+  #[serde(rename = "Name")]
+  pub name: String,
+  #[serde(rename = "ApiKey", default)]
+  pub api_key: Option<String>,
+  #[serde(rename = "CertFingerprint", default)]
+  pub cert_fingerprint: Option<String>,
+  #[serde(rename = "Namespace", default)]
+  pub namespace: String,
+  #[serde(rename = "Admin", default)]
+  pub admin: bool,
+  #[serde(rename = "RequestsPerSecond", default)]
+  pub requests_per_second: Option<f64>,
+  #[serde(rename = "MaxCounters", default)]
+  pub max_counters: Option<u64>,
+}
+
+/// The last character of every namespace, so that the handles of one client never look like
+/// they are in the namespace of another, as `svc2.x` would be in `svc`.
+const NAMESPACE_SEPARATOR: char = '.';
+
+/// An authenticated client.
+#[derive(Debug)]
+pub struct Client {
+  id: usize,
+  name: String,
+  namespace: Vec<u8>,
+  admin: bool,
+  requests_per_second: Option<f64>,
+  max_counters: Option<u64>,
+}
+
+impl Client {
+  pub fn name(&self) -> &str {
+    &self.name
+  }
+
+  /// Returns true if the client may change the endorsers of the Nimble instance.
+  pub fn is_admin(&self) -> bool {
+    self.admin
+  }
+
+  /// Returns true if the handle is in the client's namespace. An admin client without a
+  /// namespace owns every handle.
+  pub fn owns(&self, handle: &[u8]) -> bool {
+    handle.starts_with(&self.namespace)
+  }
+
+  /// Returns true if the number of counters the client may create is limited.
+  pub fn limits_counters(&self) -> bool {
+    self.max_counters.is_some()
+  }
+}
+
+/// The requests a client may still send right away, refilled at its rate up to one second's
+/// worth.
+struct Bucket {
+  tokens: f64,
+  refilled: Instant,
+}
+
+/// The clients of the endpoint.
+pub struct Clients {
+  clients: Vec<Arc<Client>>,
+  by_api_key: HashMap<Vec<u8>, usize>,
+  by_fingerprint: HashMap<Vec<u8>, usize>,
+  buckets: Mutex<Vec<Bucket>>,
+  counters: Mutex<HashMap<String, u64>>,
+  counters_path: Option<String>,
+}
+
+/// Returns the SHA-256 hash of the bytes.
+fn sha256(bytes: &[u8]) -> Vec<u8> {
+  Sha256::digest(bytes).to_vec()
+}
+
+impl Clients {
+  /// Loads the clients from the file given with `--clients`. The number of counters each client
+  /// created is kept in `counters_path`, if given, so that restarts do not reset the quotas.
+  pub fn load(path: &str, counters_path: Option<String>) -> Result<Self, String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let config: ClientsConfig =
+      serde_json::from_slice(&bytes).map_err(|e| format!("Failed to parse {}: {}", path, e))?;
+
+    let mut clients: Vec<Arc<Client>> = Vec::new();
+    let mut by_api_key = HashMap::new();
+    let mut by_fingerprint = HashMap::new();
+    for (id, client) in config.clients.into_iter().enumerate() {
+      // the counter quota is kept by name
+      if clients.iter().any(|other| other.name == client.name) {
+        return Err(format!("Client {} is listed more than once", client.name));
+      }
+      if client.namespace.is_empty() {
+        if !client.admin {
+          return Err(format!("Client {} has no Namespace", client.name));
+        }
+      } else {
+        if !client.namespace.ends_with(NAMESPACE_SEPARATOR) {
+          return Err(format!(
+            "Client {} has a Namespace that does not end with '{}'",
+            client.name, NAMESPACE_SEPARATOR
+          ));
+        }
+        let overlapping = clients.iter().find(|other| {
+          !other.namespace.is_empty()
+            && (other.namespace.starts_with(client.namespace.as_bytes())
+              || client.namespace.as_bytes().starts_with(&other.namespace))
+        });
+        if let Some(other) = overlapping {
+          return Err(format!(
+            "Client {} has a Namespace that overlaps with that of client {}",
+            client.name, other.name
+          ));
+        }
+      }
+      if let Some(api_key) = &client.api_key {
+        // only the hash of a key is kept, and compared against the hash of the one presented
+        if by_api_key.insert(sha256(api_key.as_bytes()), id).is_some() {
+          return Err(format!("Client {} reuses an API key", client.name));
+        }
+      }
+      if let Some(fingerprint) = &client.cert_fingerprint {
+        let fingerprint = hex::decode(fingerprint.replace(':', ""))
+          .map_err(|_e| format!("Client {} has an invalid CertFingerprint", client.name))?;
+        if by_fingerprint.insert(fingerprint, id).is_some() {
+          return Err(format!("Client {} reuses a certificate", client.name));
+        }
+      }
+      if client.api_key.is_none() && client.cert_fingerprint.is_none() {
+        return Err(format!(
+          "Client {} has no ApiKey or CertFingerprint",
+          client.name
+        ));
+      }
+      if let Some(rate) = client.requests_per_second {
+        if rate <= 0.0 {
+          return Err(format!(
+            "Client {} has a RequestsPerSecond that is not positive",
+            client.name
+          ));
+        }
+      }
+      clients.push(Arc::new(Client {
+        id,
+        name: client.name,
+        namespace: client.namespace.into_bytes(),
+        admin: client.admin,
+        requests_per_second: client.requests_per_second,
+        max_counters: client.max_counters,
+      }));
+    }
+
+    let counters = match &counters_path {
+      Some(p) => match std::fs::read(p) {
+        Ok(bytes) => {
+          serde_json::from_slice(&bytes).map_err(|e| format!("Failed to parse {}: {}", p, e))?
+        },
+        Err(e) if e.kind() == io::ErrorKind::NotFound => HashMap::new(),
+        Err(e) => return Err(format!("Failed to read {}: {}", p, e)),
+      },
+      None => HashMap::new(),
+    };
+
+    let now = Instant::now();
+    let buckets = clients
+      .iter()
+      .map(|client| Bucket {
+        tokens: client.requests_per_second.unwrap_or(0.0).max(1.0),
+        refilled: now,
+      })
+      .collect();
+
+    Ok(Clients {
+      clients,
+      by_api_key,
+      by_fingerprint,
+      buckets: Mutex::new(buckets),
+      counters: Mutex::new(counters),
+      counters_path,
+    })
+  }
+
+  /// Returns the client with the certificate fingerprint, or else the one with the API key.
+  pub fn authenticate(
+    &self,
+    api_key: Option<&[u8]>,
+    fingerprint: Option<&[u8]>,
+  ) -> Option<Arc<Client>> {
+    let id = fingerprint
+      .and_then(|fingerprint| self.by_fingerprint.get(fingerprint))
+      .or_else(|| api_key.and_then(|api_key| self.by_api_key.get(&sha256(api_key))))?;
+    Some(self.clients[*id].clone())
+  }
+
+  /// Takes a request off the client's rate quota.
+  ///
+  /// # Returns
+  ///
+  /// How long the client must wait before it sends the request again, if it is over its rate.
+  pub fn admit(&self, client: &Client) -> Result<(), Duration> {
+    let rate = match client.requests_per_second {
+      Some(rate) => rate,
+      None => return Ok(()),
+    };
+
+    let mut buckets = self.buckets.lock().unwrap();
+    let bucket = &mut buckets[client.id];
+    let now = Instant::now();
+    let elapsed = now.duration_since(bucket.refilled).as_secs_f64();
+    bucket.tokens = (bucket.tokens + elapsed * rate).min(rate.max(1.0));
+    bucket.refilled = now;
+    if bucket.tokens >= 1.0 {
+      bucket.tokens -= 1.0;
+      Ok(())
+    } else {
+      Err(Duration::from_secs_f64((1.0 - bucket.tokens) / rate))
+    }
+  }
+
+  /// Takes a counter off the client's counter quota before the client creates it.
+  ///
+  /// # Returns
+  ///
+  /// false if the client already created as many counters as it may.
+  pub fn reserve_counter(&self, client: &Client) -> bool {
+    let mut counters = self.counters.lock().unwrap();
+    let count = counters.entry(client.name.clone()).or_insert(0);
+    if let Some(max) = client.max_counters {
+      if *count >= max {
+        return false;
+      }
+    }
+    *count += 1;
+    self.persist_counters(&counters);
+    true
+  }
+
+  /// Returns a counter reserved with `reserve_counter` to the quota, if the client failed to
+  /// create it.
+  pub fn release_counter(&self, client: &Client) {
+    let mut counters = self.counters.lock().unwrap();
+    if let Some(count) = counters.get_mut(&client.name) {
+      *count = count.saturating_sub(1);
+    }
+    self.persist_counters(&counters);
+  }
+
+  fn persist_counters(&self, counters: &HashMap<String, u64>) {
+    let path = match &self.counters_path {
+      Some(path) => path,
+      None => return,
+    };
+    let tmp_path = format!("{}.tmp", path);
+    let res = std::fs::write(&tmp_path, serde_json::to_vec(counters).unwrap())
+      .and_then(|_| std::fs::rename(&tmp_path, path));
+    if let Err(e) = res {
+      eprintln!("Failed to persist the counter quotas to {}: {:?}", path, e);
+    }
+  }
+}
+
+/// Builds the TLS configuration of the endpoint that also asks clients for a certificate issued
+/// by one of the CAs in `client_ca`. Clients without a certificate may still authenticate with
+/// an API key.
+pub fn tls_config_with_client_auth(
+  cert: &str,
+  key: &str,
+  client_ca: &str,
+) -> Result<RustlsConfig, String> {
+  let read_pem = |path: &str| -> Result<Vec<rustls_pemfile::Item>, String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    rustls_pemfile::read_all(&mut bytes.as_slice())
+      .map_err(|e| format!("Failed to parse {}: {}", path, e))
+  };
+
+  let certs = read_pem(cert)?
+    .into_iter()
+    .filter_map(|item| match item {
+      rustls_pemfile::Item::X509Certificate(der) => Some(Certificate(der)),
+      _ => None,
+    })
+    .collect();
+  let key = read_pem(key)?
+    .into_iter()
+    .find_map(|item| match item {
+      rustls_pemfile::Item::RSAKey(der) | rustls_pemfile::Item::PKCS8Key(der) => {
+        Some(PrivateKey(der))
+      },
+      _ => None,
+    })
+    .ok_or_else(|| format!("No private key in {}", key))?;
+
+  let mut roots = RootCertStore::empty();
+  for item in read_pem(client_ca)? {
+    if let rustls_pemfile::Item::X509Certificate(der) = item {
+      roots
+        .add(&Certificate(der))
+        .map_err(|e| format!("Invalid client CA in {}: {}", client_ca, e))?;
+    }
+  }
+
+  let mut config = ServerConfig::builder()
+    .with_safe_defaults()
+    .with_client_cert_verifier(AllowAnyAnonymousOrAuthenticatedClient::new(roots))
+    .with_single_cert(certs, key)
+    .map_err(|e| format!("Invalid certificate or key: {}", e))?;
+  config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+  Ok(RustlsConfig::from_config(Arc::new(config)))
+}
+
+/// The SHA-256 fingerprint of the certificate the client presented on the connection, if any.
+#[derive(Clone, Debug)]
+pub struct ClientCert(pub Option<Vec<u8>>);
+
+/// Accepts TLS connections and records the fingerprint of the client's certificate in the
+/// requests of the connection.
+#[derive(Clone)]
+pub struct ClientCertAcceptor {
+  inner: RustlsAcceptor,
+}
+
+impl ClientCertAcceptor {
+  pub fn new(config: RustlsConfig) -> Self {
+    ClientCertAcceptor {
+      inner: RustlsAcceptor::new(config),
+    }
+  }
+}
+
+impl<I, S> Accept<I, S> for ClientCertAcceptor
+where
+  I: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+  S: Send + 'static,
+{
+  type Stream = TlsStream<I>;
+  type Service = AddExtension<S, ClientCert>;
+  type Future = BoxFuture<'static, io::Result<(Self::Stream, Self::Service)>>;
+
+  fn accept(&self, stream: I, service: S) -> Self::Future {
+    let accept = self.inner.accept(stream, service);
+    Box::pin(async move {
+      let (stream, service) = accept.await?;
+      let fingerprint = stream
+        .get_ref()
+        .1
+        .peer_certificates()
+        .and_then(|certs| certs.first())
+        .map(|cert| sha256(&cert.0));
+      Ok((stream, Extension(ClientCert(fingerprint)).layer(service)))
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use rand::random;
+
+  // a file in the temporary directory that no other test uses
+  fn temp_path(name: &str) -> String {
+    std::env::temp_dir()
+      .join(format!("nimble-{}-{}.json", name, random::<u64>()))
+      .to_str()
+      .unwrap()
+      .to_string()
+  }
+
+  // loads the clients in a `--clients` file listing the given ones
+  fn load_clients(clients: &str, counters_path: Option<String>) -> Result<Clients, String> {
+    let path = temp_path("clients");
+    std::fs::write(&path, format!(r#"{{"Clients": [{}]}}"#, clients)).unwrap();
+    let res = Clients::load(&path, counters_path);
+    std::fs::remove_file(&path).unwrap();
+    res
+  }
+
+  #[test]
+  pub fn test_load() {
+    let clients = load_clients(
+      r#"{"Name": "billing", "ApiKey": "k1", "Namespace": "billing."},
+         {"Name": "billing2", "ApiKey": "k2", "Namespace": "billing2."},
+         {"Name": "ops", "CertFingerprint": "ab:cd", "Admin": true}"#,
+      None,
+    )
+    .unwrap();
+    let billing = &clients.clients[0];
+    assert!(billing.owns(b"billing.x"));
+    assert!(!billing.owns(b"billing2.x"));
+    assert!(!billing.owns(b"billing"));
+    assert!(clients.clients[2].owns(b"billing.x"));
+    assert!(clients.clients[2].is_admin());
+
+    let invalid = [
+      (r#"{"Name": "a", "ApiKey": "k1"}"#, "has no Namespace"),
+      (
+        r#"{"Name": "a", "ApiKey": "k1", "Namespace": "a"}"#,
+        "does not end with",
+      ),
+      (
+        r#"{"Name": "a", "ApiKey": "k1", "Namespace": "a."},
+           {"Name": "b", "ApiKey": "k2", "Namespace": "a.b."}"#,
+        "overlaps",
+      ),
+      (
+        r#"{"Name": "a", "ApiKey": "k1", "Namespace": "a.b."},
+           {"Name": "b", "ApiKey": "k2", "Namespace": "a."}"#,
+        "overlaps",
+      ),
+      (
+        r#"{"Name": "a", "ApiKey": "k1", "Namespace": "a."},
+           {"Name": "a", "ApiKey": "k2", "Namespace": "b."}"#,
+        "more than once",
+      ),
+      (
+        r#"{"Name": "a", "ApiKey": "k1", "Namespace": "a."},
+           {"Name": "b", "ApiKey": "k1", "Namespace": "b."}"#,
+        "reuses an API key",
+      ),
+      (
+        r#"{"Name": "a", "CertFingerprint": "ab", "Namespace": "a."},
+           {"Name": "b", "CertFingerprint": "AB", "Namespace": "b."}"#,
+        "reuses a certificate",
+      ),
+      (r#"{"Name": "a", "Namespace": "a."}"#, "has no ApiKey"),
+      (
+        r#"{"Name": "a", "CertFingerprint": "xyz", "Namespace": "a."}"#,
+        "invalid CertFingerprint",
+      ),
+      (
+        r#"{"Name": "a", "ApiKey": "k1", "Namespace": "a.", "RequestsPerSecond": 0}"#,
+        "not positive",
+      ),
+    ];
+    for (config, err) in invalid {
+      let res = load_clients(config, None);
+      assert!(res.as_ref().err().unwrap().contains(err), "{:?}", res.err());
+    }
+  }
+
+  #[test]
+  pub fn test_authenticate() {
+    let clients = load_clients(
+      r#"{"Name": "a", "ApiKey": "k1", "Namespace": "a."},
+         {"Name": "b", "ApiKey": "k2", "CertFingerprint": "ab:cd", "Namespace": "b."}"#,
+      None,
+    )
+    .unwrap();
+    let name = |client: Option<Arc<Client>>| client.map(|client| client.name().to_string());
+
+    assert_eq!(
+      name(clients.authenticate(Some(b"k1"), None)),
+      Some("a".to_string())
+    );
+    assert_eq!(
+      name(clients.authenticate(None, Some(&[0xab, 0xcd]))),
+      Some("b".to_string())
+    );
+    // the certificate takes precedence over the API key
+    assert_eq!(
+      name(clients.authenticate(Some(b"k1"), Some(&[0xab, 0xcd]))),
+      Some("b".to_string())
+    );
+    // an unknown certificate falls back to the API key
+    assert_eq!(
+      name(clients.authenticate(Some(b"k1"), Some(&[0xef]))),
+      Some("a".to_string())
+    );
+    assert_eq!(name(clients.authenticate(Some(b"k3"), None)), None);
+    assert_eq!(name(clients.authenticate(None, None)), None);
+  }
+
+  #[test]
+  pub fn test_admit() {
+    let clients = load_clients(
+      r#"{"Name": "a", "ApiKey": "k1", "Namespace": "a.", "RequestsPerSecond": 2},
+         {"Name": "b", "ApiKey": "k2", "Namespace": "b."}"#,
+      None,
+    )
+    .unwrap();
+    let (a, b) = (&clients.clients[0], &clients.clients[1]);
+
+    // a burst of up to one second's worth is admitted, and then the client must wait
+    assert!(clients.admit(a).is_ok());
+    assert!(clients.admit(a).is_ok());
+    let wait = clients.admit(a).unwrap_err();
+    assert!(wait > Duration::ZERO && wait <= Duration::from_millis(500));
+
+    // the bucket refills at the client's rate
+    clients.buckets.lock().unwrap()[a.id].refilled -= Duration::from_millis(500);
+    assert!(clients.admit(a).is_ok());
+    assert!(clients.admit(a).is_err());
+
+    // a client without a rate is never limited
+    for _ in 0..100 {
+      assert!(clients.admit(b).is_ok());
+    }
+  }
+
+  #[test]
+  pub fn test_reserve_counter() {
+    let counters_path = temp_path("counters");
+    let config = r#"{"Name": "a", "ApiKey": "k1", "Namespace": "a.", "MaxCounters": 2},
+                    {"Name": "b", "ApiKey": "k2", "Namespace": "b."}"#;
+    let clients = load_clients(config, Some(counters_path.clone())).unwrap();
+    let (a, b) = (&clients.clients[0], &clients.clients[1]);
+
+    assert!(clients.reserve_counter(a));
+    assert!(clients.reserve_counter(a));
+    assert!(!clients.reserve_counter(a));
+    // a counter the client failed to create goes back to the quota
+    clients.release_counter(a);
+    assert!(clients.reserve_counter(a));
+    // a client without a quota is never limited
+    for _ in 0..100 {
+      assert!(clients.reserve_counter(b));
+    }
+
+    // the counts survive a restart
+    let clients = load_clients(config, Some(counters_path.clone())).unwrap();
+    assert!(!clients.reserve_counter(&clients.clients[0]));
+    clients.release_counter(&clients.clients[0]);
+    assert!(clients.reserve_counter(&clients.clients[0]));
+    std::fs::remove_file(&counters_path).unwrap();
+  }
+}
@@ -1,15 +1,27 @@
-use endpoint::{EndpointState, PublicKeyFormat, SignatureFormat};
+mod clients;
+
+use clients::{Client, ClientCert, ClientCertAcceptor, Clients};
+use endpoint::{
+  CounterOp, CounterOpResult, CounterUpdate, EndpointError, EndpointKey, EndpointState,
+  PublicKeyFormat, ResponseSignature, SignatureFormat, ViewRetention, MAX_BATCH_CONCURRENCY,
+};
 
 use axum::{
   extract::{Extension, Path, Query},
-  http::StatusCode,
-  response::IntoResponse,
-  routing::{get, put},
+  http::{header::RETRY_AFTER, HeaderValue, Method, Request, StatusCode},
+  middleware::{self, Next},
+  response::{
+    sse::{Event, KeepAlive, Sse},
+    IntoResponse, Response,
+  },
+  routing::{get, post, put},
   Json, Router,
 };
 use axum_server::tls_rustls::RustlsConfig;
+use futures::stream::{self, StreamExt};
+use percent_encoding::percent_decode_str;
 use serde_json::json;
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, convert::Infallible, sync::Arc, time::Duration};
 use tower::ServiceBuilder;
 
 use clap::{App, Arg};
@@ -60,7 +72,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .short("m")
         .long("pem")
         .takes_value(true)
-        .help("The ECDSA prime256v1 private key pem file"),
+        .help("The ECDSA prime256v1 or Ed25519 private key pem file"),
+    )
+    .arg(
+      Arg::with_name("rotate_key")
+        .long("rotate-key")
+        .takes_value(true)
+        .requires("pem")
+        .help("The pem file of a new private key to rotate to at startup"),
     )
     .arg(
       Arg::with_name("channels")
@@ -68,6 +87,52 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .long("channels")
         .takes_value(true)
         .help("The number of grpc channels"),
+    )
+    .arg(
+      Arg::with_name("snapshot")
+        .short("s")
+        .long("snapshot")
+        .takes_value(true)
+        .help("The file in which to persist the verifier state to speed up restarts"),
+    )
+    .arg(
+      Arg::with_name("retain_views")
+        .long("retain-views")
+        .takes_value(true)
+        .help("Only accept receipts from the latest N views of the view ledger")
+        .conflicts_with("min_view_height"),
+    )
+    .arg(
+      Arg::with_name("min_view_height")
+        .long("min-view-height")
+        .takes_value(true)
+        .help("Only accept receipts from views at or above this height of the view ledger"),
+    )
+    .arg(
+      Arg::with_name("refresh_interval")
+        .long("refresh-interval")
+        .takes_value(true)
+        .help("Re-read the pem file and catch up with the view ledger every N seconds"),
+    )
+    .arg(
+      Arg::with_name("clients")
+        .long("clients")
+        .takes_value(true)
+        .help("The JSON file of the clients allowed to call the endpoint, with their quotas"),
+    )
+    .arg(
+      Arg::with_name("counters_file")
+        .long("counters-file")
+        .takes_value(true)
+        .requires("clients")
+        .help("The file in which to keep the number of counters each client created"),
+    )
+    .arg(
+      Arg::with_name("client_ca")
+        .long("client-ca")
+        .takes_value(true)
+        .requires_all(&["clients", "cert", "key"])
+        .help("The CA certificates of the clients that authenticate with a TLS certificate"),
     );
   let cli_matches = config.get_matches();
   let hostname = cli_matches.value_of("host").unwrap();
@@ -76,10 +141,24 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
   let coordinator_hostname = cli_matches.value_of("coordinator").unwrap().to_string();
   let cert = cli_matches.value_of("cert");
   let key = cli_matches.value_of("key");
+  let client_ca = cli_matches.value_of("client_ca");
+  let clients = cli_matches.value_of("clients").map(|p| {
+    let counters_file = cli_matches.value_of("counters_file").map(|p| p.to_string());
+    Arc::new(Clients::load(p, counters_file).unwrap())
+  });
   let pem = cli_matches
     .value_of("pem")
     .map(|p| std::fs::read_to_string(p).expect("Failed to read the private key pem file"));
 
+  let pem_path = cli_matches.value_of("pem").map(|p| p.to_string());
+
+  let snapshot = cli_matches.value_of("snapshot").map(|p| p.to_string());
+
+  let refresh_interval: Option<u64> = cli_matches.value_of("refresh_interval").map(|x| {
+    x.parse()
+      .expect("Failed to parse the refresh interval in seconds")
+  });
+
   let num_grpc_channels: Option<usize> = if let Some(x) = cli_matches.value_of("channels") {
     match x.to_string().parse() {
       Ok(v) => Some(v),
@@ -90,29 +169,71 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
   };
 
   let endpoint_state = Arc::new(
-    EndpointState::new(coordinator_hostname, pem, num_grpc_channels)
+    EndpointState::new(coordinator_hostname, pem, num_grpc_channels, snapshot)
       .await
       .unwrap(),
   );
 
-  // Build our application by composing routes
-  let app = Router::new()
-      .route("/serviceid", get(get_identity))
-      .route("/timeoutmap", get(get_timeout_map))
-      .route("/pingallendorsers", get(ping_all_endorsers))
-      .route("/addendorsers", put(add_endorsers))
-      .route("/counters/:handle", get(read_counter).put(new_counter).post(increment_counter))
-      // Add middleware to all routes
-      .layer(
-          ServiceBuilder::new()
-              // Handle errors from middleware
-              .layer(Extension(endpoint_state))
-              .into_inner(),
-      );
+  let retention = if let Some(x) = cli_matches.value_of("retain_views") {
+    match x.parse() {
+      Ok(n) => ViewRetention::LatestViews(n),
+      Err(_) => panic!("Failed to parse the number of views to retain"),
+    }
+  } else if let Some(x) = cli_matches.value_of("min_view_height") {
+    match x.parse() {
+      Ok(h) => ViewRetention::MinHeight(h),
+      Err(_) => panic!("Failed to parse the minimum view height"),
+    }
+  } else {
+    ViewRetention::All
+  };
+  endpoint_state.set_view_retention(retention).unwrap();
+
+  if let Some(p) = cli_matches.value_of("rotate_key") {
+    let pem = std::fs::read_to_string(p).expect("Failed to read the new private key pem file");
+    endpoint_state.rotate_key(&pem).await.unwrap();
+    println!("Rotated the endpoint key to {}", p);
+  }
+
+  // Replicas behind a load balancer share the pem file and the snapshot: the refresh follows
+  // key rotations by other replicas and catches up with the view ledger off the request path
+  if let Some(secs) = refresh_interval {
+    let state = endpoint_state.clone();
+    tokio::spawn(async move {
+      let mut interval = tokio::time::interval(Duration::from_secs(secs));
+      loop {
+        interval.tick().await;
+        if let Some(path) = &pem_path {
+          match std::fs::read_to_string(path) {
+            Ok(pem) => {
+              if let Err(e) = state.refresh_key(&pem).await {
+                eprintln!("Failed to refresh the endpoint key: {:?}", e);
+              }
+            },
+            Err(e) => eprintln!("Failed to read the private key pem file: {:?}", e),
+          }
+        }
+        if let Err(e) = state.refresh_view().await {
+          eprintln!("Failed to refresh the view: {:?}", e);
+        }
+      }
+    });
+  }
+
+  let app = router(endpoint_state, clients);
 
   // Run our app with hyper
   println!("Running endpoint at {}", addr);
-  let job = if let Some(c) = cert {
+  let job = if let Some(ca) = client_ca {
+    let config = clients::tls_config_with_client_auth(cert.unwrap(), key.unwrap(), ca).unwrap();
+
+    tokio::spawn(async move {
+      let _ = axum_server::bind(addr)
+        .acceptor(ClientCertAcceptor::new(config))
+        .serve(app.into_make_service())
+        .await;
+    })
+  } else if let Some(c) = cert {
     if let Some(k) = key {
       let config = RustlsConfig::from_pem_file(c, k).await.unwrap();
 
@@ -137,6 +258,58 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
   Ok(())
 }
 
+/// Builds our application by composing routes.
+fn router(endpoint_state: Arc<EndpointState>, clients: Option<Arc<Clients>>) -> Router {
+  Router::new()
+      .route("/healthz", get(get_health))
+      .route("/readyz", get(get_readiness))
+      .route("/serviceid", get(get_identity))
+      .route("/timeoutmap", get(get_timeout_map))
+      .route("/pingallendorsers", get(ping_all_endorsers))
+      .route("/addendorsers", put(add_endorsers))
+      .route("/counters/:handle", get(read_counter).put(new_counter).post(increment_counter))
+      .route("/counters/:handle/watch", get(watch_counter))
+      .route("/counters:batch", post(batch_counters))
+      .route("/freshnessproof/:handle", get(get_freshness_proof))
+      .route("/ledgers/:handle", get(read_log_latest).put(new_log).post(append_log))
+      .route("/ledgers/:handle/:index", get(read_log_by_index))
+      // Add middleware to all routes
+      .layer(
+          ServiceBuilder::new()
+              // Handle errors from middleware
+              .layer(Extension(endpoint_state))
+              .layer(Extension(clients))
+              .layer(middleware::from_fn(authorize))
+              .into_inner(),
+      )
+}
+
+/// Response structure for every failed request. `Code` is one of the machine-readable codes
+/// that `error_response` and `bad_request` return, and `Retryable` tells the client whether the
+/// same request may succeed if it is sent again later. A `pruned` error carries the checkpoint
+/// at which the log now starts.
+#[derive(Debug, Serialize, Deserialize)]
+struct ErrorResponse {
+  #[serde(rename = "Code")]
+  pub code: String,
+  #[serde(rename = "Message")]
+  pub message: String,
+  #[serde(rename = "Retryable")]
+  pub retryable: bool,
+  #[serde(rename = "Checkpoint", skip_serializing_if = "Option::is_none")]
+  pub checkpoint: Option<CheckpointResponse>,
+}
+
+/// The height of a checkpoint and the receipts of the endorsers on it, which
+/// `VerifierState::verify_checkpoint` checks.
+#[derive(Debug, Serialize, Deserialize)]
+struct CheckpointResponse {
+  #[serde(rename = "Height")]
+  pub height: u64,
+  #[serde(rename = "Receipts")]
+  pub receipts: String,
+}
+
 /// Response structure for the get_identity endpoint.
 #[derive(Debug, Serialize, Deserialize)]
 struct GetIdentityResponse {
@@ -144,6 +317,43 @@ struct GetIdentityResponse {
   pub id: String,
   #[serde(rename = "PublicKey")]
   pub pk: String,
+  #[serde(rename = "KeyId")]
+  pub key_id: String,
+  #[serde(rename = "KeyHistory")]
+  pub key_history: Vec<KeyHistoryEntry>,
+}
+
+/// A public key of the endpoint in the response of the get_identity endpoint, with the entry
+/// of the key ledger that records it.
+#[derive(Debug, Serialize, Deserialize)]
+struct KeyHistoryEntry {
+  #[serde(rename = "KeyId")]
+  pub key_id: String,
+  #[serde(rename = "PublicKey")]
+  pub pk: String,
+  #[serde(rename = "Block")]
+  pub block: String,
+  #[serde(rename = "Nonces")]
+  pub nonces: String,
+  #[serde(rename = "Receipts")]
+  pub receipts: String,
+}
+
+/// Response structure for the get_readiness endpoint. The endpoint is ready if it reaches the
+/// coordinator, holds every view of the view ledger and is not catching up with it, and signs
+/// with the latest key.
+#[derive(Debug, Serialize, Deserialize)]
+struct ReadinessResponse {
+  #[serde(rename = "Ready")]
+  pub ready: bool,
+  #[serde(rename = "CoordinatorReachable")]
+  pub coordinator_reachable: bool,
+  #[serde(rename = "ViewCurrent")]
+  pub view_current: bool,
+  #[serde(rename = "UpdatingView")]
+  pub updating_view: bool,
+  #[serde(rename = "KeyCurrent")]
+  pub key_current: bool,
 }
 
 /// Request structure for the new_counter endpoint.
@@ -151,6 +361,8 @@ struct GetIdentityResponse {
 struct NewCounterRequest {
   #[serde(rename = "Tag")]
   pub tag: String,
+  #[serde(rename = "RequestId", default)]
+  pub request_id: Option<String>,
 }
 
 /// Response structure for the new_counter endpoint.
@@ -158,6 +370,8 @@ struct NewCounterRequest {
 struct NewCounterResponse {
   #[serde(rename = "Signature")]
   pub signature: String,
+  #[serde(rename = "KeyId")]
+  pub key_id: String,
 }
 
 /// Request structure for the increment_counter endpoint.
@@ -167,6 +381,8 @@ struct IncrementCounterRequest {
   pub tag: String,
   #[serde(rename = "ExpectedCounter")]
   pub expected_counter: u64,
+  #[serde(rename = "RequestId", default)]
+  pub request_id: Option<String>,
 }
 
 /// Response structure for the increment_counter endpoint.
@@ -174,6 +390,56 @@ struct IncrementCounterRequest {
 struct IncrementCounterResponse {
   #[serde(rename = "Signature")]
   pub signature: String,
+  #[serde(rename = "KeyId")]
+  pub key_id: String,
+}
+
+/// An operation in a request to the batch_counters endpoint. `Op` is one of `new`, `increment`,
+/// and `read`, and determines which of the other fields are required.
+#[derive(Debug, Serialize, Deserialize)]
+struct BatchOperation {
+  #[serde(rename = "Op")]
+  pub op: String,
+  #[serde(rename = "Handle")]
+  pub handle: String,
+  #[serde(rename = "Tag", default)]
+  pub tag: Option<String>,
+  #[serde(rename = "ExpectedCounter", default)]
+  pub expected_counter: Option<u64>,
+  #[serde(rename = "Nonce", default)]
+  pub nonce: Option<String>,
+  #[serde(rename = "RequestId", default)]
+  pub request_id: Option<String>,
+}
+
+/// Request structure for the batch_counters endpoint.
+#[derive(Debug, Serialize, Deserialize)]
+struct BatchCountersRequest {
+  #[serde(rename = "Operations")]
+  pub operations: Vec<BatchOperation>,
+}
+
+/// The result of an operation in a response of the batch_counters endpoint. `Error` is set if
+/// and only if the operation failed.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct BatchResult {
+  #[serde(rename = "Tag", skip_serializing_if = "Option::is_none")]
+  pub tag: Option<String>,
+  #[serde(rename = "Counter", skip_serializing_if = "Option::is_none")]
+  pub counter: Option<u64>,
+  #[serde(rename = "Signature", skip_serializing_if = "Option::is_none")]
+  pub signature: Option<String>,
+  #[serde(rename = "KeyId", skip_serializing_if = "Option::is_none")]
+  pub key_id: Option<String>,
+  #[serde(rename = "Error", skip_serializing_if = "Option::is_none")]
+  pub error: Option<serde_json::Value>,
+}
+
+/// Response structure for the batch_counters endpoint.
+#[derive(Debug, Serialize, Deserialize)]
+struct BatchCountersResponse {
+  #[serde(rename = "Results")]
+  pub results: Vec<BatchResult>,
 }
 
 /// Response structure for the read_counter endpoint.
@@ -185,6 +451,92 @@ struct ReadCounterResponse {
   pub counter: u64,
   #[serde(rename = "Signature")]
   pub signature: String,
+  #[serde(rename = "KeyId")]
+  pub key_id: String,
+}
+
+/// Data of an `update` event of the watch_counter endpoint.
+#[derive(Debug, Serialize, Deserialize)]
+struct WatchCounterUpdate {
+  #[serde(rename = "Tag")]
+  pub tag: String,
+  #[serde(rename = "Counter")]
+  pub counter: u64,
+  #[serde(rename = "Block")]
+  pub block: String,
+  #[serde(rename = "Nonces")]
+  pub nonces: String,
+  #[serde(rename = "Receipts")]
+  pub receipts: String,
+  #[serde(rename = "Signature")]
+  pub signature: String,
+  #[serde(rename = "KeyId")]
+  pub key_id: String,
+}
+
+/// Response structure for the get_freshness_proof endpoint.
+#[derive(Debug, Serialize, Deserialize)]
+struct GetFreshnessProofResponse {
+  #[serde(rename = "Proof")]
+  pub proof: String,
+}
+
+/// Request structure for the new_log endpoint.
+#[derive(Debug, Serialize, Deserialize)]
+struct NewLogRequest {
+  #[serde(rename = "Payload")]
+  pub payload: String,
+}
+
+/// Response structure for the new_log endpoint.
+#[derive(Debug, Serialize, Deserialize)]
+struct NewLogResponse {
+  #[serde(rename = "Signature")]
+  pub signature: String,
+  #[serde(rename = "KeyId")]
+  pub key_id: String,
+}
+
+/// Request structure for the append_log endpoint.
+#[derive(Debug, Serialize, Deserialize)]
+struct AppendLogRequest {
+  #[serde(rename = "Payload")]
+  pub payload: String,
+  #[serde(rename = "ExpectedHeight")]
+  pub expected_height: u64,
+}
+
+/// Response structure for the append_log endpoint.
+#[derive(Debug, Serialize, Deserialize)]
+struct AppendLogResponse {
+  #[serde(rename = "Signature")]
+  pub signature: String,
+  #[serde(rename = "KeyId")]
+  pub key_id: String,
+}
+
+/// Response structure for the read_log_by_index endpoint.
+#[derive(Debug, Serialize, Deserialize)]
+struct ReadLogByIndexResponse {
+  #[serde(rename = "Payload")]
+  pub payload: String,
+  #[serde(rename = "Signature")]
+  pub signature: String,
+  #[serde(rename = "KeyId")]
+  pub key_id: String,
+}
+
+/// Response structure for the read_log_latest endpoint.
+#[derive(Debug, Serialize, Deserialize)]
+struct ReadLogLatestResponse {
+  #[serde(rename = "Payload")]
+  pub payload: String,
+  #[serde(rename = "Height")]
+  pub height: u64,
+  #[serde(rename = "Signature")]
+  pub signature: String,
+  #[serde(rename = "KeyId")]
+  pub key_id: String,
 }
 
 /// Response structure for the get_timeout_map endpoint.
@@ -209,6 +561,298 @@ struct AddEndorsersResp {
 struct AddEndorsersRequest {
 }
 
+/// The header in which a client presents its API key.
+const API_KEY_HEADER: &str = "nimble-api-key";
+
+/// Authenticates the client of a request and enforces its namespace and quotas. An endpoint
+/// started without `--clients` accepts every caller, and the health checks are open to all.
+async fn authorize<B>(mut req: Request<B>, next: Next<B>) -> Response {
+  let clients = match req.extensions().get::<Option<Arc<Clients>>>() {
+    Some(Some(clients)) => clients.clone(),
+    _ => return next.run(req).await,
+  };
+  let path = req.uri().path().to_string();
+  if path == "/healthz" || path == "/readyz" {
+    return next.run(req).await;
+  }
+
+  let api_key = req.headers().get(API_KEY_HEADER).map(|v| v.as_bytes());
+  let fingerprint = req
+    .extensions()
+    .get::<ClientCert>()
+    .and_then(|c| c.0.as_deref());
+  let client = match clients.authenticate(api_key, fingerprint) {
+    Some(client) => client,
+    None => {
+      return error(
+        StatusCode::UNAUTHORIZED,
+        "unauthenticated",
+        "The request carries no valid API key or client certificate",
+        false,
+      )
+      .into_response()
+    },
+  };
+
+  if let Err(wait) = clients.admit(&client) {
+    eprintln!("client {} is over its rate", client.name());
+    let mut resp = error(
+      StatusCode::TOO_MANY_REQUESTS,
+      "rate_limited",
+      "The client sends requests faster than its quota allows",
+      true,
+    )
+    .into_response();
+    let secs = wait.as_secs() + u64::from(wait.subsec_nanos() > 0);
+    resp
+      .headers_mut()
+      .insert(RETRY_AFTER, HeaderValue::from(secs));
+    return resp;
+  }
+
+  let segments = path
+    .trim_start_matches('/')
+    .split('/')
+    .collect::<Vec<&str>>();
+  if segments[0] == "addendorsers" && !client.is_admin() {
+    return forbidden("Only an admin client may add endorsers").into_response();
+  }
+  if matches!(segments[0], "counters" | "freshnessproof" | "ledgers") && segments.len() >= 2 {
+    // the handle is percent-decoded like the handler's `Path` does, so that an encoded handle
+    // cannot escape the check, and one that does not decode is rejected here
+    let handle = percent_decode_str(segments[1])
+      .decode_utf8()
+      .ok()
+      .and_then(|segment| base64_url::decode(segment.as_ref()).ok());
+    match handle {
+      Some(handle) if !client.owns(&handle) => {
+        return forbidden("The handle is outside the namespace of the client").into_response();
+      },
+      Some(_) => {},
+      None => return bad_request("The handle is not base64url").into_response(),
+    }
+  }
+
+  // a new counter is taken off the quota by its handler, which can tell a retry of a creation
+  // from a new one
+  let creates = req.method() == Method::PUT && segments[0] == "ledgers" && segments.len() == 2;
+  if creates && !clients.reserve_counter(&client) {
+    return quota_exceeded().into_response();
+  }
+
+  req.extensions_mut().insert(client.clone());
+  let resp = next.run(req).await;
+  if creates && !resp.status().is_success() {
+    clients.release_counter(&client);
+  }
+  resp
+}
+
+/// Builds the response to a failed request.
+fn error(
+  status: StatusCode,
+  code: &str,
+  message: &str,
+  retryable: bool,
+) -> (StatusCode, Json<serde_json::Value>) {
+  let resp = ErrorResponse {
+    code: code.to_string(),
+    message: message.to_string(),
+    retryable,
+    checkpoint: None,
+  };
+  (status, Json(json!(resp)))
+}
+
+/// Builds the response to a request that the endpoint cannot parse.
+fn bad_request(message: &str) -> (StatusCode, Json<serde_json::Value>) {
+  error(StatusCode::BAD_REQUEST, "bad_request", message, false)
+}
+
+/// Builds the response to a request that the client is not allowed to make.
+fn forbidden(message: &str) -> (StatusCode, Json<serde_json::Value>) {
+  error(StatusCode::FORBIDDEN, "forbidden", message, false)
+}
+
+/// Builds the response to a request that would create more counters than the client may.
+fn quota_exceeded() -> (StatusCode, Json<serde_json::Value>) {
+  error(
+    StatusCode::FORBIDDEN,
+    "quota_exceeded",
+    "The client has created as many counters as its quota allows",
+    false,
+  )
+}
+
+/// Takes a new counter off the quota of the client, unless the request retries a creation that
+/// already landed, in which case it returns the original signed response.
+async fn reserve_counter(
+  state: &EndpointState,
+  clients: &Clients,
+  client: &Client,
+  handle: &[u8],
+  tag: &[u8],
+  request_id: Option<&[u8]>,
+  sigformat: SignatureFormat,
+) -> Result<Option<ResponseSignature>, (StatusCode, Json<serde_json::Value>)> {
+  // only a client with a limited quota pays for the read that recognizes a retry; the caller
+  // gives back the counters of other retries once the endpoint replays them
+  if let (Some(request_id), true) = (request_id, client.limits_counters()) {
+    let res = state
+      .replay_new_counter(handle, tag, request_id, sigformat)
+      .await;
+    if let Some(signature) = res {
+      return Ok(Some(signature));
+    }
+  }
+  if !clients.reserve_counter(client) {
+    return Err(quota_exceeded());
+  }
+  Ok(None)
+}
+
+/// Maps an error of the endpoint, including the reasons the coordinator gives for refusing a
+/// request, to the response to the client.
+fn error_response(e: EndpointError) -> (StatusCode, Json<serde_json::Value>) {
+  match e {
+    EndpointError::InvalidRequest | EndpointError::FailedToConvertCounter => error(
+      StatusCode::BAD_REQUEST,
+      "invalid_request",
+      "The coordinator rejected the arguments of the request",
+      false,
+    ),
+    EndpointError::ReservedHandle => error(
+      StatusCode::FORBIDDEN,
+      "reserved_handle",
+      "The handle is reserved for the endpoint's key ledger",
+      false,
+    ),
+    EndpointError::UnsupportedSignatureFormat => error(
+      StatusCode::BAD_REQUEST,
+      "unsupported_format",
+      "Signatures of the endpoint's key have no encoding in the requested format",
+      false,
+    ),
+    EndpointError::LedgerAlreadyExists => error(
+      StatusCode::CONFLICT,
+      "already_exists",
+      "A counter or log with the handle already exists",
+      false,
+    ),
+    EndpointError::UnexpectedHeight => error(
+      StatusCode::CONFLICT,
+      "height_mismatch",
+      "The expected counter or height does not match the counter or log",
+      false,
+    ),
+    EndpointError::LedgerQuarantined => error(
+      StatusCode::CONFLICT,
+      "quarantined",
+      "The counter or log is read-only until an operator releases it from quarantine",
+      false,
+    ),
+    EndpointError::LedgerNotFound => error(
+      StatusCode::NOT_FOUND,
+      "not_found",
+      "The counter or log does not exist",
+      false,
+    ),
+    EndpointError::EntryNotFound => error(
+      StatusCode::NOT_FOUND,
+      "entry_not_found",
+      "The counter or log has no entry at the requested index",
+      false,
+    ),
+    EndpointError::LogEntryPruned { height, receipts } => {
+      let resp = ErrorResponse {
+        code: "pruned".to_string(),
+        message: "The entry was pruned from the log".to_string(),
+        retryable: false,
+        checkpoint: Some(CheckpointResponse {
+          height,
+          receipts: base64_url::encode(&receipts),
+        }),
+      };
+      (StatusCode::NOT_FOUND, Json(json!(resp)))
+    },
+    EndpointError::CounterDeleted | EndpointError::LedgerDeleted => error(
+      StatusCode::GONE,
+      "deleted",
+      "The counter or log was deleted",
+      false,
+    ),
+    EndpointError::Unavailable | EndpointError::UnableToConnectToCoordinator => error(
+      StatusCode::SERVICE_UNAVAILABLE,
+      "unavailable",
+      "The coordinator or a quorum of endorsers cannot be reached",
+      true,
+    ),
+    EndpointError::NotEndorsed => error(
+      StatusCode::GATEWAY_TIMEOUT,
+      "not_endorsed",
+      "The write is stored but not endorsed by a quorum of endorsers",
+      false,
+    ),
+    EndpointError::KeyNotCurrent => error(
+      StatusCode::SERVICE_UNAVAILABLE,
+      "key_not_current",
+      "The endpoint's key was rotated away from, so it signs nothing until it gets the new one",
+      true,
+    ),
+    EndpointError::FailedToVerifyNewCounter
+    | EndpointError::FailedToVerifyIncrementedCounter
+    | EndpointError::FaieldToVerifyReadCounter
+    | EndpointError::FailedToVerifyNewLog
+    | EndpointError::FailedToVerifyAppendedLog
+    | EndpointError::FailedToVerifyReadLog
+    | EndpointError::FailedToApplyViewChange
+    | EndpointError::FailedToVerifyWatchedCounter
+    | EndpointError::FailedToVerifyKeyHistory
+    | EndpointError::FailedToCreateFreshnessProof => error(
+      StatusCode::BAD_GATEWAY,
+      "verification_failed",
+      "The response of the coordinator failed verification",
+      false,
+    ),
+    EndpointError::FailedToAcquireReadLock | EndpointError::FailedToAcquireWriteLock => error(
+      StatusCode::INTERNAL_SERVER_ERROR,
+      "internal",
+      "The endpoint failed to process the request",
+      true,
+    ),
+    _ => error(
+      StatusCode::BAD_GATEWAY,
+      "coordinator_error",
+      "The coordinator failed to process the request",
+      true,
+    ),
+  }
+}
+
+/// Reports that the endpoint is running.
+async fn get_health() -> impl IntoResponse {
+  (StatusCode::OK, Json(json!({ "Status": "ok" })))
+}
+
+/// Reports whether the endpoint should receive requests, so that a load balancer can drain a
+/// replica while it catches up with the view ledger.
+async fn get_readiness(Extension(state): Extension<Arc<EndpointState>>) -> impl IntoResponse {
+  let health = state.check_health().await;
+  let resp = ReadinessResponse {
+    ready: health.is_ready(),
+    coordinator_reachable: health.coordinator_reachable,
+    view_current: health.view_current,
+    updating_view: health.updating_view,
+    key_current: health.key_current,
+  };
+  let status = if resp.ready {
+    StatusCode::OK
+  } else {
+    StatusCode::SERVICE_UNAVAILABLE
+  };
+  (status, Json(json!(resp)))
+}
+
 /// Handler for the get_identity endpoint.
 async fn get_identity(
   Query(params): Query<HashMap<String, String>>,
@@ -223,15 +867,38 @@ async fn get_identity(
       "uncompressed" => PublicKeyFormat::UNCOMPRESSED,
       _ => {
         eprintln!("unsupported format");
-        return (StatusCode::BAD_REQUEST, Json(json!({})));
+        return bad_request("The public key format is not supported");
       },
     }
   };
 
   let (id, pk) = state.get_identity(pkformat).unwrap();
+  let key_id = state.get_key_id().unwrap();
+  let key_history = state
+    .get_key_history(pkformat)
+    .unwrap()
+    .into_iter()
+    .map(
+      |EndpointKey {
+         key_id,
+         public_key,
+         block,
+         nonces,
+         receipts,
+       }| KeyHistoryEntry {
+        key_id: base64_url::encode(&key_id),
+        pk: base64_url::encode(&public_key),
+        block: base64_url::encode(&block),
+        nonces: base64_url::encode(&nonces),
+        receipts: base64_url::encode(&receipts),
+      },
+    )
+    .collect();
   let resp = GetIdentityResponse {
     id: base64_url::encode(&id),
     pk: base64_url::encode(&pk),
+    key_id: base64_url::encode(&key_id),
+    key_history,
   };
   (StatusCode::OK, Json(json!(resp)))
 }
@@ -242,21 +909,32 @@ async fn new_counter(
   Json(req): Json<NewCounterRequest>,
   Query(params): Query<HashMap<String, String>>,
   Extension(state): Extension<Arc<EndpointState>>,
+  Extension(clients): Extension<Option<Arc<Clients>>>,
+  client: Option<Extension<Arc<Client>>>,
 ) -> impl IntoResponse {
   let res = base64_url::decode(&handle);
   if res.is_err() {
     eprintln!("received a bad handle {:?}", res);
-    return (StatusCode::BAD_REQUEST, Json(json!({})));
+    return bad_request("The handle is not valid base64url");
   }
   let handle = res.unwrap();
 
   let res = base64_url::decode(&req.tag);
   if res.is_err() {
     eprintln!("received a bad tag {:?}", res);
-    return (StatusCode::BAD_REQUEST, Json(json!({})));
+    return bad_request("The tag is not valid base64url");
   }
   let tag = res.unwrap();
 
+  let request_id = match req.request_id.as_ref().map(base64_url::decode) {
+    Some(Err(e)) => {
+      eprintln!("received a bad request id {:?}", e);
+      return bad_request("The request id is not valid base64url");
+    },
+    Some(Ok(request_id)) => Some(request_id),
+    None => None,
+  };
+
   let sigformat = if params.contains_key("sigformat") {
     match params["sigformat"].as_ref() {
       "der" => SignatureFormat::DER,
@@ -266,15 +944,42 @@ async fn new_counter(
     SignatureFormat::RAW
   };
 
-  let res = state.new_counter(&handle, &tag, sigformat).await;
-  if res.is_err() {
-    eprintln!("failed to create a new counter {:?}", res);
-    return (StatusCode::CONFLICT, Json(json!({})));
+  let request_id = request_id.as_deref();
+  let res = match (&clients, &client) {
+    (Some(clients), Some(Extension(client))) => {
+      let res = reserve_counter(
+        &state, clients, client, &handle, &tag, request_id, sigformat,
+      );
+      match res.await {
+        Ok(Some(signature)) => Ok(signature),
+        Ok(None) => {
+          let res = state
+            .create_counter(&handle, &tag, request_id, sigformat)
+            .await;
+          // only a counter that the request created stays off the quota
+          if !matches!(res, Ok((_, false))) {
+            clients.release_counter(client);
+          }
+          res.map(|(signature, _replayed)| signature)
+        },
+        Err(resp) => return resp,
+      }
+    },
+    _ => {
+      state
+        .new_counter(&handle, &tag, request_id, sigformat)
+        .await
+    },
+  };
+  if let Err(e) = res {
+    eprintln!("failed to create a new counter {:?}", e);
+    return error_response(e);
   }
   let signature = res.unwrap();
 
   let resp = NewCounterResponse {
-    signature: base64_url::encode(&signature),
+    signature: base64_url::encode(&signature.signature),
+    key_id: base64_url::encode(&signature.key_id),
   };
 
   (StatusCode::OK, Json(json!(resp)))
@@ -289,18 +994,18 @@ async fn read_counter(
   let res = base64_url::decode(&handle);
   if res.is_err() {
     eprintln!("received a bad handle {:?}", res);
-    return (StatusCode::BAD_REQUEST, Json(json!({})));
+    return bad_request("The handle is not valid base64url");
   }
   let handle = res.unwrap();
 
   if !params.contains_key("nonce") {
     eprintln!("missing a nonce");
-    return (StatusCode::BAD_REQUEST, Json(json!({})));
+    return bad_request("The nonce is missing");
   }
   let res = base64_url::decode(&params["nonce"]);
   if res.is_err() {
     eprintln!("received a bad nonce {:?}", res);
-    return (StatusCode::BAD_REQUEST, Json(json!({})));
+    return bad_request("The nonce is not valid base64url");
   }
   let nonce = res.unwrap();
 
@@ -314,16 +1019,376 @@ async fn read_counter(
   };
 
   let res = state.read_counter(&handle, &nonce, sigformat).await;
-  if res.is_err() {
-    eprintln!("failed to read a counter {:?}", res);
-    return (StatusCode::CONFLICT, Json(json!({})));
+  if let Err(e) = res {
+    eprintln!("failed to read a counter {:?}", e);
+    return error_response(e);
   }
   let (tag, counter, signature) = res.unwrap();
 
   let resp = ReadCounterResponse {
     tag: base64_url::encode(&tag),
     counter,
-    signature: base64_url::encode(&signature),
+    signature: base64_url::encode(&signature.signature),
+    key_id: base64_url::encode(&signature.key_id),
+  };
+
+  (StatusCode::OK, Json(json!(resp)))
+}
+
+/// Handler for the watch_counter endpoint. It streams server-sent events: an `update` event
+/// for every change of the counter, starting at the counter in the `from` parameter or at the
+/// current one, and an `error` event before the stream ends, e.g., when the counter is deleted.
+async fn watch_counter(
+  Path(handle): Path<String>,
+  Query(params): Query<HashMap<String, String>>,
+  Extension(state): Extension<Arc<EndpointState>>,
+) -> impl IntoResponse {
+  let res = base64_url::decode(&handle);
+  if res.is_err() {
+    eprintln!("received a bad handle {:?}", res);
+    return bad_request("The handle is not valid base64url").into_response();
+  }
+  let handle = res.unwrap();
+
+  let from_counter = match params.get("from").map(|from| from.parse::<u64>()) {
+    Some(Err(e)) => {
+      eprintln!("received a bad counter to watch from {:?}", e);
+      return bad_request("The counter to watch from is not a number").into_response();
+    },
+    Some(Ok(from)) => Some(from),
+    None => None,
+  };
+
+  let sigformat = if params.contains_key("sigformat") {
+    match params["sigformat"].as_ref() {
+      "der" => SignatureFormat::DER,
+      _ => SignatureFormat::RAW,
+    }
+  } else {
+    SignatureFormat::RAW
+  };
+
+  let res = state.watch_counter(&handle, from_counter, sigformat).await;
+  if let Err(e) = res {
+    eprintln!("failed to watch a counter {:?}", e);
+    return error_response(e).into_response();
+  }
+  let updates = res.unwrap().map(|res| {
+    let event = match res {
+      Ok(CounterUpdate {
+        counter,
+        tag,
+        block,
+        nonces,
+        receipts,
+        signature,
+      }) => {
+        let update = WatchCounterUpdate {
+          tag: base64_url::encode(&tag),
+          counter,
+          block: base64_url::encode(&block),
+          nonces: base64_url::encode(&nonces),
+          receipts: base64_url::encode(&receipts),
+          signature: base64_url::encode(&signature.signature),
+          key_id: base64_url::encode(&signature.key_id),
+        };
+        Event::default()
+          .event("update")
+          .data(json!(update).to_string())
+      },
+      Err(e) => {
+        eprintln!("stopped watching a counter {:?}", e);
+        let (_status, Json(error)) = error_response(e);
+        Event::default().event("error").data(error.to_string())
+      },
+    };
+    Ok::<_, Infallible>(event)
+  });
+
+  Sse::new(updates)
+    .keep_alive(KeepAlive::default())
+    .into_response()
+}
+
+/// The maximum number of operations in a request to the batch_counters endpoint.
+const MAX_BATCH_SIZE: usize = 1000;
+
+/// Decodes an operation of a batch, or returns why it is malformed.
+fn parse_batch_operation(op: &BatchOperation) -> Result<CounterOp, &'static str> {
+  let decode = |field: &Option<String>, missing, malformed| match field {
+    Some(field) => base64_url::decode(field).map_err(|_e| malformed),
+    None => Err(missing),
+  };
+
+  let handle = base64_url::decode(&op.handle).map_err(|_e| "The handle is not valid base64url")?;
+  let request_id = match &op.request_id {
+    Some(request_id) => {
+      Some(base64_url::decode(request_id).map_err(|_e| "The request id is not valid base64url")?)
+    },
+    None => None,
+  };
+
+  match op.op.as_ref() {
+    "new" => Ok(CounterOp::New {
+      handle,
+      tag: decode(
+        &op.tag,
+        "The tag is missing",
+        "The tag is not valid base64url",
+      )?,
+      request_id,
+    }),
+    "increment" => Ok(CounterOp::Increment {
+      handle,
+      tag: decode(
+        &op.tag,
+        "The tag is missing",
+        "The tag is not valid base64url",
+      )?,
+      expected_counter: op
+        .expected_counter
+        .ok_or("The expected counter is missing")?,
+      request_id,
+    }),
+    "read" => Ok(CounterOp::Read {
+      handle,
+      nonce: decode(
+        &op.nonce,
+        "The nonce is missing",
+        "The nonce is not valid base64url",
+      )?,
+    }),
+    _ => Err("The operation is not one of new, increment, and read"),
+  }
+}
+
+/// How a well-formed operation of a batch that the client may run proceeds.
+enum Admission {
+  /// The operation goes to the endpoint. `reserved` is true if it took a new counter off the
+  /// quota of the client.
+  Run { op: CounterOp, reserved: bool },
+  /// The operation retries a creation that already landed, and has the original response.
+  Replayed(ResponseSignature),
+}
+
+/// Decodes an operation of a batch, checks that the client may run it, and takes a new counter
+/// off its quota.
+async fn admit_batch_operation(
+  state: &EndpointState,
+  quota: Option<(&Clients, &Client)>,
+  op: BatchOperation,
+  sigformat: SignatureFormat,
+) -> Result<Admission, (StatusCode, Json<serde_json::Value>)> {
+  let op = parse_batch_operation(&op).map_err(bad_request)?;
+  let (clients, client) = match quota {
+    Some(quota) => quota,
+    None => {
+      return Ok(Admission::Run {
+        op,
+        reserved: false,
+      })
+    },
+  };
+
+  let handle = match &op {
+    CounterOp::New { handle, .. }
+    | CounterOp::Increment { handle, .. }
+    | CounterOp::Read { handle, .. } => handle,
+  };
+  if !client.owns(handle) {
+    return Err(forbidden(
+      "The handle is outside the namespace of the client",
+    ));
+  }
+  let creates = match &op {
+    CounterOp::New {
+      handle,
+      tag,
+      request_id,
+    } => {
+      let request_id = request_id.as_deref();
+      let res = reserve_counter(state, clients, client, handle, tag, request_id, sigformat);
+      if let Some(signature) = res.await? {
+        return Ok(Admission::Replayed(signature));
+      }
+      true
+    },
+    _ => false,
+  };
+  Ok(Admission::Run {
+    op,
+    reserved: creates,
+  })
+}
+
+/// The result of an operation of a batch that succeeded with the signature.
+fn signed_result(signature: ResponseSignature) -> BatchResult {
+  BatchResult {
+    signature: Some(base64_url::encode(&signature.signature)),
+    key_id: Some(base64_url::encode(&signature.key_id)),
+    ..Default::default()
+  }
+}
+
+/// The result of an operation of a batch that failed with the response.
+fn failed_result((_status, Json(error)): (StatusCode, Json<serde_json::Value>)) -> BatchResult {
+  BatchResult {
+    error: Some(error),
+    ..Default::default()
+  }
+}
+
+/// Handler for the batch_counters endpoint.
+async fn batch_counters(
+  Path(suffix): Path<String>,
+  Json(req): Json<BatchCountersRequest>,
+  Query(params): Query<HashMap<String, String>>,
+  Extension(state): Extension<Arc<EndpointState>>,
+  Extension(clients): Extension<Option<Arc<Clients>>>,
+  client: Option<Extension<Arc<Client>>>,
+) -> impl IntoResponse {
+  // the router reads `:batch` in `/counters:batch` as a path parameter that matches the rest
+  // of the segment, including the colon
+  if suffix != ":batch" {
+    return error(
+      StatusCode::NOT_FOUND,
+      "not_found",
+      "The path does not exist",
+      false,
+    );
+  }
+
+  if req.operations.len() > MAX_BATCH_SIZE {
+    eprintln!("received a batch of {} operations", req.operations.len());
+    return bad_request(&format!(
+      "A batch has at most {} operations",
+      MAX_BATCH_SIZE
+    ));
+  }
+
+  let sigformat = if params.contains_key("sigformat") {
+    match params["sigformat"].as_ref() {
+      "der" => SignatureFormat::DER,
+      _ => SignatureFormat::RAW,
+    }
+  } else {
+    SignatureFormat::RAW
+  };
+
+  let quota = match (&clients, &client) {
+    (Some(clients), Some(Extension(client))) => Some((clients.as_ref(), client.as_ref())),
+    _ => None,
+  };
+
+  // malformed operations, and those the client may not run, fail on their own, retries of
+  // creations that already landed are answered right away, and the rest go to the endpoint
+  let admissions = stream::iter(req.operations)
+    .map(|op| admit_batch_operation(&state, quota, op, sigformat))
+    .buffered(MAX_BATCH_CONCURRENCY)
+    .collect::<Vec<_>>()
+    .await;
+  let mut ops = Vec::new();
+  let mut reserved = Vec::new();
+  let mut settled = Vec::new();
+  for admission in admissions {
+    match admission {
+      Ok(Admission::Run {
+        op,
+        reserved: creates,
+      }) => {
+        ops.push(op);
+        reserved.push(creates);
+        settled.push(None);
+      },
+      Ok(Admission::Replayed(signature)) => settled.push(Some(signed_result(signature))),
+      Err(resp) => settled.push(Some(failed_result(resp))),
+    }
+  }
+
+  let outcomes = state.batch_counters(ops, sigformat).await;
+  if let Some((clients, client)) = quota {
+    for (outcome, creates) in outcomes.iter().zip(reserved) {
+      // only a counter that the operation created stays off the quota
+      let created = matches!(
+        outcome,
+        Ok(CounterOpResult::New {
+          replayed: false,
+          ..
+        })
+      );
+      if creates && !created {
+        clients.release_counter(client);
+      }
+    }
+  }
+
+  let mut outcomes = outcomes.into_iter();
+  let results = settled
+    .into_iter()
+    .map(|result| {
+      let outcome = match result {
+        Some(result) => return result,
+        None => outcomes.next().unwrap(),
+      };
+      match outcome {
+        Ok(CounterOpResult::New { signature, .. })
+        | Ok(CounterOpResult::Increment { signature }) => signed_result(signature),
+        Ok(CounterOpResult::Read {
+          tag,
+          counter,
+          signature,
+        }) => BatchResult {
+          tag: Some(base64_url::encode(&tag)),
+          counter: Some(counter),
+          signature: Some(base64_url::encode(&signature.signature)),
+          key_id: Some(base64_url::encode(&signature.key_id)),
+          ..Default::default()
+        },
+        Err(e) => {
+          eprintln!("failed to run an operation of a batch {:?}", e);
+          failed_result(error_response(e))
+        },
+      }
+    })
+    .collect();
+
+  let resp = BatchCountersResponse { results };
+
+  (StatusCode::OK, Json(json!(resp)))
+}
+
+/// Handler for the get_freshness_proof endpoint.
+async fn get_freshness_proof(
+  Path(handle): Path<String>,
+  Query(params): Query<HashMap<String, String>>,
+  Extension(state): Extension<Arc<EndpointState>>,
+) -> impl IntoResponse {
+  let res = base64_url::decode(&handle);
+  if res.is_err() {
+    eprintln!("received a bad handle {:?}", res);
+    return bad_request("The handle is not valid base64url");
+  }
+  let handle = res.unwrap();
+
+  if !params.contains_key("nonce") {
+    eprintln!("missing a nonce");
+    return bad_request("The nonce is missing");
+  }
+  let res = base64_url::decode(&params["nonce"]);
+  if res.is_err() {
+    eprintln!("received a bad nonce {:?}", res);
+    return bad_request("The nonce is not valid base64url");
+  }
+  let nonce = res.unwrap();
+
+  let res = state.get_freshness_proof(&handle, &nonce).await;
+  if let Err(e) = res {
+    eprintln!("failed to produce a freshness proof {:?}", e);
+    return error_response(e);
+  }
+
+  let resp = GetFreshnessProofResponse {
+    proof: base64_url::encode(&res.unwrap()),
   };
 
   (StatusCode::OK, Json(json!(resp)))
@@ -339,17 +1404,26 @@ async fn increment_counter(
   let res = base64_url::decode(&handle);
   if res.is_err() {
     eprintln!("received a bad handle {:?}", res);
-    return (StatusCode::BAD_REQUEST, Json(json!({})));
+    return bad_request("The handle is not valid base64url");
   }
   let handle = res.unwrap();
 
   let res = base64_url::decode(&req.tag);
   if res.is_err() {
     eprintln!("received a bad tag {:?}", res);
-    return (StatusCode::BAD_REQUEST, Json(json!({})));
+    return bad_request("The tag is not valid base64url");
   }
   let tag = res.unwrap();
 
+  let request_id = match req.request_id.as_ref().map(base64_url::decode) {
+    Some(Err(e)) => {
+      eprintln!("received a bad request id {:?}", e);
+      return bad_request("The request id is not valid base64url");
+    },
+    Some(Ok(request_id)) => Some(request_id),
+    None => None,
+  };
+
   let sigformat = if params.contains_key("sigformat") {
     match params["sigformat"].as_ref() {
       "der" => SignatureFormat::DER,
@@ -360,16 +1434,203 @@ async fn increment_counter(
   };
 
   let res = state
-    .increment_counter(&handle, &tag, req.expected_counter, sigformat)
+    .increment_counter(
+      &handle,
+      &tag,
+      req.expected_counter,
+      request_id.as_deref(),
+      sigformat,
+    )
     .await;
-  if res.is_err() {
-    eprintln!("failed to increment a counter {:?}", res);
-    return (StatusCode::CONFLICT, Json(json!({})));
+  if let Err(e) = res {
+    eprintln!("failed to increment a counter {:?}", e);
+    return error_response(e);
   }
   let signature = res.unwrap();
 
   let resp = IncrementCounterResponse {
-    signature: base64_url::encode(&signature),
+    signature: base64_url::encode(&signature.signature),
+    key_id: base64_url::encode(&signature.key_id),
+  };
+
+  (StatusCode::OK, Json(json!(resp)))
+}
+
+/// Handler for the new_log endpoint.
+async fn new_log(
+  Path(handle): Path<String>,
+  Json(req): Json<NewLogRequest>,
+  Query(params): Query<HashMap<String, String>>,
+  Extension(state): Extension<Arc<EndpointState>>,
+) -> impl IntoResponse {
+  let res = base64_url::decode(&handle);
+  if res.is_err() {
+    eprintln!("received a bad handle {:?}", res);
+    return bad_request("The handle is not valid base64url");
+  }
+  let handle = res.unwrap();
+
+  let res = base64_url::decode(&req.payload);
+  if res.is_err() {
+    eprintln!("received a bad payload {:?}", res);
+    return bad_request("The payload is not valid base64url");
+  }
+  let payload = res.unwrap();
+
+  let sigformat = if params.contains_key("sigformat") {
+    match params["sigformat"].as_ref() {
+      "der" => SignatureFormat::DER,
+      _ => SignatureFormat::RAW,
+    }
+  } else {
+    SignatureFormat::RAW
+  };
+
+  let res = state.new_log(&handle, &payload, sigformat).await;
+  if let Err(e) = res {
+    eprintln!("failed to create a new log {:?}", e);
+    return error_response(e);
+  }
+
+  let signature = res.unwrap();
+  let resp = NewLogResponse {
+    signature: base64_url::encode(&signature.signature),
+    key_id: base64_url::encode(&signature.key_id),
+  };
+
+  (StatusCode::OK, Json(json!(resp)))
+}
+
+/// Handler for the append_log endpoint.
+async fn append_log(
+  Path(handle): Path<String>,
+  Json(req): Json<AppendLogRequest>,
+  Query(params): Query<HashMap<String, String>>,
+  Extension(state): Extension<Arc<EndpointState>>,
+) -> impl IntoResponse {
+  let res = base64_url::decode(&handle);
+  if res.is_err() {
+    eprintln!("received a bad handle {:?}", res);
+    return bad_request("The handle is not valid base64url");
+  }
+  let handle = res.unwrap();
+
+  let res = base64_url::decode(&req.payload);
+  if res.is_err() {
+    eprintln!("received a bad payload {:?}", res);
+    return bad_request("The payload is not valid base64url");
+  }
+  let payload = res.unwrap();
+
+  let sigformat = if params.contains_key("sigformat") {
+    match params["sigformat"].as_ref() {
+      "der" => SignatureFormat::DER,
+      _ => SignatureFormat::RAW,
+    }
+  } else {
+    SignatureFormat::RAW
+  };
+
+  let res = state
+    .append_log(&handle, &payload, req.expected_height, sigformat)
+    .await;
+  if let Err(e) = res {
+    eprintln!("failed to append to a log {:?}", e);
+    return error_response(e);
+  }
+
+  let signature = res.unwrap();
+  let resp = AppendLogResponse {
+    signature: base64_url::encode(&signature.signature),
+    key_id: base64_url::encode(&signature.key_id),
+  };
+
+  (StatusCode::OK, Json(json!(resp)))
+}
+
+/// Handler for the read_log_by_index endpoint.
+async fn read_log_by_index(
+  Path((handle, index)): Path<(String, u64)>,
+  Query(params): Query<HashMap<String, String>>,
+  Extension(state): Extension<Arc<EndpointState>>,
+) -> impl IntoResponse {
+  let res = base64_url::decode(&handle);
+  if res.is_err() {
+    eprintln!("received a bad handle {:?}", res);
+    return bad_request("The handle is not valid base64url");
+  }
+  let handle = res.unwrap();
+
+  let sigformat = if params.contains_key("sigformat") {
+    match params["sigformat"].as_ref() {
+      "der" => SignatureFormat::DER,
+      _ => SignatureFormat::RAW,
+    }
+  } else {
+    SignatureFormat::RAW
+  };
+
+  let res = state.read_log_by_index(&handle, index, sigformat).await;
+  if let Err(e) = res {
+    eprintln!("failed to read a log by index {:?}", e);
+    return error_response(e);
+  }
+  let (payload, signature) = res.unwrap();
+
+  let resp = ReadLogByIndexResponse {
+    payload: base64_url::encode(&payload),
+    signature: base64_url::encode(&signature.signature),
+    key_id: base64_url::encode(&signature.key_id),
+  };
+
+  (StatusCode::OK, Json(json!(resp)))
+}
+
+/// Handler for the read_log_latest endpoint.
+async fn read_log_latest(
+  Path(handle): Path<String>,
+  Query(params): Query<HashMap<String, String>>,
+  Extension(state): Extension<Arc<EndpointState>>,
+) -> impl IntoResponse {
+  let res = base64_url::decode(&handle);
+  if res.is_err() {
+    eprintln!("received a bad handle {:?}", res);
+    return bad_request("The handle is not valid base64url");
+  }
+  let handle = res.unwrap();
+
+  if !params.contains_key("nonce") {
+    eprintln!("missing a nonce");
+    return bad_request("The nonce is missing");
+  }
+  let res = base64_url::decode(&params["nonce"]);
+  if res.is_err() {
+    eprintln!("received a bad nonce {:?}", res);
+    return bad_request("The nonce is not valid base64url");
+  }
+  let nonce = res.unwrap();
+
+  let sigformat = if params.contains_key("sigformat") {
+    match params["sigformat"].as_ref() {
+      "der" => SignatureFormat::DER,
+      _ => SignatureFormat::RAW,
+    }
+  } else {
+    SignatureFormat::RAW
+  };
+
+  let res = state.read_log_latest(&handle, &nonce, sigformat).await;
+  if let Err(e) = res {
+    eprintln!("failed to read a log {:?}", e);
+    return error_response(e);
+  }
+  let (payload, height, signature) = res.unwrap();
+
+  let resp = ReadLogLatestResponse {
+    payload: base64_url::encode(&payload),
+    height,
+    signature: base64_url::encode(&signature.signature),
+    key_id: base64_url::encode(&signature.key_id),
   };
 
   (StatusCode::OK, Json(json!(resp)))
@@ -381,9 +1642,9 @@ async fn get_timeout_map(
 ) -> impl IntoResponse {
 
   let res = state.get_timeout_map().await;
-  if res.is_err() {
-    eprintln!("failed to get the timeout map");
-    return (StatusCode::CONFLICT, Json(json!({})));
+  if let Err(e) = res {
+    eprintln!("failed to get the timeout map {:?}", e);
+    return error_response(e);
   }
   let timeout_map = res.unwrap();
 
@@ -400,9 +1661,9 @@ async fn ping_all_endorsers(
 ) -> impl IntoResponse {
 
   let res = state.ping_all_endorsers().await;
-  if res.is_err() {
-    eprintln!("failed to ping all endorsers");
-    return (StatusCode::CONFLICT, Json(json!({})));
+  if let Err(e) = res {
+    eprintln!("failed to ping all endorsers {:?}", e);
+    return error_response(e);
   }
 
   let resp = PingAllResp {};
@@ -418,30 +1679,440 @@ async fn add_endorsers(
 
   if !params.contains_key("endorsers") {
     eprintln!("missing a uri endorsers");
-    return (StatusCode::BAD_REQUEST, Json(json!({})));
+    return bad_request("The endorsers are missing");
   }
 
   let res = base64_url::decode(&params["endorsers"]);
   if res.is_err() {
     eprintln!("received no endorsers uri {:?}", res);
-    return (StatusCode::BAD_REQUEST, Json(json!({})));
+    return bad_request("The endorsers are not valid base64url");
   }
   let endorsers = res.unwrap();
   let endorsers = endorsers.as_slice();
   let endorsers = std::str::from_utf8(endorsers);
   if endorsers.is_err() {
     eprintln!("received a bad endorsers uri {:?}", endorsers);
-    return (StatusCode::BAD_REQUEST, Json(json!({})));
+    return bad_request("The endorsers are not valid UTF-8");
   }
   let endorsers = endorsers.unwrap();
 
   let res = state.add_endorsers(endorsers.to_string()).await;
-  if res.is_err() {
-    eprintln!("failed to add endorsers");
-    return (StatusCode::CONFLICT, Json(json!({})));
+  if let Err(e) = res {
+    eprintln!("failed to add endorsers {:?}", e);
+    return error_response(e);
   }
 
   let resp = AddEndorsersResp {};
 
   (StatusCode::OK, Json(json!(resp)))
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use axum::body::Body;
+  use endpoint::coordinator_proto::{
+    call_server::{Call, CallServer},
+    AddEndorsersReq, AddEndorsersResp, AppendReq, AppendResp, CheckpointLedgerReq,
+    CheckpointLedgerResp, DeleteLedgerReq, DeleteLedgerResp, GetTimeoutMapReq, GetTimeoutMapResp,
+    NewLedgerReq, NewLedgerResp, PingAllReq, PingAllResp, ReadByIndexReq, ReadByIndexResp,
+    ReadLatestReq, ReadLatestResp, ReadViewByIndexReq, ReadViewByIndexResp, ReadViewTailReq,
+    ReadViewTailResp, WatchLedgerReq, WatchLedgerResp,
+  };
+  use ledger::{
+    compute_aggregated_block_hash,
+    quorum::{QuorumPolicy, ViewConfig},
+    signature::{PrivateKey, PrivateKeyTrait, PublicKeyTrait},
+    Block, CustomSerde, IdSig, MetaBlock, NimbleDigest, NimbleHashTrait, Nonces, Receipt, Receipts,
+  };
+  use rand::random;
+  use std::sync::Mutex;
+  use tokio::net::TcpListener;
+  use tokio_stream::wrappers::TcpListenerStream;
+  use tonic::{transport::Server, Status};
+  use tower::ServiceExt;
+
+  const ATTESTATION: &[u8] = b"THIS IS A PLACE HOLDER FOR ATTESTATION";
+
+  // A coordinator with a single endorser that creates counters and reads back the entry that
+  // created them, which is all that creating counters through the endpoint needs.
+  struct FakeCoordinator {
+    sk: PrivateKey,
+    group_identity: NimbleDigest,
+    view: NimbleDigest,
+    view_block: Vec<u8>,
+    view_receipts: Vec<u8>,
+    ledgers: Mutex<HashMap<Vec<u8>, Vec<u8>>>,
+  }
+
+  impl FakeCoordinator {
+    // the receipts of the endorser on the first entry of a ledger
+    fn receipts(&self, handle: &[u8], block: &[u8], hash_nonces: NimbleDigest) -> Vec<u8> {
+      let block_hash = compute_aggregated_block_hash(
+        &NimbleDigest::digest(block).to_bytes(),
+        &hash_nonces.to_bytes(),
+      );
+      let metablock = MetaBlock::new(&NimbleDigest::default(), &block_hash, 0);
+      // the message of a receipt, as `compute_receipt_message` builds it
+      let message = self.group_identity.digest_with(
+        &self
+          .view
+          .digest_with(&NimbleDigest::digest(handle).digest_with(&metablock.hash())),
+      );
+      let mut receipts = Receipts::new();
+      receipts.add(&Receipt::new(
+        self.view,
+        metablock,
+        IdSig::new(
+          self.sk.get_public_key().unwrap(),
+          self.sk.sign(&message.to_bytes()).unwrap(),
+        ),
+      ));
+      receipts.to_bytes()
+    }
+  }
+
+  #[tonic::async_trait]
+  impl Call for FakeCoordinator {
+    async fn new_ledger(
+      &self,
+      req: tonic::Request<NewLedgerReq>,
+    ) -> Result<tonic::Response<NewLedgerResp>, Status> {
+      let NewLedgerReq { handle, block } = req.into_inner();
+      let mut ledgers = self.ledgers.lock().unwrap();
+      if ledgers.contains_key(&handle) {
+        return Err(Status::already_exists("The ledger already exists"));
+      }
+      let receipts = self.receipts(&handle, &block, NimbleDigest::default());
+      ledgers.insert(handle, block);
+      Ok(tonic::Response::new(NewLedgerResp { receipts }))
+    }
+
+    async fn append(
+      &self,
+      _req: tonic::Request<AppendReq>,
+    ) -> Result<tonic::Response<AppendResp>, Status> {
+      Err(Status::unimplemented("append"))
+    }
+
+    async fn delete_ledger(
+      &self,
+      _req: tonic::Request<DeleteLedgerReq>,
+    ) -> Result<tonic::Response<DeleteLedgerResp>, Status> {
+      Err(Status::unimplemented("delete_ledger"))
+    }
+
+    async fn checkpoint_ledger(
+      &self,
+      _req: tonic::Request<CheckpointLedgerReq>,
+    ) -> Result<tonic::Response<CheckpointLedgerResp>, Status> {
+      Err(Status::unimplemented("checkpoint_ledger"))
+    }
+
+    async fn read_latest(
+      &self,
+      _req: tonic::Request<ReadLatestReq>,
+    ) -> Result<tonic::Response<ReadLatestResp>, Status> {
+      Err(Status::unimplemented("read_latest"))
+    }
+
+    async fn read_by_index(
+      &self,
+      req: tonic::Request<ReadByIndexReq>,
+    ) -> Result<tonic::Response<ReadByIndexResp>, Status> {
+      let ReadByIndexReq { handle, index } = req.into_inner();
+      let block = match self.ledgers.lock().unwrap().get(&handle) {
+        Some(block) => block.clone(),
+        None => return Err(Status::not_found("The ledger does not exist")),
+      };
+      if index != 0 {
+        return Err(Status::out_of_range("The entry does not exist"));
+      }
+      let nonces = Nonces::new().to_bytes();
+      let receipts = self.receipts(&handle, &block, NimbleDigest::digest(&nonces));
+      Ok(tonic::Response::new(ReadByIndexResp {
+        block,
+        nonces,
+        receipts,
+      }))
+    }
+
+    type WatchLedgerStream = futures::stream::Empty<Result<WatchLedgerResp, Status>>;
+
+    async fn watch_ledger(
+      &self,
+      _req: tonic::Request<WatchLedgerReq>,
+    ) -> Result<tonic::Response<Self::WatchLedgerStream>, Status> {
+      Err(Status::unimplemented("watch_ledger"))
+    }
+
+    async fn read_view_by_index(
+      &self,
+      _req: tonic::Request<ReadViewByIndexReq>,
+    ) -> Result<tonic::Response<ReadViewByIndexResp>, Status> {
+      Ok(tonic::Response::new(ReadViewByIndexResp {
+        block: self.view_block.clone(),
+        receipts: self.view_receipts.clone(),
+      }))
+    }
+
+    async fn read_view_tail(
+      &self,
+      _req: tonic::Request<ReadViewTailReq>,
+    ) -> Result<tonic::Response<ReadViewTailResp>, Status> {
+      Ok(tonic::Response::new(ReadViewTailResp {
+        block: self.view_block.clone(),
+        receipts: self.view_receipts.clone(),
+        height: 1,
+        attestations: ATTESTATION.to_vec(),
+      }))
+    }
+
+    async fn ping_all_endorsers(
+      &self,
+      _req: tonic::Request<PingAllReq>,
+    ) -> Result<tonic::Response<PingAllResp>, Status> {
+      Err(Status::unimplemented("ping_all_endorsers"))
+    }
+
+    async fn get_timeout_map(
+      &self,
+      _req: tonic::Request<GetTimeoutMapReq>,
+    ) -> Result<tonic::Response<GetTimeoutMapResp>, Status> {
+      Err(Status::unimplemented("get_timeout_map"))
+    }
+
+    async fn add_endorsers(
+      &self,
+      _req: tonic::Request<AddEndorsersReq>,
+    ) -> Result<tonic::Response<AddEndorsersResp>, Status> {
+      Err(Status::unimplemented("add_endorsers"))
+    }
+  }
+
+  // starts a `FakeCoordinator`, and returns the router of an endpoint that uses it with the
+  // clients of the given `--clients` file
+  async fn fake_router(clients: &str) -> Router {
+    let sk = PrivateKey::new();
+    let pk = sk.get_public_key().unwrap();
+
+    // the genesis entry of the view ledger and the endorser's receipt on it
+    let config = ViewConfig::new(
+      vec![(pk.to_bytes(), "http://[::1]:9090".to_string())],
+      QuorumPolicy::Majority,
+    );
+    let genesis = Block::new(&config.to_bytes());
+    let group_identity = genesis.hash();
+    let view_metablock = MetaBlock::new(&NimbleDigest::default(), &genesis.hash(), 1);
+    let message =
+      group_identity.digest_with(&NimbleDigest::default().digest_with(&view_metablock.hash()));
+    let mut view_receipts = Receipts::new();
+    view_receipts.add(&Receipt::new(
+      NimbleDigest::default(),
+      view_metablock.clone(),
+      IdSig::new(pk, sk.sign(&message.to_bytes()).unwrap()),
+    ));
+
+    let coordinator = FakeCoordinator {
+      sk,
+      group_identity,
+      view: view_metablock.hash(),
+      view_block: genesis.to_bytes(),
+      view_receipts: view_receipts.to_bytes(),
+      ledgers: Mutex::new(HashMap::new()),
+    };
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(
+      Server::builder()
+        .add_service(CallServer::new(coordinator))
+        .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+    let state = EndpointState::new(format!("http://{}", addr), None, None, None)
+      .await
+      .unwrap();
+
+    let path = std::env::temp_dir().join(format!("nimble-clients-{}.json", random::<u64>()));
+    std::fs::write(&path, format!(r#"{{"Clients": [{}]}}"#, clients)).unwrap();
+    let clients = Clients::load(path.to_str().unwrap(), None).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    router(Arc::new(state), Some(Arc::new(clients)))
+  }
+
+  // sends a request with the API key of the client, and returns the status and body of the
+  // response
+  async fn send(
+    app: &Router,
+    method: Method,
+    uri: &str,
+    body: serde_json::Value,
+  ) -> (StatusCode, serde_json::Value) {
+    let req = Request::builder()
+      .method(method)
+      .uri(uri)
+      .header(API_KEY_HEADER, "k1")
+      .header("content-type", "application/json")
+      .body(Body::from(body.to_string()))
+      .unwrap();
+    let resp = app.clone().oneshot(req).await.unwrap();
+    let status = resp.status();
+    let bytes = hyper::body::to_bytes(resp.into_body()).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap())
+  }
+
+  // the code of a failed operation of a batch, or `None` if it succeeded with a signature
+  fn error_code(result: &serde_json::Value) -> Option<&str> {
+    if result.get("Error").is_none() {
+      assert!(result["Signature"].is_string() && result["KeyId"].is_string());
+    }
+    result
+      .get("Error")
+      .map(|error| error["Code"].as_str().unwrap())
+  }
+
+  fn new_op(handle: &[u8], request_id: &[u8]) -> serde_json::Value {
+    json!({
+      "Op": "new",
+      "Handle": base64_url::encode(handle),
+      "Tag": base64_url::encode(b"tag"),
+      "RequestId": base64_url::encode(request_id),
+    })
+  }
+
+  #[tokio::test]
+  pub async fn test_batch_counters() {
+    let app =
+      fake_router(r#"{"Name": "a", "ApiKey": "k1", "Namespace": "a.", "MaxCounters": 2}"#).await;
+    let batch = |ops: Vec<serde_json::Value>| {
+      let app = app.clone();
+      async move {
+        let (status, body) = send(
+          &app,
+          Method::POST,
+          "/counters:batch",
+          json!({ "Operations": ops }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        body["Results"].as_array().unwrap().clone()
+      }
+    };
+
+    // a batch over the size limit is rejected as a whole
+    let ops = vec![new_op(b"a.x", b"id"); MAX_BATCH_SIZE + 1];
+    let (status, body) = send(
+      &app,
+      Method::POST,
+      "/counters:batch",
+      json!({ "Operations": ops }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["Code"], "bad_request");
+
+    // each operation fails on its own, and the results are in the order of the operations
+    let results = batch(vec![
+      new_op(b"a.1", b"id1"),
+      json!({"Op": "new", "Handle": "!", "Tag": "dGFn"}),
+      new_op(b"b.1", b"id"),
+      json!({"Op": "read", "Handle": base64_url::encode(b"a.1")}),
+      json!({"Op": "delete", "Handle": base64_url::encode(b"a.1")}),
+      new_op(b"a.1", b"id2"),
+    ])
+    .await;
+    let codes = results.iter().map(error_code).collect::<Vec<_>>();
+    assert_eq!(
+      codes,
+      [
+        None,
+        Some("bad_request"),
+        Some("forbidden"),
+        Some("bad_request"),
+        Some("bad_request"),
+        Some("already_exists"),
+      ]
+    );
+
+    // a retry of a creation returns the original response, and is not taken off the quota
+    let results = batch(vec![new_op(b"a.1", b"id1"), new_op(b"a.2", b"id")]).await;
+    assert_eq!(
+      results.iter().map(error_code).collect::<Vec<_>>(),
+      [None, None]
+    );
+
+    // a client at its quota may still retry a creation
+    let results = batch(vec![new_op(b"a.3", b"id"), new_op(b"a.1", b"id1")]).await;
+    let codes = results.iter().map(error_code).collect::<Vec<_>>();
+    assert_eq!(codes, [Some("quota_exceeded"), None]);
+  }
+
+  #[tokio::test]
+  pub async fn test_new_counter_quota() {
+    let app =
+      fake_router(r#"{"Name": "a", "ApiKey": "k1", "Namespace": "a.", "MaxCounters": 2}"#).await;
+    let new_counter = |handle: &[u8], request_id: &[u8]| {
+      let app = app.clone();
+      let uri = format!("/counters/{}", base64_url::encode(handle));
+      let body = json!({
+        "Tag": base64_url::encode(b"tag"),
+        "RequestId": base64_url::encode(request_id),
+      });
+      async move { send(&app, Method::PUT, &uri, body).await }
+    };
+
+    assert_eq!(new_counter(b"a.1", b"id1").await.0, StatusCode::OK);
+    // a retry of the creation is not taken off the quota, and a failed creation goes back to it
+    assert_eq!(new_counter(b"a.1", b"id1").await.0, StatusCode::OK);
+    let (status, body) = new_counter(b"a.1", b"id2").await;
+    assert_eq!(
+      (status, body["Code"].as_str()),
+      (StatusCode::CONFLICT, Some("already_exists"))
+    );
+    assert_eq!(new_counter(b"a.2", b"id").await.0, StatusCode::OK);
+
+    // at its quota, the client may still retry a creation
+    assert_eq!(new_counter(b"a.1", b"id1").await.0, StatusCode::OK);
+    let (status, body) = new_counter(b"a.3", b"id").await;
+    assert_eq!(
+      (status, body["Code"].as_str()),
+      (StatusCode::FORBIDDEN, Some("quota_exceeded"))
+    );
+  }
+
+  #[tokio::test]
+  pub async fn test_percent_encoded_handle() {
+    let app = fake_router(r#"{"Name": "a", "ApiKey": "k1", "Namespace": "a."}"#).await;
+    let body = json!({ "Tag": base64_url::encode(b"tag") });
+
+    // `%59` is `Y`, so the handles are those of `b.1` and `a.1`, as the handlers decode them
+    assert_eq!(base64_url::encode(b"b.1"), "Yi4x");
+    assert_eq!(base64_url::encode(b"a.1"), "YS4x");
+    for (method, uri) in [
+      (Method::PUT, "/counters/%59i4x"),
+      (Method::GET, "/counters/%59i4x?nonce=bm9uY2U"),
+      (Method::PUT, "/ledgers/%59i4x"),
+    ] {
+      let (status, resp) = send(&app, method, uri, body.clone()).await;
+      assert_eq!(
+        (status, resp["Code"].as_str()),
+        (StatusCode::FORBIDDEN, Some("forbidden")),
+        "{}",
+        uri
+      );
+    }
+    let (status, _) = send(&app, Method::PUT, "/counters/%59S4x", body.clone()).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // a segment that does not decode is rejected before it reaches the handler
+    for uri in ["/counters/%FF", "/counters/Y%2Fi"] {
+      let (status, resp) = send(&app, Method::PUT, uri, body.clone()).await;
+      assert_eq!(
+        (status, resp["Code"].as_str()),
+        (StatusCode::BAD_REQUEST, Some("bad_request")),
+        "{}",
+        uri
+      );
+    }
+  }
+}
@@ -0,0 +1,296 @@
+//! Receipts that aggregate the BLS signatures of a quorum of endorsers, behind the `bls` feature.
+//!
+//! This is a library primitive only: endorsers still sign with their ECDSA or Ed25519 keys, and
+//! no RPC registers BLS keys with the coordinator or the endpoint. A verifier that obtains the
+//! BLS keys of the endorsers out of band registers them with
+//! `VerifierState::register_aggregate_key` before verifying an `AggregatedReceipt`.
+use crate::{
+  compute_aggregated_block_hash, compute_receipt_message,
+  errors::VerificationError,
+  signature::{bls, AggregateSignatureTrait, PublicKey, PublicKeyTrait, SignatureTrait},
+  CustomSerde, CustomSerdeError, IdSig, MetaBlock, NimbleDigest, NimbleHashTrait, VerifierState,
+};
+use std::collections::HashSet;
+
+/// A receipt in which the BLS signatures of a quorum of endorsers are aggregated into one
+/// signature, so its size and verification cost do not grow with the number of endorsers
+#[derive(Debug, Clone)]
+pub struct AggregatedReceipt {
+  view: NimbleDigest,
+  metablock: MetaBlock,
+  sig: Vec<u8>,
+  signers: Vec<Vec<u8>>,
+}
+
+impl AggregatedReceipt {
+  /// Aggregates BLS signatures on the same metablock into a receipt.
+  ///
+  /// # Arguments
+  ///
+  /// * `view` - The view in which the endorsers signed.
+  /// * `metablock` - The metablock signed by the endorsers.
+  /// * `id_sigs` - The identity (the public key in the view) and BLS signature of each endorser.
+  pub fn new(
+    view: NimbleDigest,
+    metablock: MetaBlock,
+    id_sigs: &[(Vec<u8>, Vec<u8>)],
+  ) -> Result<Self, VerificationError> {
+    let mut signers = Vec::new();
+    let mut sigs = Vec::new();
+    for (id, sig) in id_sigs {
+      if signers.contains(id) {
+        return Err(VerificationError::DuplicateIds);
+      }
+      if id.len() != PublicKey::num_bytes() {
+        return Err(VerificationError::InvalidPublicKey);
+      }
+      signers.push(id.clone());
+      sigs.push(bls::Signature::from_bytes(sig).map_err(|_e| VerificationError::InvalidSignature)?);
+    }
+    let sig = bls::Signature::aggregate(&sigs).map_err(|_e| VerificationError::InvalidSignature)?;
+
+    Ok(AggregatedReceipt {
+      view,
+      metablock,
+      sig: sig.to_bytes(),
+      signers,
+    })
+  }
+
+  pub fn get_view(&self) -> &NimbleDigest {
+    &self.view
+  }
+
+  pub fn get_metablock(&self) -> &MetaBlock {
+    &self.metablock
+  }
+
+  pub fn get_signers(&self) -> &Vec<Vec<u8>> {
+    &self.signers
+  }
+
+  /// Verifies the receipt against a block with one pairing check and returns its height.
+  pub fn verify(
+    &self,
+    verifier_state: &VerifierState,
+    handle_bytes: &[u8],
+    block_bytes: &[u8],
+    hash_nonces_bytes: &[u8],
+    expected_height: Option<usize>,
+  ) -> Result<usize, VerificationError> {
+    let block_hash = compute_aggregated_block_hash(
+      &NimbleDigest::digest(block_bytes).to_bytes(),
+      hash_nonces_bytes,
+    );
+    if block_hash != *self.metablock.get_block_hash() {
+      return Err(VerificationError::InvalidBlockHash);
+    }
+    if let Some(h) = expected_height {
+      if h != self.metablock.get_height() {
+        return Err(VerificationError::InvalidHeight);
+      }
+    }
+
+    let pks = verifier_state.get_pks_for_view(&self.view)?;
+    let policy = verifier_state.get_policy_for_view(&self.view)?;
+    let signers = self.signers.iter().cloned().collect::<HashSet<Vec<u8>>>();
+    if signers.len() != self.signers.len() {
+      return Err(VerificationError::DuplicateIds);
+    }
+    if !policy.is_satisfied(pks, &signers) {
+      return Err(VerificationError::InsufficientReceipts);
+    }
+
+    let mut bls_pks = Vec::new();
+    for id in &self.signers {
+      let bls_pk = verifier_state.get_aggregate_key(id)?;
+      bls_pks.push(
+        bls::PublicKey::from_bytes(bls_pk).map_err(|_e| VerificationError::InvalidPublicKey)?,
+      );
+    }
+
+    let message = compute_receipt_message(
+      verifier_state.get_group_identity(),
+      &self.view,
+      &NimbleDigest::digest(handle_bytes),
+      &self.metablock.hash(),
+    );
+    let sig =
+      bls::Signature::from_bytes(&self.sig).map_err(|_e| VerificationError::InvalidSignature)?;
+    sig
+      .verify_aggregate(&bls_pks, &message.to_bytes())
+      .map_err(|_e| VerificationError::InvalidSignature)?;
+
+    Ok(self.metablock.get_height())
+  }
+}
+
+impl CustomSerde for AggregatedReceipt {
+  fn to_bytes(&self) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    bytes.extend(&self.view.to_bytes());
+    bytes.extend(&self.metablock.to_bytes());
+    bytes.extend(&self.sig);
+    for id in &self.signers {
+      bytes.extend(id);
+    }
+    bytes
+  }
+
+  fn from_bytes(bytes: &[u8]) -> Result<AggregatedReceipt, CustomSerdeError> {
+    let fixed = NimbleDigest::num_bytes() + MetaBlock::num_bytes() + bls::Signature::num_bytes();
+    if bytes.len() <= fixed || (bytes.len() - fixed) % PublicKey::num_bytes() != 0 {
+      eprintln!(
+        "bytes len {} is incorrect for aggregated receipt",
+        bytes.len()
+      );
+      return Err(CustomSerdeError::IncorrectLength);
+    }
+
+    let view = NimbleDigest::from_bytes(&bytes[0..NimbleDigest::num_bytes()])?;
+    let metablock = MetaBlock::from_bytes(
+      &bytes[NimbleDigest::num_bytes()..NimbleDigest::num_bytes() + MetaBlock::num_bytes()],
+    )?;
+    let sig = bytes[NimbleDigest::num_bytes() + MetaBlock::num_bytes()..fixed].to_vec();
+    let signers = bytes[fixed..]
+      .chunks(PublicKey::num_bytes())
+      .map(|id| id.to_vec())
+      .collect();
+
+    Ok(AggregatedReceipt {
+      view,
+      metablock,
+      sig,
+      signers,
+    })
+  }
+}
+
+impl VerifierState {
+  /// Registers the BLS public key that an endorser uses for aggregated receipts.
+  ///
+  /// # Arguments
+  ///
+  /// * `id_sig` - The endorser's identity and its signature on `bls_pk`.
+  /// * `bls_pk` - The BLS public key.
+  /// * `pop` - A proof of possession of the BLS secret key.
+  pub fn register_aggregate_key(
+    &mut self,
+    id_sig: &IdSig,
+    bls_pk: &[u8],
+    pop: &[u8],
+  ) -> Result<(), VerificationError> {
+    id_sig.verify(bls_pk)?;
+    let pk =
+      bls::PublicKey::from_bytes(bls_pk).map_err(|_e| VerificationError::InvalidPublicKey)?;
+    let pop = bls::Signature::from_bytes(pop).map_err(|_e| VerificationError::InvalidSignature)?;
+    pk.verify_possession(&pop)
+      .map_err(|_e| VerificationError::InvalidSignature)?;
+    self
+      .aggregate_keys
+      .insert(id_sig.get_id().clone(), bls_pk.to_vec());
+    Ok(())
+  }
+
+  pub fn get_aggregate_key(&self, id: &[u8]) -> Result<&Vec<u8>, VerificationError> {
+    match self.aggregate_keys.get(id) {
+      Some(pk) => Ok(pk),
+      None => Err(VerificationError::InvalidPublicKey),
+    }
+  }
+
+  pub fn verify_aggregated_append(
+    &self,
+    handle_bytes: &[u8],
+    block_bytes: &[u8],
+    hash_nonces_bytes: &[u8],
+    expected_height: usize,
+    receipt_bytes: &[u8],
+  ) -> Result<(), VerificationError> {
+    let receipt = AggregatedReceipt::from_bytes(receipt_bytes)
+      .map_err(|_e| VerificationError::InvalidReceipt)?;
+    receipt.verify(
+      self,
+      handle_bytes,
+      block_bytes,
+      hash_nonces_bytes,
+      Some(expected_height),
+    )?;
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    quorum::ViewConfig,
+    signature::{PrivateKey, PrivateKeyTrait},
+    Block,
+  };
+
+  #[test]
+  pub fn test_aggregated_receipt() {
+    let endorsers = (0..3)
+      .map(|_| (PrivateKey::new(), bls::PrivateKey::new()))
+      .collect::<Vec<_>>();
+    let hostnames = endorsers
+      .iter()
+      .map(|(sk, _)| (sk.get_public_key().unwrap().to_bytes(), String::new()))
+      .collect();
+    let config = ViewConfig::new(hostnames, Default::default());
+    let view = NimbleDigest::digest(b"view");
+
+    let mut verifier_state = VerifierState::new();
+    let (pks, policy) = config.get_pks_and_policy().unwrap();
+    verifier_state.vk_map.insert(view, pks);
+    verifier_state.policy_map.insert(view, policy);
+    for (sk, bls_sk) in &endorsers {
+      let bls_pk = bls_sk.get_public_key().unwrap().to_bytes();
+      let id_sig = IdSig::new(sk.get_public_key().unwrap(), sk.sign(&bls_pk).unwrap());
+      verifier_state
+        .register_aggregate_key(&id_sig, &bls_pk, &bls_sk.prove_possession().to_bytes())
+        .unwrap();
+    }
+
+    let handle = NimbleDigest::digest(b"handle").to_bytes();
+    let block = Block::new(b"block");
+    let hash_nonces = NimbleDigest::default().to_bytes();
+    let block_hash = compute_aggregated_block_hash(&block.hash().to_bytes(), &hash_nonces);
+    let metablock = MetaBlock::new(&NimbleDigest::default(), &block_hash, 1);
+    let message = compute_receipt_message(
+      verifier_state.get_group_identity(),
+      &view,
+      &NimbleDigest::digest(&handle),
+      &metablock.hash(),
+    );
+
+    let id_sigs = endorsers[..2]
+      .iter()
+      .map(|(sk, bls_sk)| {
+        (
+          sk.get_public_key().unwrap().to_bytes(),
+          bls_sk.sign(&message.to_bytes()).unwrap().to_bytes(),
+        )
+      })
+      .collect::<Vec<_>>();
+    let receipt = AggregatedReceipt::new(view, metablock.clone(), &id_sigs).unwrap();
+    let receipt_bytes = receipt.to_bytes();
+    assert!(verifier_state
+      .verify_aggregated_append(&handle, &block.to_bytes(), &hash_nonces, 1, &receipt_bytes)
+      .is_ok());
+
+    // one signer is not a quorum of three
+    let receipt = AggregatedReceipt::new(view, metablock, &id_sigs[..1]).unwrap();
+    assert_eq!(
+      receipt.verify(
+        &verifier_state,
+        &handle,
+        &block.to_bytes(),
+        &hash_nonces,
+        Some(1)
+      ),
+      Err(VerificationError::InsufficientReceipts)
+    );
+  }
+}
@@ -56,4 +56,11 @@ pub enum VerificationError {
   InsufficentEndorsers,
   /// returned if the ledger tail maps are inconsistent
   InconsistentLedgerTailMaps,
+  /// returned if the quorum policy of a view is malformed
+  InvalidQuorumPolicy,
+  /// returned if a snapshot of the verifier state is malformed or does not match its digest
+  InvalidSnapshot,
+  /// returned if the purported view is not known after the verifier's view retention policy
+  /// dropped views, so it may be one of them
+  PrunedView,
 }
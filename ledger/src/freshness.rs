@@ -0,0 +1,317 @@
+use crate::{
+  errors::VerificationError, Block, CustomSerde, CustomSerdeError, NimbleDigest, NimbleHashTrait,
+  VerifierState,
+};
+use std::convert::TryInto;
+
+/// The version of the serialized form produced by `FreshnessProof::to_bytes`
+pub const FRESHNESS_PROOF_VERSION: u8 = 1;
+
+/// A self-contained proof that a block is the latest block of a ledger as of a nonce.
+///
+/// It bundles a `ReadLatestResp` with the chain of view ledger entries from the genesis view
+/// to the view in which the receipts were issued, so it can be verified without contacting
+/// Nimble; the verifier only needs the group identity of the Nimble instance.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct FreshnessProof {
+  handle: Vec<u8>,
+  block: Vec<u8>,
+  nonces: Vec<u8>,
+  receipts: Vec<u8>,
+  view_entries: Vec<(Vec<u8>, Vec<u8>)>,
+  attestations: Vec<u8>,
+}
+
+impl FreshnessProof {
+  /// Creates a new freshness proof.
+  ///
+  /// # Arguments
+  ///
+  /// * `handle` - The handle of the ledger.
+  /// * `block`, `nonces`, `receipts` - The response to reading the latest block of the ledger.
+  /// * `view_entries` - The blocks and receipts of the view ledger, starting from the genesis view
+  ///   and ending with the view in which `receipts` were issued.
+  /// * `attestations` - The attestation reports for the last view in `view_entries`.
+  pub fn new(
+    handle: &[u8],
+    block: &[u8],
+    nonces: &[u8],
+    receipts: &[u8],
+    view_entries: Vec<(Vec<u8>, Vec<u8>)>,
+    attestations: &[u8],
+  ) -> Self {
+    FreshnessProof {
+      handle: handle.to_vec(),
+      block: block.to_vec(),
+      nonces: nonces.to_vec(),
+      receipts: receipts.to_vec(),
+      view_entries,
+      attestations: attestations.to_vec(),
+    }
+  }
+
+  pub fn get_handle(&self) -> &Vec<u8> {
+    &self.handle
+  }
+
+  pub fn get_block(&self) -> &Vec<u8> {
+    &self.block
+  }
+
+  pub fn get_nonces(&self) -> &Vec<u8> {
+    &self.nonces
+  }
+
+  /// Verifies that the block is the latest block of the ledger for the given nonce and returns
+  /// its height.
+  ///
+  /// # Arguments
+  ///
+  /// * `group_identity` - The hash of the genesis block of the view ledger of the Nimble instance.
+  /// * `nonce` - The nonce the proof must be fresh with respect to.
+  pub fn verify(
+    &self,
+    group_identity: &NimbleDigest,
+    nonce: &[u8],
+  ) -> Result<usize, VerificationError> {
+    if self.view_entries.is_empty() {
+      return Err(VerificationError::InvalidView);
+    }
+
+    let genesis = Block::from_bytes(&self.view_entries[0].0)
+      .map_err(|_e| VerificationError::InvalidGenesisBlock)?;
+    if genesis.hash() != *group_identity {
+      return Err(VerificationError::InvalidGroupIdentity);
+    }
+
+    // replay the view ledger the same way an endpoint does: the last view is vouched for by
+    // the attestation reports, and each view authorizes the view before it
+    let mut vs = VerifierState::new();
+    vs.set_group_identity(*group_identity);
+    let (last_block, last_receipts) = self.view_entries.last().unwrap();
+    vs.apply_view_change(last_block, last_receipts, Some(&self.attestations))?;
+    for (block, receipts) in self.view_entries.iter().rev().skip(1) {
+      vs.apply_view_change(block, receipts, None)?;
+    }
+
+    vs.verify_read_latest(
+      &self.handle,
+      &self.block,
+      &self.nonces,
+      nonce,
+      &self.receipts,
+    )
+  }
+}
+
+fn write_field(bytes: &mut Vec<u8>, field: &[u8]) {
+  bytes.extend(&(field.len() as u64).to_le_bytes());
+  bytes.extend(field);
+}
+
+fn read_u64(bytes: &[u8], pos: &mut usize) -> Result<u64, CustomSerdeError> {
+  if bytes.len() < *pos + 8 {
+    return Err(CustomSerdeError::IncorrectLength);
+  }
+  let v = u64::from_le_bytes(bytes[*pos..*pos + 8].try_into().unwrap());
+  *pos += 8;
+  Ok(v)
+}
+
+fn read_field(bytes: &[u8], pos: &mut usize) -> Result<Vec<u8>, CustomSerdeError> {
+  let len = read_u64(bytes, pos)? as usize;
+  if bytes.len() - *pos < len {
+    return Err(CustomSerdeError::IncorrectLength);
+  }
+  let field = bytes[*pos..*pos + len].to_vec();
+  *pos += len;
+  Ok(field)
+}
+
+// The serialized form is the version byte followed by the handle, block, nonces, receipts and
+// attestations, then the number of view entries and the block and receipts of each entry.
+// Every variable-length field is prefixed with its length as a little-endian u64.
+impl CustomSerde for FreshnessProof {
+  fn to_bytes(&self) -> Vec<u8> {
+    let mut bytes = vec![FRESHNESS_PROOF_VERSION];
+    write_field(&mut bytes, &self.handle);
+    write_field(&mut bytes, &self.block);
+    write_field(&mut bytes, &self.nonces);
+    write_field(&mut bytes, &self.receipts);
+    write_field(&mut bytes, &self.attestations);
+    bytes.extend(&(self.view_entries.len() as u64).to_le_bytes());
+    for (block, receipts) in &self.view_entries {
+      write_field(&mut bytes, block);
+      write_field(&mut bytes, receipts);
+    }
+    bytes
+  }
+
+  fn from_bytes(bytes: &[u8]) -> Result<FreshnessProof, CustomSerdeError> {
+    if bytes.is_empty() {
+      return Err(CustomSerdeError::IncorrectLength);
+    }
+    if bytes[0] != FRESHNESS_PROOF_VERSION {
+      eprintln!("unsupported freshness proof version {}", bytes[0]);
+      return Err(CustomSerdeError::UnsupportedVersion);
+    }
+
+    let mut pos = 1;
+    let handle = read_field(bytes, &mut pos)?;
+    let block = read_field(bytes, &mut pos)?;
+    let nonces = read_field(bytes, &mut pos)?;
+    let receipts = read_field(bytes, &mut pos)?;
+    let attestations = read_field(bytes, &mut pos)?;
+    let num_entries = read_u64(bytes, &mut pos)?;
+    let mut view_entries = Vec::new();
+    for _ in 0..num_entries {
+      let block = read_field(bytes, &mut pos)?;
+      let receipts = read_field(bytes, &mut pos)?;
+      view_entries.push((block, receipts));
+    }
+    if pos != bytes.len() {
+      return Err(CustomSerdeError::IncorrectLength);
+    }
+
+    Ok(FreshnessProof {
+      handle,
+      block,
+      nonces,
+      receipts,
+      view_entries,
+      attestations,
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    compute_aggregated_block_hash, compute_receipt_message,
+    quorum::{QuorumPolicy, ViewConfig},
+    signature::{PrivateKey, PrivateKeyTrait, PublicKeyTrait},
+    IdSig, MetaBlock, Nonces, Receipt, Receipts,
+  };
+
+  const ATTESTATION: &[u8] = b"THIS IS A PLACE HOLDER FOR ATTESTATION";
+
+  /// Builds the proof that an endorser would issue for reading the latest block of a ledger with
+  /// `nonce`, in the genesis view of an instance with that single endorser. Returns the group
+  /// identity along with the proof.
+  fn genuine_proof(nonce: &[u8]) -> (NimbleDigest, FreshnessProof) {
+    let sk = PrivateKey::new();
+    let pk = sk.get_public_key().unwrap();
+    let sign =
+      |message: &NimbleDigest| IdSig::new(pk.clone(), sk.sign(&message.to_bytes()).unwrap());
+
+    // the genesis entry of the view ledger and the endorser's receipt on it
+    let config = ViewConfig::new(
+      vec![(pk.to_bytes(), "http://[::1]:9090".to_string())],
+      QuorumPolicy::Majority,
+    );
+    let genesis = Block::new(&config.to_bytes());
+    let group_identity = genesis.hash();
+    let view_metablock = MetaBlock::new(&NimbleDigest::default(), &genesis.hash(), 1);
+    let message =
+      group_identity.digest_with(&NimbleDigest::default().digest_with(&view_metablock.hash()));
+    let mut view_receipts = Receipts::new();
+    view_receipts.add(&Receipt::new(
+      NimbleDigest::default(),
+      view_metablock.clone(),
+      sign(&message),
+    ));
+
+    // the endorser's receipt on the tail of the ledger, bound to the nonce of the read
+    let handle = b"handle";
+    let block = Block::new(b"block");
+    let nonces = Nonces::new();
+    let block_hash =
+      compute_aggregated_block_hash(&block.hash().to_bytes(), &nonces.hash().to_bytes());
+    let metablock = MetaBlock::new(&NimbleDigest::digest(b"prev"), &block_hash, 3);
+    let view = view_metablock.hash();
+    let message = compute_receipt_message(
+      &group_identity,
+      &view,
+      &NimbleDigest::digest(handle),
+      &metablock.hash().digest_with_bytes(nonce),
+    );
+    let mut receipts = Receipts::new();
+    receipts.add(&Receipt::new(view, metablock, sign(&message)));
+
+    let proof = FreshnessProof::new(
+      handle,
+      &block.to_bytes(),
+      &nonces.to_bytes(),
+      &receipts.to_bytes(),
+      vec![(genesis.to_bytes(), view_receipts.to_bytes())],
+      ATTESTATION,
+    );
+    (group_identity, proof)
+  }
+
+  #[test]
+  pub fn test_freshness_proof_serde() {
+    let proof = FreshnessProof::new(
+      b"handle",
+      b"block",
+      &[],
+      b"receipts",
+      vec![
+        (b"view1".to_vec(), b"r1".to_vec()),
+        (b"view2".to_vec(), vec![]),
+      ],
+      b"attestations",
+    );
+    let bytes = proof.to_bytes();
+    assert_eq!(bytes[0], FRESHNESS_PROOF_VERSION);
+    assert_eq!(FreshnessProof::from_bytes(&bytes).unwrap(), proof);
+
+    assert_eq!(
+      FreshnessProof::from_bytes(&bytes[..bytes.len() - 1]),
+      Err(CustomSerdeError::IncorrectLength)
+    );
+    let mut future = bytes.clone();
+    future[0] = FRESHNESS_PROOF_VERSION + 1;
+    assert_eq!(
+      FreshnessProof::from_bytes(&future),
+      Err(CustomSerdeError::UnsupportedVersion)
+    );
+
+    // a proof must be anchored in the genesis view of the given instance
+    assert_eq!(
+      proof.verify(&NimbleDigest::digest(b"other"), b"nonce"),
+      Err(VerificationError::InvalidGroupIdentity)
+    );
+  }
+
+  #[test]
+  pub fn test_verify_freshness_proof() {
+    let nonce = [7u8; 16];
+    let (group_identity, proof) = genuine_proof(&nonce);
+    assert_eq!(proof.verify(&group_identity, &nonce), Ok(3));
+
+    // the proof survives serialization
+    let parsed = FreshnessProof::from_bytes(&proof.to_bytes()).unwrap();
+    assert_eq!(parsed.verify(&group_identity, &nonce), Ok(3));
+
+    // the receipt is not fresh with respect to another nonce
+    assert!(proof.verify(&group_identity, &[8u8; 16]).is_err());
+
+    // a receipt whose signature was tampered with fails
+    let mut tampered = proof.clone();
+    let last = tampered.receipts.len() - 1;
+    tampered.receipts[last] ^= 1;
+    assert!(tampered.verify(&group_identity, &nonce).is_err());
+
+    // so does a receipt for another block
+    let mut tampered = proof.clone();
+    tampered.block = Block::new(b"other block").to_bytes();
+    assert!(tampered.verify(&group_identity, &nonce).is_err());
+
+    // and a view ledger entry without the attestation of its view
+    let mut tampered = proof;
+    tampered.attestations = b"forged".to_vec();
+    assert!(tampered.verify(&group_identity, &nonce).is_err());
+  }
+}
@@ -0,0 +1,124 @@
+//! The gRPC health service that the servers of Nimble expose.
+use std::{pin::Pin, sync::Arc};
+use tonic::{codegen::futures_core::Stream, Request, Response, Status};
+
+#[allow(clippy::derive_partial_eq_without_eq)]
+pub mod health_proto {
+  tonic::include_proto!("grpc.health.v1");
+}
+
+use health_proto::{
+  health_check_response::ServingStatus,
+  health_server::{Health, HealthServer},
+  HealthCheckRequest, HealthCheckResponse,
+};
+
+/// The service whose status is `SERVING` as long as the server runs, for liveness probes. The
+/// status of every other service the server knows, including the empty name that stands for the
+/// whole server, is its readiness.
+pub const LIVENESS_SERVICE: &str = "liveness";
+
+/// A server whose readiness the health service reports.
+#[tonic::async_trait]
+pub trait Readiness: Send + Sync + 'static {
+  /// Returns true if the server can serve requests.
+  async fn is_ready(&self) -> bool;
+}
+
+/// Answers health checks of a server with its readiness.
+pub struct HealthService<R> {
+  readiness: Arc<R>,
+  services: Vec<String>,
+}
+
+impl<R: Readiness> HealthService<R> {
+  /// Creates the health service of a server that serves the given gRPC services.
+  pub fn new_server(readiness: Arc<R>, services: &[&str]) -> HealthServer<Self> {
+    HealthServer::new(HealthService {
+      readiness,
+      services: services.iter().map(|s| s.to_string()).collect(),
+    })
+  }
+}
+
+#[tonic::async_trait]
+impl<R: Readiness> Health for HealthService<R> {
+  async fn check(
+    &self,
+    req: Request<HealthCheckRequest>,
+  ) -> Result<Response<HealthCheckResponse>, Status> {
+    let service = req.into_inner().service;
+    let status = if service == LIVENESS_SERVICE {
+      ServingStatus::Serving
+    } else if service.is_empty() || self.services.contains(&service) {
+      if self.readiness.is_ready().await {
+        ServingStatus::Serving
+      } else {
+        ServingStatus::NotServing
+      }
+    } else {
+      return Err(Status::not_found(format!("Unknown service {}", service)));
+    };
+
+    Ok(Response::new(HealthCheckResponse {
+      status: status as i32,
+    }))
+  }
+
+  type WatchStream =
+    Pin<Box<dyn Stream<Item = Result<HealthCheckResponse, Status>> + Send + 'static>>;
+
+  async fn watch(
+    &self,
+    _req: Request<HealthCheckRequest>,
+  ) -> Result<Response<Self::WatchStream>, Status> {
+    // the protocol lets clients fall back to polling with Check
+    Err(Status::unimplemented("Watch is not supported; use Check"))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use tonic::Code;
+
+  struct FixedReadiness(bool);
+
+  #[tonic::async_trait]
+  impl Readiness for FixedReadiness {
+    async fn is_ready(&self) -> bool {
+      self.0
+    }
+  }
+
+  async fn check(ready: bool, service: &str) -> Result<ServingStatus, Code> {
+    let health = HealthService {
+      readiness: Arc::new(FixedReadiness(ready)),
+      services: vec!["nimble.Test".to_string()],
+    };
+    let req = Request::new(HealthCheckRequest {
+      service: service.to_string(),
+    });
+    match health.check(req).await {
+      Ok(resp) => Ok(ServingStatus::from_i32(resp.into_inner().status).unwrap()),
+      Err(status) => Err(status.code()),
+    }
+  }
+
+  #[tokio::test]
+  pub async fn check_health_service() {
+    assert_eq!(check(true, "").await, Ok(ServingStatus::Serving));
+    assert_eq!(check(true, "nimble.Test").await, Ok(ServingStatus::Serving));
+    assert_eq!(check(false, "").await, Ok(ServingStatus::NotServing));
+    assert_eq!(
+      check(false, "nimble.Test").await,
+      Ok(ServingStatus::NotServing)
+    );
+    // a server that is not ready is still alive
+    assert_eq!(
+      check(false, LIVENESS_SERVICE).await,
+      Ok(ServingStatus::Serving)
+    );
+    assert_eq!(check(true, "nimble.Unknown").await, Err(Code::NotFound));
+  }
+}
@@ -1,6 +1,15 @@
+#[cfg(feature = "bls")]
+pub mod aggregate;
 pub mod errors;
+pub mod freshness;
+pub mod health;
+pub mod quorum;
 pub mod signature;
-use crate::signature::{PublicKey, PublicKeyTrait, Signature, SignatureTrait};
+pub mod snapshot;
+use crate::{
+  quorum::{QuorumPolicy, ViewConfig},
+  signature::{PublicKey, PublicKeyTrait, Signature, SignatureTrait},
+};
 use digest::Output;
 use errors::VerificationError;
 use generic_array::{typenum::U32, GenericArray};
@@ -364,22 +373,57 @@ pub fn compute_aggregated_block_hash(
   NimbleDigest::digest(hash_block_bytes).digest_with_bytes(hash_nonces_bytes)
 }
 
+const TOMBSTONE_PREFIX: &[u8] = b"NIMBLE_TOMBSTONE";
+
+/// Returns the block that marks the ledger with the given handle as deleted. Coordinators do not
+/// accept it from clients, so a ledger whose tail holds it was deleted with `DeleteLedger`.
+pub fn tombstone_block(handle: &Handle) -> Block {
+  Block::new(&[TOMBSTONE_PREFIX, &handle.to_bytes()].concat())
+}
+
+pub fn is_tombstone(handle: &Handle, block: &Block) -> bool {
+  block.block == tombstone_block(handle).block
+}
+
+/// Checks if an entry of a ledger tail map is a tombstone whose block matches the signed metablock
+pub fn is_tombstone_entry(entry: &LedgerTailMapEntry) -> bool {
+  let (handle, metablock, block, nonces) = match (
+    NimbleDigest::from_bytes(&entry.handle),
+    MetaBlock::from_bytes(&entry.metablock),
+    Block::from_bytes(&entry.block),
+    Nonces::from_bytes(&entry.nonces),
+  ) {
+    (Ok(handle), Ok(metablock), Ok(block), Ok(nonces)) => (handle, metablock, block, nonces),
+    _ => return false,
+  };
+  is_tombstone(&handle, &block)
+    && *metablock.get_block_hash()
+      == compute_aggregated_block_hash(&block.hash().to_bytes(), &nonces.hash().to_bytes())
+}
+
+/// Computes the message that endorsers sign for the tail of a ledger in a view.
+pub(crate) fn compute_receipt_message(
+  group_identity: &NimbleDigest,
+  view: &NimbleDigest,
+  handle: &NimbleDigest,
+  tail_hash: &NimbleDigest,
+) -> NimbleDigest {
+  group_identity.digest_with(&view.digest_with(&handle.digest_with(tail_hash)))
+}
+
 pub fn retrieve_public_keys_from_config(
   config: &[u8],
 ) -> Result<HashSet<Vec<u8>>, VerificationError> {
-  let endorsers: EndorserHostnames = bincode::deserialize(config).map_err(|e| {
-    eprintln!("Failed to deserialize the view genesis block {:?}", e);
-    VerificationError::InvalidGenesisBlock
-  })?;
-  let mut pks = HashSet::new();
-  for (pk_bytes, _uri) in &endorsers {
-    let pk = PublicKey::from_bytes(pk_bytes).map_err(|_e| VerificationError::InvalidPublicKey)?;
-    pks.insert(pk.to_bytes());
-  }
-
+  let (pks, _policy) = retrieve_view_config(config)?;
   Ok(pks)
 }
 
+pub fn retrieve_view_config(
+  config: &[u8],
+) -> Result<(HashSet<Vec<u8>>, QuorumPolicy), VerificationError> {
+  ViewConfig::from_bytes(config)?.get_pks_and_policy()
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct Receipts {
   receipts: HashMap<ExtendedMetaBlock, Vec<IdSig>>,
@@ -451,19 +495,17 @@ impl Receipts {
     for (ex_meta_block, id_sigs) in &self.receipts {
       let view = ex_meta_block.get_view();
       let pks = verifier_state.get_pks_for_view(view)?;
-      if id_sigs.len() < pks.len() / 2 + 1 {
+      let policy = verifier_state.get_policy_for_view(view)?;
+      if id_sigs.len() < policy.min_signers(pks.len()) {
         continue;
       }
 
-      let mut num_receipts = 0;
-      for id_sig in id_sigs {
-        let id = id_sig.get_id();
-        if pks.contains(id) {
-          num_receipts += 1;
-        }
-      }
+      let signers = id_sigs
+        .iter()
+        .map(|id_sig| id_sig.get_id().clone())
+        .collect::<HashSet<Vec<u8>>>();
 
-      if num_receipts > pks.len() / 2 {
+      if policy.is_satisfied(pks, &signers) {
         return Ok(ex_meta_block.get_metablock().get_height());
       }
     }
@@ -528,7 +570,8 @@ impl Receipts {
 
     for (ex_meta_block, id_sigs) in &self.receipts {
       let pks = verifier_state.get_pks_for_view(ex_meta_block.get_view())?;
-      if id_sigs.len() < pks.len() / 2 + 1 {
+      let policy = verifier_state.get_policy_for_view(ex_meta_block.get_view())?;
+      if id_sigs.len() < policy.min_signers(pks.len()) {
         continue;
       }
 
@@ -548,23 +591,22 @@ impl Receipts {
         None => ex_meta_block.get_metablock().hash(),
       };
 
-      let message = verifier_state.get_group_identity().digest_with(
-        &ex_meta_block
-          .get_view()
-          .digest_with(&NimbleDigest::digest(handle_bytes).digest_with(&tail_hash)),
+      let message = compute_receipt_message(
+        verifier_state.get_group_identity(),
+        ex_meta_block.get_view(),
+        &NimbleDigest::digest(handle_bytes),
+        &tail_hash,
       );
 
-      let mut num_receipts = 0;
+      let mut signers = HashSet::new();
       for id_sig in id_sigs {
         id_sig
           .verify(&message.to_bytes())
           .map_err(|_e| VerificationError::InvalidSignature)?;
-        if pks.contains(id_sig.get_id()) {
-          num_receipts += 1;
-        }
+        signers.insert(id_sig.get_id().clone());
       }
 
-      if num_receipts > pks.len() / 2 {
+      if policy.is_satisfied(pks, &signers) {
         return Ok(ex_meta_block.get_metablock().get_height());
       }
     }
@@ -572,6 +614,64 @@ impl Receipts {
     Err(VerificationError::InvalidReceipt)
   }
 
+  /// Returns the metablocks that carry valid signatures from a quorum of the endorsers of their
+  /// view. Unlike `verify`, it takes the handle under which the ledger is stored (the digest of
+  /// the handle chosen by the client), so it works on entries read directly from a ledger store.
+  pub fn get_endorsed_metablocks(
+    &self,
+    verifier_state: &VerifierState,
+    handle: &NimbleDigest,
+  ) -> Vec<MetaBlock> {
+    let mut metablocks = Vec::new();
+    for (ex_meta_block, id_sigs) in &self.receipts {
+      let view = ex_meta_block.get_view();
+      let (pks, policy) = match (
+        verifier_state.get_pks_for_view(view),
+        verifier_state.get_policy_for_view(view),
+      ) {
+        (Ok(pks), Ok(policy)) => (pks, policy),
+        _ => continue,
+      };
+
+      let message = compute_receipt_message(
+        verifier_state.get_group_identity(),
+        view,
+        handle,
+        &ex_meta_block.get_metablock().hash(),
+      );
+      let signers = id_sigs
+        .iter()
+        .filter(|id_sig| {
+          pks.contains(id_sig.get_id()) && id_sig.verify(&message.to_bytes()).is_ok()
+        })
+        .map(|id_sig| id_sig.get_id().clone())
+        .collect::<HashSet<Vec<u8>>>();
+
+      if policy.is_satisfied(pks, &signers) {
+        metablocks.push(ex_meta_block.get_metablock().clone());
+      }
+    }
+    metablocks
+  }
+
+  /// Returns the endorsers whose receipts carry a valid signature on a view ledger entry.
+  pub fn get_view_change_signers(&self, group_identity: &NimbleDigest) -> HashSet<Vec<u8>> {
+    let mut signers = HashSet::new();
+    for (ex_meta_block, id_sigs) in &self.receipts {
+      let message = group_identity.digest_with(
+        &ex_meta_block
+          .get_view()
+          .digest_with(&ex_meta_block.get_metablock().hash()),
+      );
+      for id_sig in id_sigs {
+        if id_sig.verify(&message.to_bytes()).is_ok() {
+          signers.insert(id_sig.get_id().clone());
+        }
+      }
+    }
+    signers
+  }
+
   #[allow(clippy::too_many_arguments)]
   pub fn verify_view_change(
     &self,
@@ -605,11 +705,11 @@ impl Receipts {
     }
 
     // retrieve public keys of endorsers in the configuration
-    let new_pks = retrieve_public_keys_from_config(new_config)?;
-    let old_pks = if old_metablock.get_height() > 0 {
-      retrieve_public_keys_from_config(old_config)?
+    let (new_pks, new_policy) = retrieve_view_config(new_config)?;
+    let (old_pks, old_policy) = if old_metablock.get_height() > 0 {
+      retrieve_view_config(old_config)?
     } else {
-      HashSet::new()
+      (HashSet::new(), QuorumPolicy::Majority)
     };
 
     if new_pks.len() < MIN_NUM_ENDORSERS {
@@ -636,23 +736,14 @@ impl Receipts {
       return Err(VerificationError::InvalidGroupIdentity);
     }
 
-    // compute max cut
-    let max_cut_hash = if ledger_tail_maps.len() == 1 {
-      produce_hash_of_state(&ledger_tail_maps[0].entries)
-    } else {
-      let max_cut = compute_max_cut(ledger_tail_maps);
-      produce_hash_of_state(&max_cut)
-    };
+    // compute max cut, which leaves out deleted ledgers
+    let max_cut_hash = produce_hash_of_state(&compute_max_cut(ledger_tail_maps));
 
     // check ledger tail maps
     let mut state_hashes = HashSet::new();
-    if ledger_tail_maps.len() == 1 {
-      state_hashes.insert(max_cut_hash);
-    } else {
-      for ledger_tail_map in ledger_tail_maps {
-        let hash = produce_hash_of_state(&ledger_tail_map.entries);
-        state_hashes.insert(hash);
-      }
+    for ledger_tail_map in ledger_tail_maps {
+      let hash = produce_hash_of_state(&ledger_tail_map.entries);
+      state_hashes.insert(hash);
     }
 
     let mut ledger_entries: HashMap<(Vec<u8>, u64), Vec<u8>> = HashMap::new();
@@ -714,8 +805,8 @@ impl Receipts {
       }
     }
 
-    let mut num_receipts_for_old_pks = 0;
-    let mut num_receipts_for_new_pks = 0;
+    let mut signers_from_old_pks = HashSet::new();
+    let mut signers_from_new_pks = HashSet::new();
     let mut used_ledger_tail_maps = HashSet::<NimbleDigest>::new();
 
     let new_metablock_hash = new_metablock.hash();
@@ -741,7 +832,7 @@ impl Receipts {
             eprintln!("the hashed state is invalid");
             return Err(VerificationError::InvalidView);
           }
-          num_receipts_for_new_pks += 1;
+          signers_from_new_pks.insert(id_sig.get_id().clone());
         }
 
         if old_pks.contains(id_sig.get_id()) {
@@ -751,7 +842,7 @@ impl Receipts {
             eprintln!("ledger tail map is missing");
             return Err(VerificationError::MissingLedgerTailMap);
           }
-          num_receipts_for_old_pks += 1;
+          signers_from_old_pks.insert(id_sig.get_id().clone());
         }
       }
     }
@@ -761,12 +852,12 @@ impl Receipts {
       return Err(VerificationError::RedundantLedgerTailMap);
     }
 
-    if old_metablock.get_height() > 0 && num_receipts_for_old_pks < old_pks.len() / 2 + 1 {
+    if old_metablock.get_height() > 0 && !old_policy.is_satisfied(&old_pks, &signers_from_old_pks) {
       eprintln!("insufficent receipts from old config");
       return Err(VerificationError::InsufficientReceipts);
     }
 
-    if num_receipts_for_new_pks < new_pks.len() / 2 + 1 {
+    if !new_policy.is_satisfied(&new_pks, &signers_from_new_pks) {
       eprintln!("insufficent receipts from new config");
       return Err(VerificationError::InsufficientReceipts);
     }
@@ -779,14 +870,14 @@ impl Receipts {
     verifier_state: &VerifierState,
     config: &[u8],
     attestations: Option<&[u8]>,
-  ) -> Result<(MetaBlock, HashSet<Vec<u8>>), VerificationError> {
+  ) -> Result<(MetaBlock, HashSet<Vec<u8>>, QuorumPolicy), VerificationError> {
     if self.is_empty() {
       return Err(VerificationError::InsufficientReceipts);
     }
 
     let config_hash = NimbleDigest::digest(config);
 
-    let pks = retrieve_public_keys_from_config(config)?;
+    let (pks, policy) = retrieve_view_config(config)?;
 
     for (ex_meta_block, id_sigs) in &self.receipts {
       if config_hash != *ex_meta_block.get_metablock().get_block_hash() {
@@ -799,7 +890,7 @@ impl Receipts {
           .digest_with(&ex_meta_block.get_metablock().hash()),
       );
 
-      let mut num_receipts = 0;
+      let mut signers = HashSet::new();
       for id_sig in id_sigs {
         let id = id_sig.get_id();
 
@@ -811,10 +902,10 @@ impl Receipts {
           continue;
         }
 
-        num_receipts += 1;
+        signers.insert(id.clone());
       }
 
-      if num_receipts * 2 > pks.len() {
+      if policy.is_satisfied(&pks, &signers) {
         let is_verified = if let Some(attestation_reports) = attestations {
           attestation_reports == "THIS IS A PLACE HOLDER FOR ATTESTATION".as_bytes().to_vec()
         } else {
@@ -822,7 +913,7 @@ impl Receipts {
         };
 
         if is_verified {
-          return Ok((ex_meta_block.get_metablock().clone(), pks));
+          return Ok((ex_meta_block.get_metablock().clone(), pks, policy));
         }
       }
     }
@@ -831,6 +922,18 @@ impl Receipts {
   }
 }
 
+/// Which views a `VerifierState` keeps accepting receipts from
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ViewRetention {
+  /// keep every view
+  #[default]
+  All,
+  /// keep only the latest N views
+  LatestViews(usize),
+  /// keep only the views at or above the given height of the view ledger
+  MinHeight(usize),
+}
+
 /// VerifierState keeps track of public keys of any valid view
 #[derive(Debug, Default)]
 pub struct VerifierState {
@@ -838,18 +941,34 @@ pub struct VerifierState {
   // In our context, we don't need views to be ordered, so we use a HashMap
   // However, we require that a new view is "authorized" by the latest view, so we keep track of the latest_view in a separate variable
   vk_map: HashMap<NimbleDigest, HashSet<Vec<u8>>>,
+  // The quorum policy recorded in the view ledger block of each view
+  policy_map: HashMap<NimbleDigest, QuorumPolicy>,
   group_identity: NimbleDigest,
   view_ledger_height: usize,
   verified_views: HashSet<NimbleDigest>,
+  // The height of each view in the view ledger
+  view_heights: HashMap<NimbleDigest, usize>,
+  // The views below this height in the view ledger were dropped by the retention policy
+  pruned_below: usize,
+  retention: ViewRetention,
+  // The BLS public key registered by each endorser for aggregated receipts
+  #[cfg(feature = "bls")]
+  aggregate_keys: HashMap<Vec<u8>, Vec<u8>>,
 }
 
 impl VerifierState {
   pub fn new() -> Self {
     VerifierState {
       vk_map: HashMap::new(),
+      policy_map: HashMap::new(),
       group_identity: NimbleDigest::default(),
       view_ledger_height: 0,
       verified_views: HashSet::new(),
+      view_heights: HashMap::new(),
+      pruned_below: 0,
+      retention: ViewRetention::All,
+      #[cfg(feature = "bls")]
+      aggregate_keys: HashMap::new(),
     }
   }
 
@@ -864,10 +983,72 @@ impl VerifierState {
     let res = self.vk_map.get(view);
     match res {
       Some(pks) => Ok(pks),
-      None => Err(VerificationError::ViewNotFound),
+      None => Err(self.unknown_view_error()),
+    }
+  }
+
+  pub fn get_policy_for_view(
+    &self,
+    view: &NimbleDigest,
+  ) -> Result<&QuorumPolicy, VerificationError> {
+    let res = self.policy_map.get(view);
+    match res {
+      Some(policy) => Ok(policy),
+      None => Err(self.unknown_view_error()),
     }
   }
 
+  /// Returns the public keys of the endorsers and the quorum policy of the latest view.
+  pub fn get_latest_view(&self) -> Option<(&HashSet<Vec<u8>>, &QuorumPolicy)> {
+    let (view, _height) = self
+      .view_heights
+      .iter()
+      .max_by_key(|(_view, height)| **height)?;
+    Some((self.vk_map.get(view)?, self.policy_map.get(view)?))
+  }
+
+  /// The error for a view that is not known. Only the digest of the view is at hand, which does
+  /// not tell its height, so once views were dropped an unknown view may be one of them.
+  fn unknown_view_error(&self) -> VerificationError {
+    if self.pruned_below > 0 {
+      VerificationError::PrunedView
+    } else {
+      VerificationError::ViewNotFound
+    }
+  }
+
+  pub fn get_view_retention(&self) -> ViewRetention {
+    self.retention
+  }
+
+  /// Sets which views receipts are accepted from and drops the views that fall outside of it.
+  pub fn set_view_retention(&mut self, retention: ViewRetention) {
+    self.retention = retention;
+    self.prune_views();
+  }
+
+  fn prune_views(&mut self) {
+    let min_height = match self.retention {
+      ViewRetention::All => return,
+      ViewRetention::LatestViews(n) => (self.view_ledger_height + 1).saturating_sub(n.max(1)),
+      ViewRetention::MinHeight(h) => h,
+    };
+
+    let pruned = self
+      .view_heights
+      .iter()
+      .filter(|(_view, height)| **height < min_height)
+      .map(|(view, _height)| *view)
+      .collect::<Vec<NimbleDigest>>();
+    for view in pruned {
+      self.vk_map.remove(&view);
+      self.policy_map.remove(&view);
+      self.view_heights.remove(&view);
+      self.verified_views.remove(&view);
+    }
+    self.pruned_below = self.pruned_below.max(min_height);
+  }
+
   pub fn get_group_identity(&self) -> &NimbleDigest {
     &self.group_identity
   }
@@ -891,12 +1072,20 @@ impl VerifierState {
 
     let res = receipts.verify_view_change_receipts(self, config, attestations);
     match res {
-      Ok((meta_block, pks)) => {
-        self.verified_views.insert(*meta_block.get_prev());
+      Ok((meta_block, pks, policy)) => {
+        // the previous view is one below the new one in the view ledger
+        if meta_block.get_height() > self.pruned_below {
+          self.verified_views.insert(*meta_block.get_prev());
+        }
         self.vk_map.insert(meta_block.hash(), pks);
+        self.policy_map.insert(meta_block.hash(), policy);
+        self
+          .view_heights
+          .insert(meta_block.hash(), meta_block.get_height());
         if self.view_ledger_height < meta_block.get_height() {
           self.view_ledger_height = meta_block.get_height();
         }
+        self.prune_views();
         Ok(())
       },
       Err(e) => Err(e),
@@ -949,6 +1138,25 @@ impl VerifierState {
     }
   }
 
+  /// Verifies that the receipts endorse a tombstone at `expected_height`, i.e., that the ledger
+  /// was deleted rather than rolled back.
+  pub fn verify_delete(
+    &self,
+    handle_bytes: &[u8],
+    hash_nonces_bytes: &[u8],
+    expected_height: usize,
+    receipts_bytes: &[u8],
+  ) -> Result<(), VerificationError> {
+    let block = tombstone_block(&NimbleDigest::digest(handle_bytes));
+    self.verify_append(
+      handle_bytes,
+      &block.to_bytes(),
+      hash_nonces_bytes,
+      expected_height,
+      receipts_bytes,
+    )
+  }
+
   pub fn verify_read_latest(
     &self,
     handle_bytes: &[u8],
@@ -986,9 +1194,44 @@ impl VerifierState {
       Err(e) => Err(e),
     }
   }
+
+  /// Verifies that the receipts of a checkpoint endorse a metablock at `height`, i.e., that a
+  /// pruned ledger starts at an entry its endorsers agreed on. Returns that metablock, whose
+  /// `prev` commits to the entries that were pruned.
+  pub fn verify_checkpoint(
+    &self,
+    handle_bytes: &[u8],
+    height: usize,
+    receipts_bytes: &[u8],
+  ) -> Result<MetaBlock, VerificationError> {
+    let receipts =
+      Receipts::from_bytes(receipts_bytes).map_err(|_e| VerificationError::InvalidReceipt)?;
+    receipts
+      .get_endorsed_metablocks(self, &NimbleDigest::digest(handle_bytes))
+      .into_iter()
+      .find(|metablock| metablock.get_height() == height)
+      .ok_or(VerificationError::InvalidHeight)
+  }
 }
 
+/// Computes the highest tail of every ledger across the maps, leaving out deleted ledgers
 pub fn compute_max_cut(ledger_tail_maps: &Vec<LedgerTailMap>) -> Vec<LedgerTailMapEntry> {
+  compute_highest_tails(ledger_tail_maps)
+    .into_iter()
+    .filter(|entry| !is_tombstone_entry(entry))
+    .collect()
+}
+
+/// Returns the handles of the ledgers whose highest tail across the maps is a tombstone
+pub fn compute_deleted_ledgers(ledger_tail_maps: &[LedgerTailMap]) -> Vec<Handle> {
+  compute_highest_tails(ledger_tail_maps)
+    .iter()
+    .filter(|entry| is_tombstone_entry(entry))
+    .filter_map(|entry| NimbleDigest::from_bytes(&entry.handle).ok())
+    .collect()
+}
+
+fn compute_highest_tails(ledger_tail_maps: &[LedgerTailMap]) -> Vec<LedgerTailMapEntry> {
   if ledger_tail_maps.is_empty() {
     Vec::new()
   } else {
@@ -1102,6 +1345,8 @@ pub enum CustomSerdeError {
   IncorrectLength,
   /// returned if deserializing any byte entry into the Rust type fails
   InternalError,
+  /// returned if the serialized form has a version that is not supported
+  UnsupportedVersion,
 }
 
 pub trait CustomSerde
@@ -1389,6 +1634,43 @@ mod tests {
     assert_eq!(block_1_hash.to_bytes(), expected_hash_message_1_op.unwrap());
   }
 
+  #[test]
+  pub fn test_view_retention() {
+    let mut vs = VerifierState::new();
+    let views = (1..=3)
+      .map(|h| NimbleDigest::digest(&[h as u8]))
+      .collect::<Vec<_>>();
+    for (i, view) in views.iter().enumerate() {
+      vs.vk_map.insert(*view, HashSet::new());
+      vs.policy_map.insert(*view, QuorumPolicy::Majority);
+      vs.view_heights.insert(*view, i + 1);
+    }
+    vs.view_ledger_height = 3;
+    assert_eq!(
+      vs.get_pks_for_view(&NimbleDigest::digest(b"unknown")),
+      Err(VerificationError::ViewNotFound)
+    );
+
+    vs.set_view_retention(ViewRetention::LatestViews(2));
+    assert_eq!(
+      vs.get_pks_for_view(&views[0]),
+      Err(VerificationError::PrunedView)
+    );
+    assert!(vs.get_pks_for_view(&views[1]).is_ok());
+    // an unknown view cannot be told apart from a dropped one by its digest
+    assert_eq!(
+      vs.get_pks_for_view(&NimbleDigest::digest(b"unknown")),
+      Err(VerificationError::PrunedView)
+    );
+
+    vs.set_view_retention(ViewRetention::MinHeight(3));
+    assert_eq!(
+      vs.get_policy_for_view(&views[1]),
+      Err(VerificationError::PrunedView)
+    );
+    assert!(vs.get_policy_for_view(&views[2]).is_ok());
+  }
+
   #[test]
   pub fn test_hash_of_state() {
     let map = (0..1024 * 1023)
@@ -1407,4 +1689,49 @@ mod tests {
     let hash = produce_hash_of_state(&map);
     assert_ne!(hash, NimbleDigest::default());
   }
+
+  #[test]
+  pub fn test_max_cut_drops_tombstones() {
+    let tail_entry = |handle: &Handle, block: &Block, height: usize| {
+      let nonces = Nonces::new();
+      let block_hash =
+        compute_aggregated_block_hash(&block.hash().to_bytes(), &nonces.hash().to_bytes());
+      LedgerTailMapEntry {
+        handle: handle.to_bytes(),
+        metablock: MetaBlock::new(&NimbleDigest::default(), &block_hash, height).to_bytes(),
+        height: height as u64,
+        block: block.to_bytes(),
+        nonces: nonces.to_bytes(),
+      }
+    };
+
+    let mut handles = (0..3)
+      .map(|i: u8| NimbleDigest::digest(&[i]))
+      .collect::<Vec<Handle>>();
+    handles.sort();
+    let deleted = handles[1];
+    let old_map = LedgerTailMap {
+      entries: handles
+        .iter()
+        .map(|h| tail_entry(h, &Block::new(b"data"), 1))
+        .collect(),
+    };
+    let mut new_map = old_map.clone();
+    new_map.entries[1] = tail_entry(&deleted, &tombstone_block(&deleted), 2);
+
+    // a tombstone is only honoured for its own handle and when it matches the metablock
+    assert!(is_tombstone_entry(&new_map.entries[1]));
+    assert!(!is_tombstone(&handles[0], &tombstone_block(&deleted)));
+    let mut forged = new_map.entries[1].clone();
+    forged.metablock = old_map.entries[1].metablock.clone();
+    assert!(!is_tombstone_entry(&forged));
+
+    let maps = vec![old_map, new_map];
+    let max_cut = compute_max_cut(&maps);
+    assert_eq!(max_cut.len(), 2);
+    assert!(max_cut
+      .iter()
+      .all(|entry| entry.handle != deleted.to_bytes()));
+    assert_eq!(compute_deleted_ledgers(&maps), vec![deleted]);
+  }
 }
@@ -0,0 +1,366 @@
+use crate::{
+  errors::VerificationError,
+  signature::{PublicKey, PublicKeyTrait},
+  EndorserHostnames,
+};
+use bincode::Options;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Prefix of the tagged view config formats. A legacy endorser list starts with its length, which
+/// can never be u64::MAX, so the prefix tells the formats apart.
+const VIEW_CONFIG_TAG: [u8; 8] = [0xff; 8];
+/// Version byte of a tagged config holding the endorsers and the policy
+const VIEW_CONFIG_POLICY: u8 = 1;
+/// Version byte of a tagged config holding the whole `ViewConfig`
+const VIEW_CONFIG_FULL: u8 = 2;
+
+/// A rule that decides which sets of endorsers in a view form a quorum
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub enum QuorumPolicy {
+  /// a strict majority of the endorsers in the view
+  #[default]
+  Majority,
+  /// at least k of the endorsers in the view
+  Threshold(usize),
+  /// endorsers whose combined weight reaches the threshold; endorsers without a weight count as 1
+  Weighted {
+    weights: Vec<(Vec<u8>, u64)>,
+    threshold: u64,
+  },
+  /// a strict majority that includes at least one endorser from each failure domain
+  FailureDomains(Vec<(Vec<u8>, String)>),
+}
+
+impl QuorumPolicy {
+  fn weight_of(weights: &[(Vec<u8>, u64)], pk: &[u8]) -> u64 {
+    weights
+      .iter()
+      .find(|(id, _w)| id.as_slice() == pk)
+      .map(|(_id, w)| *w)
+      .unwrap_or(1)
+  }
+
+  /// Checks that any two quorums of the policy over the given endorsers intersect.
+  pub fn validate(&self, pks: &HashSet<Vec<u8>>) -> Result<(), VerificationError> {
+    let n = pks.len();
+    match self {
+      QuorumPolicy::Majority => Ok(()),
+      QuorumPolicy::Threshold(k) => {
+        if *k > n || k * 2 <= n {
+          eprintln!("threshold {} is not a quorum of {} endorsers", k, n);
+          return Err(VerificationError::InvalidQuorumPolicy);
+        }
+        Ok(())
+      },
+      QuorumPolicy::Weighted { weights, threshold } => {
+        if weights.iter().any(|(id, _w)| !pks.contains(id)) {
+          eprintln!("weight assigned to an endorser outside of the view");
+          return Err(VerificationError::InvalidQuorumPolicy);
+        }
+        let ids = weights.iter().map(|(id, _w)| id).collect::<HashSet<_>>();
+        if ids.len() != weights.len() {
+          eprintln!("more than one weight assigned to an endorser");
+          return Err(VerificationError::InvalidQuorumPolicy);
+        }
+        if weights.iter().any(|(_id, w)| *w == 0) {
+          eprintln!("weight of an endorser is zero");
+          return Err(VerificationError::InvalidQuorumPolicy);
+        }
+        let total: u64 = pks.iter().map(|pk| Self::weight_of(weights, pk)).sum();
+        if *threshold > total || threshold.saturating_mul(2) <= total {
+          eprintln!(
+            "weight threshold {} is not a quorum of {}",
+            threshold, total
+          );
+          return Err(VerificationError::InvalidQuorumPolicy);
+        }
+        Ok(())
+      },
+      QuorumPolicy::FailureDomains(tags) => {
+        if tags.iter().any(|(id, _tag)| !pks.contains(id)) {
+          eprintln!("failure domain assigned to an endorser outside of the view");
+          return Err(VerificationError::InvalidQuorumPolicy);
+        }
+        Ok(())
+      },
+    }
+  }
+
+  /// Returns the smallest number of signers that could satisfy the policy.
+  pub fn min_signers(&self, n: usize) -> usize {
+    match self {
+      QuorumPolicy::Majority => n / 2 + 1,
+      QuorumPolicy::Threshold(k) => *k,
+      QuorumPolicy::Weighted { weights, threshold } => {
+        let mut sorted = weights.iter().map(|(_id, w)| *w).collect::<Vec<u64>>();
+        sorted.resize(n.max(sorted.len()), 1);
+        sorted.sort_unstable_by(|a, b| b.cmp(a));
+        let mut sum = 0u64;
+        for (i, w) in sorted.iter().enumerate() {
+          sum = sum.saturating_add(*w);
+          if sum >= *threshold {
+            return i + 1;
+          }
+        }
+        n + 1
+      },
+      QuorumPolicy::FailureDomains(tags) => {
+        let domains = tags.iter().map(|(_id, tag)| tag).collect::<HashSet<_>>();
+        (n / 2 + 1).max(domains.len())
+      },
+    }
+  }
+
+  /// Checks whether the signers form a quorum of the endorsers in the view.
+  ///
+  /// # Arguments
+  ///
+  /// * `pks` - The public keys of the endorsers in the view.
+  /// * `signers` - The public keys of the endorsers that produced valid signatures.
+  pub fn is_satisfied(&self, pks: &HashSet<Vec<u8>>, signers: &HashSet<Vec<u8>>) -> bool {
+    let valid = signers
+      .iter()
+      .filter(|id| pks.contains(*id))
+      .collect::<Vec<&Vec<u8>>>();
+    match self {
+      QuorumPolicy::Majority => valid.len() * 2 > pks.len(),
+      QuorumPolicy::Threshold(k) => valid.len() >= *k,
+      QuorumPolicy::Weighted { weights, threshold } => {
+        let weight: u64 = valid.iter().map(|id| Self::weight_of(weights, id)).sum();
+        weight >= *threshold
+      },
+      QuorumPolicy::FailureDomains(tags) => {
+        if valid.len() * 2 <= pks.len() {
+          return false;
+        }
+        let domains = tags.iter().map(|(_id, tag)| tag).collect::<HashSet<_>>();
+        let covered = tags
+          .iter()
+          .filter(|(id, _tag)| valid.contains(&id))
+          .map(|(_id, tag)| tag)
+          .collect::<HashSet<_>>();
+        covered.len() == domains.len()
+      },
+    }
+  }
+}
+
+/// The contents of a view ledger block: the endorsers of the view, the quorum policy and,
+/// optionally, the time at which the view was created. The block is hashed into the view and the
+/// genesis block into the group identity, so the coordinator leaves the time out of the block.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ViewConfig {
+  pub endorsers: EndorserHostnames,
+  pub policy: QuorumPolicy,
+  /// seconds since the Unix epoch, or 0 if the time was not recorded
+  pub created_at: u64,
+}
+
+impl ViewConfig {
+  pub fn new(endorsers: EndorserHostnames, policy: QuorumPolicy) -> Self {
+    ViewConfig {
+      endorsers,
+      policy,
+      created_at: 0,
+    }
+  }
+
+  pub fn with_created_at(mut self, created_at: u64) -> Self {
+    self.created_at = created_at;
+    self
+  }
+
+  /// Serializes the config in the oldest format that can represent it: with the majority policy
+  /// and no creation time the untagged endorser list, otherwise a tagged format with a version
+  /// byte for the endorsers and policy alone or the whole config.
+  pub fn to_bytes(&self) -> Vec<u8> {
+    let (version, body) = if self.created_at != 0 {
+      (VIEW_CONFIG_FULL, bincode::serialize(self).unwrap())
+    } else if self.policy != QuorumPolicy::Majority {
+      (
+        VIEW_CONFIG_POLICY,
+        bincode::serialize(&(&self.endorsers, &self.policy)).unwrap(),
+      )
+    } else {
+      return bincode::serialize(&self.endorsers).unwrap();
+    };
+    let mut bytes = VIEW_CONFIG_TAG.to_vec();
+    bytes.push(version);
+    bytes.extend(body);
+    bytes
+  }
+
+  pub fn from_bytes(bytes: &[u8]) -> Result<ViewConfig, VerificationError> {
+    let options = bincode::DefaultOptions::new()
+      .with_fixint_encoding()
+      .reject_trailing_bytes();
+    let res = match bytes.strip_prefix(&VIEW_CONFIG_TAG[..]) {
+      Some([VIEW_CONFIG_POLICY, body @ ..]) => options
+        .deserialize::<(EndorserHostnames, QuorumPolicy)>(body)
+        .map(|(endorsers, policy)| ViewConfig::new(endorsers, policy)),
+      Some([VIEW_CONFIG_FULL, body @ ..]) => options.deserialize::<ViewConfig>(body),
+      Some(_) => {
+        eprintln!("Unknown version of the view genesis block");
+        return Err(VerificationError::InvalidGenesisBlock);
+      },
+      None => options
+        .deserialize::<EndorserHostnames>(bytes)
+        .map(|endorsers| ViewConfig::new(endorsers, QuorumPolicy::Majority)),
+    };
+    res.map_err(|e| {
+      eprintln!("Failed to deserialize the view genesis block {:?}", e);
+      VerificationError::InvalidGenesisBlock
+    })
+  }
+
+  /// Returns the validated public keys of the endorsers along with the quorum policy.
+  pub fn get_pks_and_policy(&self) -> Result<(HashSet<Vec<u8>>, QuorumPolicy), VerificationError> {
+    let mut pks = HashSet::new();
+    for (pk_bytes, _uri) in &self.endorsers {
+      let pk = PublicKey::from_bytes(pk_bytes).map_err(|_e| VerificationError::InvalidPublicKey)?;
+      pks.insert(pk.to_bytes());
+    }
+    self.policy.validate(&pks)?;
+    Ok((pks, self.policy.clone()))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn pks(n: u8) -> HashSet<Vec<u8>> {
+    (0..n).map(|i| vec![i]).collect()
+  }
+
+  #[test]
+  pub fn test_threshold_and_majority() {
+    let all = pks(5);
+    let three = pks(3);
+    let two = pks(2);
+    assert!(QuorumPolicy::Majority.is_satisfied(&all, &three));
+    assert!(!QuorumPolicy::Majority.is_satisfied(&all, &two));
+
+    assert!(QuorumPolicy::Threshold(4).validate(&all).is_ok());
+    assert!(QuorumPolicy::Threshold(2).validate(&all).is_err());
+    assert!(!QuorumPolicy::Threshold(4).is_satisfied(&all, &three));
+    assert!(QuorumPolicy::Threshold(4).is_satisfied(&all, &pks(4)));
+    assert_eq!(QuorumPolicy::Threshold(4).min_signers(5), 4);
+  }
+
+  #[test]
+  pub fn test_weighted() {
+    let all = pks(3);
+    let policy = QuorumPolicy::Weighted {
+      weights: vec![(vec![0], 3)],
+      threshold: 3,
+    };
+    assert!(policy.validate(&all).is_ok());
+    assert_eq!(policy.min_signers(3), 1);
+    assert!(policy.is_satisfied(&all, &[vec![0]].iter().cloned().collect()));
+    assert!(!policy.is_satisfied(&all, &[vec![1], vec![2]].iter().cloned().collect()));
+
+    let duplicate = QuorumPolicy::Weighted {
+      weights: vec![(vec![0], 3), (vec![0], 1)],
+      threshold: 3,
+    };
+    assert!(duplicate.validate(&all).is_err());
+    let zero = QuorumPolicy::Weighted {
+      weights: vec![(vec![0], 0)],
+      threshold: 2,
+    };
+    assert!(zero.validate(&all).is_err());
+  }
+
+  #[test]
+  pub fn test_failure_domains() {
+    let all = pks(4);
+    let policy = QuorumPolicy::FailureDomains(vec![
+      (vec![0], "east".to_string()),
+      (vec![1], "east".to_string()),
+      (vec![2], "west".to_string()),
+      (vec![3], "west".to_string()),
+    ]);
+    assert!(policy.validate(&all).is_ok());
+    let both_domains = [vec![0], vec![1], vec![3]].iter().cloned().collect();
+    assert!(policy.is_satisfied(&all, &both_domains));
+    let only_east = [vec![0], vec![1]].iter().cloned().collect();
+    assert!(!policy.is_satisfied(&all, &only_east));
+  }
+
+  #[test]
+  pub fn test_view_config_serde() {
+    let endorsers = vec![(vec![1, 2, 3], "http://localhost:9090".to_string())];
+    let legacy = bincode::serialize(&endorsers).unwrap();
+    let config = ViewConfig::from_bytes(&legacy).unwrap();
+    assert_eq!(config.policy, QuorumPolicy::Majority);
+    assert_eq!(config.endorsers, endorsers);
+    assert_eq!(config.to_bytes(), legacy);
+
+    let config = ViewConfig::new(endorsers.clone(), QuorumPolicy::Threshold(1));
+    let parsed = ViewConfig::from_bytes(&config.to_bytes()).unwrap();
+    assert_eq!(parsed.policy, QuorumPolicy::Threshold(1));
+    assert_eq!(parsed.endorsers, endorsers);
+    assert_eq!(parsed.created_at, 0);
+
+    let config = ViewConfig::new(endorsers.clone(), QuorumPolicy::Majority).with_created_at(42);
+    let parsed = ViewConfig::from_bytes(&config.to_bytes()).unwrap();
+    assert_eq!(parsed.policy, QuorumPolicy::Majority);
+    assert_eq!(parsed.endorsers, endorsers);
+    assert_eq!(parsed.created_at, 42);
+  }
+
+  #[test]
+  pub fn test_view_config_from_bytes_formats() {
+    let endorsers = vec![
+      (vec![1, 2, 3], "http://localhost:9090".to_string()),
+      (vec![4, 5, 6], "http://localhost:9091".to_string()),
+    ];
+    let policy = QuorumPolicy::FailureDomains(vec![(vec![1, 2, 3], "east".to_string())]);
+
+    // just the endorser list, as written before quorum policies
+    let parsed = ViewConfig::from_bytes(&bincode::serialize(&endorsers).unwrap()).unwrap();
+    assert_eq!(parsed.endorsers, endorsers);
+    assert_eq!(parsed.policy, QuorumPolicy::Majority);
+    assert_eq!(parsed.created_at, 0);
+
+    // the endorsers and the policy
+    let mut bytes = [0xff; 8].to_vec();
+    bytes.push(1);
+    bytes.extend(bincode::serialize(&(&endorsers, &policy)).unwrap());
+    let parsed = ViewConfig::from_bytes(&bytes).unwrap();
+    assert_eq!(parsed.endorsers, endorsers);
+    assert_eq!(parsed.policy, policy);
+    assert_eq!(parsed.created_at, 0);
+
+    // the whole config, with the time of the view change
+    let config = ViewConfig {
+      endorsers: endorsers.clone(),
+      policy: policy.clone(),
+      created_at: 1700000000,
+    };
+    let mut bytes = [0xff; 8].to_vec();
+    bytes.push(2);
+    bytes.extend(bincode::serialize(&config).unwrap());
+    let parsed = ViewConfig::from_bytes(&bytes).unwrap();
+    assert_eq!(parsed.endorsers, endorsers);
+    assert_eq!(parsed.policy, policy);
+    assert_eq!(parsed.created_at, 1700000000);
+
+    // trailing bytes and unknown versions are rejected
+    bytes.push(0);
+    assert!(ViewConfig::from_bytes(&bytes).is_err());
+    let mut legacy = bincode::serialize(&endorsers).unwrap();
+    legacy.push(0);
+    assert!(ViewConfig::from_bytes(&legacy).is_err());
+    let mut unknown = [0xff; 8].to_vec();
+    unknown.push(3);
+    assert!(ViewConfig::from_bytes(&unknown).is_err());
+
+    assert_eq!(
+      ViewConfig::from_bytes(b"not a view").err(),
+      Some(VerificationError::InvalidGenesisBlock)
+    );
+  }
+}
@@ -5,8 +5,13 @@ use openssl::{
   ec::*,
   ecdsa::EcdsaSig,
   nid::Nid,
-  pkey::{Private, Public},
+  pkey::{Id, PKey, Private, Public},
+  sign::{Signer, Verifier},
 };
+use std::str::FromStr;
+
+#[cfg(feature = "bls")]
+pub mod bls;
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum CryptoError {
@@ -20,6 +25,10 @@ pub enum CryptoError {
   InvalidPrivateKeyPem,
   /// returned if there is an error when deriving a signature from DER
   FailedToGetSigFromDER,
+  /// returned if the name of a signature scheme is not recognized
+  UnknownScheme,
+  /// returned if signatures of the scheme have no DER encoding
+  NoDerEncoding,
 }
 
 pub trait PublicKeyTrait {
@@ -31,39 +40,90 @@ pub trait PublicKeyTrait {
 }
 
 pub trait PrivateKeyTrait {
+  type PublicKey: PublicKeyTrait;
+  type Signature: SignatureTrait<PublicKey = Self::PublicKey>;
   fn new() -> Self
   where
     Self: Sized;
-  fn get_public_key(&self) -> Result<PublicKey, CryptoError>
-  where
-    PublicKey: PublicKeyTrait;
-  fn sign(&self, msg: &[u8]) -> Result<Signature, CryptoError>
-  where
-    Signature: SignatureTrait;
+  fn get_public_key(&self) -> Result<Self::PublicKey, CryptoError>;
+  fn sign(&self, msg: &[u8]) -> Result<Self::Signature, CryptoError>;
 }
 
 pub trait SignatureTrait {
+  type PublicKey: PublicKeyTrait;
   fn num_bytes() -> usize;
   fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError>
   where
     Self: Sized;
-  fn verify(&self, pk: &PublicKey, msg: &[u8]) -> Result<(), CryptoError>
-  where
-    PublicKey: PublicKeyTrait;
+  fn verify(&self, pk: &Self::PublicKey, msg: &[u8]) -> Result<(), CryptoError>;
   fn to_bytes(&self) -> Vec<u8>;
 }
 
-/// Types and concrete implementations of types for ECDSA algorithm with P-256 using OpenSSL
+/// Signature schemes whose signatures on the same message can be combined into one
+pub trait AggregateSignatureTrait: SignatureTrait + Sized {
+  /// Combines signatures on the same message into a single signature of the same size.
+  fn aggregate(sigs: &[Self]) -> Result<Self, CryptoError>;
+  /// Verifies an aggregate signature on `msg` by all of `pks`.
+  fn verify_aggregate(&self, pks: &[Self::PublicKey], msg: &[u8]) -> Result<(), CryptoError>;
+}
+
+/// The signature schemes that endorsers and endpoints can sign with
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum SignatureScheme {
+  /// ECDSA with P-256
+  #[default]
+  P256,
+  /// Ed25519
+  Ed25519,
+}
+
+impl FromStr for SignatureScheme {
+  type Err = CryptoError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.to_lowercase().as_str() {
+      "p256" | "p-256" | "ecdsa" => Ok(SignatureScheme::P256),
+      "ed25519" => Ok(SignatureScheme::Ed25519),
+      _ => Err(CryptoError::UnknownScheme),
+    }
+  }
+}
+
+// Serialized public keys are 33 bytes and start with a scheme tag. For P-256 the tag is the
+// prefix of the compressed SEC1 encoding (0x02 or 0x03), so existing keys keep their encoding.
+// Ed25519 keys are the tag followed by the 32-byte raw key. Uncompressed P-256 keys (65 bytes,
+// starting with 0x04) are accepted too and serialized compressed.
+const ED25519_TAG: u8 = 0xED;
+
+// The order of the Ed25519 group, big-endian. The S half of a signature must be below it.
+const ED25519_ORDER: [u8; 32] = [
+  0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+  0x14, 0xde, 0xf9, 0xde, 0xa2, 0xf7, 0x9c, 0xd6, 0x58, 0x12, 0x63, 0x1a, 0x5c, 0xf5, 0xd3, 0xed,
+];
+
+/// Types and concrete implementations of types for ECDSA with P-256 and Ed25519 using OpenSSL
 pub struct PublicKey {
-  key: EcKey<Public>,
+  key: PublicKeyInner,
+}
+
+enum PublicKeyInner {
+  P256(EcKey<Public>),
+  Ed25519(PKey<Public>),
 }
 
 pub struct PrivateKey {
-  key: EcKey<Private>,
+  key: PrivateKeyInner,
+}
+
+enum PrivateKeyInner {
+  P256(EcKey<Private>),
+  Ed25519(PKey<Private>),
 }
 
+// Signatures of both schemes are 64 bytes (r || s for ECDSA) and are interpreted according to
+// the scheme of the public key they are verified against.
 pub struct Signature {
-  sig: EcdsaSig,
+  sig: Vec<u8>,
 }
 
 impl PublicKeyTrait for PublicKey {
@@ -72,6 +132,21 @@ impl PublicKeyTrait for PublicKey {
   }
 
   fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
+    let uncompressed = bytes.len() == 2 * Self::num_bytes() - 1 && bytes[0] == 0x04;
+    if bytes.len() != Self::num_bytes() && !uncompressed {
+      return Err(CryptoError::InvalidPublicKeyBytes);
+    }
+
+    if bytes[0] == ED25519_TAG {
+      let res = PKey::public_key_from_raw_bytes(&bytes[1..], Id::ED25519);
+      return match res {
+        Ok(key) => Ok(PublicKey {
+          key: PublicKeyInner::Ed25519(key),
+        }),
+        Err(_) => Err(CryptoError::InvalidPublicKeyBytes),
+      };
+    }
+
     let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
     let point = {
       let mut ctx = BigNumContext::new().unwrap();
@@ -84,83 +159,171 @@ impl PublicKeyTrait for PublicKey {
 
     let res = EcKey::from_public_key(&group, &point);
     if let Ok(key) = res {
-      Ok(PublicKey { key })
+      Ok(PublicKey {
+        key: PublicKeyInner::P256(key),
+      })
     } else {
       Err(CryptoError::InvalidPublicKeyBytes)
     }
   }
 
   fn to_bytes(&self) -> Vec<u8> {
-    let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
-    let mut ctx = BigNumContext::new().unwrap();
-    self
-      .key
-      .public_key()
-      .to_bytes(&group, PointConversionForm::COMPRESSED, &mut ctx)
-      .unwrap()
+    match &self.key {
+      PublicKeyInner::P256(key) => {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let mut ctx = BigNumContext::new().unwrap();
+        key
+          .public_key()
+          .to_bytes(&group, PointConversionForm::COMPRESSED, &mut ctx)
+          .unwrap()
+      },
+      PublicKeyInner::Ed25519(key) => {
+        concat(vec![vec![ED25519_TAG], key.raw_public_key().unwrap()])
+      },
+    }
   }
 }
 
 impl PublicKey {
+  pub fn get_scheme(&self) -> SignatureScheme {
+    match &self.key {
+      PublicKeyInner::P256(_) => SignatureScheme::P256,
+      PublicKeyInner::Ed25519(_) => SignatureScheme::Ed25519,
+    }
+  }
+
   pub fn to_der(&self) -> Vec<u8> {
-    self.key.public_key_to_der().unwrap()
+    match &self.key {
+      PublicKeyInner::P256(key) => key.public_key_to_der().unwrap(),
+      PublicKeyInner::Ed25519(key) => key.public_key_to_der().unwrap(),
+    }
   }
 
+  /// Returns the uncompressed point for P-256 and the raw 32-byte key for Ed25519.
   pub fn to_uncompressed(&self) -> Vec<u8> {
-    let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
-    let mut ctx = BigNumContext::new().unwrap();
-    self
-      .key
-      .public_key()
-      .to_bytes(&group, PointConversionForm::UNCOMPRESSED, &mut ctx)
-      .unwrap()
+    match &self.key {
+      PublicKeyInner::P256(key) => {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let mut ctx = BigNumContext::new().unwrap();
+        key
+          .public_key()
+          .to_bytes(&group, PointConversionForm::UNCOMPRESSED, &mut ctx)
+          .unwrap()
+      },
+      PublicKeyInner::Ed25519(key) => key.raw_public_key().unwrap(),
+    }
   }
 }
 
 impl PrivateKeyTrait for PrivateKey {
+  type PublicKey = PublicKey;
+  type Signature = Signature;
+
   fn new() -> Self {
-    let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
-    let key = EcKey::generate(&group).unwrap();
-    PrivateKey { key }
+    PrivateKey::new_with_scheme(SignatureScheme::default())
   }
 
   fn get_public_key(&self) -> Result<PublicKey, CryptoError> {
-    let key = {
-      let point = self.key.public_key();
-      let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
-      let res = EcKey::from_public_key(&group, point);
-      if res.is_err() {
-        return Err(CryptoError::InvalidPublicKeyBytes);
-      }
-      res.unwrap()
+    let key = match &self.key {
+      PrivateKeyInner::P256(key) => {
+        let point = key.public_key();
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let res = EcKey::from_public_key(&group, point);
+        if res.is_err() {
+          return Err(CryptoError::InvalidPublicKeyBytes);
+        }
+        PublicKeyInner::P256(res.unwrap())
+      },
+      PrivateKeyInner::Ed25519(key) => {
+        let res = key
+          .raw_public_key()
+          .and_then(|raw| PKey::public_key_from_raw_bytes(&raw, Id::ED25519));
+        if res.is_err() {
+          return Err(CryptoError::InvalidPublicKeyBytes);
+        }
+        PublicKeyInner::Ed25519(res.unwrap())
+      },
     };
     Ok(PublicKey { key })
   }
 
   fn sign(&self, msg: &[u8]) -> Result<Signature, CryptoError> {
-    let sig = {
-      let res = EcdsaSig::sign(msg, &self.key);
-      if res.is_err() {
-        return Err(CryptoError::SignatureGenerationError);
-      }
-      res.unwrap()
+    let sig = match &self.key {
+      PrivateKeyInner::P256(key) => {
+        let res = EcdsaSig::sign(msg, key);
+        if res.is_err() {
+          return Err(CryptoError::SignatureGenerationError);
+        }
+        let sig = res.unwrap();
+        let r = sig
+          .r()
+          .to_vec_padded((Signature::num_bytes() / 2) as i32)
+          .unwrap();
+        let s = sig
+          .s()
+          .to_vec_padded((Signature::num_bytes() / 2) as i32)
+          .unwrap();
+        concat(vec![r, s]).to_vec()
+      },
+      PrivateKeyInner::Ed25519(key) => {
+        let res =
+          Signer::new_without_digest(key).and_then(|mut signer| signer.sign_oneshot_to_vec(msg));
+        if res.is_err() {
+          return Err(CryptoError::SignatureGenerationError);
+        }
+        res.unwrap()
+      },
     };
     Ok(Signature { sig })
   }
 }
 
 impl PrivateKey {
+  pub fn new_with_scheme(scheme: SignatureScheme) -> Self {
+    let key = match scheme {
+      SignatureScheme::P256 => {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        PrivateKeyInner::P256(EcKey::generate(&group).unwrap())
+      },
+      SignatureScheme::Ed25519 => PrivateKeyInner::Ed25519(PKey::generate_ed25519().unwrap()),
+    };
+    PrivateKey { key }
+  }
+
+  pub fn get_scheme(&self) -> SignatureScheme {
+    match &self.key {
+      PrivateKeyInner::P256(_) => SignatureScheme::P256,
+      PrivateKeyInner::Ed25519(_) => SignatureScheme::Ed25519,
+    }
+  }
+
+  /// Reads a P-256 or an Ed25519 private key from PEM.
   pub fn from_pem(pem: &[u8]) -> Result<PrivateKey, CryptoError> {
-    let res = EcKey::private_key_from_pem(pem);
+    let res = PKey::private_key_from_pem(pem);
     if res.is_err() {
       return Err(CryptoError::InvalidPrivateKeyPem);
     }
-    let key = res.unwrap();
+    let pkey = res.unwrap();
+    let key = match pkey.id() {
+      Id::EC => {
+        let res = pkey.ec_key();
+        match res {
+          Ok(key) if key.group().curve_name() == Some(Nid::X9_62_PRIME256V1) => {
+            PrivateKeyInner::P256(key)
+          },
+          _ => return Err(CryptoError::InvalidPrivateKeyPem),
+        }
+      },
+      Id::ED25519 => PrivateKeyInner::Ed25519(pkey),
+      _ => return Err(CryptoError::InvalidPrivateKeyPem),
+    };
     Ok(PrivateKey { key })
   }
 }
 
 impl SignatureTrait for Signature {
+  type PublicKey = PublicKey;
+
   fn num_bytes() -> usize {
     64
   }
@@ -170,64 +333,108 @@ impl SignatureTrait for Signature {
       return Err(CryptoError::InvalidSignature);
     }
 
-    let r = {
-      let res = BigNum::from_slice(&bytes[0..Self::num_bytes() / 2]);
-      if res.is_err() {
+    // the scheme is only known once the signature is verified against a key, so the bytes must
+    // encode an ECDSA or an Ed25519 signature; both have the same size
+    let sig = Signature {
+      sig: bytes.to_vec(),
+    };
+    if sig.to_ecdsa_sig().is_err() && !sig.is_ed25519_sig() {
+      return Err(CryptoError::InvalidSignature);
+    }
+    Ok(sig)
+  }
+
+  fn verify(&self, pk: &PublicKey, msg: &[u8]) -> Result<(), CryptoError> {
+    let res = match &pk.key {
+      PublicKeyInner::P256(key) => self.to_ecdsa_sig()?.verify(msg, key),
+      PublicKeyInner::Ed25519(_) if !self.is_ed25519_sig() => {
         return Err(CryptoError::InvalidSignature);
-      }
-      res.unwrap()
+      },
+      PublicKeyInner::Ed25519(key) => Verifier::new_without_digest(key)
+        .and_then(|mut verifier| verifier.verify_oneshot(&self.sig, msg)),
     };
-    let s = {
-      let res = BigNum::from_slice(&bytes[Self::num_bytes() / 2..]);
+    if let Ok(true) = res {
+      Ok(())
+    } else {
+      Err(CryptoError::InvalidSignature)
+    }
+  }
+
+  fn to_bytes(&self) -> Vec<u8> {
+    self.sig.clone()
+  }
+}
+
+impl Signature {
+  /// Returns the ECDSA signature if r and s are both in [1, n - 1], where n is the order of P-256.
+  fn to_ecdsa_sig(&self) -> Result<EcdsaSig, CryptoError> {
+    let r = {
+      let res = BigNum::from_slice(&self.sig[0..Self::num_bytes() / 2]);
       if res.is_err() {
         return Err(CryptoError::InvalidSignature);
       }
       res.unwrap()
     };
-
-    let sig = {
-      let res = EcdsaSig::from_private_components(r, s);
+    let s = {
+      let res = BigNum::from_slice(&self.sig[Self::num_bytes() / 2..]);
       if res.is_err() {
         return Err(CryptoError::InvalidSignature);
       }
       res.unwrap()
     };
 
-    Ok(Signature { sig })
-  }
+    let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+    let mut order = BigNum::new().unwrap();
+    let mut ctx = BigNumContext::new().unwrap();
+    group.order(&mut order, &mut ctx).unwrap();
+    let zero = BigNum::new().unwrap();
+    if r <= zero || r >= order || s <= zero || s >= order {
+      return Err(CryptoError::InvalidSignature);
+    }
 
-  fn verify(&self, pk: &PublicKey, msg: &[u8]) -> Result<(), CryptoError> {
-    let res = self.sig.verify(msg, &pk.key);
-    if let Ok(true) = res {
-      Ok(())
-    } else {
-      Err(CryptoError::InvalidSignature)
+    let res = EcdsaSig::from_private_components(r, s);
+    if res.is_err() {
+      return Err(CryptoError::InvalidSignature);
     }
+    Ok(res.unwrap())
   }
 
-  fn to_bytes(&self) -> Vec<u8> {
-    let r = self
-      .sig
-      .r()
-      .to_vec_padded((Self::num_bytes() / 2) as i32)
-      .unwrap();
-    let s = self
-      .sig
-      .s()
-      .to_vec_padded((Self::num_bytes() / 2) as i32)
-      .unwrap();
-    concat(vec![r, s]).to_vec()
+  /// Checks that S, the little-endian second half of an Ed25519 signature, is below the order.
+  fn is_ed25519_sig(&self) -> bool {
+    let s = self.sig[Self::num_bytes() / 2..]
+      .iter()
+      .rev()
+      .cloned()
+      .collect::<Vec<u8>>();
+    s.as_slice() < &ED25519_ORDER[..]
   }
-}
 
-impl Signature {
-  pub fn to_der(&self) -> Vec<u8> {
-    self.sig.to_der().unwrap()
+  /// Returns the DER encoding of an ECDSA signature. Ed25519 signatures have no DER encoding.
+  pub fn to_der(&self, scheme: SignatureScheme) -> Result<Vec<u8>, CryptoError> {
+    match scheme {
+      SignatureScheme::P256 => self
+        .to_ecdsa_sig()?
+        .to_der()
+        .map_err(|_e| CryptoError::InvalidSignature),
+      SignatureScheme::Ed25519 => Err(CryptoError::NoDerEncoding),
+    }
   }
 
   pub fn from_der(der: &[u8]) -> Result<Self, CryptoError> {
     match EcdsaSig::from_der(der) {
-      Ok(sig) => Ok(Signature { sig }),
+      Ok(sig) => {
+        let r = sig
+          .r()
+          .to_vec_padded((Self::num_bytes() / 2) as i32)
+          .unwrap();
+        let s = sig
+          .s()
+          .to_vec_padded((Self::num_bytes() / 2) as i32)
+          .unwrap();
+        Ok(Signature {
+          sig: concat(vec![r, s]).to_vec(),
+        })
+      },
       Err(_) => Err(CryptoError::FailedToGetSigFromDER),
     }
   }
@@ -296,4 +503,91 @@ mod tests {
     let res = sig.verify(&pk, &m);
     assert!(res.is_ok());
   }
+
+  #[test]
+  fn test_ed25519_and_mixed_schemes() {
+    let sk = PrivateKey::new_with_scheme(SignatureScheme::Ed25519);
+    let pk = sk.get_public_key().unwrap();
+    let pk_bytes = pk.to_bytes();
+    assert_eq!(pk_bytes.len(), PublicKey::num_bytes());
+    assert_eq!(pk_bytes[0], ED25519_TAG);
+
+    let pk = PublicKey::from_bytes(&pk_bytes).unwrap();
+    assert_eq!(pk.get_scheme(), SignatureScheme::Ed25519);
+    let msg = b"hello world";
+    let sig = Signature::from_bytes(&sk.sign(msg).unwrap().to_bytes()).unwrap();
+    assert!(sig.verify(&pk, msg).is_ok());
+    assert!(sig.verify(&pk, b"hello world2").is_err());
+
+    // a signature is only valid under the scheme of the key that produced it
+    let p256_pk = PrivateKey::new().get_public_key().unwrap();
+    assert_eq!(p256_pk.get_scheme(), SignatureScheme::P256);
+    assert!(sig.verify(&p256_pk, msg).is_err());
+  }
+
+  #[test]
+  fn test_der_encoding() {
+    let msg = b"hello world";
+    let sk = PrivateKey::new();
+    let sig = sk.sign(msg).unwrap();
+    let der = sig.to_der(SignatureScheme::P256).unwrap();
+    let sig2 = Signature::from_der(&der).unwrap();
+    assert_eq!(sig2.to_bytes(), sig.to_bytes());
+    assert!(sig2.verify(&sk.get_public_key().unwrap(), msg).is_ok());
+
+    // Ed25519 signatures have no DER encoding
+    let sk = PrivateKey::new_with_scheme(SignatureScheme::Ed25519);
+    let sig = sk.sign(msg).unwrap();
+    assert_eq!(
+      sig.to_der(SignatureScheme::Ed25519).unwrap_err(),
+      CryptoError::NoDerEncoding
+    );
+
+    assert!(Signature::from_bytes(&[0u8; 63]).is_err());
+  }
+
+  #[test]
+  fn test_signature_encoding() {
+    // above the orders of both P-256 and Ed25519
+    assert!(Signature::from_bytes(&[0xffu8; 64]).is_err());
+
+    // r = 0 is not an ECDSA signature, and S above the order is not an Ed25519 signature
+    let mut bytes = [0xffu8; 64];
+    bytes[..32].copy_from_slice(&[0u8; 32]);
+    assert!(Signature::from_bytes(&bytes).is_err());
+
+    // S = 0 is a valid Ed25519 scalar, but r = 0 never verifies under a P-256 key
+    let sig = Signature::from_bytes(&[0u8; 64]).unwrap();
+    let msg = b"hello world";
+    let pk = PrivateKey::new().get_public_key().unwrap();
+    assert!(sig.verify(&pk, msg).is_err());
+
+    // bytes that only encode an ECDSA signature never verify under an Ed25519 key
+    let sk = PrivateKey::new_with_scheme(SignatureScheme::Ed25519);
+    let mut bytes = sk.sign(msg).unwrap().to_bytes();
+    bytes[32..].copy_from_slice(&[0x01u8; 32]);
+    bytes[63] = 0xff;
+    let sig = Signature::from_bytes(&bytes).unwrap();
+    assert!(sig.verify(&sk.get_public_key().unwrap(), msg).is_err());
+  }
+
+  #[test]
+  fn test_uncompressed_pk() {
+    let sk = PrivateKey::new();
+    let pk = sk.get_public_key().unwrap();
+    let uncompressed = pk.to_uncompressed();
+    assert_eq!(uncompressed.len(), 65);
+
+    // an uncompressed key is serialized compressed
+    let parsed = PublicKey::from_bytes(&uncompressed).unwrap();
+    assert_eq!(parsed.to_bytes(), pk.to_bytes());
+    let msg = b"hello world";
+    assert!(sk.sign(msg).unwrap().verify(&parsed, msg).is_ok());
+
+    // other 65-byte encodings are rejected
+    let mut hybrid = uncompressed.clone();
+    hybrid[0] = 0x06;
+    assert!(PublicKey::from_bytes(&hybrid).is_err());
+    assert!(PublicKey::from_bytes(&uncompressed[..64]).is_err());
+  }
 }
@@ -0,0 +1,215 @@
+//! BLS signatures on BLS12-381 (public keys in G1, signatures in G2) using blst.
+//!
+//! Signatures by different endorsers on the same message can be aggregated into one
+//! 96-byte signature that is verified with a single pairing check. Because aggregation
+//! of public keys is susceptible to rogue-key attacks, a public key must only be
+//! accepted together with a valid proof of possession of its secret key.
+use super::{
+  AggregateSignatureTrait, CryptoError, PrivateKeyTrait, PublicKeyTrait, SignatureTrait,
+};
+use blst::{min_pk, BLST_ERROR};
+use core::fmt::Debug;
+use rand::RngCore;
+
+const SIG_DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+const POP_DST: &[u8] = b"BLS_POP_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+
+#[derive(Clone)]
+pub struct PublicKey {
+  key: min_pk::PublicKey,
+}
+
+pub struct PrivateKey {
+  key: min_pk::SecretKey,
+}
+
+#[derive(Clone)]
+pub struct Signature {
+  sig: min_pk::Signature,
+}
+
+impl PublicKeyTrait for PublicKey {
+  fn num_bytes() -> usize {
+    48
+  }
+
+  fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
+    // key_validate rejects the identity and points outside the prime-order subgroup
+    match min_pk::PublicKey::key_validate(bytes) {
+      Ok(key) => Ok(PublicKey { key }),
+      Err(_) => Err(CryptoError::InvalidPublicKeyBytes),
+    }
+  }
+
+  fn to_bytes(&self) -> Vec<u8> {
+    self.key.compress().to_vec()
+  }
+}
+
+impl PublicKey {
+  /// Checks a proof of possession of the secret key produced by `PrivateKey::prove_possession`.
+  pub fn verify_possession(&self, pop: &Signature) -> Result<(), CryptoError> {
+    let res = pop
+      .sig
+      .verify(true, &self.to_bytes(), POP_DST, &[], &self.key, false);
+    if res == BLST_ERROR::BLST_SUCCESS {
+      Ok(())
+    } else {
+      Err(CryptoError::InvalidSignature)
+    }
+  }
+}
+
+impl PrivateKeyTrait for PrivateKey {
+  type PublicKey = PublicKey;
+  type Signature = Signature;
+
+  fn new() -> Self {
+    let mut ikm = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut ikm);
+    let key = min_pk::SecretKey::key_gen(&ikm, &[]).unwrap();
+    PrivateKey { key }
+  }
+
+  fn get_public_key(&self) -> Result<PublicKey, CryptoError> {
+    Ok(PublicKey {
+      key: self.key.sk_to_pk(),
+    })
+  }
+
+  fn sign(&self, msg: &[u8]) -> Result<Signature, CryptoError> {
+    Ok(Signature {
+      sig: self.key.sign(msg, SIG_DST, &[]),
+    })
+  }
+}
+
+impl PrivateKey {
+  pub fn from_bytes(bytes: &[u8]) -> Result<PrivateKey, CryptoError> {
+    match min_pk::SecretKey::from_bytes(bytes) {
+      Ok(key) => Ok(PrivateKey { key }),
+      Err(_) => Err(CryptoError::InvalidPrivateKeyPem),
+    }
+  }
+
+  pub fn to_bytes(&self) -> Vec<u8> {
+    self.key.to_bytes().to_vec()
+  }
+
+  /// Produces a proof of possession of the secret key, which is a signature on the public key
+  /// under a separate domain.
+  pub fn prove_possession(&self) -> Signature {
+    let pk = self.key.sk_to_pk().compress();
+    Signature {
+      sig: self.key.sign(&pk, POP_DST, &[]),
+    }
+  }
+}
+
+impl SignatureTrait for Signature {
+  type PublicKey = PublicKey;
+
+  fn num_bytes() -> usize {
+    96
+  }
+
+  fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
+    match min_pk::Signature::sig_validate(bytes, true) {
+      Ok(sig) => Ok(Signature { sig }),
+      Err(_) => Err(CryptoError::InvalidSignature),
+    }
+  }
+
+  fn verify(&self, pk: &PublicKey, msg: &[u8]) -> Result<(), CryptoError> {
+    let res = self.sig.verify(true, msg, SIG_DST, &[], &pk.key, false);
+    if res == BLST_ERROR::BLST_SUCCESS {
+      Ok(())
+    } else {
+      Err(CryptoError::InvalidSignature)
+    }
+  }
+
+  fn to_bytes(&self) -> Vec<u8> {
+    self.sig.compress().to_vec()
+  }
+}
+
+impl AggregateSignatureTrait for Signature {
+  fn aggregate(sigs: &[Signature]) -> Result<Signature, CryptoError> {
+    let sigs = sigs
+      .iter()
+      .map(|s| &s.sig)
+      .collect::<Vec<&min_pk::Signature>>();
+    match min_pk::AggregateSignature::aggregate(&sigs, false) {
+      Ok(agg) => Ok(Signature {
+        sig: agg.to_signature(),
+      }),
+      Err(_) => Err(CryptoError::InvalidSignature),
+    }
+  }
+
+  fn verify_aggregate(&self, pks: &[PublicKey], msg: &[u8]) -> Result<(), CryptoError> {
+    if pks.is_empty() {
+      return Err(CryptoError::InvalidSignature);
+    }
+    let pks = pks
+      .iter()
+      .map(|pk| &pk.key)
+      .collect::<Vec<&min_pk::PublicKey>>();
+    let res = self.sig.fast_aggregate_verify(true, msg, SIG_DST, &pks);
+    if res == BLST_ERROR::BLST_SUCCESS {
+      Ok(())
+    } else {
+      Err(CryptoError::InvalidSignature)
+    }
+  }
+}
+
+impl Debug for PublicKey {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    write!(f, "PublicKey({:?})", self.to_bytes())
+  }
+}
+
+impl Debug for Signature {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    write!(f, "Signature({:?})", self.to_bytes())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_aggregate_verify() {
+    let msg = b"hello world";
+    let sks = (0..4)
+      .map(|_| PrivateKey::new())
+      .collect::<Vec<PrivateKey>>();
+    let pks = sks
+      .iter()
+      .map(|sk| sk.get_public_key().unwrap())
+      .collect::<Vec<PublicKey>>();
+    let sigs = sks
+      .iter()
+      .map(|sk| sk.sign(msg).unwrap())
+      .collect::<Vec<Signature>>();
+
+    let agg = Signature::aggregate(&sigs).unwrap();
+    let agg = Signature::from_bytes(&agg.to_bytes()).unwrap();
+    assert!(agg.verify_aggregate(&pks, msg).is_ok());
+    assert!(agg.verify_aggregate(&pks[1..], msg).is_err());
+    assert!(agg.verify_aggregate(&pks, b"hello world2").is_err());
+  }
+
+  #[test]
+  fn test_proof_of_possession() {
+    let sk = PrivateKey::new();
+    let pk = PublicKey::from_bytes(&sk.get_public_key().unwrap().to_bytes()).unwrap();
+    assert!(pk.verify_possession(&sk.prove_possession()).is_ok());
+    // a regular signature on the public key is not a proof of possession
+    let sig = sk.sign(&pk.to_bytes()).unwrap();
+    assert!(pk.verify_possession(&sig).is_err());
+  }
+}
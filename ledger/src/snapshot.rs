@@ -0,0 +1,153 @@
+use crate::{errors::VerificationError, quorum::QuorumPolicy, NimbleDigest, VerifierState};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// The version of the snapshots produced by `VerifierState::to_snapshot`
+pub const VERIFIER_STATE_SNAPSHOT_VERSION: u8 = 2;
+
+/// A view in a snapshot
+#[derive(Serialize, Deserialize)]
+struct SnapshotView {
+  view: Vec<u8>,
+  height: u64,
+  // the public keys of the endorsers, sorted
+  pks: Vec<Vec<u8>>,
+  policy: QuorumPolicy,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SnapshotContents {
+  group_identity: Vec<u8>,
+  view_ledger_height: u64,
+  // sorted by view
+  views: Vec<SnapshotView>,
+  verified_views: Vec<Vec<u8>>,
+  pruned_below: u64,
+}
+
+impl VerifierState {
+  /// Serializes the state into a snapshot: a version byte, the SHA-256 digest of the contents,
+  /// and the contents. The encoding is deterministic, so equal states produce equal snapshots.
+  /// BLS aggregate keys and the view retention policy are not part of the snapshot and have to
+  /// be set again.
+  pub fn to_snapshot(&self) -> Vec<u8> {
+    let mut views = self
+      .vk_map
+      .iter()
+      .map(|(view, pks)| {
+        let mut pks = pks.iter().cloned().collect::<Vec<Vec<u8>>>();
+        pks.sort();
+        let policy = self.policy_map.get(view).cloned().unwrap_or_default();
+        let height = self.view_heights.get(view).cloned().unwrap_or_default();
+        SnapshotView {
+          view: view.to_bytes(),
+          height: height as u64,
+          pks,
+          policy,
+        }
+      })
+      .collect::<Vec<SnapshotView>>();
+    views.sort_by(|a, b| a.view.cmp(&b.view));
+
+    let mut verified_views = self
+      .verified_views
+      .iter()
+      .map(|view| view.to_bytes())
+      .collect::<Vec<Vec<u8>>>();
+    verified_views.sort();
+
+    let contents = SnapshotContents {
+      group_identity: self.group_identity.to_bytes(),
+      view_ledger_height: self.view_ledger_height as u64,
+      views,
+      verified_views,
+      pruned_below: self.pruned_below as u64,
+    };
+    let contents_bytes = bincode::serialize(&contents).unwrap();
+
+    let mut bytes = vec![VERIFIER_STATE_SNAPSHOT_VERSION];
+    bytes.extend(NimbleDigest::digest(&contents_bytes).to_bytes());
+    bytes.extend(contents_bytes);
+    bytes
+  }
+
+  /// Restores a state from a snapshot produced by `to_snapshot`, checking its digest.
+  pub fn from_snapshot(bytes: &[u8]) -> Result<VerifierState, VerificationError> {
+    if bytes.len() <= 1 + NimbleDigest::num_bytes() {
+      return Err(VerificationError::InvalidSnapshot);
+    }
+    if bytes[0] != VERIFIER_STATE_SNAPSHOT_VERSION {
+      eprintln!("unsupported verifier state snapshot version {}", bytes[0]);
+      return Err(VerificationError::InvalidSnapshot);
+    }
+    let digest = NimbleDigest::from_bytes(&bytes[1..1 + NimbleDigest::num_bytes()])
+      .map_err(|_e| VerificationError::InvalidSnapshot)?;
+    let contents_bytes = &bytes[1 + NimbleDigest::num_bytes()..];
+    if NimbleDigest::digest(contents_bytes) != digest {
+      return Err(VerificationError::InvalidSnapshot);
+    }
+
+    let contents: SnapshotContents =
+      bincode::deserialize(contents_bytes).map_err(|_e| VerificationError::InvalidSnapshot)?;
+
+    let parse_digest =
+      |d: &[u8]| NimbleDigest::from_bytes(d).map_err(|_e| VerificationError::InvalidSnapshot);
+
+    let mut vs = VerifierState::new();
+    vs.group_identity = parse_digest(&contents.group_identity)?;
+    vs.view_ledger_height = contents.view_ledger_height as usize;
+    let mut vk_map = HashMap::new();
+    let mut policy_map = HashMap::new();
+    let mut view_heights = HashMap::new();
+    for entry in contents.views {
+      let view = parse_digest(&entry.view)?;
+      vk_map.insert(view, entry.pks.into_iter().collect::<HashSet<Vec<u8>>>());
+      policy_map.insert(view, entry.policy);
+      view_heights.insert(view, entry.height as usize);
+    }
+    vs.vk_map = vk_map;
+    vs.policy_map = policy_map;
+    vs.view_heights = view_heights;
+    for view in contents.verified_views {
+      vs.verified_views.insert(parse_digest(&view)?);
+    }
+    vs.pruned_below = contents.pruned_below as usize;
+
+    Ok(vs)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  pub fn test_snapshot_roundtrip() {
+    let mut vs = VerifierState::new();
+    vs.set_group_identity(NimbleDigest::digest(b"genesis"));
+    vs.view_ledger_height = 2;
+    let view = NimbleDigest::digest(b"view");
+    vs.vk_map
+      .insert(view, [vec![1], vec![2], vec![3]].iter().cloned().collect());
+    vs.policy_map.insert(view, QuorumPolicy::Threshold(2));
+    vs.verified_views.insert(NimbleDigest::digest(b"prev"));
+
+    let snapshot = vs.to_snapshot();
+    let restored = VerifierState::from_snapshot(&snapshot).unwrap();
+    assert_eq!(restored.to_snapshot(), snapshot);
+    assert_eq!(restored.get_view_ledger_height(), 2);
+    assert_eq!(
+      restored.get_policy_for_view(&view).unwrap(),
+      &QuorumPolicy::Threshold(2)
+    );
+    assert!(restored.is_verified_view(&NimbleDigest::digest(b"prev")));
+
+    let mut tampered = snapshot;
+    let last = tampered.len() - 1;
+    tampered[last] ^= 1;
+    assert_eq!(
+      VerifierState::from_snapshot(&tampered).err(),
+      Some(VerificationError::InvalidSnapshot)
+    );
+  }
+}
@@ -15,6 +15,8 @@ struct GetIdentityResponse {
   pub id: String,
   #[serde(rename = "PublicKey")]
   pub pk: String,
+  #[serde(rename = "KeyId")]
+  pub key_id: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -27,6 +29,8 @@ struct NewCounterRequest {
 struct NewCounterResponse {
   #[serde(rename = "Signature")]
   pub signature: String,
+  #[serde(rename = "KeyId")]
+  pub key_id: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -41,6 +45,8 @@ struct IncrementCounterRequest {
 struct IncrementCounterResponse {
   #[serde(rename = "Signature")]
   pub signature: String,
+  #[serde(rename = "KeyId")]
+  pub key_id: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -51,6 +57,8 @@ struct ReadCounterResponse {
   pub counter: u64,
   #[serde(rename = "Signature")]
   pub signature: String,
+  #[serde(rename = "KeyId")]
+  pub key_id: String,
 }
 
 #[allow(dead_code)]
@@ -79,6 +87,11 @@ async fn main() {
         .short("n")
         .help("The number of ledgers")
         .default_value("0"),
+    )
+    .arg(
+      Arg::with_name("check_health")
+        .long("check-health")
+        .help("Only check that the endpoint is ready, and exit with a non-zero status if not"),
     );
   let cli_matches = config.get_matches();
   let endpoint_addr = cli_matches.value_of("endpoint").unwrap();
@@ -96,6 +109,22 @@ async fn main() {
     .build()
     .unwrap();
 
+  if cli_matches.is_present("check_health") {
+    let res = client.get(format!("{}/readyz", endpoint_addr)).send().await;
+    let ready = match res {
+      Ok(resp) => {
+        let ready = resp.status() == reqwest::StatusCode::OK;
+        println!("{}", resp.text().await.unwrap_or_default());
+        ready
+      },
+      Err(e) => {
+        eprintln!("readyz failed: {:?}", e);
+        false
+      },
+    };
+    std::process::exit(if ready { 0 } else { 1 });
+  }
+
   // Step 0: Obtain the identity and public key of the instance
   let get_identity_url = reqwest::Url::parse_with_params(
     &format!("{}/serviceid", endpoint_addr),
@@ -116,6 +145,7 @@ async fn main() {
   let pk_bytes = base64_url::decode(&get_identity_resp.pk).unwrap();
   let id = NimbleDigest::from_bytes(&id_bytes).unwrap();
   let pk = PublicKey::from_bytes(&pk_bytes).unwrap();
+  let key_id = base64_url::decode(&get_identity_resp.key_id).unwrap();
 
   println!("id={:?}", id);
   println!("pk={:?}", pk);
@@ -143,8 +173,12 @@ async fn main() {
 
   let new_counter_resp: NewCounterResponse = resp.json().await.unwrap();
   let signature = base64_url::decode(&new_counter_resp.signature).unwrap();
+  assert_eq!(
+    base64_url::decode(&new_counter_resp.key_id).unwrap(),
+    key_id
+  );
 
-  // verify a message that unequivocally identifies the counter and tag
+  // verify a message that unequivocally identifies the counter and tag, and the signing key
   let msg = {
     let s = format!(
       "{}.{}.{}.{}.{}",
@@ -154,7 +188,7 @@ async fn main() {
       base64_url::encode(&0_u64.to_le_bytes()),
       base64_url::encode(&tag_bytes),
     );
-    NimbleDigest::digest(s.as_bytes())
+    NimbleDigest::digest(s.as_bytes()).digest_with_bytes(&key_id)
   };
 
   let signature = Signature::from_bytes(&signature).unwrap();
@@ -182,8 +216,12 @@ async fn main() {
   let tag = base64_url::decode(&read_counter_resp.tag).unwrap();
   let counter = read_counter_resp.counter;
   let signature = base64_url::decode(&read_counter_resp.signature).unwrap();
+  assert_eq!(
+    base64_url::decode(&read_counter_resp.key_id).unwrap(),
+    key_id
+  );
 
-  // verify a message that unequivocally identifies the counter and tag
+  // verify a message that unequivocally identifies the counter and tag, and the signing key
   let msg = {
     let s = format!(
       "{}.{}.{}.{}.{}.{}",
@@ -194,7 +232,7 @@ async fn main() {
       base64_url::encode(&tag),
       base64_url::encode(&nonce_bytes),
     );
-    NimbleDigest::digest(s.as_bytes())
+    NimbleDigest::digest(s.as_bytes()).digest_with_bytes(&key_id)
   };
 
   let signature = Signature::from_bytes(&signature).unwrap();
@@ -231,8 +269,12 @@ async fn main() {
 
     let increment_counter_resp: IncrementCounterResponse = resp.json().await.unwrap();
     let signature = base64_url::decode(&increment_counter_resp.signature).unwrap();
+    assert_eq!(
+      base64_url::decode(&increment_counter_resp.key_id).unwrap(),
+      key_id
+    );
 
-    // verify a message that unequivocally identifies the counter and tag
+    // verify a message that unequivocally identifies the counter and tag, and the signing key
     let msg = {
       let s = format!(
         "{}.{}.{}.{}.{}",
@@ -242,7 +284,7 @@ async fn main() {
         base64_url::encode(&(expected_counter as u64).to_le_bytes()),
         base64_url::encode(&tag),
       );
-      NimbleDigest::digest(s.as_bytes())
+      NimbleDigest::digest(s.as_bytes()).digest_with_bytes(&key_id)
     };
 
     let signature = Signature::from_bytes(&signature).unwrap();
@@ -273,8 +315,12 @@ async fn main() {
   let counter = read_counter_resp.counter;
   assert_eq!(counter, expected_counter as u64);
   let signature = base64_url::decode(&read_counter_resp.signature).unwrap();
+  assert_eq!(
+    base64_url::decode(&read_counter_resp.key_id).unwrap(),
+    key_id
+  );
 
-  // verify a message that unequivocally identifies the counter and tag
+  // verify a message that unequivocally identifies the counter and tag, and the signing key
   let msg = {
     let s = format!(
       "{}.{}.{}.{}.{}.{}",
@@ -285,7 +331,7 @@ async fn main() {
       base64_url::encode(&tag),
       base64_url::encode(&nonce_bytes),
     );
-    NimbleDigest::digest(s.as_bytes())
+    NimbleDigest::digest(s.as_bytes()).digest_with_bytes(&key_id)
   };
 
   let signature = Signature::from_bytes(&signature).unwrap();
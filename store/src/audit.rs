@@ -0,0 +1,566 @@
+use crate::{
+  errors::LedgerStoreError,
+  ledger::{LedgerEntry, LedgerStore},
+};
+use ledger::{
+  compute_aggregated_block_hash, CustomSerde, Handle, MetaBlock, NimbleDigest, NimbleHashTrait,
+  VerifierState,
+};
+use serde::Serialize;
+
+/// A problem found at an index of a ledger by `audit_store`
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(tag = "Kind", content = "Index")]
+pub enum AuditFinding {
+  /// returned if the entry cannot be read from the store
+  Gap(usize),
+  /// returned if no receipts are attached to the entry
+  MissingReceipts(usize),
+  /// returned if no metablock in the receipts is endorsed by a quorum of its view
+  LacksQuorum(usize),
+  /// returned if the endorsed metablock does not match the block and nonces in the store
+  InvalidBlockHash(usize),
+  /// returned if the endorsed metablocks disagree or do not extend the previous entry
+  Fork(usize),
+}
+
+/// The result of auditing one ledger
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct LedgerAudit {
+  pub handle: String,
+  pub height: usize,
+  // the index at which a pruned ledger starts
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub checkpoint: Option<usize>,
+  pub findings: Vec<AuditFinding>,
+}
+
+/// A machine-readable report of an audit of a ledger store
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct AuditReport {
+  pub group_identity: String,
+  pub view_ledger: LedgerAudit,
+  pub ledgers: Vec<LedgerAudit>,
+}
+
+impl AuditReport {
+  pub fn is_clean(&self) -> bool {
+    self.view_ledger.findings.is_empty() && self.ledgers.iter().all(|l| l.findings.is_empty())
+  }
+}
+
+/// Replays the view ledger and every ledger in a store and verifies their hash chains.
+///
+/// # Arguments
+///
+/// * `ledger_store` - The store to audit.
+/// * `attestations` - The attestation reports that vouch for the latest view; every earlier
+///   view is verified through the view that follows it, as an endpoint does.
+///
+/// # Returns
+///
+/// The report, or a `LedgerStoreError` if the store cannot be enumerated.
+pub async fn audit_store(
+  ledger_store: &dyn LedgerStore,
+  attestations: &[u8],
+) -> Result<AuditReport, LedgerStoreError> {
+  let mut verifier_state = VerifierState::new();
+  let view_ledger = audit_view_ledger(ledger_store, attestations, &mut verifier_state).await?;
+
+  let mut handles = ledger_store.list_ledgers().await?;
+  handles.sort();
+
+  let mut ledgers = Vec::new();
+  for handle in handles {
+    ledgers.push(audit_ledger(ledger_store, &verifier_state, &handle).await);
+  }
+
+  Ok(AuditReport {
+    group_identity: hex::encode(verifier_state.get_group_identity().to_bytes()),
+    view_ledger,
+    ledgers,
+  })
+}
+
+async fn audit_view_ledger(
+  ledger_store: &dyn LedgerStore,
+  attestations: &[u8],
+  verifier_state: &mut VerifierState,
+) -> Result<LedgerAudit, LedgerStoreError> {
+  let (_tail, height) = ledger_store.read_view_ledger_tail().await?;
+  let mut findings = Vec::new();
+
+  // the entry at index 0 is a placeholder; the views start at index 1
+  let mut entries: Vec<Option<LedgerEntry>> = Vec::new();
+  for idx in 1..=height {
+    match ledger_store.read_view_ledger_by_index(idx).await {
+      Ok(entry) => entries.push(Some(entry)),
+      Err(e) => {
+        eprintln!(
+          "Failed to read the view ledger entry at index {} ({:?})",
+          idx, e
+        );
+        findings.push(AuditFinding::Gap(idx));
+        entries.push(None);
+      },
+    }
+  }
+
+  findings.extend(check_view_entries(&entries, attestations, verifier_state));
+  findings.sort_by_key(finding_index);
+  Ok(LedgerAudit {
+    handle: String::new(),
+    height,
+    checkpoint: None,
+    findings,
+  })
+}
+
+/// Replays the entries of the view ledger, starting at index 1, into `verifier_state` and
+/// checks their metablock chain. A `None` entry is one that could not be read.
+pub(crate) fn check_view_entries(
+  entries: &[Option<LedgerEntry>],
+  attestations: &[u8],
+  verifier_state: &mut VerifierState,
+) -> Vec<AuditFinding> {
+  let mut findings = Vec::new();
+
+  if let Some(Some(genesis)) = entries.first() {
+    verifier_state.set_group_identity(genesis.get_block().hash());
+  }
+
+  // the latest view that has a quorum is vouched for by the attestation reports, and each view
+  // authorizes the view before it
+  let mut anchored = false;
+  for (i, entry) in entries.iter().enumerate().rev() {
+    if let Some(entry) = entry {
+      let res = verifier_state.apply_view_change(
+        &entry.get_block().to_bytes(),
+        &entry.get_receipts().to_bytes(),
+        if anchored { None } else { Some(attestations) },
+      );
+      if res.is_ok() {
+        anchored = true;
+      } else if entry.get_receipts().is_empty() {
+        findings.push(AuditFinding::MissingReceipts(i + 1));
+      } else {
+        findings.push(AuditFinding::LacksQuorum(i + 1));
+      }
+    }
+  }
+
+  let mut prev: Option<MetaBlock> = None;
+  for (i, entry) in entries.iter().enumerate() {
+    let idx = i + 1;
+    let metablock = match entry {
+      Some(entry) if !entry.get_receipts().is_empty() => {
+        match entry.get_receipts().get_metablock() {
+          Ok(metablock) => {
+            if *metablock.get_block_hash() != entry.get_block().hash() {
+              findings.push(AuditFinding::InvalidBlockHash(idx));
+            }
+            Some(metablock)
+          },
+          Err(_) => {
+            findings.push(AuditFinding::Fork(idx));
+            None
+          },
+        }
+      },
+      _ => None,
+    };
+
+    if let (Some(metablock), Some(prev)) = (&metablock, &prev) {
+      if *metablock.get_prev() != prev.hash() || metablock.get_height() != idx {
+        findings.push(AuditFinding::Fork(idx));
+      }
+    }
+    prev = metablock;
+  }
+
+  findings.sort_by_key(finding_index);
+  findings
+}
+
+async fn audit_ledger(
+  ledger_store: &dyn LedgerStore,
+  verifier_state: &VerifierState,
+  handle: &Handle,
+) -> LedgerAudit {
+  let mut findings = Vec::new();
+  let height = match ledger_store.read_ledger_tail(handle).await {
+    Ok((_entry, height)) => height,
+    Err(e) => {
+      eprintln!("Failed to read the tail of ledger {:?} ({:?})", handle, e);
+      findings.push(AuditFinding::Gap(0));
+      return LedgerAudit {
+        handle: hex::encode(handle.to_bytes()),
+        height: 0,
+        checkpoint: None,
+        findings,
+      };
+    },
+  };
+
+  // the chain of a pruned ledger is verified from its checkpoint, whose endorsed metablock
+  // vouches for the entries that were dropped
+  let checkpoint = match ledger_store.read_ledger_checkpoint(handle).await {
+    Ok(checkpoint) => checkpoint.map(|c| c.get_height()),
+    Err(e) => {
+      eprintln!(
+        "Failed to read the checkpoint of ledger {:?} ({:?})",
+        handle, e
+      );
+      None
+    },
+  };
+
+  let mut checker = ChainChecker::new(handle);
+  for idx in checkpoint.unwrap_or(0)..=height {
+    match ledger_store.read_ledger_by_index(handle, idx).await {
+      Ok(entry) => findings.extend(checker.check(verifier_state, idx, &entry)),
+      Err(e) => {
+        eprintln!(
+          "Failed to read index {} of ledger {:?} ({:?})",
+          idx, handle, e
+        );
+        findings.push(AuditFinding::Gap(idx));
+        checker.skip();
+      },
+    }
+  }
+
+  LedgerAudit {
+    handle: hex::encode(handle.to_bytes()),
+    height,
+    checkpoint,
+    findings,
+  }
+}
+
+/// Checks the entries of a ledger, in order of their indices, against the metablocks that
+/// its endorsers signed
+pub(crate) struct ChainChecker {
+  handle: Handle,
+  // the hash of the previous metablock, unless it could not be established
+  prev: Option<NimbleDigest>,
+}
+
+impl ChainChecker {
+  pub(crate) fn new(handle: &Handle) -> Self {
+    ChainChecker {
+      handle: *handle,
+      prev: None,
+    }
+  }
+
+  /// Records that the entry at the next index could not be read
+  pub(crate) fn skip(&mut self) {
+    self.prev = None;
+  }
+
+  pub(crate) fn check(
+    &mut self,
+    verifier_state: &VerifierState,
+    idx: usize,
+    entry: &LedgerEntry,
+  ) -> Vec<AuditFinding> {
+    let mut findings = Vec::new();
+    let block_hash = compute_aggregated_block_hash(
+      &entry.get_block().hash().to_bytes(),
+      &entry.get_nonces().hash().to_bytes(),
+    );
+    let expected = if idx == 0 {
+      Some(MetaBlock::genesis(&block_hash))
+    } else {
+      self
+        .prev
+        .map(|prev| MetaBlock::new(&prev, &block_hash, idx))
+    };
+
+    if entry.get_receipts().is_empty() {
+      findings.push(AuditFinding::MissingReceipts(idx));
+      self.prev = expected.map(|m| m.hash());
+      return findings;
+    }
+
+    // the same metablock can be endorsed in several views
+    let mut endorsed: Vec<MetaBlock> = Vec::new();
+    for metablock in entry
+      .get_receipts()
+      .get_endorsed_metablocks(verifier_state, &self.handle)
+    {
+      if !endorsed.contains(&metablock) {
+        endorsed.push(metablock);
+      }
+    }
+    if endorsed.is_empty() {
+      findings.push(AuditFinding::LacksQuorum(idx));
+      self.prev = expected.map(|m| m.hash());
+      return findings;
+    }
+
+    if endorsed.iter().any(|m| *m.get_block_hash() != block_hash) {
+      findings.push(AuditFinding::InvalidBlockHash(idx));
+    }
+    let prev = self.prev;
+    let extends_chain = |m: &MetaBlock| {
+      m.get_height() == idx
+        && match prev {
+          Some(prev) => *m.get_prev() == prev,
+          None => true,
+        }
+    };
+    if endorsed.len() > 1 || !endorsed.iter().all(extends_chain) {
+      findings.push(AuditFinding::Fork(idx));
+    }
+
+    // later entries are checked against what the endorsers signed
+    self.prev = if endorsed.len() == 1 {
+      Some(endorsed[0].hash())
+    } else {
+      expected.map(|m| m.hash())
+    };
+    findings
+  }
+}
+
+fn finding_index(finding: &AuditFinding) -> usize {
+  match finding {
+    AuditFinding::Gap(idx)
+    | AuditFinding::MissingReceipts(idx)
+    | AuditFinding::LacksQuorum(idx)
+    | AuditFinding::InvalidBlockHash(idx)
+    | AuditFinding::Fork(idx) => *idx,
+  }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+  use super::*;
+  use crate::ledger::in_memory::InMemoryLedgerStore;
+  use ledger::{
+    quorum::ViewConfig,
+    signature::{PrivateKey, PrivateKeyTrait, PublicKeyTrait},
+    Block, IdSig, Nonces, Receipt, Receipts,
+  };
+
+  pub(crate) const ATTESTATIONS: &str = "THIS IS A PLACE HOLDER FOR ATTESTATION";
+
+  fn sign(
+    sks: &[PrivateKey],
+    view: &NimbleDigest,
+    metablock: &MetaBlock,
+    message: &NimbleDigest,
+  ) -> Receipts {
+    let mut receipts = Receipts::new();
+    for sk in sks {
+      let id_sig = IdSig::new(
+        sk.get_public_key().unwrap(),
+        sk.sign(&message.to_bytes()).unwrap(),
+      );
+      receipts.add(&Receipt::new(*view, metablock.clone(), id_sig));
+    }
+    receipts
+  }
+
+  pub(crate) struct Fixture {
+    pub sks: Vec<PrivateKey>,
+    pub group_identity: NimbleDigest,
+    pub view: NimbleDigest,
+    pub handle: Handle,
+    // the hash of the metablock of the tail of the ledger
+    pub prev: NimbleDigest,
+  }
+
+  // creates a view of three endorsers, and a ledger with a genesis block and one appended block
+  // that are each endorsed by two of them
+  pub(crate) async fn populate_store(store: &dyn LedgerStore) -> Fixture {
+    let sks = (0..3).map(|_| PrivateKey::new()).collect::<Vec<_>>();
+    let hostnames = sks
+      .iter()
+      .map(|sk| (sk.get_public_key().unwrap().to_bytes(), String::new()))
+      .collect();
+    let config = ViewConfig::new(hostnames, Default::default()).to_bytes();
+
+    // the first view
+    let group_identity = NimbleDigest::digest(&config);
+    let view_metablock = MetaBlock::new(&NimbleDigest::default(), &group_identity, 1);
+    let state_hash = NimbleDigest::default();
+    let message = group_identity.digest_with(&state_hash.digest_with(&view_metablock.hash()));
+    store
+      .append_view_ledger(&Block::new(&config), 1)
+      .await
+      .unwrap();
+    store
+      .attach_view_ledger_receipts(1, &sign(&sks, &state_hash, &view_metablock, &message))
+      .await
+      .unwrap();
+    let view = view_metablock.hash();
+
+    let handle = NimbleDigest::digest(b"handle");
+    let genesis = Block::new(b"genesis");
+    store.create_ledger(&handle, genesis.clone()).await.unwrap();
+    let hash_nonces = Nonces::new().hash().to_bytes();
+    let mut prev = NimbleDigest::default();
+    for (idx, block) in [genesis, Block::new(b"block1")].iter().enumerate() {
+      if idx > 0 {
+        store.append_ledger(&handle, block, idx).await.unwrap();
+      }
+      let block_hash = compute_aggregated_block_hash(&block.hash().to_bytes(), &hash_nonces);
+      let metablock = if idx == 0 {
+        MetaBlock::genesis(&block_hash)
+      } else {
+        MetaBlock::new(&prev, &block_hash, idx)
+      };
+      let message =
+        group_identity.digest_with(&view.digest_with(&handle.digest_with(&metablock.hash())));
+      store
+        .attach_ledger_receipts(&handle, idx, &sign(&sks[..2], &view, &metablock, &message))
+        .await
+        .unwrap();
+      prev = metablock.hash();
+    }
+
+    Fixture {
+      sks,
+      group_identity,
+      view,
+      handle,
+      prev,
+    }
+  }
+
+  #[tokio::test]
+  pub async fn test_audit_store() {
+    let store = InMemoryLedgerStore::new();
+    let Fixture {
+      sks,
+      group_identity,
+      view,
+      handle,
+      prev,
+    } = populate_store(&store).await;
+    let hash_nonces = Nonces::new().hash().to_bytes();
+
+    let report = audit_store(&store, ATTESTATIONS.as_bytes()).await.unwrap();
+    assert!(report.is_clean());
+    assert_eq!(
+      report.group_identity,
+      hex::encode(group_identity.to_bytes())
+    );
+    assert_eq!(report.ledgers.len(), 1);
+    assert_eq!(report.ledgers[0].height, 1);
+
+    // an entry without receipts, then an entry endorsed by a single endorser whose metablock
+    // does not extend the chain
+    store
+      .append_ledger(&handle, &Block::new(b"block2"), 2)
+      .await
+      .unwrap();
+    store
+      .append_ledger(&handle, &Block::new(b"block3"), 3)
+      .await
+      .unwrap();
+    let block_hash =
+      compute_aggregated_block_hash(&Block::new(b"block3").hash().to_bytes(), &hash_nonces);
+    let metablock = MetaBlock::new(&prev, &block_hash, 3);
+    let message =
+      group_identity.digest_with(&view.digest_with(&handle.digest_with(&metablock.hash())));
+    store
+      .attach_ledger_receipts(&handle, 3, &sign(&sks[..1], &view, &metablock, &message))
+      .await
+      .unwrap();
+
+    let report = audit_store(&store, ATTESTATIONS.as_bytes()).await.unwrap();
+    assert!(!report.is_clean());
+    assert_eq!(
+      report.ledgers[0].findings,
+      vec![
+        AuditFinding::MissingReceipts(2),
+        AuditFinding::LacksQuorum(3)
+      ]
+    );
+
+    // a quorum endorsing a metablock that skips the entry at index 2 is a fork
+    store
+      .attach_ledger_receipts(&handle, 3, &sign(&sks[1..], &view, &metablock, &message))
+      .await
+      .unwrap();
+    let report = audit_store(&store, ATTESTATIONS.as_bytes()).await.unwrap();
+    assert_eq!(
+      report.ledgers[0].findings,
+      vec![AuditFinding::MissingReceipts(2), AuditFinding::Fork(3)]
+    );
+  }
+
+  #[tokio::test]
+  pub async fn test_audit_pruned_ledger() {
+    let store = InMemoryLedgerStore::new();
+    let Fixture {
+      sks,
+      group_identity,
+      view,
+      handle,
+      prev,
+    } = populate_store(&store).await;
+    let hash_nonces = Nonces::new().hash().to_bytes();
+
+    store
+      .append_ledger(&handle, &Block::new(b"block2"), 2)
+      .await
+      .unwrap();
+    let block_hash =
+      compute_aggregated_block_hash(&Block::new(b"block2").hash().to_bytes(), &hash_nonces);
+    let metablock = MetaBlock::new(&prev, &block_hash, 2);
+    let message =
+      group_identity.digest_with(&view.digest_with(&handle.digest_with(&metablock.hash())));
+    store
+      .attach_ledger_receipts(&handle, 2, &sign(&sks[..2], &view, &metablock, &message))
+      .await
+      .unwrap();
+
+    let checkpoint = store.prune_ledger(&handle, 1).await.unwrap();
+    assert_eq!(checkpoint.get_height(), 1);
+    match store.read_ledger_by_index(&handle, 0).await {
+      Err(LedgerStoreError::Pruned(c)) => assert_eq!(c.get_height(), 1),
+      res => panic!("expected the entry to be pruned, got {:?}", res),
+    }
+    assert!(store.read_ledger_by_index(&handle, 1).await.is_ok());
+    assert_eq!(store.read_ledger_tail(&handle).await.unwrap().1, 2);
+    // pruning again at the checkpoint is a no-op, and entries below it are gone
+    assert_eq!(
+      store.prune_ledger(&handle, 1).await.unwrap().get_height(),
+      1
+    );
+    assert!(matches!(
+      store.prune_ledger(&handle, 0).await,
+      Err(LedgerStoreError::Pruned(_))
+    ));
+
+    let report = audit_store(&store, ATTESTATIONS.as_bytes()).await.unwrap();
+    assert!(report.is_clean());
+    assert_eq!(report.ledgers[0].checkpoint, Some(1));
+    assert_eq!(report.ledgers[0].height, 2);
+
+    // an entry can only be a checkpoint once its receipts are attached
+    store
+      .append_ledger(&handle, &Block::new(b"block3"), 3)
+      .await
+      .unwrap();
+    assert!(matches!(
+      store.prune_ledger(&handle, 3).await,
+      Err(LedgerStoreError::LedgerError(
+        crate::errors::StorageError::InvalidCheckpoint
+      ))
+    ));
+    assert!(matches!(
+      store.prune_ledger(&handle, 4).await,
+      Err(LedgerStoreError::LedgerError(
+        crate::errors::StorageError::InvalidIndex
+      ))
+    ));
+  }
+}
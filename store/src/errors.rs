@@ -50,14 +50,24 @@ pub enum StorageError {
   UnhandledError,
   /// return if the name for the nimble database is not acceptable for the store
   InvalidDBName,
+  /// return if an export to import is malformed or truncated
+  InvalidExport,
+  /// return if an export fails verification or changes on import
+  FailedVerification,
+  /// return if the store to import into already contains views or ledgers
+  StoreNotEmpty,
+  /// return if the entry to checkpoint at carries no receipts for its height
+  InvalidCheckpoint,
 }
 
+use crate::ledger::Checkpoint;
 use std::fmt::Display;
 
 #[derive(Clone, Debug)]
 pub enum LedgerStoreError {
   LedgerError(StorageError),
   MongoDBError(mongodb::error::Error),
+  Pruned(Checkpoint),
 }
 
 impl Display for LedgerStoreError {
@@ -65,6 +75,11 @@ impl Display for LedgerStoreError {
     match self {
       LedgerStoreError::LedgerError(storage_error) => write!(f, "{:?}", storage_error),
       LedgerStoreError::MongoDBError(mongodb_error) => write!(f, "{:?}", mongodb_error),
+      LedgerStoreError::Pruned(checkpoint) => write!(
+        f,
+        "entry is pruned; ledger starts at {}",
+        checkpoint.get_height()
+      ),
     }
   }
 }
@@ -1,6 +1,6 @@
 use crate::{
   errors::{LedgerStoreError, StorageError},
-  ledger::{LedgerEntry, LedgerStore},
+  ledger::{Checkpoint, LedgerEntry, LedgerStore},
 };
 use async_trait::async_trait;
 use azure_data_tables::{clients::TableClient, prelude::*};
@@ -8,6 +8,7 @@ use azure_data_tables::{clients::TableClient, prelude::*};
 use azure_core::Etag;
 use azure_storage::core::prelude::*;
 use base64_url;
+use futures::StreamExt;
 use ledger::{Block, CustomSerde, Handle, NimbleDigest, Nonce, Nonces, Receipts};
 use serde::{Deserialize, Serialize};
 use std::{
@@ -21,6 +22,7 @@ use std::{
 use http::{self, StatusCode};
 
 const TAIL: &str = "TAIL";
+const CHECKPOINT: &str = "CHECKPOINT"; // a copy of the first entry of a pruned ledger
 
 enum AzureOp {
   Append,
@@ -150,6 +152,22 @@ struct DBEntryNonceProjection {
   pub nonces: String,
 }
 
+// This is a projection so you only read the keys of a row, not the rest
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct DBEntryKeyProjection {
+  #[serde(rename = "PartitionKey")]
+  pub handle: String,
+  #[serde(rename = "RowKey")]
+  pub row: String,
+}
+
+// This is a projection so you only read the handle, not the rest
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct DBEntryHandleProjection {
+  #[serde(rename = "PartitionKey")]
+  pub handle: String,
+}
+
 #[derive(Debug)]
 pub struct TableLedgerStore {
   client: Arc<TableClient>,
@@ -671,7 +689,21 @@ async fn read_ledger_internal(
   };
   let index = checked_conversion!(actual_idx, i64).to_string();
 
-  let (entry, _etag) = find_db_entry(ledger, handle, &index).await?;
+  let entry = match find_db_entry(ledger.clone(), handle, &index).await {
+    Ok((entry, _etag)) => entry,
+    Err(LedgerStoreError::LedgerError(StorageError::InvalidIndex)) => {
+      // the entry may have been pruned
+      if let Some(checkpoint) = read_checkpoint_internal(handle, ledger).await? {
+        if actual_idx < checkpoint.get_height() {
+          return Err(LedgerStoreError::Pruned(checkpoint));
+        }
+      }
+      return Err(LedgerStoreError::LedgerError(StorageError::InvalidIndex));
+    },
+    Err(e) => {
+      return Err(e);
+    },
+  };
   let ret_block = match Block::from_bytes(&string_decode(&entry.block)?) {
     Ok(b) => b,
     Err(e) => {
@@ -703,6 +735,123 @@ async fn read_ledger_internal(
   ))
 }
 
+async fn read_checkpoint_internal(
+  handle: &str,
+  ledger: Arc<TableClient>,
+) -> Result<Option<Checkpoint>, LedgerStoreError> {
+  let entry = match find_db_entry(ledger, handle, CHECKPOINT).await {
+    Ok((entry, _etag)) => entry,
+    Err(LedgerStoreError::LedgerError(StorageError::InvalidIndex)) => {
+      return Ok(None);
+    },
+    Err(e) => {
+      return Err(e);
+    },
+  };
+
+  match Receipts::from_bytes(&string_decode(&entry.receipts)?) {
+    Ok(receipts) => Ok(Some(Checkpoint::new(
+      checked_conversion!(entry.height, usize),
+      receipts,
+    ))),
+    Err(e) => {
+      eprintln!("Unable to decode checkpoint receipts {:?}", e);
+      Err(LedgerStoreError::LedgerError(
+        StorageError::DeserializationError,
+      ))
+    },
+  }
+}
+
+async fn prune_ledger_internal(
+  handle: &str,
+  height: usize,
+  ledger: Arc<TableClient>,
+) -> Result<Checkpoint, LedgerStoreError> {
+  // 1. Check that the entry exists and carries the receipts for its height
+  let index = checked_conversion!(height, i64).to_string();
+  let (ledger_entry, _height) = read_ledger_internal(handle, Some(height), ledger.clone()).await?;
+  let checkpoint = Checkpoint::from_entry(height, &ledger_entry)?;
+
+  let base = match read_checkpoint_internal(handle, ledger.clone()).await? {
+    Some(c) => c.get_height(),
+    None => 0,
+  };
+  if base == height {
+    return Ok(checkpoint);
+  }
+
+  // 2. Copy the entry into the checkpoint row before the rows below it are deleted, so reads
+  // of a pruned index always find the checkpoint
+  let (mut entry, _etag) = find_db_entry(ledger.clone(), handle, &index).await?;
+  entry.row = CHECKPOINT.to_owned();
+
+  let partition_client = ledger.as_partition_key_client(handle);
+  let checkpoint_client = match partition_client.as_entity_client(CHECKPOINT) {
+    Ok(v) => v,
+    Err(e) => {
+      eprintln!("Error in prune_ledger: {:?}", e);
+      return Err(LedgerStoreError::LedgerError(StorageError::UnhandledError));
+    },
+  };
+  let checkpoint_write = match find_db_entry(ledger.clone(), handle, CHECKPOINT).await {
+    Ok((_entry, etag)) => checkpoint_client
+      .update()
+      .to_transaction_operation(&entry, &IfMatchCondition::Etag(etag)),
+    Err(LedgerStoreError::LedgerError(StorageError::InvalidIndex)) => {
+      ledger.insert().to_transaction_operation(&entry)
+    },
+    Err(e) => {
+      return Err(e);
+    },
+  };
+  let mut transaction = Transaction::default();
+  match checkpoint_write {
+    Ok(v) => transaction.add(v),
+    Err(e) => {
+      eprintln!("Cannot create transaction operation due to error: {:?}", e);
+      return Err(LedgerStoreError::LedgerError(StorageError::UnhandledError));
+    },
+  };
+
+  let res = partition_client
+    .submit_transaction()
+    .execute(&transaction)
+    .await;
+  if let Err(err) = res {
+    eprintln!("Error writing checkpoint in azure table: {:?}", err);
+    return Err(parse_error_status(get_error_status!(err)));
+  }
+  for r in res.unwrap().operation_responses {
+    if r.status_code.is_client_error() || r.status_code.is_server_error() {
+      return Err(parse_error_status(r.status_code));
+    }
+  }
+
+  // 3. Delete the rows below the checkpoint; rows that are already gone were deleted by an
+  // earlier, interrupted prune
+  for idx in base..height {
+    let row = checked_conversion!(idx, i64).to_string();
+    let row_client = match partition_client.as_entity_client(&row) {
+      Ok(v) => v,
+      Err(e) => {
+        eprintln!("Error in prune_ledger: {:?}", e);
+        return Err(LedgerStoreError::LedgerError(StorageError::UnhandledError));
+      },
+    };
+    if let Err(err) = row_client.delete().execute().await {
+      match parse_error_status(get_error_status!(err)) {
+        LedgerStoreError::LedgerError(StorageError::KeyDoesNotExist) => {},
+        e => {
+          return Err(e);
+        },
+      }
+    }
+  }
+
+  Ok(checkpoint)
+}
+
 async fn get_cached_entry(
   handle: &str,
   cache: &CacheMap,
@@ -930,6 +1079,117 @@ impl LedgerStore for TableLedgerStore {
     Ok(ledger_entry)
   }
 
+  async fn list_ledgers(&self) -> Result<Vec<Handle>, LedgerStoreError> {
+    // every ledger has exactly one tail row, so the partitions of the tail rows are the ledgers
+    let filter = format!("RowKey eq '{}'", TAIL);
+    let mut stream = Box::pin(
+      self
+        .client
+        .query()
+        .filter(Filter::new(&filter))
+        .stream::<DBEntryHandleProjection>(),
+    );
+
+    let mut handles = Vec::new();
+    while let Some(res) = stream.next().await {
+      let response = match res {
+        Ok(r) => r,
+        Err(err) => {
+          return Err(parse_error_status(get_error_status!(err)));
+        },
+      };
+      for entry in response.entities {
+        let handle = match NimbleDigest::from_bytes(&string_decode(&entry.handle)?) {
+          Ok(h) => h,
+          Err(_) => {
+            return Err(LedgerStoreError::LedgerError(
+              StorageError::DeserializationError,
+            ));
+          },
+        };
+        if handle != self.view_handle {
+          handles.push(handle);
+        }
+      }
+    }
+
+    Ok(handles)
+  }
+
+  async fn delete_ledger(&self, handle: &Handle) -> Result<(), LedgerStoreError> {
+    // a ledger is a partition, so every row of the partition is deleted, the tail row last
+    let handle_string = base64_url::encode(&handle.to_bytes());
+    let filter = format!("PartitionKey eq '{}'", handle_string);
+    let mut stream = Box::pin(
+      self
+        .client
+        .query()
+        .filter(Filter::new(&filter))
+        .stream::<DBEntryKeyProjection>(),
+    );
+
+    let mut rows = Vec::new();
+    while let Some(res) = stream.next().await {
+      let response = match res {
+        Ok(r) => r,
+        Err(err) => {
+          return Err(parse_error_status(get_error_status!(err)));
+        },
+      };
+      rows.extend(response.entities.into_iter().map(|entry| entry.row));
+    }
+    if rows.is_empty() {
+      return Err(LedgerStoreError::LedgerError(StorageError::KeyDoesNotExist));
+    }
+    rows.sort_by_key(|row| row == TAIL);
+
+    let partition_client = self.client.as_partition_key_client(&handle_string);
+    for row in rows {
+      let row_client = match partition_client.as_entity_client(&row) {
+        Ok(v) => v,
+        Err(e) => {
+          eprintln!("Error in delete_ledger: {:?}", e);
+          return Err(LedgerStoreError::LedgerError(StorageError::UnhandledError));
+        },
+      };
+      if let Err(err) = row_client.delete().execute().await {
+        return Err(parse_error_status(get_error_status!(err)));
+      }
+    }
+
+    if let Ok(mut cache_map) = self.cache.write() {
+      cache_map.remove(&handle_string);
+      Ok(())
+    } else {
+      Err(LedgerStoreError::LedgerError(
+        StorageError::LedgerWriteLockFailed,
+      ))
+    }
+  }
+
+  async fn prune_ledger(
+    &self,
+    handle: &Handle,
+    height: usize,
+  ) -> Result<Checkpoint, LedgerStoreError> {
+    if *handle == self.view_handle {
+      return Err(LedgerStoreError::LedgerError(StorageError::BadRequest));
+    }
+
+    let ledger = self.client.clone();
+    let handle_string = base64_url::encode(&handle.to_bytes());
+    prune_ledger_internal(&handle_string, height, ledger).await
+  }
+
+  async fn read_ledger_checkpoint(
+    &self,
+    handle: &Handle,
+  ) -> Result<Option<Checkpoint>, LedgerStoreError> {
+    let ledger = self.client.clone();
+    let handle_string = base64_url::encode(&handle.to_bytes());
+    read_checkpoint_internal(&handle_string, ledger).await
+  }
+
   async fn read_view_ledger_tail(&self) -> Result<(LedgerEntry, usize), LedgerStoreError> {
     self.read_ledger_tail(&self.view_handle).await
   }
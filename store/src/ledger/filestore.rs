@@ -1,6 +1,6 @@
 use crate::{
   errors::{LedgerStoreError, StorageError},
-  ledger::{LedgerEntry, LedgerStore},
+  ledger::{Checkpoint, LedgerEntry, LedgerStore},
 };
 use async_trait::async_trait;
 use bincode;
@@ -14,7 +14,7 @@ use std::{
   fmt::Debug,
   fs,
   fs::{File, OpenOptions},
-  io::{prelude::*, SeekFrom},
+  io::{self, prelude::*, SeekFrom},
   path::{Path, PathBuf},
   sync::{Arc, RwLock},
 };
@@ -32,7 +32,7 @@ macro_rules! checked_conversion {
   };
 }
 
-type FileLock = Arc<RwLock<File>>;
+type FileLock = Arc<RwLock<LedgerFile>>;
 type FileMap = Arc<RwLock<HashMap<Handle, FileLock>>>;
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -41,6 +41,12 @@ struct StoreEntry {
   pub receipts: Vec<u8>,
 }
 
+#[derive(Debug)]
+struct LedgerFile {
+  file: File,
+  base: usize, // the index of the entry in the first slot, which is nonzero once the ledger is pruned
+}
+
 #[derive(Debug)]
 pub struct FileStore {
   dir_path: PathBuf,
@@ -89,7 +95,7 @@ impl FileStore {
       },
     };
 
-    let file_len = match view_ledger.metadata() {
+    let file_len = match view_ledger.file.metadata() {
       Ok(m) => m.len(),
       Err(e) => {
         eprintln!("Failed to access file metadata {:?}", e);
@@ -108,7 +114,7 @@ impl FileStore {
       // Guaranteed to be the size of 1 file entry
       let ser_entry = serialize_entry(&entry)?;
 
-      write_at(SeekFrom::Start(0), &mut view_ledger, &ser_entry)?;
+      write_at(SeekFrom::Start(0), &mut view_ledger.file, &ser_entry)?;
     }
 
     let file_store = FileStore {
@@ -189,6 +195,66 @@ fn write_at(index: SeekFrom, ledger: &mut File, buf: &[u8]) -> Result<(), Ledger
   Ok(())
 }
 
+fn read_entry_at(ledger: &mut File, slot: usize) -> Result<StoreEntry, LedgerStoreError> {
+  let offset = match slot.checked_mul(ENTRY_SIZE) {
+    Some(v) => checked_conversion!(v, u64),
+    None => {
+      return Err(LedgerStoreError::LedgerError(StorageError::InvalidIndex));
+    },
+  };
+
+  let mut serialized_entry = [0; ENTRY_SIZE];
+  read_at(SeekFrom::Start(offset), ledger, &mut serialized_entry)?;
+
+  match bincode::deserialize(&serialized_entry) {
+    Ok(e) => Ok(e),
+    Err(_) => Err(LedgerStoreError::LedgerError(
+      StorageError::DeserializationError,
+    )),
+  }
+}
+
+// A pruned ledger keeps its checkpoint in the first slot, and the checkpoint's receipts carry
+// the index of that entry. The genesis entry of a ledger that was never pruned has index 0.
+fn read_base(ledger: &mut File) -> Result<usize, LedgerStoreError> {
+  let file_len = match ledger.metadata() {
+    Ok(m) => checked_conversion!(m.len(), usize),
+    Err(e) => {
+      eprintln!("Failed to access file metadata {:?}", e);
+      return Err(LedgerStoreError::LedgerError(StorageError::UnhandledError));
+    },
+  };
+  if file_len < ENTRY_SIZE {
+    return Ok(0);
+  }
+
+  let entry = read_entry_at(ledger, 0)?;
+  match Receipts::from_bytes(&entry.receipts) {
+    Ok(receipts) => Ok(receipts.get_metablock().map_or(0, |m| m.get_height())),
+    Err(_) => Err(LedgerStoreError::LedgerError(
+      StorageError::DeserializationError,
+    )),
+  }
+}
+
+// Returns the slot of the entry at index `idx`, or the checkpoint if the entry is pruned
+fn slot_of(ledger: &mut LedgerFile, idx: usize) -> Result<usize, LedgerStoreError> {
+  if idx >= ledger.base {
+    Ok(idx - ledger.base)
+  } else {
+    let entry = read_entry_at(&mut ledger.file, 0)?;
+    match Receipts::from_bytes(&entry.receipts) {
+      Ok(receipts) => Err(LedgerStoreError::Pruned(Checkpoint::new(
+        ledger.base,
+        receipts,
+      ))),
+      Err(_) => Err(LedgerStoreError::LedgerError(
+        StorageError::DeserializationError,
+      )),
+    }
+  }
+}
+
 fn open_and_lock(
   handle: &Handle,
   dir_path: &Path,
@@ -212,7 +278,7 @@ fn open_and_lock(
     // Check if the ledger exists.
     let mut options = OpenOptions::new();
     let file_name = dir_path.join(&hex::encode(&handle.to_bytes()));
-    let ledger = match options
+    let mut ledger = match options
       .read(true)
       .write(true)
       .create(create_flag)
@@ -241,7 +307,8 @@ fn open_and_lock(
       },
     };
 
-    let ledger_arc = Arc::new(RwLock::new(ledger));
+    let base = read_base(&mut ledger)?;
+    let ledger_arc = Arc::new(RwLock::new(LedgerFile { file: ledger, base }));
 
     map.insert(*handle, ledger_arc.clone());
     Ok(ledger_arc)
@@ -268,14 +335,14 @@ async fn read_ledger_op(
   // Find where to seek
   let index = match req_idx {
     Some(idx) => idx,
-    None => match ledger.metadata() {
+    None => match ledger.file.metadata() {
       Ok(m) => {
         if checked_conversion!(m.len(), usize) < ENTRY_SIZE {
           eprintln!("Trying to read an empty file");
           return Err(LedgerStoreError::LedgerError(StorageError::UnhandledError));
         }
 
-        (checked_conversion!(m.len(), usize) / ENTRY_SIZE) - 1
+        ledger.base + (checked_conversion!(m.len(), usize) / ENTRY_SIZE) - 1
       },
       Err(e) => {
         eprintln!("Failed to access file metadata {:?}", e);
@@ -284,7 +351,8 @@ async fn read_ledger_op(
     },
   };
 
-  let offset = match index.checked_mul(ENTRY_SIZE) {
+  let slot = slot_of(&mut ledger, index)?;
+  let offset = match slot.checked_mul(ENTRY_SIZE) {
     Some(v) => checked_conversion!(v, u64),
     None => {
       return Err(LedgerStoreError::LedgerError(StorageError::InvalidIndex));
@@ -292,7 +360,11 @@ async fn read_ledger_op(
   };
 
   let mut serialized_entry = [0; ENTRY_SIZE];
-  read_at(SeekFrom::Start(offset), &mut ledger, &mut serialized_entry)?;
+  read_at(
+    SeekFrom::Start(offset),
+    &mut ledger.file,
+    &mut serialized_entry,
+  )?;
 
   let entry: StoreEntry = match bincode::deserialize(&serialized_entry) {
     Ok(e) => e,
@@ -334,7 +406,7 @@ impl LedgerStore for FileStore {
     };
 
     // 2. Check if non-empty file
-    match ledger.metadata() {
+    match ledger.file.metadata() {
       Ok(m) => {
         if m.len() > 0 {
           return Err(LedgerStoreError::LedgerError(StorageError::DuplicateKey));
@@ -354,7 +426,7 @@ impl LedgerStore for FileStore {
 
     // Serialize the entry
     let ser_entry = serialize_entry(&init_entry)?;
-    write_at(SeekFrom::Start(0), &mut ledger, &ser_entry)?;
+    write_at(SeekFrom::Start(0), &mut ledger.file, &ser_entry)?;
 
     Ok(())
   }
@@ -376,8 +448,8 @@ impl LedgerStore for FileStore {
       },
     };
 
-    let next_index = match ledger.metadata() {
-      Ok(m) => checked_conversion!(m.len(), usize) / ENTRY_SIZE,
+    let next_index = match ledger.file.metadata() {
+      Ok(m) => ledger.base + checked_conversion!(m.len(), usize) / ENTRY_SIZE,
       Err(e) => {
         eprintln!("Failed to access file metadata {:?}", e);
         return Err(LedgerStoreError::LedgerError(StorageError::UnhandledError));
@@ -404,7 +476,7 @@ impl LedgerStore for FileStore {
 
     let ser_entry = serialize_entry(&new_entry)?;
 
-    write_at(SeekFrom::End(0), &mut ledger, &ser_entry)?;
+    write_at(SeekFrom::End(0), &mut ledger.file, &ser_entry)?;
     Ok((next_index, Nonces::new()))
   }
 
@@ -423,14 +495,6 @@ impl LedgerStore for FileStore {
     idx: usize,
     receipts: &Receipts,
   ) -> Result<(), LedgerStoreError> {
-    // 1. Get the desired offset
-    let offset = match idx.checked_mul(ENTRY_SIZE) {
-      Some(v) => checked_conversion!(v, u64),
-      None => {
-        return Err(LedgerStoreError::LedgerError(StorageError::InvalidIndex));
-      },
-    };
-
     let ledger_lock = open_and_lock(handle, &self.dir_path, &self.open_files, false)?;
 
     let mut ledger = match ledger_lock.write() {
@@ -442,11 +506,20 @@ impl LedgerStore for FileStore {
       },
     };
 
+    // 1. Get the desired offset
+    let slot = slot_of(&mut ledger, idx)?;
+    let offset = match slot.checked_mul(ENTRY_SIZE) {
+      Some(v) => checked_conversion!(v, u64),
+      None => {
+        return Err(LedgerStoreError::LedgerError(StorageError::InvalidIndex));
+      },
+    };
+
     let seek_from = SeekFrom::Start(offset);
 
     // 2. Find the appropriate entry in the ledger
     let mut serialized_entry = [0; ENTRY_SIZE];
-    read_at(seek_from, &mut ledger, &mut serialized_entry)?;
+    read_at(seek_from, &mut ledger.file, &mut serialized_entry)?;
 
     let mut ledger_entry: StoreEntry = match bincode::deserialize(&serialized_entry) {
       Ok(e) => e,
@@ -469,7 +542,7 @@ impl LedgerStore for FileStore {
     let ser_entry = serialize_entry(&ledger_entry)?;
 
     // 6. Update entry
-    write_at(seek_from, &mut ledger, &ser_entry)?;
+    write_at(seek_from, &mut ledger.file, &ser_entry)?;
 
     Ok(())
   }
@@ -511,6 +584,178 @@ impl LedgerStore for FileStore {
       .await
   }
 
+  async fn list_ledgers(&self) -> Result<Vec<Handle>, LedgerStoreError> {
+    let dir = match fs::read_dir(&self.dir_path) {
+      Ok(d) => d,
+      Err(e) => {
+        eprintln!("Unable to read path {:?}, error: {:?}", &self.dir_path, e);
+        return Err(LedgerStoreError::LedgerError(StorageError::UnhandledError));
+      },
+    };
+
+    // every ledger is a file named after the hex encoding of its handle
+    let mut handles = Vec::new();
+    for entry in dir.flatten() {
+      let file_name = entry.file_name();
+      let handle_bytes = match file_name.to_str().map(hex::decode) {
+        Some(Ok(b)) => b,
+        _ => continue,
+      };
+      if let Ok(handle) = NimbleDigest::from_bytes(&handle_bytes) {
+        if handle != self.view_handle {
+          handles.push(handle);
+        }
+      }
+    }
+
+    Ok(handles)
+  }
+
+  async fn delete_ledger(&self, handle: &Handle) -> Result<(), LedgerStoreError> {
+    // close the file, which also releases its lock, before removing it
+    match self.open_files.write() {
+      Ok(mut map) => {
+        map.remove(handle);
+      },
+      Err(_) => {
+        return Err(LedgerStoreError::LedgerError(
+          StorageError::LedgerWriteLockFailed,
+        ));
+      },
+    };
+
+    let file_name = self.dir_path.join(hex::encode(handle.to_bytes()));
+    match fs::remove_file(&file_name) {
+      Ok(_) => Ok(()),
+      Err(e) => {
+        eprintln!(
+          "Unable to remove ledger file {:?}, error: {:?}",
+          file_name, e
+        );
+        Err(LedgerStoreError::LedgerError(StorageError::InvalidKey))
+      },
+    }
+  }
+
+  async fn prune_ledger(
+    &self,
+    handle: &Handle,
+    height: usize,
+  ) -> Result<Checkpoint, LedgerStoreError> {
+    if *handle == self.view_handle {
+      return Err(LedgerStoreError::LedgerError(StorageError::BadRequest));
+    }
+
+    let ledger_lock = open_and_lock(handle, &self.dir_path, &self.open_files, false)?;
+
+    let mut ledger = match ledger_lock.write() {
+      Ok(v) => v,
+      Err(_) => {
+        return Err(LedgerStoreError::LedgerError(
+          StorageError::LedgerWriteLockFailed,
+        ));
+      },
+    };
+
+    // 1. Check that the entry exists and carries the receipts for its height
+    let slot = slot_of(&mut ledger, height)?;
+    let file_len = match ledger.file.metadata() {
+      Ok(m) => checked_conversion!(m.len(), usize),
+      Err(e) => {
+        eprintln!("Failed to access file metadata {:?}", e);
+        return Err(LedgerStoreError::LedgerError(StorageError::UnhandledError));
+      },
+    };
+    if slot >= file_len / ENTRY_SIZE {
+      return Err(LedgerStoreError::LedgerError(StorageError::InvalidIndex));
+    }
+
+    let entry = read_entry_at(&mut ledger.file, slot)?;
+    let ledger_entry = match (
+      Block::from_bytes(&entry.block),
+      Receipts::from_bytes(&entry.receipts),
+    ) {
+      (Ok(block), Ok(receipts)) => LedgerEntry::new(block, receipts, None),
+      _ => {
+        return Err(LedgerStoreError::LedgerError(
+          StorageError::DeserializationError,
+        ));
+      },
+    };
+    let checkpoint = Checkpoint::from_entry(height, &ledger_entry)?;
+    if slot == 0 {
+      return Ok(checkpoint);
+    }
+
+    // 2. Copy the entries from the checkpoint onward into a new file and swap it in, so a crash
+    // leaves either the old or the pruned ledger behind
+    let file_name = self.dir_path.join(hex::encode(handle.to_bytes()));
+    let pruned_name = file_name.with_extension("prune");
+    let copied = OpenOptions::new()
+      .write(true)
+      .create(true)
+      .truncate(true)
+      .open(&pruned_name)
+      .and_then(|mut pruned| {
+        ledger
+          .file
+          .seek(SeekFrom::Start((slot * ENTRY_SIZE) as u64))?;
+        io::copy(&mut ledger.file, &mut pruned)?;
+        pruned.sync_all()
+      })
+      .and_then(|_| fs::rename(&pruned_name, &file_name));
+    if let Err(e) = copied {
+      eprintln!(
+        "Unable to prune ledger file {:?}, error: {:?}",
+        file_name, e
+      );
+      return Err(LedgerStoreError::LedgerError(StorageError::UnhandledError));
+    }
+
+    // 3. Reopen the ledger, since the old file handle still refers to the unpruned file
+    let file = match OpenOptions::new().read(true).write(true).open(&file_name) {
+      Ok(f) => f,
+      Err(e) => {
+        eprintln!("Error opening ledger file {:?}", e);
+        return Err(LedgerStoreError::LedgerError(StorageError::UnhandledError));
+      },
+    };
+    if file.try_lock_exclusive().is_err() {
+      return Err(LedgerStoreError::LedgerError(
+        StorageError::LedgerWriteLockFailed,
+      ));
+    }
+    ledger.file = file;
+    ledger.base = height;
+
+    Ok(checkpoint)
+  }
+
+  async fn read_ledger_checkpoint(
+    &self,
+    handle: &Handle,
+  ) -> Result<Option<Checkpoint>, LedgerStoreError> {
+    let ledger_lock = open_and_lock(handle, &self.dir_path, &self.open_files, false)?;
+
+    let mut ledger = match ledger_lock.write() {
+      Ok(v) => v,
+      Err(_) => {
+        return Err(LedgerStoreError::LedgerError(
+          StorageError::LedgerWriteLockFailed,
+        ));
+      },
+    };
+
+    if ledger.base == 0 {
+      return Ok(None);
+    }
+    match slot_of(&mut ledger, 0) {
+      Err(LedgerStoreError::Pruned(checkpoint)) => Ok(Some(checkpoint)),
+      Err(e) => Err(e),
+      Ok(_) => Ok(None),
+    }
+  }
+
   async fn append_view_ledger(
     &self,
     block: &Block,
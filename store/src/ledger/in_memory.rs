@@ -1,7 +1,7 @@
 use super::{Block, Handle, NimbleDigest, Nonce, Nonces, Receipts};
 use crate::{
   errors::{LedgerStoreError, StorageError},
-  ledger::{LedgerEntry, LedgerStore},
+  ledger::{Checkpoint, LedgerEntry, LedgerStore},
 };
 use async_trait::async_trait;
 use std::{
@@ -9,9 +9,50 @@ use std::{
   sync::{Arc, RwLock},
 };
 
-type LedgerArray = Arc<RwLock<Vec<LedgerEntry>>>;
+type LedgerArray = Arc<RwLock<InMemoryLedger>>;
 type NonceArray = Arc<RwLock<Vec<Nonce>>>;
 
+#[derive(Debug, Default)]
+struct InMemoryLedger {
+  base: usize, // the index of the first entry, which is nonzero once the ledger is pruned
+  entries: Vec<LedgerEntry>,
+}
+
+impl InMemoryLedger {
+  fn new(genesis_entry: LedgerEntry) -> Self {
+    InMemoryLedger {
+      base: 0,
+      entries: vec![genesis_entry],
+    }
+  }
+
+  // the index at which the next entry is appended
+  fn next_index(&self) -> usize {
+    self.base + self.entries.len()
+  }
+
+  fn checkpoint(&self) -> Option<Checkpoint> {
+    if self.base > 0 {
+      Some(Checkpoint::new(
+        self.base,
+        self.entries[0].get_receipts().clone(),
+      ))
+    } else {
+      None
+    }
+  }
+
+  fn position(&self, idx: usize) -> Result<usize, LedgerStoreError> {
+    if idx < self.base {
+      Err(LedgerStoreError::Pruned(self.checkpoint().unwrap()))
+    } else if idx < self.next_index() {
+      Ok(idx - self.base)
+    } else {
+      Err(LedgerStoreError::LedgerError(StorageError::InvalidIndex))
+    }
+  }
+}
+
 #[derive(Debug, Default)]
 pub struct InMemoryLedgerStore {
   ledgers: Arc<RwLock<HashMap<Handle, LedgerArray>>>,
@@ -67,7 +108,9 @@ impl LedgerStore for InMemoryLedgerStore {
     if let Ok(mut ledgers_map) = self.ledgers.write() {
       if let Ok(mut nonce_map) = self.nonces.write() {
         if let hash_map::Entry::Vacant(e) = ledgers_map.entry(*handle) {
-          e.insert(Arc::new(RwLock::new(vec![genesis_ledger_entry])));
+          e.insert(Arc::new(RwLock::new(InMemoryLedger::new(
+            genesis_ledger_entry,
+          ))));
 
           if let hash_map::Entry::Vacant(n) = nonce_map.entry(*handle) {
             n.insert(Arc::new(RwLock::new(Vec::new())));
@@ -99,7 +142,7 @@ impl LedgerStore for InMemoryLedgerStore {
     if let Ok(ledgers_map) = self.ledgers.read() {
       if ledgers_map.contains_key(handle) {
         if let Ok(mut ledgers) = ledgers_map[handle].write() {
-          if expected_height == ledgers.len() {
+          if expected_height == ledgers.next_index() {
             let nonces = self.drain_nonces(handle)?;
 
             let ledger_entry = LedgerEntry {
@@ -107,9 +150,9 @@ impl LedgerStore for InMemoryLedgerStore {
               receipts: Receipts::new(),
               nonces: nonces.clone(),
             };
-            ledgers.push(ledger_entry);
+            ledgers.entries.push(ledger_entry);
 
-            Ok(((ledgers.len() - 1), nonces))
+            Ok(((ledgers.next_index() - 1), nonces))
           } else {
             Err(LedgerStoreError::LedgerError(
               StorageError::IncorrectConditionalData,
@@ -140,13 +183,9 @@ impl LedgerStore for InMemoryLedgerStore {
     if let Ok(ledgers_map) = self.ledgers.read() {
       if ledgers_map.contains_key(handle) {
         if let Ok(mut ledgers) = ledgers_map[handle].write() {
-          let height = idx;
-          if height < ledgers.len() {
-            ledgers[height].receipts.merge_receipts(receipts);
-            Ok(())
-          } else {
-            Err(LedgerStoreError::LedgerError(StorageError::InvalidIndex))
-          }
+          let pos = ledgers.position(idx)?;
+          ledgers.entries[pos].receipts.merge_receipts(receipts);
+          Ok(())
         } else {
           Err(LedgerStoreError::LedgerError(
             StorageError::LedgerWriteLockFailed,
@@ -170,7 +209,7 @@ impl LedgerStore for InMemoryLedgerStore {
     if let Ok(ledgers_map) = self.ledgers.read() {
       if ledgers_map.contains_key(handle) {
         if let Ok(ledgers) = ledgers_map[handle].read() {
-          let height = ledgers.len();
+          let height = ledgers.next_index();
 
           if let Ok(nonce_map) = self.nonces.read() {
             if nonce_map.contains_key(handle) {
@@ -214,8 +253,8 @@ impl LedgerStore for InMemoryLedgerStore {
     if let Ok(ledgers_map) = self.ledgers.read() {
       if ledgers_map.contains_key(handle) {
         if let Ok(ledgers) = ledgers_map[handle].read() {
-          let ledgers_entry = ledgers[ledgers.len() - 1].clone();
-          Ok((ledgers_entry, ledgers.len() - 1))
+          let ledgers_entry = ledgers.entries[ledgers.entries.len() - 1].clone();
+          Ok((ledgers_entry, ledgers.next_index() - 1))
         } else {
           Err(LedgerStoreError::LedgerError(
             StorageError::LedgerReadLockFailed,
@@ -239,11 +278,91 @@ impl LedgerStore for InMemoryLedgerStore {
     if let Ok(ledgers_map) = self.ledgers.read() {
       if ledgers_map.contains_key(handle) {
         if let Ok(ledgers) = ledgers_map[handle].read() {
-          if idx < ledgers.len() {
-            Ok(ledgers[idx].clone())
-          } else {
-            Err(LedgerStoreError::LedgerError(StorageError::InvalidIndex))
-          }
+          let pos = ledgers.position(idx)?;
+          Ok(ledgers.entries[pos].clone())
+        } else {
+          Err(LedgerStoreError::LedgerError(
+            StorageError::LedgerReadLockFailed,
+          ))
+        }
+      } else {
+        Err(LedgerStoreError::LedgerError(StorageError::KeyDoesNotExist))
+      }
+    } else {
+      Err(LedgerStoreError::LedgerError(
+        StorageError::LedgerMapReadLockFailed,
+      ))
+    }
+  }
+
+  async fn list_ledgers(&self) -> Result<Vec<Handle>, LedgerStoreError> {
+    if let Ok(ledgers_map) = self.ledgers.read() {
+      Ok(ledgers_map.keys().cloned().collect())
+    } else {
+      Err(LedgerStoreError::LedgerError(
+        StorageError::LedgerMapReadLockFailed,
+      ))
+    }
+  }
+
+  async fn delete_ledger(&self, handle: &Handle) -> Result<(), LedgerStoreError> {
+    if let Ok(mut nonce_map) = self.nonces.write() {
+      nonce_map.remove(handle);
+    } else {
+      return Err(LedgerStoreError::LedgerError(
+        StorageError::LedgerMapWriteLockFailed,
+      ));
+    }
+
+    if let Ok(mut ledgers_map) = self.ledgers.write() {
+      if ledgers_map.remove(handle).is_some() {
+        Ok(())
+      } else {
+        Err(LedgerStoreError::LedgerError(StorageError::KeyDoesNotExist))
+      }
+    } else {
+      Err(LedgerStoreError::LedgerError(
+        StorageError::LedgerMapWriteLockFailed,
+      ))
+    }
+  }
+
+  async fn prune_ledger(
+    &self,
+    handle: &Handle,
+    height: usize,
+  ) -> Result<Checkpoint, LedgerStoreError> {
+    if let Ok(ledgers_map) = self.ledgers.read() {
+      if ledgers_map.contains_key(handle) {
+        if let Ok(mut ledgers) = ledgers_map[handle].write() {
+          let pos = ledgers.position(height)?;
+          let checkpoint = Checkpoint::from_entry(height, &ledgers.entries[pos])?;
+          ledgers.entries.drain(..pos);
+          ledgers.base = height;
+          Ok(checkpoint)
+        } else {
+          Err(LedgerStoreError::LedgerError(
+            StorageError::LedgerWriteLockFailed,
+          ))
+        }
+      } else {
+        Err(LedgerStoreError::LedgerError(StorageError::KeyDoesNotExist))
+      }
+    } else {
+      Err(LedgerStoreError::LedgerError(
+        StorageError::LedgerMapReadLockFailed,
+      ))
+    }
+  }
+
+  async fn read_ledger_checkpoint(
+    &self,
+    handle: &Handle,
+  ) -> Result<Option<Checkpoint>, LedgerStoreError> {
+    if let Ok(ledgers_map) = self.ledgers.read() {
+      if ledgers_map.contains_key(handle) {
+        if let Ok(ledgers) = ledgers_map[handle].read() {
+          Ok(ledgers.checkpoint())
         } else {
           Err(LedgerStoreError::LedgerError(
             StorageError::LedgerReadLockFailed,
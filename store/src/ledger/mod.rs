@@ -6,7 +6,7 @@ pub mod filestore;
 pub mod in_memory;
 pub mod mongodb_cosmos;
 
-use crate::errors::LedgerStoreError;
+use crate::errors::{LedgerStoreError, StorageError};
 
 #[derive(Debug, Default, Clone)]
 pub struct LedgerEntry {
@@ -45,6 +45,41 @@ impl LedgerEntry {
   }
 }
 
+/// The endorsed entry at which a pruned ledger now starts: the receipts of the
+/// entry at `height`, which endorse the metablock at that height
+#[derive(Debug, Default, Clone)]
+pub struct Checkpoint {
+  height: usize,
+  receipts: Receipts,
+}
+
+impl Checkpoint {
+  pub fn new(height: usize, receipts: Receipts) -> Self {
+    Self { height, receipts }
+  }
+
+  pub fn get_height(&self) -> usize {
+    self.height
+  }
+
+  pub fn get_receipts(&self) -> &Receipts {
+    &self.receipts
+  }
+
+  /// Builds the checkpoint at `height` from the entry stored there, which must carry receipts
+  /// for a metablock at that height
+  pub(crate) fn from_entry(height: usize, entry: &LedgerEntry) -> Result<Self, LedgerStoreError> {
+    match entry.get_receipts().get_metablock() {
+      Ok(metablock) if metablock.get_height() == height => {
+        Ok(Checkpoint::new(height, entry.get_receipts().clone()))
+      },
+      _ => Err(LedgerStoreError::LedgerError(
+        StorageError::InvalidCheckpoint,
+      )),
+    }
+  }
+}
+
 #[async_trait]
 pub trait LedgerStore {
   async fn create_ledger(
@@ -78,6 +113,17 @@ pub trait LedgerStore {
     handle: &Handle,
     idx: usize,
   ) -> Result<LedgerEntry, LedgerStoreError>;
+  async fn list_ledgers(&self) -> Result<Vec<Handle>, LedgerStoreError>; // excludes the view ledger
+  async fn delete_ledger(&self, handle: &Handle) -> Result<(), LedgerStoreError>; // drops every entry
+  async fn prune_ledger(
+    &self,
+    handle: &Handle,
+    height: usize,
+  ) -> Result<Checkpoint, LedgerStoreError>; // drops the entries below height
+  async fn read_ledger_checkpoint(
+    &self,
+    handle: &Handle,
+  ) -> Result<Option<Checkpoint>, LedgerStoreError>;
   async fn append_view_ledger(
     &self,
     block: &Block,
@@ -145,6 +191,20 @@ mod tests {
     let data_at_index = res.unwrap();
     assert_eq!(data_at_index.block.to_bytes(), initial_value);
 
+    let res = state.list_ledgers().await;
+    assert!(res.is_ok());
+    assert!(res.unwrap().contains(&handle));
+
+    let res = state.delete_ledger(&handle).await;
+    assert!(res.is_ok());
+
+    let res = state.read_ledger_tail(&handle).await;
+    assert!(res.is_err());
+
+    let res = state.list_ledgers().await;
+    assert!(res.is_ok());
+    assert!(!res.unwrap().contains(&handle));
+
     let res = state.reset_store().await;
     assert!(res.is_ok());
   }
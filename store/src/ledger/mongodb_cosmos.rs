@@ -1,6 +1,6 @@
 use crate::{
   errors::{LedgerStoreError, StorageError},
-  ledger::{LedgerEntry, LedgerStore},
+  ledger::{Checkpoint, LedgerEntry, LedgerStore},
 };
 use async_trait::async_trait;
 use bincode;
@@ -9,6 +9,7 @@ use ledger::{Block, CustomSerde, Handle, NimbleDigest, Nonce, Nonces, Receipts};
 use mongodb::{
   bson::{doc, spec::BinarySubtype, Binary},
   error::WriteFailure::WriteError,
+  options::FindOneOptions,
   Client, Collection,
 };
 use serde::{Deserialize, Serialize};
@@ -388,6 +389,12 @@ async fn read_ledger_op(
 
   let ledger_entry = match res.unwrap() {
     None => {
+      // the entry may have been pruned
+      if let Some(checkpoint) = read_checkpoint_op(ledger).await? {
+        if index < checked_conversion!(checkpoint.get_height(), i64) {
+          return Err(LedgerStoreError::Pruned(checkpoint));
+        }
+      }
       return Err(LedgerStoreError::LedgerError(StorageError::KeyDoesNotExist));
     },
     Some(s) => s,
@@ -407,6 +414,54 @@ async fn read_ledger_op(
   Ok((res, checked_conversion!(index, usize)))
 }
 
+async fn prune_ledger_op(
+  height: usize,
+  ledger: &Collection<DBEntry>,
+) -> Result<Checkpoint, LedgerStoreError> {
+  // 1. Check that the entry exists and carries the receipts for its height
+  let (entry, _height) = match read_ledger_op(Some(height), ledger).await {
+    Err(LedgerStoreError::LedgerError(StorageError::KeyDoesNotExist)) => {
+      return Err(LedgerStoreError::LedgerError(StorageError::InvalidIndex));
+    },
+    res => res?,
+  };
+  let checkpoint = Checkpoint::from_entry(height, &entry)?;
+
+  // 2. Drop the entries below it; the first remaining entry is the checkpoint
+  ledger
+    .delete_many(
+      doc! {
+          "_id": { "$lt": checked_conversion!(height, i64) },
+      },
+      None,
+    )
+    .await?;
+
+  Ok(checkpoint)
+}
+
+async fn read_checkpoint_op(
+  ledger: &Collection<DBEntry>,
+) -> Result<Option<Checkpoint>, LedgerStoreError> {
+  let base = find_ledger_base(ledger).await?;
+  if base == 0 {
+    return Ok(None);
+  }
+
+  let db_entry = find_db_entry(ledger, base).await?;
+  let entry: SerializedLedgerEntry =
+    bincode::deserialize(&db_entry.value.bytes).expect("failed to deserialize entry");
+  match Receipts::from_bytes(&entry.receipts) {
+    Ok(receipts) => Ok(Some(Checkpoint::new(
+      checked_conversion!(base, usize),
+      receipts,
+    ))),
+    Err(_) => Err(LedgerStoreError::LedgerError(
+      StorageError::DeserializationError,
+    )),
+  }
+}
+
 async fn get_cached_height(
   handle: &Handle,
   cache: &CacheMap,
@@ -497,14 +552,23 @@ async fn find_ledger_height(ledger: &Collection<DBEntry>) -> Result<i64, LedgerS
   // In contrast, count_documents returns an accurate count but requires scanning all docs.
   let count = checked_conversion!(ledger.estimated_document_count(None).await?, i64);
 
-  // The height or offset is count - 1 since we index from 0.
+  // The height or offset is count - 1 since we index from the first entry, which is 0
+  // unless the ledger was pruned.
   if count > 0 {
-    Ok(count - 1)
+    Ok(find_ledger_base(ledger).await? + count - 1)
   } else {
     Err(LedgerStoreError::LedgerError(StorageError::KeyDoesNotExist))
   }
 }
 
+async fn find_ledger_base(ledger: &Collection<DBEntry>) -> Result<i64, LedgerStoreError> {
+  let options = FindOneOptions::builder().sort(doc! { "_id": 1 }).build();
+  match ledger.find_one(None, options).await? {
+    Some(db_entry) => Ok(db_entry.index),
+    None => Err(LedgerStoreError::LedgerError(StorageError::KeyDoesNotExist)),
+  }
+}
+
 async fn loop_and_read(
   handle: &Handle,
   index: Option<usize>,
@@ -620,6 +684,85 @@ impl LedgerStore for MongoCosmosLedgerStore {
     Ok(entry)
   }
 
+  async fn list_ledgers(&self) -> Result<Vec<Handle>, LedgerStoreError> {
+    let client = self.client.clone();
+    let names = client
+      .database(&self.dbname)
+      .list_collection_names(None)
+      .await?;
+
+    // every ledger is a collection named after the hex encoding of its handle
+    Ok(
+      names
+        .iter()
+        .filter_map(|name| hex::decode(name).ok())
+        .filter_map(|bytes| NimbleDigest::from_bytes(&bytes).ok())
+        .filter(|handle| *handle != self.view_handle)
+        .collect(),
+    )
+  }
+
+  async fn delete_ledger(&self, handle: &Handle) -> Result<(), LedgerStoreError> {
+    let client = self.client.clone();
+    client
+      .database(&self.dbname)
+      .collection::<DBEntry>(&hex::encode(handle.to_bytes()))
+      .drop(None)
+      .await?;
+
+    if let Ok(mut cache_map) = self.cache.write() {
+      cache_map.remove(handle);
+      Ok(())
+    } else {
+      Err(LedgerStoreError::LedgerError(
+        StorageError::LedgerWriteLockFailed,
+      ))
+    }
+  }
+
+  async fn prune_ledger(
+    &self,
+    handle: &Handle,
+    height: usize,
+  ) -> Result<Checkpoint, LedgerStoreError> {
+    if *handle == self.view_handle {
+      return Err(LedgerStoreError::LedgerError(StorageError::BadRequest));
+    }
+
+    let client = self.client.clone();
+    let ledger = client
+      .database(&self.dbname)
+      .collection::<DBEntry>(&hex::encode(handle.to_bytes()));
+
+    loop {
+      with_retry!(
+        prune_ledger_op(height, &ledger).await,
+        handle,
+        &self.cache,
+        &ledger
+      );
+    }
+  }
+
+  async fn read_ledger_checkpoint(
+    &self,
+    handle: &Handle,
+  ) -> Result<Option<Checkpoint>, LedgerStoreError> {
+    let client = self.client.clone();
+    let ledger = client
+      .database(&self.dbname)
+      .collection::<DBEntry>(&hex::encode(handle.to_bytes()));
+
+    loop {
+      with_retry!(
+        read_checkpoint_op(&ledger).await,
+        handle,
+        &self.cache,
+        &ledger
+      );
+    }
+  }
+
   async fn read_view_ledger_tail(&self) -> Result<(LedgerEntry, usize), LedgerStoreError> {
     self.read_ledger_tail(&self.view_handle).await
   }
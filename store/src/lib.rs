@@ -1,3 +1,5 @@
+pub mod audit;
 pub mod content;
 pub mod errors;
 pub mod ledger;
+pub mod migrate;
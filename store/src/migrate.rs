@@ -0,0 +1,692 @@
+use crate::{
+  audit::{check_view_entries, AuditFinding, ChainChecker},
+  errors::{LedgerStoreError, StorageError},
+  ledger::{LedgerEntry, LedgerStore},
+};
+use ledger::{
+  Block, CustomSerde, Handle, NimbleDigest, Nonce, Nonces, Receipt, Receipts, VerifierState,
+};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek, SeekFrom, Write};
+
+/// The version of the export format written by `export_store`
+pub const EXPORT_VERSION: u8 = 1;
+
+// An export is the version byte followed by records, each of which is a little-endian u64
+// length and the bincode encoding of an `ExportRecord`. The view ledger comes first, then each
+// ledger as a header followed by its entries in order, and finally a digest of every record.
+// The header of a pruned ledger is followed by its checkpoint, and its entries start there.
+#[derive(Debug, Serialize, Deserialize)]
+enum ExportRecord {
+  ViewEntry {
+    block: Vec<u8>,
+    receipts: Vec<u8>,
+  },
+  Ledger {
+    handle: Vec<u8>,
+    height: usize,
+  },
+  LedgerEntry {
+    block: Vec<u8>,
+    receipts: Vec<u8>,
+    nonces: Vec<u8>,
+  },
+  End {
+    digest: Vec<u8>,
+  },
+  Checkpoint {
+    height: usize,
+    receipts: Vec<u8>,
+  },
+}
+
+/// What was exported, verified, or imported
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MigrationSummary {
+  pub view_entries: usize,
+  pub ledgers: usize,
+  pub entries: usize,
+}
+
+struct RecordWriter<'a> {
+  writer: &'a mut dyn Write,
+  digest: NimbleDigest,
+}
+
+impl<'a> RecordWriter<'a> {
+  fn write(&mut self, record: &ExportRecord) -> Result<(), LedgerStoreError> {
+    let bytes = bincode::serialize(record).map_err(|e| {
+      eprintln!("Failed to serialize an export record ({:?})", e);
+      LedgerStoreError::LedgerError(StorageError::SerializationError)
+    })?;
+    self.digest = self.digest.digest_with_bytes(&bytes);
+    let res = self
+      .writer
+      .write_all(&(bytes.len() as u64).to_le_bytes())
+      .and_then(|_| self.writer.write_all(&bytes));
+    if let Err(e) = res {
+      eprintln!("Failed to write the export ({:?})", e);
+      return Err(LedgerStoreError::LedgerError(StorageError::UnhandledError));
+    }
+    Ok(())
+  }
+}
+
+struct RecordReader<'a> {
+  reader: &'a mut dyn Read,
+  digest: NimbleDigest,
+}
+
+impl<'a> RecordReader<'a> {
+  // returns the record and the digest of the records that precede it
+  fn read(&mut self) -> Result<(ExportRecord, NimbleDigest), LedgerStoreError> {
+    let mut len = [0u8; 8];
+    let res = self.reader.read_exact(&mut len);
+    if let Err(e) = res {
+      eprintln!("Failed to read the length of an export record ({:?})", e);
+      return Err(LedgerStoreError::LedgerError(StorageError::InvalidExport));
+    }
+    let len = u64::from_le_bytes(len);
+    let mut bytes = Vec::new();
+    let res = (&mut *self.reader).take(len).read_to_end(&mut bytes);
+    if res.is_err() || bytes.len() as u64 != len {
+      eprintln!("The export ends in the middle of a record");
+      return Err(LedgerStoreError::LedgerError(StorageError::InvalidExport));
+    }
+    let record = bincode::deserialize(&bytes).map_err(|e| {
+      eprintln!("Failed to deserialize an export record ({:?})", e);
+      LedgerStoreError::LedgerError(StorageError::InvalidExport)
+    })?;
+    let preceding = self.digest;
+    self.digest = self.digest.digest_with_bytes(&bytes);
+    Ok((record, preceding))
+  }
+}
+
+/// Writes the view ledger and every ledger in a store, with their receipts and nonces, to
+/// `writer` in a format that `import_store` accepts.
+///
+/// The export is a consistent cut of a store that may be in use: the height of every ledger is
+/// fixed before any entry is written, and a tail entry whose receipts have not been attached
+/// yet is left out, as are ledgers created after the export starts. A ledger that was pruned
+/// is exported from its checkpoint.
+///
+/// # Arguments
+///
+/// * `ledger_store` - The store to export.
+/// * `writer` - The destination of the export.
+///
+/// # Returns
+///
+/// A summary of what was exported, or a `LedgerStoreError` if the store cannot be read.
+pub async fn export_store(
+  ledger_store: &dyn LedgerStore,
+  writer: &mut dyn Write,
+) -> Result<MigrationSummary, LedgerStoreError> {
+  let res = writer.write_all(&[EXPORT_VERSION]);
+  if let Err(e) = res {
+    eprintln!("Failed to write the export ({:?})", e);
+    return Err(LedgerStoreError::LedgerError(StorageError::UnhandledError));
+  }
+  let mut writer = RecordWriter {
+    writer,
+    digest: NimbleDigest::default(),
+  };
+  let mut summary = MigrationSummary::default();
+
+  let (view_tail, mut view_height) = ledger_store.read_view_ledger_tail().await?;
+  if view_height > 0 && view_tail.get_receipts().is_empty() {
+    view_height -= 1;
+  }
+  let mut handles = ledger_store.list_ledgers().await?;
+  handles.sort();
+  let mut cut = Vec::new();
+  for handle in handles {
+    let checkpoint = ledger_store.read_ledger_checkpoint(&handle).await?;
+    let (tail, height) = ledger_store.read_ledger_tail(&handle).await?;
+    // the entry at the checkpoint has its receipts, so the cut never goes below it
+    if !tail.get_receipts().is_empty() {
+      cut.push((handle, height, checkpoint));
+    } else if height > 0 {
+      cut.push((handle, height - 1, checkpoint));
+    }
+  }
+
+  for idx in 1..=view_height {
+    let entry = ledger_store.read_view_ledger_by_index(idx).await?;
+    writer.write(&ExportRecord::ViewEntry {
+      block: entry.get_block().to_bytes(),
+      receipts: entry.get_receipts().to_bytes(),
+    })?;
+    summary.view_entries += 1;
+  }
+
+  for (handle, height, checkpoint) in cut {
+    writer.write(&ExportRecord::Ledger {
+      handle: handle.to_bytes(),
+      height,
+    })?;
+    let first_idx = match checkpoint {
+      Some(checkpoint) => {
+        writer.write(&ExportRecord::Checkpoint {
+          height: checkpoint.get_height(),
+          receipts: checkpoint.get_receipts().to_bytes(),
+        })?;
+        checkpoint.get_height()
+      },
+      None => 0,
+    };
+    for idx in first_idx..=height {
+      let entry = ledger_store.read_ledger_by_index(&handle, idx).await?;
+      writer.write(&ExportRecord::LedgerEntry {
+        block: entry.get_block().to_bytes(),
+        receipts: entry.get_receipts().to_bytes(),
+        nonces: entry.get_nonces().to_bytes(),
+      })?;
+      summary.entries += 1;
+    }
+    summary.ledgers += 1;
+  }
+
+  let digest = writer.digest.to_bytes();
+  writer.write(&ExportRecord::End { digest })?;
+  Ok(summary)
+}
+
+/// Reads an export and verifies its integrity and the hash chains of every ledger in it,
+/// without writing anything.
+///
+/// # Arguments
+///
+/// * `reader` - The export, as written by `export_store`.
+/// * `attestations` - The attestation reports that vouch for the latest view in the export.
+///
+/// # Returns
+///
+/// A summary of the export, or a `LedgerStoreError` if it is malformed or fails verification.
+pub async fn verify_export(
+  reader: &mut dyn Read,
+  attestations: &[u8],
+) -> Result<MigrationSummary, LedgerStoreError> {
+  replay(reader, attestations, None).await
+}
+
+/// Imports an export into an empty store. The whole export is verified before anything is
+/// written, and every entry is then checked to be returned by the store exactly as written.
+///
+/// # Arguments
+///
+/// * `ledger_store` - The store to import into; it must not contain any views or ledgers.
+/// * `reader` - The export, as written by `export_store`. It is read twice, from its current
+///   position, so that nothing is written from an export that turns out to be bad.
+/// * `attestations` - The attestation reports that vouch for the latest view in the export.
+///
+/// # Returns
+///
+/// A summary of what was imported, or a `LedgerStoreError` if the export is malformed, fails
+/// verification, or differs from what the store returns. An export that fails verification
+/// leaves the store untouched, while a store that fails part way keeps the entries that were
+/// already written.
+pub async fn import_store<R: Read + Seek>(
+  ledger_store: &dyn LedgerStore,
+  reader: &mut R,
+  attestations: &[u8],
+) -> Result<MigrationSummary, LedgerStoreError> {
+  let (_tail, view_height) = ledger_store.read_view_ledger_tail().await?;
+  if view_height != 0 || !ledger_store.list_ledgers().await?.is_empty() {
+    eprintln!("The store to import into is not empty");
+    return Err(LedgerStoreError::LedgerError(StorageError::StoreNotEmpty));
+  }
+  // the first pass verifies all of the export, including its digest, and the second writes it
+  let start = reader.stream_position().map_err(|e| {
+    eprintln!("Failed to read the export ({:?})", e);
+    LedgerStoreError::LedgerError(StorageError::InvalidExport)
+  })?;
+  replay(reader, attestations, None).await?;
+  if let Err(e) = reader.seek(SeekFrom::Start(start)) {
+    eprintln!("Failed to rewind the export ({:?})", e);
+    return Err(LedgerStoreError::LedgerError(StorageError::InvalidExport));
+  }
+  replay(reader, attestations, Some(ledger_store)).await
+}
+
+// the ledger whose entries are being replayed
+struct CurrentLedger {
+  handle: Handle,
+  height: usize,
+  // the index of the first entry, which is the checkpoint of a pruned ledger
+  first_idx: usize,
+  checkpoint: Option<Receipts>,
+  next_idx: usize,
+  checker: ChainChecker,
+}
+
+async fn replay(
+  reader: &mut dyn Read,
+  attestations: &[u8],
+  target: Option<&dyn LedgerStore>,
+) -> Result<MigrationSummary, LedgerStoreError> {
+  let mut version = [0u8; 1];
+  let res = reader.read_exact(&mut version);
+  if res.is_err() || version[0] != EXPORT_VERSION {
+    eprintln!("The export does not start with version {}", EXPORT_VERSION);
+    return Err(LedgerStoreError::LedgerError(StorageError::InvalidExport));
+  }
+  let mut reader = RecordReader {
+    reader,
+    digest: NimbleDigest::default(),
+  };
+
+  let mut summary = MigrationSummary::default();
+  let mut verifier_state = VerifierState::new();
+  let mut view_entries: Vec<Option<LedgerEntry>> = Vec::new();
+  let mut views_verified = false;
+  let mut current: Option<CurrentLedger> = None;
+
+  loop {
+    let (record, digest) = reader.read()?;
+    match record {
+      ExportRecord::ViewEntry { block, receipts } => {
+        if views_verified {
+          eprintln!("The export has a view ledger entry after the first ledger");
+          return Err(LedgerStoreError::LedgerError(StorageError::InvalidExport));
+        }
+        let entry = decode_entry(&block, &receipts, &[])?;
+        let idx = view_entries.len() + 1;
+        if let Some(ledger_store) = target {
+          ledger_store
+            .append_view_ledger(entry.get_block(), idx)
+            .await?;
+          ledger_store
+            .attach_view_ledger_receipts(idx, entry.get_receipts())
+            .await?;
+          let stored = ledger_store.read_view_ledger_by_index(idx).await?;
+          if !same_entry(&entry, &stored) {
+            eprintln!("The view ledger entry at index {} changed on import", idx);
+            return Err(LedgerStoreError::LedgerError(
+              StorageError::FailedVerification,
+            ));
+          }
+        }
+        view_entries.push(Some(entry));
+        summary.view_entries += 1;
+      },
+      ExportRecord::Ledger { handle, height } => {
+        if !views_verified {
+          verify_views(&view_entries, attestations, &mut verifier_state)?;
+          views_verified = true;
+        }
+        finish_ledger(&current)?;
+        let handle = NimbleDigest::from_bytes(&handle).map_err(|_| {
+          eprintln!("The export has a malformed handle");
+          LedgerStoreError::LedgerError(StorageError::InvalidExport)
+        })?;
+        current = Some(CurrentLedger {
+          handle,
+          height,
+          first_idx: 0,
+          checkpoint: None,
+          next_idx: 0,
+          checker: ChainChecker::new(&handle),
+        });
+        summary.ledgers += 1;
+      },
+      ExportRecord::Checkpoint { height, receipts } => {
+        let ledger = match current.as_mut() {
+          Some(ledger) if ledger.next_idx == 0 && ledger.checkpoint.is_none() => ledger,
+          _ => {
+            eprintln!("The export has a checkpoint that does not follow a ledger header");
+            return Err(LedgerStoreError::LedgerError(StorageError::InvalidExport));
+          },
+        };
+        let receipts = Receipts::from_bytes(&receipts);
+        match receipts {
+          Ok(receipts) if height > 0 && height <= ledger.height => {
+            ledger.first_idx = height;
+            ledger.next_idx = height;
+            ledger.checkpoint = Some(receipts);
+          },
+          _ => {
+            eprintln!("The export has a malformed checkpoint");
+            return Err(LedgerStoreError::LedgerError(StorageError::InvalidExport));
+          },
+        }
+      },
+      ExportRecord::LedgerEntry {
+        block,
+        receipts,
+        nonces,
+      } => {
+        let ledger = match current.as_mut() {
+          Some(ledger) if ledger.next_idx <= ledger.height => ledger,
+          _ => {
+            eprintln!("The export has a ledger entry outside of a ledger");
+            return Err(LedgerStoreError::LedgerError(StorageError::InvalidExport));
+          },
+        };
+        let idx = ledger.next_idx;
+        let entry = decode_entry(&block, &receipts, &nonces)?;
+        let findings = ledger.checker.check(&verifier_state, idx, &entry);
+        if !findings.is_empty() {
+          report_findings(&ledger.handle, &findings);
+          return Err(LedgerStoreError::LedgerError(
+            StorageError::FailedVerification,
+          ));
+        }
+        // the checkpoint is the first entry of a pruned ledger, with the same receipts
+        let is_checkpoint = ledger.checkpoint.is_some() && idx == ledger.first_idx;
+        if let Some(receipts) = ledger.checkpoint.as_ref().filter(|_| is_checkpoint) {
+          if sorted_receipts(receipts) != sorted_receipts(entry.get_receipts()) {
+            eprintln!(
+              "The checkpoint of ledger {:?} does not match its entry at index {}",
+              ledger.handle, idx
+            );
+            return Err(LedgerStoreError::LedgerError(
+              StorageError::FailedVerification,
+            ));
+          }
+        }
+        if let Some(ledger_store) = target {
+          write_ledger_entry(ledger_store, &ledger.handle, idx, &entry, is_checkpoint).await?;
+        }
+        ledger.next_idx += 1;
+        summary.entries += 1;
+      },
+      ExportRecord::End { digest: expected } => {
+        if expected != digest.to_bytes() {
+          eprintln!("The digest of the export does not match its contents");
+          return Err(LedgerStoreError::LedgerError(
+            StorageError::FailedVerification,
+          ));
+        }
+        if !views_verified {
+          verify_views(&view_entries, attestations, &mut verifier_state)?;
+        }
+        finish_ledger(&current)?;
+        return Ok(summary);
+      },
+    }
+  }
+}
+
+fn verify_views(
+  view_entries: &[Option<LedgerEntry>],
+  attestations: &[u8],
+  verifier_state: &mut VerifierState,
+) -> Result<(), LedgerStoreError> {
+  let findings = check_view_entries(view_entries, attestations, verifier_state);
+  if !findings.is_empty() {
+    eprintln!(
+      "The view ledger in the export fails verification: {:?}",
+      findings
+    );
+    return Err(LedgerStoreError::LedgerError(
+      StorageError::FailedVerification,
+    ));
+  }
+  Ok(())
+}
+
+fn finish_ledger(current: &Option<CurrentLedger>) -> Result<(), LedgerStoreError> {
+  if let Some(ledger) = current {
+    if ledger.next_idx != ledger.height + 1 {
+      eprintln!(
+        "The export has {} of the {} entries of ledger {:?}",
+        ledger.next_idx - ledger.first_idx,
+        ledger.height + 1 - ledger.first_idx,
+        ledger.handle
+      );
+      return Err(LedgerStoreError::LedgerError(StorageError::InvalidExport));
+    }
+  }
+  Ok(())
+}
+
+fn report_findings(handle: &Handle, findings: &[AuditFinding]) {
+  eprintln!(
+    "Ledger {} in the export fails verification: {:?}",
+    hex::encode(handle.to_bytes()),
+    findings
+  );
+}
+
+fn decode_entry(
+  block: &[u8],
+  receipts: &[u8],
+  nonces: &[u8],
+) -> Result<LedgerEntry, LedgerStoreError> {
+  let receipts = Receipts::from_bytes(receipts);
+  let nonces = Nonces::from_bytes(nonces);
+  match (receipts, nonces) {
+    (Ok(receipts), Ok(nonces)) => Ok(LedgerEntry::new(Block::new(block), receipts, Some(nonces))),
+    _ => {
+      eprintln!("The export has malformed receipts or nonces");
+      Err(LedgerStoreError::LedgerError(StorageError::InvalidExport))
+    },
+  }
+}
+
+async fn write_ledger_entry(
+  ledger_store: &dyn LedgerStore,
+  handle: &Handle,
+  idx: usize,
+  entry: &LedgerEntry,
+  is_checkpoint: bool,
+) -> Result<(), LedgerStoreError> {
+  if idx == 0 {
+    ledger_store
+      .create_ledger(handle, entry.get_block().clone())
+      .await?;
+  } else {
+    if is_checkpoint {
+      create_placeholders(ledger_store, handle, idx).await?;
+    }
+    // nonces are attached to the ledger before the block that they are drained into
+    for nonce in entry.get_nonces().get() {
+      ledger_store.attach_ledger_nonce(handle, nonce).await?;
+    }
+    ledger_store
+      .append_ledger(handle, entry.get_block(), idx)
+      .await?;
+  }
+  ledger_store
+    .attach_ledger_receipts(handle, idx, entry.get_receipts())
+    .await?;
+  if is_checkpoint {
+    ledger_store.prune_ledger(handle, idx).await?;
+  }
+
+  let stored = ledger_store.read_ledger_by_index(handle, idx).await?;
+  if !same_entry(entry, &stored) {
+    eprintln!(
+      "The entry at index {} of ledger {:?} changed on import",
+      idx, handle
+    );
+    return Err(LedgerStoreError::LedgerError(
+      StorageError::FailedVerification,
+    ));
+  }
+  Ok(())
+}
+
+// Creates a ledger with placeholder entries below `height`, so that the entries of a pruned
+// ledger can be appended at their indices; pruning at its checkpoint drops the placeholders.
+async fn create_placeholders(
+  ledger_store: &dyn LedgerStore,
+  handle: &Handle,
+  height: usize,
+) -> Result<(), LedgerStoreError> {
+  ledger_store.create_ledger(handle, Block::new(&[])).await?;
+  for idx in 1..height {
+    ledger_store
+      .append_ledger(handle, &Block::new(&[]), idx)
+      .await?;
+  }
+  Ok(())
+}
+
+fn same_entry(expected: &LedgerEntry, stored: &LedgerEntry) -> bool {
+  expected.get_block().to_bytes() == stored.get_block().to_bytes()
+    && sorted_receipts(expected.get_receipts()) == sorted_receipts(stored.get_receipts())
+    && sorted_nonces(expected.get_nonces()) == sorted_nonces(stored.get_nonces())
+}
+
+// receipts and nonces are sets, so their serialization is not in a canonical order
+fn sorted_receipts(receipts: &Receipts) -> Vec<Vec<u8>> {
+  let mut chunks = receipts
+    .to_bytes()
+    .chunks(Receipt::num_bytes())
+    .map(|c| c.to_vec())
+    .collect::<Vec<_>>();
+  chunks.sort();
+  chunks
+}
+
+fn sorted_nonces(nonces: &Nonces) -> Vec<Vec<u8>> {
+  let mut nonces = nonces.get().iter().map(Nonce::to_bytes).collect::<Vec<_>>();
+  nonces.sort();
+  nonces
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    audit::{
+      audit_store,
+      tests::{populate_store, ATTESTATIONS},
+    },
+    ledger::in_memory::InMemoryLedgerStore,
+  };
+  use std::io::Cursor;
+
+  #[tokio::test]
+  pub async fn test_export_import() {
+    let source = InMemoryLedgerStore::new();
+    let fixture = populate_store(&source).await;
+
+    let mut export = Vec::new();
+    let exported = export_store(&source, &mut export).await.unwrap();
+    assert_eq!(
+      exported,
+      MigrationSummary {
+        view_entries: 1,
+        ledgers: 1,
+        entries: 2,
+      }
+    );
+
+    // an entry whose receipts are not attached yet is not part of the cut
+    source
+      .append_ledger(&fixture.handle, &Block::new(b"block2"), 2)
+      .await
+      .unwrap();
+    let mut later = Vec::new();
+    assert_eq!(export_store(&source, &mut later).await.unwrap(), exported);
+    assert_eq!(later, export);
+
+    let verified = verify_export(&mut export.as_slice(), ATTESTATIONS.as_bytes())
+      .await
+      .unwrap();
+    assert_eq!(verified, exported);
+
+    let target = InMemoryLedgerStore::new();
+    let imported = import_store(&target, &mut Cursor::new(&export), ATTESTATIONS.as_bytes())
+      .await
+      .unwrap();
+    assert_eq!(imported, exported);
+    let report = audit_store(&target, ATTESTATIONS.as_bytes()).await.unwrap();
+    assert!(report.is_clean());
+    assert_eq!(report.ledgers.len(), 1);
+    assert_eq!(report.ledgers[0].height, 1);
+
+    // the target is no longer empty
+    let res = import_store(&target, &mut Cursor::new(&export), ATTESTATIONS.as_bytes()).await;
+    assert!(matches!(
+      res,
+      Err(LedgerStoreError::LedgerError(StorageError::StoreNotEmpty))
+    ));
+
+    // any byte that changes in transit is detected, and a truncated export is rejected
+    for pos in [0, 20, export.len() / 2, export.len() - 1] {
+      let mut tampered = export.clone();
+      tampered[pos] ^= 1;
+      let res = verify_export(&mut tampered.as_slice(), ATTESTATIONS.as_bytes()).await;
+      assert!(res.is_err());
+    }
+    // nothing is written from an export that fails verification
+    let truncated = &export[..export.len() - 10];
+    let target = InMemoryLedgerStore::new();
+    let res = import_store(
+      &target,
+      &mut Cursor::new(truncated),
+      ATTESTATIONS.as_bytes(),
+    )
+    .await;
+    assert!(res.is_err());
+    let mut tampered = export.clone();
+    let last = tampered.len() - 1;
+    tampered[last] ^= 1;
+    let res = import_store(
+      &target,
+      &mut Cursor::new(&tampered),
+      ATTESTATIONS.as_bytes(),
+    )
+    .await;
+    assert!(res.is_err());
+    assert_eq!(target.read_view_ledger_tail().await.unwrap().1, 0);
+    assert!(target.list_ledgers().await.unwrap().is_empty());
+  }
+
+  #[tokio::test]
+  pub async fn test_export_import_pruned_ledger() {
+    let source = InMemoryLedgerStore::new();
+    let fixture = populate_store(&source).await;
+    let checkpoint = source.prune_ledger(&fixture.handle, 1).await.unwrap();
+
+    // the pruned ledger is exported from its checkpoint
+    let mut export = Vec::new();
+    let exported = export_store(&source, &mut export).await.unwrap();
+    assert_eq!(
+      exported,
+      MigrationSummary {
+        view_entries: 1,
+        ledgers: 1,
+        entries: 1,
+      }
+    );
+    let verified = verify_export(&mut export.as_slice(), ATTESTATIONS.as_bytes())
+      .await
+      .unwrap();
+    assert_eq!(verified, exported);
+
+    let target = InMemoryLedgerStore::new();
+    let imported = import_store(&target, &mut Cursor::new(&export), ATTESTATIONS.as_bytes())
+      .await
+      .unwrap();
+    assert_eq!(imported, exported);
+    let imported_checkpoint = target
+      .read_ledger_checkpoint(&fixture.handle)
+      .await
+      .unwrap()
+      .unwrap();
+    assert_eq!(imported_checkpoint.get_height(), 1);
+    assert_eq!(
+      sorted_receipts(imported_checkpoint.get_receipts()),
+      sorted_receipts(checkpoint.get_receipts())
+    );
+    assert!(matches!(
+      target.read_ledger_by_index(&fixture.handle, 0).await,
+      Err(LedgerStoreError::Pruned(_))
+    ));
+    let report = audit_store(&target, ATTESTATIONS.as_bytes()).await.unwrap();
+    assert!(report.is_clean());
+    assert_eq!(report.ledgers[0].checkpoint, Some(1));
+    assert_eq!(report.ledgers[0].height, 1);
+
+    // the exports of the source and the target are the same
+    let mut again = Vec::new();
+    export_store(&target, &mut again).await.unwrap();
+    assert_eq!(again, export);
+  }
+}
@@ -0,0 +1,216 @@
+use clap::{App, Arg, ArgMatches};
+use std::{
+  collections::HashMap,
+  fs::{self, File},
+  io::{BufReader, BufWriter, Write},
+  process,
+};
+use store::{
+  audit::audit_store,
+  errors::{LedgerStoreError, StorageError},
+  ledger::{
+    azure_table::TableLedgerStore, filestore::FileStore, mongodb_cosmos::MongoCosmosLedgerStore,
+    LedgerStore,
+  },
+  migrate::{export_store, import_store, MigrationSummary},
+};
+
+// the coordinator vouches for the latest view with the same placeholder
+const ATTESTATION_STR: &str = "THIS IS A PLACE HOLDER FOR ATTESTATION";
+
+/// Opens the ledger store selected on the command line.
+async fn open_store(
+  cli_matches: &ArgMatches<'_>,
+) -> Result<Box<dyn LedgerStore + Send + Sync>, LedgerStoreError> {
+  let mut ledger_store_args = HashMap::<String, String>::new();
+  if let Some(x) = cli_matches.value_of("cosmosurl") {
+    ledger_store_args.insert(String::from("COSMOS_URL"), x.to_string());
+  }
+  if let Some(x) = cli_matches.value_of("nimbledb") {
+    ledger_store_args.insert(String::from("NIMBLE_DB"), x.to_string());
+  }
+  if let Some(x) = cli_matches.value_of("storage_account") {
+    ledger_store_args.insert(String::from("STORAGE_ACCOUNT"), x.to_string());
+  }
+  if let Some(x) = cli_matches.value_of("storage_master_key") {
+    ledger_store_args.insert(String::from("STORAGE_MASTER_KEY"), x.to_string());
+  }
+  if let Some(x) = cli_matches.value_of("dir") {
+    ledger_store_args.insert(String::from("NIMBLE_FSTORE_DIR"), x.to_string());
+  }
+
+  match cli_matches.value_of("store").unwrap() {
+    "mongodb_cosmos" => Ok(Box::new(
+      MongoCosmosLedgerStore::new(&ledger_store_args).await?,
+    )),
+    "table" => Ok(Box::new(TableLedgerStore::new(&ledger_store_args).await?)),
+    "filestore" => Ok(Box::new(FileStore::new(&ledger_store_args).await?)),
+    store => {
+      eprintln!("Unsupported store type {}", store);
+      Err(LedgerStoreError::LedgerError(StorageError::BadRequest))
+    },
+  }
+}
+
+fn open_export(path: &str) -> BufReader<File> {
+  match File::open(path) {
+    Ok(f) => BufReader::new(f),
+    Err(e) => {
+      eprintln!("Failed to open the export {}: {:?}", path, e);
+      process::exit(2);
+    },
+  }
+}
+
+fn print_summary(action: &str, summary: &MigrationSummary) {
+  println!(
+    "{} {} view ledger entries and {} ledgers with {} entries",
+    action, summary.view_entries, summary.ledgers, summary.entries
+  );
+}
+
+/// Main function to start the ledger store control tool.
+#[tokio::main]
+async fn main() {
+  let config = App::new("store_ctrl")
+    .arg(
+      Arg::with_name("store")
+        .short("s")
+        .long("store")
+        .help("The type of store: filestore, mongodb_cosmos or table")
+        .default_value("filestore"),
+    )
+    .arg(
+      Arg::with_name("nimbledb")
+        .short("n")
+        .long("nimbledb")
+        .help("The database name")
+        .default_value("nimble_cosmosdb"),
+    )
+    .arg(
+      Arg::with_name("cosmosurl")
+        .short("c")
+        .long("cosmosurl")
+        .takes_value(true)
+        .help("The COSMOS URL"),
+    )
+    .arg(
+      Arg::with_name("storage_account")
+        .short("a")
+        .long("storage_account")
+        .takes_value(true)
+        .help("The storage account name"),
+    )
+    .arg(
+      Arg::with_name("storage_master_key")
+        .short("k")
+        .long("storage_master_key")
+        .takes_value(true)
+        .help("The storage master key"),
+    )
+    .arg(
+      Arg::with_name("dir")
+        .short("d")
+        .long("dir")
+        .takes_value(true)
+        .help("The directory of the file store"),
+    )
+    .arg(
+      Arg::with_name("audit")
+        .long("audit")
+        .takes_value(true)
+        .min_values(0)
+        .help("Audit every ledger, writing a JSON report to stdout or the given file"),
+    )
+    .arg(
+      Arg::with_name("export")
+        .long("export")
+        .visible_alias("backup")
+        .takes_value(true)
+        .conflicts_with("import")
+        .help("Export a consistent cut of the view ledger and every ledger to the given file"),
+    )
+    .arg(
+      Arg::with_name("import")
+        .long("import")
+        .visible_alias("restore")
+        .takes_value(true)
+        .help("Verify the given export and import it into an empty store"),
+    );
+
+  let cli_matches = config.get_matches();
+
+  let ledger_store = match open_store(&cli_matches).await {
+    Ok(s) => s,
+    Err(e) => {
+      eprintln!("Failed to open the ledger store: {:?}", e);
+      process::exit(2);
+    },
+  };
+
+  if let Some(path) = cli_matches.value_of("export") {
+    let file = match File::create(path) {
+      Ok(f) => f,
+      Err(e) => {
+        eprintln!("Failed to create the export {}: {:?}", path, e);
+        process::exit(2);
+      },
+    };
+    let mut writer = BufWriter::new(file);
+    let summary = match export_store(ledger_store.as_ref(), &mut writer).await {
+      Ok(s) => s,
+      Err(e) => {
+        eprintln!("Failed to export the ledger store: {:?}", e);
+        process::exit(2);
+      },
+    };
+    if let Err(e) = writer.flush() {
+      eprintln!("Failed to write the export {}: {:?}", path, e);
+      process::exit(2);
+    }
+    print_summary("Exported", &summary);
+  }
+
+  if let Some(path) = cli_matches.value_of("import") {
+    // the whole export is verified before anything is written to the store
+    let res = import_store(
+      ledger_store.as_ref(),
+      &mut open_export(path),
+      ATTESTATION_STR.as_bytes(),
+    )
+    .await;
+    match res {
+      Ok(summary) => print_summary("Imported", &summary),
+      Err(e) => {
+        eprintln!("Failed to import {}: {:?}", path, e);
+        process::exit(2);
+      },
+    }
+  }
+
+  if cli_matches.is_present("audit") {
+    let report = match audit_store(ledger_store.as_ref(), ATTESTATION_STR.as_bytes()).await {
+      Ok(r) => r,
+      Err(e) => {
+        eprintln!("Failed to audit the ledger store: {:?}", e);
+        process::exit(2);
+      },
+    };
+
+    let json = serde_json::to_string_pretty(&report).unwrap();
+    match cli_matches.value_of("audit") {
+      Some(path) => {
+        if let Err(e) = fs::write(path, &json) {
+          eprintln!("Failed to write the report to {}: {:?}", path, e);
+          process::exit(2);
+        }
+      },
+      None => println!("{}", json),
+    }
+
+    // a non-zero exit code lets scripts detect findings without parsing the report
+    if !report.is_clean() {
+      process::exit(1);
+    }
+  }
+}